@@ -196,6 +196,7 @@ async fn main() {
             heuristics::euclidiean,
             SearchInfo {
                 agent_radius: POINT_RADIUS,
+                ..Default::default()
             },
         );
 
@@ -218,9 +219,16 @@ async fn main() {
                     COLORSCHEME.edge,
                 );
             }
+
+            // Highlight the portal under the mouse
+            let mouse: Vec2 = mouse_position().into();
+            if let Some((portal, _)) = portals.pick_portal(mouse, PICK_RADIUS) {
+                let [a, b] = portal.face().vertices;
+                draw_line(a.x, a.y, b.x, b.y, THICKNESS * 2.0, COLORSCHEME.end);
+            }
         }
 
-        if let Some(path) = path {
+        if let Ok(path) = path {
             path.draw();
         }
 
@@ -235,6 +243,7 @@ const EDGE_RADIUS: f32 = 4.0;
 const PATH_THICKNESS: f32 = 4.0;
 const NORMAL_LEN: f32 = 16.0;
 const ARROW_LEN: f32 = 4.0;
+const PICK_RADIUS: f32 = 8.0;
 
 trait Draw {
     fn draw(&self);
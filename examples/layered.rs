@@ -181,22 +181,25 @@ async fn main() {
             }
         }
 
-        start += nav.locate(layer, start).unwrap().depth;
+        let layer_id = nav.layer_of(layer).unwrap();
+
+        start += nav.locate(layer_id, start).unwrap().depth;
 
         draw_circle(start.x, start.y, POINT_RADIUS, COLORSCHEME.start);
         draw_circle(end.x, end.y, POINT_RADIUS, COLORSCHEME.end);
 
         let path = nav.find_path(
-            layer,
+            layer_id,
             start,
             end,
             heuristics::euclidiean,
             SearchInfo {
                 agent_radius: POINT_RADIUS,
+                ..Default::default()
             },
         );
 
-        let (_, tree, portals) = nav.layer(layer).unwrap();
+        let (_, tree, portals) = nav.layer(layer_id).unwrap();
         tree.descendants()
             .filter(|(_, val)| val.depth().max(10) <= depth)
             .for_each(|(_, val)| val.draw());
@@ -213,7 +216,7 @@ async fn main() {
             }
         }
 
-        if let Some(path) = path {
+        if let Ok(path) = path {
             path.draw();
         }
 
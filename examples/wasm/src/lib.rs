@@ -0,0 +1,98 @@
+//! Exposes path finding to JavaScript using `wasm-bindgen`.
+//!
+//! Build with `wasm-pack build --target web` and serve this directory, then
+//! open `index.html`.
+use bsp_pathfinding::*;
+use glam::Vec2;
+use wasm_bindgen::prelude::*;
+
+/// A navigation context built from polygons, see [NavigationContext].
+#[wasm_bindgen]
+pub struct Navigation {
+    nav: NavigationContext,
+}
+
+#[wasm_bindgen]
+impl Navigation {
+    /// Creates the navigation context from the obstacles.
+    ///
+    /// `vertices` holds the `x, y` pairs of all polygons, and `lengths` the
+    /// number of vertices of each polygon. Polygons are wound
+    /// counterclockwise and implicitly closed.
+    #[wasm_bindgen(constructor)]
+    pub fn new(vertices: &[f32], lengths: &[u32]) -> Result<Navigation, JsError> {
+        let total = lengths.iter().map(|&len| len as usize).sum::<usize>();
+        if vertices.len() != total * 2 {
+            return Err(JsError::new(
+                "Vertex count does not match the polygon lengths",
+            ));
+        }
+
+        let points = vertices
+            .chunks_exact(2)
+            .map(|v| Vec2::new(v[0], v[1]))
+            .collect::<Vec<_>>();
+
+        let mut rest = &points[..];
+        let shapes = lengths
+            .iter()
+            .map(|&len| {
+                let (polygon, tail) = rest.split_at(len as usize);
+                rest = tail;
+
+                let mut ring = polygon.to_vec();
+                ring.extend(polygon.first());
+                Shape::new(&ring)
+            })
+            .collect::<Vec<_>>();
+
+        Ok(Self {
+            nav: NavigationContext::new(shapes.iter().flatten()),
+        })
+    }
+
+    /// Finds a path and returns its waypoints as `x, y` pairs
+    #[wasm_bindgen(js_name = findPath)]
+    pub fn find_path(
+        &self,
+        start_x: f32,
+        start_y: f32,
+        end_x: f32,
+        end_y: f32,
+        agent_radius: f32,
+    ) -> Result<Vec<f32>, JsError> {
+        let info = SearchInfo {
+            agent_radius,
+            ..Default::default()
+        };
+
+        let path = self.nav.find_path(
+            Vec2::new(start_x, start_y),
+            Vec2::new(end_x, end_y),
+            heuristics::euclidiean,
+            info,
+        )?;
+
+        Ok(path
+            .iter()
+            .flat_map(|waypoint| {
+                let p = waypoint.point();
+                [p.x, p.y]
+            })
+            .collect())
+    }
+
+    /// Returns the portals as `x1, y1, x2, y2` quadruples, e.g; to draw the
+    /// navigable space
+    pub fn portals(&self) -> Vec<f32> {
+        self.nav
+            .portals()
+            .iter()
+            .flatten()
+            .flat_map(|portal| {
+                let [a, b] = portal.face().vertices();
+                [a.x, a.y, b.x, b.y]
+            })
+            .collect()
+    }
+}
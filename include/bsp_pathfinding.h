@@ -0,0 +1,78 @@
+#ifndef BSP_PATHFINDING_H
+#define BSP_PATHFINDING_H
+
+/* Generated by cbindgen from src/ffi.rs, do not edit */
+
+#include <stddef.h>
+#include <stdint.h>
+
+// The result of a call. Negative values are errors.
+typedef enum BspNavStatus {
+  BSP_NAV_STATUS_OK = 0,
+  // A pointer was null, or an id or length was invalid
+  BSP_NAV_STATUS_INVALID_ARGUMENT = -1,
+  // The path did not fit in the output buffer
+  BSP_NAV_STATUS_BUFFER_TOO_SMALL = -2,
+  BSP_NAV_STATUS_START_COVERED = -3,
+  BSP_NAV_STATUS_END_COVERED = -4,
+  BSP_NAV_STATUS_NO_ROUTE = -5,
+  BSP_NAV_STATUS_PORTAL_TOO_NARROW = -6,
+  BSP_NAV_STATUS_EMPTY_SCENE = -7,
+  BSP_NAV_STATUS_OUT_OF_BOUNDS = -8,
+} BspNavStatus;
+
+// The obstacles and navigation data, created by [nav_create] and freed by
+// [nav_destroy].
+//
+// The navigation data is rebuilt by the first search after the obstacles
+// change.
+typedef struct BspNav BspNav;
+
+// Creates an empty navigation handle
+struct BspNav *nav_create(void);
+
+// Frees a handle created by [nav_create]. Null is ignored.
+//
+// # Safety
+// `nav` must be null or a handle from [nav_create] which was not yet
+// destroyed.
+void nav_destroy(struct BspNav *nav);
+
+// Adds an obstacle of `len` points, wound counterclockwise and implicitly
+// closed. The id of the obstacle is written to `out_id`.
+//
+// # Safety
+// `nav` must be a valid handle, `points` must hold `2 * len` floats, and
+// `out_id` must be writable.
+enum BspNavStatus nav_add_shape(struct BspNav *nav,
+                                const float *points,
+                                size_t len,
+                                uint32_t *out_id);
+
+// Removes the obstacle `id`
+//
+// # Safety
+// `nav` must be a valid handle.
+enum BspNavStatus nav_remove_shape(struct BspNav *nav, uint32_t id);
+
+// Finds a path for an agent of `agent_radius` and writes its waypoints to
+// `out_points`, which holds room for `capacity` points.
+//
+// The number of waypoints is written to `out_len`. If the path does not
+// fit, [NavStatus::BufferTooSmall] is returned and nothing but `out_len` is
+// written, which allows retrying with a larger buffer.
+//
+// # Safety
+// `nav` must be a valid handle, `out_points` must hold `2 * capacity`
+// floats, and `out_len` must be writable.
+enum BspNavStatus nav_find_path(struct BspNav *nav,
+                                float start_x,
+                                float start_y,
+                                float end_x,
+                                float end_y,
+                                float agent_radius,
+                                float *out_points,
+                                size_t capacity,
+                                size_t *out_len);
+
+#endif  /* BSP_PATHFINDING_H */
@@ -0,0 +1,317 @@
+use alloc::vec::Vec;
+
+use glam::Vec2;
+use rkyv::{rancor, util::AlignedVec, Archive, Deserialize, Serialize};
+use slotmap::SecondaryMap;
+
+use crate::{
+    util::plane_distance, BSPTree, CompactPortals, Face, NavigationContext, NodeIndex, OffMeshLink,
+    PortalDirection, PortalRecord, Portals, PrebuiltError, PrebuiltNode,
+};
+
+/// Navigation data laid out for zero-copy access with `rkyv`, e.g; to
+/// memory-map a baked level instead of parsing it.
+///
+/// Nodes are stored depth first, front first, and are referred to by their
+/// position in that order. See [NavigationContext::to_archive].
+///
+/// Only point location, [ArchivedNavArchive::locate], is answered in place
+/// without allocating. Path queries need a [NavigationContext], which
+/// [NavigationContext::from_archive] rebuilds from the archive, allocating
+/// the tree and portals. This still skips parsing and portal generation.
+#[derive(Debug, Clone, PartialEq, Archive, Serialize, Deserialize)]
+pub struct NavArchive {
+    nodes: Vec<ArchiveNode>,
+    portals: Vec<ArchivePortal>,
+    links: Vec<ArchiveLink>,
+    areas: Vec<(u32, u32)>,
+    clearance: f32,
+}
+
+#[derive(Debug, Clone, PartialEq, Archive, Serialize, Deserialize)]
+struct ArchiveNode {
+    faces: Vec<ArchiveFace>,
+    front: Option<u32>,
+    back: Option<u32>,
+}
+
+#[derive(Debug, Clone, Copy, PartialEq, Archive, Serialize, Deserialize)]
+struct ArchiveFace {
+    normal: [f32; 2],
+    vertices: [[f32; 2]; 2],
+}
+
+#[derive(Debug, Clone, Copy, PartialEq, Archive, Serialize, Deserialize)]
+struct ArchivePortal {
+    vertices: [[f32; 2]; 2],
+    src: u32,
+    dst: u32,
+    adjacent: [bool; 2],
+    cost: f32,
+    // 0: both, 1: forward, 2: backward
+    direction: u8,
+    disabled: bool,
+    clearance: Option<f32>,
+}
+
+#[derive(Debug, Clone, Copy, PartialEq, Archive, Serialize, Deserialize)]
+struct ArchiveLink {
+    src: u32,
+    dst: u32,
+    entry: [f32; 2],
+    exit: [f32; 2],
+    cost: f32,
+}
+
+impl NavArchive {
+    /// Writes the archive to an aligned buffer
+    pub fn to_bytes(&self) -> AlignedVec {
+        rkyv::to_bytes::<rancor::Error>(self).expect("Archive is serializable")
+    }
+
+    /// Validates `bytes` and returns the archive they contain without
+    /// copying. The bytes need to be aligned to 16, as from [Self::to_bytes].
+    pub fn access(bytes: &[u8]) -> Result<&ArchivedNavArchive, rancor::Error> {
+        rkyv::access::<ArchivedNavArchive, rancor::Error>(bytes)
+    }
+
+    /// Returns the number of nodes
+    pub fn len(&self) -> usize {
+        self.nodes.len()
+    }
+
+    /// Returns true if the archived context was empty
+    pub fn is_empty(&self) -> bool {
+        self.nodes.is_empty()
+    }
+}
+
+impl ArchivedNavArchive {
+    /// Returns the position of the node containing `point` and whether the
+    /// point is covered, in the same way as [BSPTree::locate].
+    /// Does not allocate.
+    ///
+    /// Returns None if the archive is empty.
+    pub fn locate(&self, point: Vec2) -> Option<(usize, bool)> {
+        let mut index = 0;
+        loop {
+            let face = to_face(self.nodes.get(index)?.faces.first()?);
+            let node = &self.nodes[index];
+            let dot = plane_distance(point, face.midpoint(), face.normal());
+
+            let (next, covered) = if dot >= 0.0 {
+                (node.front.as_ref(), false)
+            } else {
+                (node.back.as_ref(), true)
+            };
+
+            match next {
+                Some(next) => index = next.to_native() as usize,
+                None => return Some((index, covered)),
+            }
+        }
+    }
+
+    /// Returns the number of nodes
+    pub fn len(&self) -> usize {
+        self.nodes.len()
+    }
+
+    /// Returns true if the archived context was empty
+    pub fn is_empty(&self) -> bool {
+        self.nodes.is_empty()
+    }
+}
+
+impl NavigationContext {
+    /// Returns the navigation data in a form which can be archived with
+    /// `rkyv`, see [NavArchive].
+    ///
+    /// User data of portals is not included.
+    pub fn to_archive(&self) -> NavArchive {
+        let clearance = self.connectivity().clearance();
+        let tree = match self.tree() {
+            Some(tree) => tree,
+            None => {
+                return NavArchive {
+                    nodes: Vec::new(),
+                    portals: Vec::new(),
+                    links: Vec::new(),
+                    areas: Vec::new(),
+                    clearance,
+                }
+            }
+        };
+
+        let order = tree.preorder();
+        let mut positions = SecondaryMap::new();
+        for (i, &index) in order.iter().enumerate() {
+            positions.insert(index, i as u32);
+        }
+
+        let position = |index: Option<NodeIndex>| index.and_then(|v| positions.get(v).copied());
+
+        let nodes = order
+            .iter()
+            .map(|&index| {
+                let node = &tree.nodes()[index];
+                ArchiveNode {
+                    faces: node
+                        .faces()
+                        .iter()
+                        .map(|face| ArchiveFace {
+                            normal: face.normal().into(),
+                            vertices: face.vertices.map(Into::into),
+                        })
+                        .collect(),
+                    front: position(node.front()),
+                    back: position(node.back()),
+                }
+            })
+            .collect();
+
+        let compact = CompactPortals::from(self.portals());
+        let portals = compact
+            .portals
+            .iter()
+            .filter_map(|v| {
+                Some(ArchivePortal {
+                    vertices: v.vertices.map(Into::into),
+                    src: position(Some(v.src))?,
+                    dst: position(Some(v.dst))?,
+                    adjacent: v.adjacent,
+                    cost: v.cost,
+                    direction: match v.direction {
+                        PortalDirection::Both => 0,
+                        PortalDirection::Forward => 1,
+                        PortalDirection::Backward => 2,
+                    },
+                    disabled: v.disabled,
+                    clearance: v.clearance,
+                })
+            })
+            .collect();
+
+        let links = compact
+            .links
+            .iter()
+            .filter_map(|v| {
+                Some(ArchiveLink {
+                    src: position(Some(v.src))?,
+                    dst: position(Some(v.dst))?,
+                    entry: v.entry.into(),
+                    exit: v.exit.into(),
+                    cost: v.cost,
+                })
+            })
+            .collect();
+
+        let mut areas = self
+            .areas()
+            .iter()
+            .filter_map(|(index, &area)| Some((position(Some(index))?, area)))
+            .collect::<Vec<_>>();
+        areas.sort_unstable();
+
+        NavArchive {
+            nodes,
+            portals,
+            links,
+            areas,
+            clearance,
+        }
+    }
+
+    /// Restores a context from an archive, e.g; one accessed in place with
+    /// [NavArchive::access].
+    ///
+    /// The tree is validated, see [BSPTree::from_prebuilt]. Unlike
+    /// [ArchivedNavArchive::locate], this allocates the whole context.
+    pub fn from_archive(archive: &ArchivedNavArchive) -> Result<Self, PrebuiltError> {
+        let clearance = archive.clearance.to_native();
+        if archive.nodes.is_empty() {
+            return Ok(Self::from_parts(
+                None,
+                Portals::new(),
+                SecondaryMap::new(),
+                clearance,
+            ));
+        }
+
+        let prebuilt = archive
+            .nodes
+            .iter()
+            .map(|node| {
+                PrebuiltNode::new(
+                    node.faces.iter().map(to_face).collect(),
+                    node.front.as_ref().map(|v| v.to_native() as usize),
+                    node.back.as_ref().map(|v| v.to_native() as usize),
+                )
+            })
+            .collect();
+
+        let tree = BSPTree::from_prebuilt(prebuilt)?;
+        let order = tree.preorder();
+        let node = |position: &rkyv::rend::u32_le| {
+            order
+                .get(position.to_native() as usize)
+                .copied()
+                .unwrap_or_default()
+        };
+
+        let portals = archive
+            .portals
+            .iter()
+            .map(|v| PortalRecord {
+                vertices: to_vec2_pair(&v.vertices),
+                src: node(&v.src),
+                dst: node(&v.dst),
+                adjacent: v.adjacent,
+                cost: v.cost.to_native(),
+                direction: match v.direction {
+                    1 => PortalDirection::Forward,
+                    2 => PortalDirection::Backward,
+                    _ => PortalDirection::Both,
+                },
+                disabled: v.disabled,
+                clearance: v.clearance.as_ref().map(|v| v.to_native()),
+            })
+            .collect();
+
+        let links = archive
+            .links
+            .iter()
+            .map(|v| OffMeshLink {
+                src: node(&v.src),
+                dst: node(&v.dst),
+                entry: to_vec2(&v.entry),
+                exit: to_vec2(&v.exit),
+                cost: v.cost.to_native(),
+            })
+            .collect();
+
+        let areas = archive
+            .areas
+            .iter()
+            .map(|v| (node(&v.0), v.1.to_native()))
+            .collect();
+
+        let portals = Portals::from(CompactPortals { portals, links });
+        Ok(Self::from_parts(Some(tree), portals, areas, clearance))
+    }
+}
+
+fn to_vec2(v: &[rkyv::rend::f32_le; 2]) -> Vec2 {
+    Vec2::new(v[0].to_native(), v[1].to_native())
+}
+
+fn to_vec2_pair(v: &[[rkyv::rend::f32_le; 2]; 2]) -> [Vec2; 2] {
+    [to_vec2(&v[0]), to_vec2(&v[1])]
+}
+
+fn to_face(face: &ArchivedArchiveFace) -> Face {
+    Face {
+        normal: to_vec2(&face.normal),
+        vertices: to_vec2_pair(&face.vertices),
+    }
+}
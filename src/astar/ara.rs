@@ -0,0 +1,311 @@
+use alloc::{collections::BinaryHeap, vec::Vec};
+use core::{cmp::Reverse, time::Duration};
+
+use glam::Vec2;
+use ordered_float::OrderedFloat;
+use slotmap::SecondaryMap;
+
+use crate::{
+    util::{Deadline, HashSet},
+    BSPTree, NodeIndex, PortalRef, Portals, TOLERANCE,
+};
+
+use super::{center, resolve_clip, shorten, Path, PathError, SearchInfo, WayPoint};
+
+/// Anytime Repairing A*.
+///
+/// Quickly finds a path using a heuristic inflated by
+/// [SearchInfo::heuristic_weight], and improves it across subsequent calls to
+/// [AraStar::improve] by tightening the weight towards 1, reusing the search
+/// effort of the previous iterations.
+///
+/// Each returned path is at most [AraStar::weight] times longer than the
+/// optimal path.
+#[derive(Debug, Clone)]
+pub struct AraStar<'a> {
+    info: SearchInfo<'a>,
+    heuristic: fn(Vec2, Vec2) -> f32,
+    start: Vec2,
+    start_node: NodeIndex,
+    goal: Vec2,
+    goal_node: NodeIndex,
+
+    weight: f32,
+    weight_step: f32,
+
+    g: SecondaryMap<NodeIndex, f32>,
+    parents: SecondaryMap<NodeIndex, (NodeIndex, PortalRef)>,
+    open: BinaryHeap<Reverse<(OrderedFloat<f32>, NodeIndex)>>,
+    // The current key of each node in the open set
+    queued: SecondaryMap<NodeIndex, OrderedFloat<f32>>,
+    closed: HashSet<NodeIndex>,
+    // Nodes which improved after being closed in the current iteration
+    incons: HashSet<NodeIndex>,
+
+    // True while an iteration is in progress
+    searching: bool,
+    path: Option<Path>,
+}
+
+impl<'a> AraStar<'a> {
+    /// Creates an anytime planner from `start` to `goal`.
+    /// The initial weight is taken from [SearchInfo::heuristic_weight] and is
+    /// lowered by `weight_step` after each completed iteration.
+    pub fn new(
+        tree: &BSPTree,
+        start: Vec2,
+        goal: Vec2,
+        heuristic: fn(Vec2, Vec2) -> f32,
+        info: SearchInfo<'a>,
+        weight_step: f32,
+    ) -> Result<Self, PathError> {
+        let start_node = tree.locate(start);
+        let goal_node = tree.locate(goal);
+
+        if start_node.covered() {
+            return Err(PathError::StartCovered);
+        }
+
+        if goal_node.covered() {
+            return Err(PathError::EndCovered);
+        }
+
+        let mut planner = Self {
+            info,
+            heuristic,
+            start,
+            start_node: start_node.index(),
+            goal,
+            goal_node: goal_node.index(),
+            weight: info.heuristic_weight.max(1.0),
+            weight_step,
+            g: SecondaryMap::new(),
+            parents: SecondaryMap::new(),
+            open: BinaryHeap::new(),
+            queued: SecondaryMap::new(),
+            closed: HashSet::default(),
+            incons: HashSet::default(),
+            searching: true,
+            path: None,
+        };
+
+        // The start's position does not depend on the portals
+        let key = planner.weight * (heuristic)(start, goal) * info.heuristic_scale();
+        planner.g.insert(planner.start_node, 0.0);
+        planner.queued.insert(planner.start_node, OrderedFloat(key));
+        planner
+            .open
+            .push(Reverse((OrderedFloat(key), planner.start_node)));
+
+        Ok(planner)
+    }
+
+    /// Improves the path for at most `budget` and returns the best path found
+    /// so far.
+    ///
+    /// Returns `Ok(None)` if the first path was not yet found within the
+    /// budget. Without the `std` feature, or on wasm32, the search always runs
+    /// until the path is optimal.
+    pub fn improve(
+        &mut self,
+        portals: &Portals,
+        budget: Duration,
+    ) -> Result<Option<&Path>, PathError> {
+        let deadline = Deadline::after(budget);
+
+        loop {
+            if !self.searching {
+                if self.is_optimal() {
+                    break;
+                }
+
+                self.tighten(portals);
+            }
+
+            if !self.improve_path(portals, deadline) {
+                break;
+            }
+
+            self.searching = false;
+
+            if self.g(self.goal_node).is_infinite() {
+                return Err(PathError::NoRoute);
+            }
+
+            self.publish(portals);
+        }
+
+        Ok(self.path.as_ref())
+    }
+
+    /// Returns the current suboptimality bound
+    pub fn weight(&self) -> f32 {
+        self.weight
+    }
+
+    /// Returns true if the current path is optimal and no further
+    /// improvement is possible
+    pub fn is_optimal(&self) -> bool {
+        self.path.is_some() && !self.searching && self.weight <= 1.0
+    }
+
+    /// Returns the best path found so far
+    pub fn path(&self) -> Option<&Path> {
+        self.path.as_ref()
+    }
+
+    fn g(&self, node: NodeIndex) -> f32 {
+        self.g.get(node).copied().unwrap_or(f32::INFINITY)
+    }
+
+    /// Returns the position used for measuring costs through `node`
+    fn position(&self, portals: &Portals, node: NodeIndex) -> Vec2 {
+        if node == self.start_node {
+            self.start
+        } else if node == self.goal_node {
+            self.goal
+        } else {
+            center(portals, node)
+        }
+    }
+
+    fn key(&self, portals: &Portals, node: NodeIndex) -> OrderedFloat<f32> {
+        let h = (self.heuristic)(self.position(portals, node), self.goal);
+        OrderedFloat(self.g(node) + self.weight * h * self.info.heuristic_scale())
+    }
+
+    fn enqueue(&mut self, portals: &Portals, node: NodeIndex) {
+        let key = self.key(portals, node);
+        self.queued.insert(node, key);
+        self.open.push(Reverse((key, node)));
+    }
+
+    /// Returns the lowest valid entry of the open set without removing it
+    fn top(&mut self) -> Option<(OrderedFloat<f32>, NodeIndex)> {
+        while let Some(&Reverse((key, node))) = self.open.peek() {
+            if self.queued.get(node) == Some(&key) {
+                return Some((key, node));
+            }
+
+            // Stale entry
+            self.open.pop();
+        }
+
+        None
+    }
+
+    /// Lowers the weight and prepares for the next iteration
+    fn tighten(&mut self, portals: &Portals) {
+        self.weight = (self.weight - self.weight_step).max(1.0);
+
+        // Keys depend on the weight and need to be recomputed
+        let nodes = self
+            .queued
+            .keys()
+            .chain(self.incons.drain())
+            .collect::<Vec<_>>();
+
+        self.open.clear();
+        self.queued.clear();
+        self.closed.clear();
+        self.searching = true;
+
+        for node in nodes {
+            self.enqueue(portals, node);
+        }
+    }
+
+    /// Runs the current iteration until the goal is settled.
+    /// Returns false if the deadline was reached.
+    fn improve_path(&mut self, portals: &Portals, deadline: Deadline) -> bool {
+        while let Some((key, node)) = self.top() {
+            if self.g(self.goal_node) <= key.0 {
+                return true;
+            }
+
+            if deadline.passed() {
+                return false;
+            }
+
+            self.open.pop();
+            self.queued.remove(node);
+            self.closed.insert(node);
+
+            let src = self.position(portals, node);
+            let g = self.g(node);
+
+            for portal in portals.get(node) {
+                let dst = portal.dst();
+                if dst == node || !self.info.allows(dst) {
+                    continue;
+                }
+
+                let face = portal.apply_margin(self.info.agent_radius);
+                if portal.clearance() < self.info.agent_radius {
+                    continue;
+                }
+
+                let penalty = match self.info.portal_cost(portals, portal) {
+                    Some(penalty) => penalty,
+                    None => continue,
+                };
+
+                let mid = face.midpoint();
+                let cost = src.distance(mid) * self.info.traversal_cost(node)
+                    + mid.distance(self.position(portals, dst)) * self.info.traversal_cost(dst)
+                    + penalty;
+
+                if g + cost < self.g(dst) {
+                    self.g.insert(dst, g + cost);
+                    self.parents.insert(dst, (node, portal.portal_ref()));
+
+                    if self.closed.contains(&dst) {
+                        self.incons.insert(dst);
+                    } else {
+                        self.enqueue(portals, dst);
+                    }
+                }
+            }
+        }
+
+        true
+    }
+
+    /// Reconstructs the path to the goal
+    fn publish(&mut self, portals: &Portals) {
+        let mut steps = Vec::new();
+        let mut current = self.goal_node;
+        while current != self.start_node {
+            let (prev, portal) = self.parents[current];
+            steps.push((current, portal));
+            current = prev;
+        }
+
+        let path = self.path.get_or_insert_with(Default::default);
+        path.clear();
+        path.push(WayPoint::new(self.start, self.start_node, None));
+
+        for &(node, portal) in steps.iter().rev() {
+            let prev = path.last().expect("Path is not empty").point();
+            let towards = if node == self.goal_node {
+                self.goal
+            } else {
+                center(portals, node)
+            };
+
+            let point = portals
+                .from_ref(portal)
+                .clip(prev, towards, self.info.corner_radius());
+            path.push(WayPoint::new(point, node, Some(portal)));
+        }
+
+        path.push(WayPoint::new(self.goal, self.goal_node, None));
+
+        shorten(portals, path, self.info.corner_radius());
+        resolve_clip(portals, path, self.info.corner_radius());
+
+        if self.info.snap_waypoints {
+            path.snap_to_portals(portals, self.info.corner_radius(), TOLERANCE);
+        }
+    }
+}
@@ -0,0 +1,244 @@
+use alloc::{collections::BinaryHeap, vec};
+
+use glam::Vec2;
+use slotmap::{secondary::Entry, SecondaryMap};
+
+use crate::{util::HashSet, NodeIndex, Portal, Portals, TOLERANCE};
+
+use super::{resolve_clip, shorten, Backtrace, Path, SearchInfo, SearchObserver, WayPoint};
+
+/// One direction of a bidirectional search
+struct Frontier<'a> {
+    open: BinaryHeap<Backtrace<'a>>,
+    backtraces: SecondaryMap<NodeIndex, Backtrace<'a>>,
+    closed: HashSet<NodeIndex>,
+    // The point this direction searches towards
+    target: Vec2,
+    // Searches against the direction of travel
+    backward: bool,
+}
+
+impl<'a> Frontier<'a> {
+    fn new(node: NodeIndex, point: Vec2, target: Vec2, heuristic: f32, backward: bool) -> Self {
+        let start = Backtrace::start(node, point, heuristic, 1.0);
+        let mut backtraces = SecondaryMap::new();
+        backtraces.insert(node, start);
+
+        Self {
+            open: BinaryHeap::from(vec![start]),
+            backtraces,
+            closed: HashSet::default(),
+            target,
+            backward,
+        }
+    }
+
+    /// Returns the lowest total cost in the open set
+    fn top(&mut self) -> f32 {
+        while let Some(top) = self.open.peek() {
+            if !self.closed.contains(&top.node) {
+                return top.total_cost;
+            }
+
+            self.open.pop();
+        }
+
+        f32::INFINITY
+    }
+
+    fn pop(&mut self) -> Option<Backtrace<'a>> {
+        while let Some(current) = self.open.pop() {
+            if self.closed.insert(current.node) {
+                return Some(current);
+            }
+        }
+
+        None
+    }
+
+    /// Pushes all neighbours of `current` to the open set
+    fn expand(
+        &mut self,
+        portals: &'a Portals,
+        current: &Backtrace<'a>,
+        heuristic: &impl Fn(Vec2, Vec2) -> f32,
+        info: &SearchInfo,
+        observer: &mut impl SearchObserver,
+    ) {
+        let target = self.target;
+        let target_rel = target - current.point;
+        let cost = info.traversal_cost(current.node);
+
+        let neighbours = if self.backward {
+            portals.incoming(current.node)
+        } else {
+            portals.get(current.node)
+        };
+
+        for portal in neighbours {
+            if portal.dst() == current.node
+                || self.closed.contains(&portal.dst())
+                || !info.allows(portal.dst())
+            {
+                continue;
+            }
+
+            let face = portal.apply_margin(info.agent_radius);
+            if portal.clearance() < info.agent_radius {
+                continue;
+            }
+
+            let (p1, p2) = face.into_tuple();
+            let p = if portal.normal().dot(target_rel) > 0.0 {
+                portal.clip(current.point, target, info.corner_radius())
+            } else if (heuristic)(p1, target) < (heuristic)(p2, target) {
+                p1
+            } else {
+                p2
+            };
+
+            let backtrace = Backtrace::new(
+                portal,
+                p,
+                current,
+                (heuristic)(p, target),
+                info.heuristic_weight,
+                cost,
+                portals.cost(portal.portal_ref()),
+            );
+
+            match self.backtraces.entry(backtrace.node).unwrap() {
+                Entry::Occupied(mut val) => {
+                    if val.get().total_cost > backtrace.total_cost {
+                        val.insert(backtrace);
+                    } else {
+                        continue;
+                    }
+                }
+                Entry::Vacant(entry) => {
+                    entry.insert(backtrace);
+                }
+            }
+
+            observer.on_push(backtrace.node);
+            self.open.push(backtrace);
+        }
+    }
+}
+
+/// Searches from both `start` and `end` simultaneously and stitches the
+/// searches together where they meet.
+///
+/// Returns false and leaves `path` untouched if the searches do not meet.
+pub(super) fn bidirectional(
+    portals: &Portals,
+    (start, start_node): (Vec2, NodeIndex),
+    (end, end_node): (Vec2, NodeIndex),
+    heuristic: impl Fn(Vec2, Vec2) -> f32,
+    info: SearchInfo,
+    path: &mut Option<Path>,
+    observer: &mut impl SearchObserver,
+) -> bool {
+    let heuristic_scale = info.heuristic_scale();
+    let heuristic = |a: Vec2, b: Vec2| (heuristic)(a, b) * heuristic_scale;
+
+    let h = (heuristic)(start, end) * info.heuristic_weight;
+    let mut forward = Frontier::new(start_node, start, end, h, false);
+    let mut backward = Frontier::new(end_node, end, start, h, true);
+
+    observer.on_push(start_node);
+    observer.on_push(end_node);
+
+    // The cheapest known connection and the node it meets in
+    let mut best: Option<(f32, NodeIndex)> = None;
+
+    loop {
+        let (f_top, b_top) = (forward.top(), backward.top());
+        let bound = best.map(|(cost, _)| cost).unwrap_or(f32::INFINITY);
+
+        // Neither direction can improve on the best connection
+        if f_top >= bound || b_top >= bound {
+            break;
+        }
+
+        let (frontier, other) = if f_top <= b_top {
+            (&mut forward, &backward)
+        } else {
+            (&mut backward, &forward)
+        };
+
+        let current = match frontier.pop() {
+            Some(current) => current,
+            None => break,
+        };
+
+        observer.on_expand(current.node);
+
+        if let Some(meet) = other.backtraces.get(current.node) {
+            let cost = current.start_cost
+                + meet.start_cost
+                + current.point.distance(meet.point) * info.traversal_cost(current.node);
+
+            if cost < bound {
+                best = Some((cost, current.node));
+            }
+        }
+
+        frontier.expand(portals, &current, &heuristic, &info, observer);
+    }
+
+    let meet = match best {
+        Some((_, meet)) => meet,
+        None => return false,
+    };
+
+    let path = path.get_or_insert_with(Default::default);
+    path.clear();
+
+    // Start to the meeting node
+    let mut current = Some(meet);
+    while let Some(node) = current {
+        let backtrace = &forward.backtraces[node];
+        path.push(WayPoint::new(
+            backtrace.point,
+            backtrace.node,
+            backtrace.portal.as_ref().map(Portal::portal_ref),
+        ));
+        current = backtrace.prev;
+    }
+
+    path.reverse();
+
+    // Meeting node to the end, crossing the backward portals in reverse
+    let mut current = meet;
+    while let Some(prev) = backward.backtraces[current].prev {
+        let backtrace = &backward.backtraces[current];
+        let face = backtrace.portal.map(|portal| portal.portal_ref.face);
+
+        let portal = portals
+            .get(current)
+            .find(|portal| portal.dst() == prev && Some(portal.portal_ref.face) == face)
+            .map(|portal| portal.portal_ref());
+
+        if path
+            .last()
+            .map(|last| last.point.distance_squared(backtrace.point) > TOLERANCE)
+            .unwrap_or(true)
+        {
+            path.push(WayPoint::new(backtrace.point, prev, portal));
+        }
+
+        current = prev;
+    }
+
+    path.push(WayPoint::new(end, end_node, None));
+
+    shorten(portals, path, info.corner_radius());
+    resolve_clip(portals, path, info.corner_radius());
+
+    if info.snap_waypoints {
+        path.snap_to_portals(portals, info.corner_radius(), TOLERANCE);
+    }
+
+    true
+}
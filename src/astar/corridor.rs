@@ -0,0 +1,180 @@
+use alloc::vec::Vec;
+
+use glam::Vec2;
+use slotmap::Key;
+
+use crate::{
+    util::{cell_contains, closest_on_segment, edges},
+    BSPTree, Face, NodeIndex, PortalRef, Portals,
+};
+
+use super::Path;
+
+/// The convex leaf regions a [Path] passes through, and the portal segments
+/// connecting them.
+///
+/// Describes the free space around the path, which an agent may deviate
+/// into, e.g; due to local avoidance, without leaving the planned route.
+/// Custom steering or funnel algorithms may be run over the segments.
+#[derive(Debug, Clone, Default, PartialEq)]
+pub struct Corridor {
+    nodes: Vec<NodeIndex>,
+    // The convex region of each node, in counterclockwise order
+    cells: Vec<Vec<Vec2>>,
+    // The portal from each node to the next, None for off-mesh links
+    portals: Vec<Option<PortalRef>>,
+    segments: Vec<Option<Face>>,
+}
+
+impl Corridor {
+    /// Creates the corridor of the nodes visited by `path`.
+    ///
+    /// Nodes which the path only touches at a corner are included, such that
+    /// consecutive nodes always share a portal, unless they are connected by
+    /// an off-mesh link.
+    /// Waypoints without a node, such as those of a path through an empty
+    /// scene, are skipped.
+    pub fn new(tree: &BSPTree, portals: &Portals, path: &Path) -> Self {
+        let mut corridor = Self::default();
+        let mut linked = false;
+
+        for waypoint in path.iter() {
+            let node = waypoint.node;
+            if node.is_null() || corridor.nodes.last() == Some(&node) {
+                linked |= waypoint.link().is_some();
+                continue;
+            }
+
+            let cell = match tree.cell(node) {
+                Some(cell) => cell,
+                None => continue,
+            };
+
+            match corridor.nodes.last() {
+                None => {}
+                Some(_) if linked => corridor.push_portal(portals, None),
+                Some(&last) => corridor.connect(tree, portals, last, node, waypoint.point),
+            }
+
+            corridor.nodes.push(node);
+            corridor.cells.push(cell);
+            linked = waypoint.link().is_some();
+        }
+
+        corridor
+    }
+
+    /// Connects `from` to `to` through a shared portal, or through a common
+    /// neighbour the path passed at a corner
+    fn connect(
+        &mut self,
+        tree: &BSPTree,
+        portals: &Portals,
+        from: NodeIndex,
+        to: NodeIndex,
+        point: Vec2,
+    ) {
+        let dist = |portal: &PortalRef| {
+            portals
+                .from_ref(*portal)
+                .face()
+                .closest_point(point)
+                .distance_squared(point)
+        };
+
+        let closest = |a: &(PortalRef, f32), b: &(PortalRef, f32)| a.1.total_cmp(&b.1);
+        let direct = portals
+            .get(from)
+            .filter(|portal| portal.dst() == to)
+            .map(|portal| (portal.portal_ref(), dist(&portal)))
+            .min_by(closest);
+
+        if let Some((portal, _)) = direct {
+            self.push_portal(portals, Some(portal));
+            return;
+        }
+
+        let via = portals
+            .get(from)
+            .filter_map(|a| {
+                let b = portals.get(a.dst()).find(|b| b.dst() == to)?;
+                Some(((a.portal_ref(), b.portal_ref()), dist(&a) + dist(&b)))
+            })
+            .min_by(|a, b| a.1.total_cmp(&b.1))
+            .and_then(|(portals, _)| Some((portals, tree.cell(portals.0.dst())?)));
+
+        match via {
+            Some(((a, b), cell)) => {
+                self.push_portal(portals, Some(a));
+                self.nodes.push(a.dst());
+                self.cells.push(cell);
+                self.push_portal(portals, Some(b));
+            }
+            None => self.push_portal(portals, None),
+        }
+    }
+
+    fn push_portal(&mut self, portals: &Portals, portal: Option<PortalRef>) {
+        self.portals.push(portal);
+        self.segments
+            .push(portal.map(|portal| *portals.from_ref(portal).face()));
+    }
+
+    /// Get the corridor's nodes, in the order they are visited.
+    pub fn nodes(&self) -> &[NodeIndex] {
+        &self.nodes
+    }
+
+    /// Returns the convex region of each node, in counterclockwise order
+    pub fn cells(&self) -> &[Vec<Vec2>] {
+        &self.cells
+    }
+
+    /// Returns the portal from each node to the next, or None where the nodes
+    /// are connected by an off-mesh link.
+    pub fn portals(&self) -> &[Option<PortalRef>] {
+        &self.portals
+    }
+
+    /// Returns the segment of each portal in [Self::portals], which is the
+    /// edge shared by consecutive cells.
+    pub fn segments(&self) -> &[Option<Face>] {
+        &self.segments
+    }
+
+    /// Returns the number of nodes in the corridor
+    pub fn len(&self) -> usize {
+        self.nodes.len()
+    }
+
+    /// Returns true if the corridor contains no nodes
+    pub fn is_empty(&self) -> bool {
+        self.nodes.is_empty()
+    }
+
+    /// Returns true if `point` lies inside the corridor
+    pub fn contains(&self, point: Vec2) -> bool {
+        self.cells.iter().any(|cell| cell_contains(cell, point))
+    }
+
+    /// Projects `point` into the free space of the corridor.
+    ///
+    /// Points inside the corridor are returned as is, otherwise the closest
+    /// point on the corridor's boundary is returned. Allows an agent which
+    /// drifted off the path to recover without replanning.
+    pub fn clamp(&self, point: Vec2) -> Vec2 {
+        if self.cells.is_empty() || self.contains(point) {
+            return point;
+        }
+
+        self.cells
+            .iter()
+            .flat_map(|cell| edges(cell))
+            .map(|(a, b)| closest_on_segment(a, b, point))
+            .min_by(|a, b| {
+                a.distance_squared(point)
+                    .total_cmp(&b.distance_squared(point))
+            })
+            .unwrap_or(point)
+    }
+}
@@ -0,0 +1,108 @@
+#[cfg(feature = "std")]
+use std::{boxed::Box, sync::RwLock};
+
+use alloc::vec::Vec;
+
+use glam::Vec2;
+
+use crate::{BSPTree, Face, NodeIndex, PortalRef, Portals, TOLERANCE};
+
+use super::Path;
+
+/// A problem found in a produced path, e.g; a waypoint pulled into a wall
+/// corner by aggressive margins. See [Path::diagnose].
+#[derive(Debug, Clone, PartialEq)]
+pub enum Diagnostic {
+    /// A waypoint lies inside covered space
+    CoveredWaypoint {
+        /// The index of the waypoint in the path.
+        index: usize,
+        point: Vec2,
+        /// The node the waypoint belongs to in the path.
+        node: NodeIndex,
+        /// The portal crossed to reach the waypoint.
+        portal: Option<PortalRef>,
+        /// The faces of the leaf covering the waypoint, and of the portal.
+        faces: Vec<Face>,
+        /// How far the waypoint is inside the covered space.
+        depth: f32,
+    },
+}
+
+#[cfg(feature = "std")]
+type Hook = Box<dyn Fn(&Diagnostic) + Send + Sync>;
+
+#[cfg(feature = "std")]
+static HOOK: RwLock<Option<Hook>> = RwLock::new(None);
+
+/// Sets the function receiving the diagnostics of paths produced by searches
+/// in debug builds, e.g; to forward them to `log` or `tracing`.
+///
+/// Without a hook, paths are not diagnosed. Release builds do not diagnose
+/// paths either, see [Path::diagnose] to do so explicitly.
+#[cfg(feature = "std")]
+pub fn set_diagnostic_hook(hook: impl Fn(&Diagnostic) + Send + Sync + 'static) {
+    *HOOK.write().unwrap_or_else(|e| e.into_inner()) = Some(Box::new(hook));
+}
+
+/// Removes the hook set by [set_diagnostic_hook]
+#[cfg(feature = "std")]
+pub fn clear_diagnostic_hook() {
+    *HOOK.write().unwrap_or_else(|e| e.into_inner()) = None;
+}
+
+impl Path {
+    /// Returns the problems found in the path, such as waypoints inside
+    /// covered space.
+    pub fn diagnose(&self, tree: &BSPTree, portals: &Portals) -> Vec<Diagnostic> {
+        self.iter()
+            .enumerate()
+            .filter_map(|(index, waypoint)| {
+                let located = tree.locate(waypoint.point());
+                let depth = located.depth().length();
+                if !located.covered() || depth <= TOLERANCE {
+                    return None;
+                }
+
+                let faces = located
+                    .node()
+                    .faces()
+                    .iter()
+                    .copied()
+                    .chain(
+                        waypoint
+                            .portal()
+                            .map(|portal| *portals.from_ref(portal).face()),
+                    )
+                    .collect();
+
+                Some(Diagnostic::CoveredWaypoint {
+                    index,
+                    point: waypoint.point(),
+                    node: waypoint.node(),
+                    portal: waypoint.portal(),
+                    faces,
+                    depth,
+                })
+            })
+            .collect()
+    }
+}
+
+/// Reports the diagnostics of a produced path to the hook in debug builds.
+/// Does nothing if no hook is set.
+#[cfg(feature = "std")]
+pub(crate) fn report(tree: &BSPTree, portals: &Portals, path: &Path) {
+    if !cfg!(debug_assertions) {
+        return;
+    }
+
+    let hook = HOOK.read().unwrap_or_else(|e| e.into_inner());
+    if let Some(hook) = hook.as_ref() {
+        path.diagnose(tree, portals).iter().for_each(hook);
+    }
+}
+
+/// Without std there is nowhere to report to
+#[cfg(not(feature = "std"))]
+pub(crate) fn report(_: &BSPTree, _: &Portals, _: &Path) {}
@@ -0,0 +1,349 @@
+use alloc::collections::BinaryHeap;
+use core::cmp::Reverse;
+
+use glam::Vec2;
+use ordered_float::OrderedFloat;
+use slotmap::SecondaryMap;
+use smallvec::SmallVec;
+
+use crate::{
+    util::{HashMap, HashSet},
+    BSPTree, NodeIndex, Portal, PortalRef, Portals,
+};
+
+use super::{center, resolve_clip, shorten, Path, PathError, WayPoint};
+
+type Key = (OrderedFloat<f32>, OrderedFloat<f32>);
+
+/// Incremental planner which keeps its search state between queries.
+///
+/// Costs of edges between nodes may be changed, or nodes blocked entirely,
+/// after which [DStarLite::path] repairs only the affected parts of the
+/// search instead of planning from scratch. Changes made to the portals
+/// themselves, such as [Portals::set_enabled] or [Portals::set_cost], must be
+/// reported through [DStarLite::portal_changed]. The agent may move along the
+/// path using [DStarLite::set_start] without invalidating the search.
+///
+/// The search runs from the goal towards the start, which means the goal is
+/// fixed for the lifetime of the planner.
+#[derive(Debug, Clone)]
+pub struct DStarLite {
+    start: Vec2,
+    start_node: NodeIndex,
+    goal: Vec2,
+    goal_node: NodeIndex,
+    agent_radius: f32,
+
+    // The start node at the time of the last edge change
+    last: NodeIndex,
+    km: f32,
+    g: SecondaryMap<NodeIndex, f32>,
+    rhs: SecondaryMap<NodeIndex, f32>,
+    open: BinaryHeap<Reverse<(Key, NodeIndex)>>,
+    // The current key of each node in the open set
+    queued: SecondaryMap<NodeIndex, Key>,
+
+    scales: HashMap<(NodeIndex, NodeIndex), f32>,
+    blocked: HashSet<NodeIndex>,
+}
+
+impl DStarLite {
+    /// Creates a planner from `start` to `goal` for an agent of `agent_radius`.
+    pub fn new(
+        tree: &BSPTree,
+        start: Vec2,
+        goal: Vec2,
+        agent_radius: f32,
+    ) -> Result<Self, PathError> {
+        let start_node = tree.locate(start);
+        let goal_node = tree.locate(goal);
+
+        if start_node.covered() {
+            return Err(PathError::StartCovered);
+        }
+
+        if goal_node.covered() {
+            return Err(PathError::EndCovered);
+        }
+
+        let mut planner = Self {
+            start,
+            start_node: start_node.index(),
+            goal,
+            goal_node: goal_node.index(),
+            agent_radius,
+            last: start_node.index(),
+            km: 0.0,
+            g: SecondaryMap::new(),
+            rhs: SecondaryMap::new(),
+            open: BinaryHeap::new(),
+            queued: SecondaryMap::new(),
+            scales: HashMap::default(),
+            blocked: HashSet::default(),
+        };
+
+        planner.rhs.insert(planner.goal_node, 0.0);
+        let key = (OrderedFloat(0.0), OrderedFloat(0.0));
+        planner.enqueue(planner.goal_node, key);
+
+        Ok(planner)
+    }
+
+    /// Moves the start of the path, e.g; as the agent walks along it.
+    pub fn set_start(&mut self, tree: &BSPTree, start: Vec2) -> Result<(), PathError> {
+        let node = tree.locate(start);
+        if node.covered() {
+            return Err(PathError::StartCovered);
+        }
+
+        self.start = start;
+        self.start_node = node.index();
+        Ok(())
+    }
+
+    /// Scales the cost of moving between the adjacent nodes `a` and `b`.
+    ///
+    /// A scale of [f32::INFINITY] makes the edge impassable. Scales below 1
+    /// are clamped to 1 to keep the search admissible.
+    pub fn set_edge_cost(&mut self, portals: &Portals, a: NodeIndex, b: NodeIndex, scale: f32) {
+        let scale = scale.max(1.0);
+        let old = self.scales.insert(edge_key(a, b), scale).unwrap_or(1.0);
+        if old != scale {
+            self.changed(portals, &[a, b]);
+        }
+    }
+
+    /// Returns the cost scale of the edge between `a` and `b`
+    pub fn edge_cost(&self, a: NodeIndex, b: NodeIndex) -> f32 {
+        self.scales.get(&edge_key(a, b)).copied().unwrap_or(1.0)
+    }
+
+    /// Reports that the cost or availability of `portal` has changed, e.g; when
+    /// a door is opened or closed.
+    ///
+    /// Must be called after the change has been made to `portals`.
+    pub fn portal_changed(&mut self, portals: &Portals, portal: PortalRef) {
+        self.changed(portals, &[portal.src(), portal.dst()]);
+    }
+
+    /// Blocks or unblocks all movement through `node`, e.g; a dynamic
+    /// obstacle.
+    pub fn set_blocked(&mut self, portals: &Portals, node: NodeIndex, blocked: bool) {
+        let changed = if blocked {
+            self.blocked.insert(node)
+        } else {
+            self.blocked.remove(&node)
+        };
+
+        if changed {
+            let mut affected: SmallVec<[NodeIndex; 8]> = neighbours(portals, node).collect();
+            affected.push(node);
+            self.changed(portals, &affected);
+        }
+    }
+
+    /// Returns true if `node` is blocked
+    pub fn is_blocked(&self, node: NodeIndex) -> bool {
+        self.blocked.contains(&node)
+    }
+
+    /// Repairs the search and returns the current path from the start to the
+    /// goal.
+    pub fn path(&mut self, portals: &Portals) -> Result<Path, PathError> {
+        self.compute(portals);
+
+        if self.g(self.start_node).is_infinite() {
+            return Err(PathError::NoRoute);
+        }
+
+        let mut path = Path::new();
+        path.push(WayPoint::new(self.start, self.start_node, None));
+
+        let mut current = self.start_node;
+        // Every step strictly decreases g, bound it nonetheless
+        for _ in 0..=self.g.len() {
+            if current == self.goal_node {
+                break;
+            }
+
+            let (portal, next, _) = self
+                .successors(portals, current)
+                .map(|(portal, next, cost)| (portal, next, cost + self.g(next)))
+                .min_by(|a, b| a.2.total_cmp(&b.2))
+                .filter(|(_, _, cost)| cost.is_finite())
+                .ok_or(PathError::NoRoute)?;
+
+            let prev = path.last().expect("Path is not empty").point();
+            let towards = if next == self.goal_node {
+                self.goal
+            } else {
+                center(portals, next)
+            };
+
+            let point = portal.clip(prev, towards, self.agent_radius);
+            path.push(WayPoint::new(point, next, Some(portal.portal_ref())));
+            current = next;
+        }
+
+        if current != self.goal_node {
+            return Err(PathError::NoRoute);
+        }
+
+        path.push(WayPoint::new(self.goal, self.goal_node, None));
+
+        shorten(portals, &mut path, self.agent_radius);
+        resolve_clip(portals, &mut path, self.agent_radius);
+
+        Ok(path)
+    }
+
+    /// Get the planner's start.
+    pub fn start(&self) -> Vec2 {
+        self.start
+    }
+
+    /// Get the planner's goal.
+    pub fn goal(&self) -> Vec2 {
+        self.goal
+    }
+
+    fn g(&self, node: NodeIndex) -> f32 {
+        self.g.get(node).copied().unwrap_or(f32::INFINITY)
+    }
+
+    fn rhs(&self, node: NodeIndex) -> f32 {
+        self.rhs.get(node).copied().unwrap_or(f32::INFINITY)
+    }
+
+    fn heuristic(&self, portals: &Portals, node: NodeIndex) -> f32 {
+        center(portals, self.start_node).distance(center(portals, node))
+    }
+
+    fn key(&self, portals: &Portals, node: NodeIndex) -> Key {
+        let min = self.g(node).min(self.rhs(node));
+        (
+            OrderedFloat(min + self.heuristic(portals, node) + self.km),
+            OrderedFloat(min),
+        )
+    }
+
+    fn enqueue(&mut self, node: NodeIndex, key: Key) {
+        self.queued.insert(node, key);
+        self.open.push(Reverse((key, node)));
+    }
+
+    /// Returns the lowest valid entry of the open set without removing it
+    fn top(&mut self) -> Option<(Key, NodeIndex)> {
+        while let Some(&Reverse((key, node))) = self.open.peek() {
+            if self.queued.get(node) == Some(&key) {
+                return Some((key, node));
+            }
+
+            // Stale entry
+            self.open.pop();
+        }
+
+        None
+    }
+
+    /// Returns the passable edges from `node` and their costs
+    fn successors<'a>(
+        &'a self,
+        portals: &'a Portals,
+        node: NodeIndex,
+    ) -> impl Iterator<Item = (Portal<'a>, NodeIndex, f32)> + 'a {
+        let src = center(portals, node);
+        let blocked = self.blocked.contains(&node);
+
+        portals.get(node).filter_map(move |portal| {
+            let dst = portal.dst();
+            if blocked || dst == node || self.blocked.contains(&dst) {
+                return None;
+            }
+
+            let face = portal.apply_margin(self.agent_radius);
+            if portal.clearance() < self.agent_radius {
+                return None;
+            }
+
+            let mid = face.midpoint();
+            let cost = (src.distance(mid) + mid.distance(center(portals, dst)))
+                * self.edge_cost(node, dst)
+                + portals.cost(portal.portal_ref());
+
+            Some((portal, dst, cost))
+        })
+    }
+
+    fn update_vertex(&mut self, portals: &Portals, node: NodeIndex) {
+        if node != self.goal_node {
+            let rhs = self
+                .successors(portals, node)
+                .map(|(_, next, cost)| cost + self.g(next))
+                .fold(f32::INFINITY, f32::min);
+
+            self.rhs.insert(node, rhs);
+        }
+
+        self.queued.remove(node);
+        if self.g(node) != self.rhs(node) {
+            let key = self.key(portals, node);
+            self.enqueue(node, key);
+        }
+    }
+
+    fn changed(&mut self, portals: &Portals, nodes: &[NodeIndex]) {
+        self.km += center(portals, self.last).distance(center(portals, self.start_node));
+        self.last = self.start_node;
+
+        for &node in nodes {
+            self.update_vertex(portals, node);
+        }
+    }
+
+    fn compute(&mut self, portals: &Portals) {
+        while let Some((old_key, node)) = self.top() {
+            let start = self.start_node;
+            if old_key >= self.key(portals, start) && self.rhs(start) == self.g(start) {
+                break;
+            }
+
+            let new_key = self.key(portals, node);
+            if old_key < new_key {
+                self.enqueue(node, new_key);
+                continue;
+            }
+
+            self.queued.remove(node);
+
+            let (g, rhs) = (self.g(node), self.rhs(node));
+            if g > rhs {
+                self.g.insert(node, rhs);
+            } else {
+                self.g.insert(node, f32::INFINITY);
+                self.update_vertex(portals, node);
+            }
+
+            let preds: SmallVec<[NodeIndex; 8]> = neighbours(portals, node).collect();
+            for pred in preds {
+                self.update_vertex(portals, pred);
+            }
+        }
+    }
+}
+
+/// Returns the nodes which may move into `node`
+fn neighbours(portals: &Portals, node: NodeIndex) -> impl Iterator<Item = NodeIndex> + '_ {
+    portals
+        .incoming(node)
+        .map(|portal| portal.dst())
+        .filter(move |&dst| dst != node)
+}
+
+fn edge_key(a: NodeIndex, b: NodeIndex) -> (NodeIndex, NodeIndex) {
+    if a < b {
+        (a, b)
+    } else {
+        (b, a)
+    }
+}
@@ -0,0 +1,44 @@
+use core::fmt::Display;
+
+use glam::Vec2;
+
+/// Describes why a path query failed
+#[derive(Debug, Clone, Copy, PartialEq)]
+pub enum PathError {
+    /// The start point is inside an obstacle
+    StartCovered,
+    /// The end point is inside an obstacle
+    EndCovered,
+    /// The start and end are not connected
+    NoRoute,
+    /// The end could only be reached through portals too narrow for the agent.
+    /// `found` is the width of the widest route to the end, which is the
+    /// largest agent diameter that would fit.
+    PortalTooNarrow { needed: f32, found: f32 },
+    /// There is no navigation data to search
+    EmptyScene,
+    /// The point is outside the bounds of the scene.
+    /// See [SearchInfo::out_of_bounds](crate::SearchInfo::out_of_bounds).
+    OutOfBounds { point: Vec2 },
+}
+
+impl Display for PathError {
+    fn fmt(&self, f: &mut core::fmt::Formatter<'_>) -> core::fmt::Result {
+        match self {
+            PathError::StartCovered => write!(f, "Start point is covered"),
+            PathError::EndCovered => write!(f, "End point is covered"),
+            PathError::NoRoute => write!(f, "No route between start and end"),
+            PathError::PortalTooNarrow { needed, found } => write!(
+                f,
+                "Portals are too narrow for agent. Needed {}, widest route was {}",
+                needed, found
+            ),
+            PathError::EmptyScene => write!(f, "Scene is empty"),
+            PathError::OutOfBounds { point } => {
+                write!(f, "Point {} is outside the bounds of the scene", point)
+            }
+        }
+    }
+}
+
+impl core::error::Error for PathError {}
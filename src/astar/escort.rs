@@ -0,0 +1,170 @@
+use alloc::{vec, vec::Vec};
+
+use glam::Vec2;
+
+use crate::{BSPTree, Portals, TOLERANCE};
+
+use super::{Path, SearchInfo, WayPoint};
+
+/// A path for a leader and a follower walking beside it, see [escort].
+#[derive(Debug, Clone, Default)]
+pub struct EscortPath {
+    leader: Path,
+    follower: Path,
+    offsets: Vec<f32>,
+}
+
+impl EscortPath {
+    /// Get the escort path's leader path.
+    pub fn leader(&self) -> &Path {
+        &self.leader
+    }
+
+    /// Get the escort path's follower path.
+    /// Each waypoint corresponds to the leader waypoint of the same index.
+    pub fn follower(&self) -> &Path {
+        &self.follower
+    }
+
+    /// Returns the lateral offset of the follower at each waypoint, which is
+    /// either the requested offset, or 0 where the follower falls back to
+    /// walking in single file.
+    pub fn offsets(&self) -> &[f32] {
+        &self.offsets
+    }
+
+    /// Returns true if the follower walks in single file behind the leader at
+    /// waypoint `index`
+    pub fn is_single_file(&self, index: usize) -> bool {
+        self.offsets.get(index).is_some_and(|&offset| offset == 0.0)
+    }
+
+    /// Returns the leader and follower paths
+    pub fn into_inner(self) -> (Path, Path) {
+        (self.leader, self.follower)
+    }
+}
+
+/// Plans the path of a follower at a lateral `offset` of `leader`, e.g; an
+/// escort walking beside its charge. Positive offsets are to the left of the
+/// direction of travel.
+///
+/// The follower crosses the same portals as the leader, which keeps the pair
+/// together at junctions. Where a portal or the space around a waypoint is
+/// too narrow to fit both agents of [SearchInfo::agent_radius] side by side,
+/// the follower falls back to single file, and walks through the leader's
+/// waypoint. The same holds where the follower would stand in a node the
+/// leader may not enter, see [SearchInfo::allowed_areas].
+pub fn escort(
+    tree: &BSPTree,
+    portals: &Portals,
+    leader: Path,
+    offset: f32,
+    info: &SearchInfo,
+) -> EscortPath {
+    let corridor = leader.corridor(tree, portals);
+    let radius = info.agent_radius;
+    let points = leader.points();
+
+    let mut offsets = points
+        .iter()
+        .enumerate()
+        .map(|(i, waypoint)| {
+            let side = side(points, i);
+            let lateral = waypoint.point() + side * offset;
+
+            // The follower passes the portal beside the leader
+            let beside = waypoint.portal().is_none_or(|portal| {
+                let (p1, p2) = portals.from_ref(portal).apply_margin(radius).into_tuple();
+                let t = (lateral - p1).dot(p2 - p1) / (p2 - p1).length_squared();
+                (0.0..=1.0).contains(&t)
+            });
+
+            let located = tree.locate(lateral);
+            let fits = beside
+                && corridor.contains(lateral)
+                && !located.covered()
+                && info.allows(located.index())
+                && tree
+                    .closest_face(lateral)
+                    .map(|(_, p)| p.distance(lateral) >= radius)
+                    .unwrap_or(true);
+
+            if fits && side != Vec2::ZERO {
+                offset
+            } else {
+                0.0
+            }
+        })
+        .collect::<Vec<_>>();
+
+    let follower_point =
+        |i: usize, offsets: &[f32]| points[i].point() + side(points, i) * offsets[i];
+
+    // Fall back to single file where the follower would cut through a wall
+    // between waypoints. The leader's own segments are always free.
+    let mut changed = true;
+    while changed {
+        changed = false;
+        for i in 1..points.len() {
+            let (a, b) = (follower_point(i - 1, &offsets), follower_point(i, &offsets));
+            let rel = b - a;
+            let blocked = rel.length() > TOLERANCE && tree.raycast(a, rel, rel.length()).is_some();
+            if blocked && (offsets[i - 1] != 0.0 || offsets[i] != 0.0) {
+                offsets[i - 1] = 0.0;
+                offsets[i] = 0.0;
+                changed = true;
+            }
+        }
+    }
+
+    let mut follower = Path::new();
+    for (i, waypoint) in points.iter().enumerate() {
+        let point = follower_point(i, &offsets);
+        let node = match waypoint.portal() {
+            Some(_) => waypoint.node(),
+            None => tree.locate(point).index(),
+        };
+
+        let mut follower_waypoint = WayPoint::new(point, node, waypoint.portal());
+        if let Some(link) = waypoint.link() {
+            follower_waypoint = follower_waypoint.with_link(link);
+        }
+        follower.push(follower_waypoint);
+    }
+
+    EscortPath {
+        leader,
+        follower,
+        offsets,
+    }
+}
+
+/// Offsets the whole path when there is no geometry to collide with
+pub(crate) fn escort_open(leader: Path, offset: f32) -> EscortPath {
+    let points = leader.points();
+    let follower = Path::from_points(
+        (0..points.len())
+            .map(|i| {
+                WayPoint::new(
+                    points[i].point() + side(points, i) * offset,
+                    points[i].node(),
+                    None,
+                )
+            })
+            .collect::<Vec<_>>(),
+    );
+
+    EscortPath {
+        offsets: vec![offset; points.len()],
+        leader,
+        follower,
+    }
+}
+
+/// Returns the direction to the left of travel at waypoint `i`
+fn side(points: &[WayPoint], i: usize) -> Vec2 {
+    let prev = points[i.saturating_sub(1)].point();
+    let next = points[(i + 1).min(points.len() - 1)].point();
+    (next - prev).normalize_or_zero().perp()
+}
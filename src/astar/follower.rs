@@ -0,0 +1,88 @@
+use glam::Vec2;
+use smallvec::SmallVec;
+
+use crate::{Path, Portals, WayPoint};
+
+use super::{resolve_clip, shorten};
+
+/// Follows a [Path] waypoint by waypoint as an agent moves along it.
+#[derive(Debug, Clone, Default)]
+pub struct PathFollower {
+    path: Path,
+    current: usize,
+}
+
+impl PathFollower {
+    /// Starts following `path`.
+    /// The first waypoint is the start position and is considered reached.
+    pub fn new(path: Path) -> Self {
+        let current = path.len().min(1);
+        Self { path, current }
+    }
+
+    /// Get a reference to the followed path.
+    pub fn path(&self) -> &Path {
+        &self.path
+    }
+
+    /// Returns the index of the next waypoint to reach
+    pub fn index(&self) -> usize {
+        self.current
+    }
+
+    /// Returns the next waypoint to reach
+    pub fn current(&self) -> Option<&WayPoint> {
+        self.path.get(self.current)
+    }
+
+    /// Returns true if the last waypoint has been reached
+    pub fn is_finished(&self) -> bool {
+        self.current >= self.path.len()
+    }
+
+    /// Advances past all waypoints within `radius` of `position` and returns
+    /// the next point to steer towards.
+    pub fn advance(&mut self, position: Vec2, radius: f32) -> Option<Vec2> {
+        while let Some(waypoint) = self.current() {
+            if waypoint.distance(position) > radius {
+                return Some(waypoint.point());
+            }
+
+            self.current += 1;
+        }
+
+        None
+    }
+
+    /// Re-runs the funnel over the next `n_segments` segments as seen from the
+    /// agent's current `position`.
+    ///
+    /// Allows late corrections, e.g; after the agent was pushed aside by local
+    /// avoidance, without replanning the whole path.
+    pub fn resmooth_ahead(
+        &mut self,
+        portals: &Portals,
+        position: Vec2,
+        agent_radius: f32,
+        n_segments: usize,
+    ) {
+        let end = (self.current + n_segments).min(self.path.len());
+        if self.current >= end {
+            return;
+        }
+
+        let window = &mut self.path[self.current..end];
+
+        let mut points: SmallVec<[WayPoint; 8]> = SmallVec::with_capacity(window.len() + 1);
+        points.push(WayPoint::new(position, window[0].node, None));
+        points.extend_from_slice(window);
+
+        shorten(portals, &mut points, agent_radius);
+        resolve_clip(portals, &mut points, agent_radius);
+
+        window
+            .iter_mut()
+            .zip(&points[1..])
+            .for_each(|(dst, src)| dst.point = src.point);
+    }
+}
@@ -1,20 +1,49 @@
-use core::slice;
-use std::{
-    collections::{BinaryHeap, HashSet},
+use alloc::{collections::BinaryHeap, vec};
+use core::{
+    any::Any,
+    fmt::Debug,
     ops::{Deref, DerefMut, RangeBounds},
+    slice,
+    time::Duration,
 };
 
 use glam::Vec2;
+use ordered_float::OrderedFloat;
 use slotmap::{secondary::Entry, Key, SecondaryMap};
 use smallvec::{Drain, SmallVec};
 
-use crate::{BSPTree, NodeIndex, Portal, PortalRef, Portals, TOLERANCE};
+use crate::{
+    util::{Deadline, HashSet},
+    BSPTree, LinkId, NodeIndex, OffMeshLink, Portal, PortalRef, Portals, TOLERANCE,
+};
+
+mod ara;
+mod bidirectional;
+mod corridor;
+mod diagnostic;
+mod dstar;
+mod error;
+mod escort;
+mod follower;
+mod observer;
+mod region;
+
+pub use ara::*;
+pub use corridor::*;
+pub use diagnostic::*;
+pub use dstar::*;
+pub use error::*;
+pub use escort::*;
+pub use follower::*;
+pub use observer::*;
+pub use region::*;
 
 #[derive(Debug, Clone, Copy, PartialEq)]
 pub struct WayPoint {
     point: Vec2,
     node: NodeIndex,
     portal: Option<PortalRef>,
+    link: Option<LinkId>,
 }
 
 impl Deref for WayPoint {
@@ -31,23 +60,48 @@ impl WayPoint {
             point,
             node,
             portal,
+            link: None,
         }
     }
 
+    /// Marks the segment to the next waypoint as traversing an off-mesh link
+    pub fn with_link(mut self, link: LinkId) -> Self {
+        self.link = Some(link);
+        self
+    }
+
     /// Get the way point's point.
     pub fn point(&self) -> Vec2 {
         self.point
     }
 
+    /// Get the way point's node.
+    pub fn node(&self) -> NodeIndex {
+        self.node
+    }
+
     /// Get the way point's portal.
     pub fn portal(&self) -> Option<PortalRef> {
         self.portal
     }
+
+    /// Returns the user data of the crossed portal, see [Portals::set_data]
+    pub fn data<'p, T: Any>(&self, portals: &'p Portals) -> Option<&'p T> {
+        portals.data(self.portal?)
+    }
+
+    /// Returns the off-mesh link which is traversed from this waypoint to the
+    /// next, e.g; to trigger a jump animation.
+    pub fn link(&self) -> Option<LinkId> {
+        self.link
+    }
 }
 
 #[derive(Debug, Clone, Default)]
 pub struct Path {
     points: SmallVec<[WayPoint; 8]>,
+    partial: bool,
+    truncated: bool,
 }
 
 impl<'a> IntoIterator for &'a Path {
@@ -68,9 +122,25 @@ impl Path {
     pub fn from_points(points: impl Into<SmallVec<[WayPoint; 8]>>) -> Self {
         Self {
             points: points.into(),
+            partial: false,
+            truncated: false,
         }
     }
 
+    /// Returns true if the path does not reach the goal, but ends at the
+    /// reachable point closest to it.
+    /// See [SearchInfo::allow_partial].
+    pub fn is_partial(&self) -> bool {
+        self.partial
+    }
+
+    /// Returns true if the search was cut short by exceeding
+    /// [SearchInfo::max_expansions] or [SearchInfo::max_duration].
+    /// Truncated paths are always partial.
+    pub fn is_truncated(&self) -> bool {
+        self.truncated
+    }
+
     /// Get a reference to the path's points.
     pub fn points(&self) -> &[WayPoint] {
         self.points.as_ref()
@@ -84,6 +154,12 @@ impl Path {
         self.points.append(&mut other.points)
     }
 
+    /// Returns the leaf regions and portal segments the path passes through.
+    /// See [Corridor].
+    pub fn corridor(&self, tree: &BSPTree, portals: &Portals) -> Corridor {
+        Corridor::new(tree, portals, self)
+    }
+
     /// Creates a path using the euclidian path
     pub fn euclidian(start: Vec2, end: Vec2) -> Path {
         Path::from_points(vec![
@@ -93,12 +169,55 @@ impl Path {
     }
 
     pub fn clear(&mut self) {
-        self.points.clear()
+        self.points.clear();
+        self.partial = false;
+        self.truncated = false;
     }
 
     pub fn drain<R: RangeBounds<usize>>(&mut self, range: R) -> Drain<'_, [WayPoint; 8]> {
         self.points.drain(range)
     }
+
+    /// Snaps waypoints lying within `epsilon` of their portal's line exactly
+    /// onto it, clamped to inside of the portal with `margin` applied.
+    ///
+    /// Removes the slight overshoots left by clipping and shortening, which
+    /// may otherwise place waypoints fractionally inside the neighbouring
+    /// solid region.
+    pub fn snap_to_portals(&mut self, portals: &Portals, margin: f32, epsilon: f32) {
+        for waypoint in &mut self.points {
+            let portal = match waypoint.portal {
+                Some(portal) => portals.from_ref(portal),
+                None => continue,
+            };
+
+            let face = portal.apply_margin(margin);
+            let normal = portal.normal();
+            let dist = (waypoint.point - face.vertices[0]).dot(normal);
+            if dist.abs() > epsilon {
+                continue;
+            }
+
+            let projected = waypoint.point - normal * dist;
+            waypoint.point = face.closest_point(projected);
+        }
+    }
+}
+
+impl Path {
+    /// Moves waypoints on portals away from walls, see
+    /// [SearchInfo::clearance_weight]
+    fn keep_clear(&mut self, portals: &Portals, info: &SearchInfo) {
+        if info.clearance_weight <= 0.0 {
+            return;
+        }
+
+        for waypoint in &mut self.points {
+            if let Some(portal) = waypoint.portal {
+                waypoint.point = info.keep_clear(&portals.from_ref(portal), waypoint.point);
+            }
+        }
+    }
 }
 
 impl Deref for Path {
@@ -123,58 +242,432 @@ struct Backtrace<'a> {
     point: Vec2,
     portal: Option<Portal<'a>>,
     prev: Option<NodeIndex>,
+    // The node from whose point this node is reached in a straight line.
+    // Always `prev` unless shortcut by [SearchKind::ThetaStar]
+    anchor: NodeIndex,
     start_cost: f32,
     total_cost: f32,
+    // The sum of portal costs from the start, see [Portals::cost]
+    penalty: f32,
+    // The off-mesh link and its entry point, if the node was reached by one
+    link: Option<(LinkId, Vec2)>,
 }
 
 impl<'a> Backtrace<'a> {
-    fn start(node: NodeIndex, point: Vec2, heuristic: f32) -> Self {
+    /// `heuristic` is inflated by `weight`, see [SearchInfo::heuristic_weight]
+    fn start(node: NodeIndex, point: Vec2, heuristic: f32, weight: f32) -> Self {
         Self {
             node,
             point,
             portal: None,
             prev: None,
+            anchor: node,
             start_cost: 0.0,
-            total_cost: heuristic,
+            total_cost: heuristic * weight,
+            penalty: 0.0,
+            link: None,
         }
     }
 
-    fn new(portal: Portal<'a>, point: Vec2, prev: &Backtrace, heuristic: f32) -> Self {
-        let start_cost = prev.start_cost + point.distance(prev.point);
+    /// `cost` is the cost per unit of distance travelled from `prev`, and
+    /// `penalty` the cost of crossing `portal`.
+    /// `heuristic` is inflated by `weight`, see [SearchInfo::heuristic_weight]
+    #[allow(clippy::too_many_arguments)]
+    fn new(
+        portal: Portal<'a>,
+        point: Vec2,
+        prev: &Backtrace,
+        heuristic: f32,
+        weight: f32,
+        cost: f32,
+        penalty: f32,
+    ) -> Self {
+        let start_cost = prev.start_cost + point.distance(prev.point) * cost + penalty;
         Self {
             node: portal.dst(),
             portal: Some(portal),
             point,
             prev: Some(prev.node),
+            anchor: prev.node,
             start_cost,
-            total_cost: start_cost + heuristic,
+            total_cost: start_cost + heuristic * weight,
+            penalty: prev.penalty + penalty,
+            link: None,
+        }
+    }
+
+    /// Reach the exit of an off-mesh link from `prev` through its entry.
+    /// `cost` is the cost per unit of distance travelled from `prev` to the
+    /// entry.
+    fn link(
+        id: LinkId,
+        link: &OffMeshLink,
+        prev: &Backtrace,
+        heuristic: f32,
+        weight: f32,
+        cost: f32,
+    ) -> Self {
+        let start_cost = prev.start_cost + link.entry.distance(prev.point) * cost + link.cost;
+        Self {
+            node: link.dst,
+            portal: None,
+            point: link.exit,
+            prev: Some(prev.node),
+            // Nothing may be seen through a link
+            anchor: link.dst,
+            start_cost,
+            total_cost: start_cost + heuristic * weight,
+            penalty: prev.penalty + link.cost,
+            link: Some((id, link.entry)),
+        }
+    }
+
+    /// Reach this node in a straight line from `anchor` instead if cheaper.
+    /// The line crosses the same portals, and pays for them.
+    fn reanchor(&mut self, anchor: &Backtrace, cost: f32) {
+        let start_cost = anchor.start_cost
+            + self.point.distance(anchor.point) * cost
+            + (self.penalty - anchor.penalty);
+        if start_cost < self.start_cost {
+            self.total_cost += start_cost - self.start_cost;
+            self.start_cost = start_cost;
+            self.anchor = anchor.node;
         }
     }
 }
 
 // Order by lowest total_cost
 impl<'a> PartialOrd for Backtrace<'a> {
-    fn partial_cmp(&self, other: &Self) -> Option<std::cmp::Ordering> {
-        other.total_cost.partial_cmp(&self.total_cost)
+    fn partial_cmp(&self, other: &Self) -> Option<core::cmp::Ordering> {
+        Some(self.cmp(other))
     }
 }
 
 impl<'a> Eq for Backtrace<'a> {}
 
 impl<'a> Ord for Backtrace<'a> {
-    fn cmp(&self, other: &Self) -> std::cmp::Ordering {
+    fn cmp(&self, other: &Self) -> core::cmp::Ordering {
         other
             .total_cost
             .partial_cmp(&self.total_cost)
-            .unwrap_or(std::cmp::Ordering::Equal)
+            .unwrap_or(core::cmp::Ordering::Equal)
+    }
+}
+
+/// Determines how the search expands nodes
+#[derive(Default, Debug, Clone, Copy, PartialEq, Eq)]
+pub enum SearchKind {
+    /// Plain A*, where costs are measured between consecutive portal
+    /// crossings
+    #[default]
+    AStar,
+    /// Any-angle search which measures costs along straight lines to the
+    /// furthest visible ancestor, which yields a route that does not hug the
+    /// portals
+    ThetaStar,
+    /// Searches from both the start and the goal simultaneously, which
+    /// expands fewer nodes along long corridors.
+    /// Only applies to searches for a single goal without a search budget.
+    /// Falls back to [Self::AStar] to produce partial paths and detailed
+    /// errors if no path is found.
+    Bidirectional,
+}
+
+/// Determines what quantity a search minimizes
+#[derive(Default, Debug, Clone, Copy, PartialEq, Eq)]
+pub enum Optimize {
+    /// Find the shortest path
+    #[default]
+    Distance,
+    /// Find the fastest path, using [SearchInfo::node_speeds]
+    Time,
+}
+
+/// Determines how a search treats points outside the bounds of the scene,
+/// see [BSPTree::bounds]
+#[derive(Default, Debug, Clone, Copy, PartialEq, Eq)]
+pub enum BoundsPolicy {
+    /// The leaves at the edge of the scene extend past the bounds, and points
+    /// outside are located in them
+    #[default]
+    Extend,
+    /// Move points outside onto the closest point of the bounds
+    Clamp,
+    /// Fail with [PathError::OutOfBounds], e.g; to surface teleport bugs
+    Error,
+}
+
+/// Decides the extra cost of crossing a portal, in units of distance, or
+/// None if the agent may not cross it, e.g; from the portal's user data.
+/// See [Portals::data].
+#[derive(Clone, Copy)]
+pub struct EdgeCost<'a>(pub &'a dyn Fn(&Portals, Portal) -> Option<f32>);
+
+impl<'a> Debug for EdgeCost<'a> {
+    fn fmt(&self, f: &mut core::fmt::Formatter<'_>) -> core::fmt::Result {
+        f.write_str("EdgeCost")
     }
 }
 
-#[derive(Default, Debug, Clone, Copy, PartialEq)]
-pub struct SearchInfo {
+impl<'a> PartialEq for EdgeCost<'a> {
+    fn eq(&self, other: &Self) -> bool {
+        core::ptr::addr_eq(self.0, other.0)
+    }
+}
+
+/// The area of nodes which have not been assigned one, see
+/// [SearchInfo::node_areas].
+pub const DEFAULT_AREA: u32 = 1;
+
+#[derive(Debug, Clone, Copy, PartialEq)]
+pub struct SearchInfo<'a> {
     pub agent_radius: f32,
+    /// If the goal is unreachable, return a path to the reachable point
+    /// closest to the goal instead of failing.
+    pub allow_partial: bool,
+    pub optimize: Optimize,
+    /// The speed of the agent in each node, e.g; slow in water and fast on
+    /// roads. Nodes not present move at a speed of 1.
+    /// Only used for [Optimize::Time].
+    pub node_speeds: Option<&'a SecondaryMap<NodeIndex, f32>>,
+    /// Multiplies the cost of travelling through each node, e.g; to avoid mud
+    /// or shallow water. Nodes not present have a multiplier of 1.
+    pub node_costs: Option<&'a SecondaryMap<NodeIndex, f32>>,
+    /// The area bitmask of each node, e.g; water, lava or road. The meaning
+    /// of each bit is up to the user. Nodes not present have the area
+    /// [DEFAULT_AREA].
+    pub node_areas: Option<&'a SecondaryMap<NodeIndex, u32>>,
+    /// The areas the agent may enter, e.g; a hovercraft may cross water
+    /// while infantry may not. A node is traversable if its area shares a bit
+    /// with the allowed areas. Defaults to all areas.
+    pub allowed_areas: u32,
+    /// Nodes which may not be entered by this query, e.g; areas on fire or
+    /// reserved by another squad, without mutating the navigation data.
+    pub blocked_nodes: Option<&'a HashSet<NodeIndex>>,
+    /// Inflates the heuristic, trading optimality for speed.
+    /// A weight of `w > 1` yields paths at most `w` times longer than the
+    /// optimal path while expanding fewer nodes. Defaults to 1.
+    pub heuristic_weight: f32,
+    pub kind: SearchKind,
+    /// Snap waypoints within [TOLERANCE] of a portal onto it.
+    /// See [Path::snap_to_portals].
+    pub snap_waypoints: bool,
+    /// Stop the search after expanding this many nodes.
+    /// The path to the point closest to the goal is then returned, see
+    /// [Path::is_truncated].
+    pub max_expansions: Option<usize>,
+    /// Stop the search after running for this long.
+    /// The path to the point closest to the goal is then returned, see
+    /// [Path::is_truncated]. Ignored without the `std` feature, and on wasm32
+    /// where there is no clock.
+    pub max_duration: Option<Duration>,
+    /// Accept reaching any point within this distance of the goal, even if
+    /// in a neighbouring node. The path then ends at that point.
+    /// Allows approaching goals inside of geometry.
+    pub goal_radius: f32,
+    /// The clearance kept from corners when the path turns around them,
+    /// e.g; a towed trailer which needs wider turns.
+    /// Defaults to [Self::agent_radius], which is always used for deciding if
+    /// a portal is wide enough to pass.
+    pub corner_radius: Option<f32>,
+    /// Adds to or forbids crossing each portal on top of [Portals::cost].
+    /// Negative costs are clamped to 0.
+    /// [SearchKind::Bidirectional] falls back to [SearchKind::AStar].
+    pub edge_cost: Option<EdgeCost<'a>>,
+    /// The cost per radian of heading change at each portal crossing, which
+    /// favors straighter routes, e.g; for vehicles.
+    /// Nodes are still expanded once, regardless of heading, which means the
+    /// penalty shapes the route rather than guaranteeing the straightest one.
+    /// [SearchKind::Bidirectional] falls back to [SearchKind::AStar].
+    pub turn_penalty: f32,
+    /// Penalizes crossing portals close to walls, so agents walk down the
+    /// middle of corridors instead of grazing them.
+    /// Each crossing costs this many units of distance per unit of clearance
+    /// lacking to the middle of the portal, and waypoints are kept at least
+    /// `min(clearance_weight, 1)` of the way from the walls to the middle.
+    /// [SearchKind::Bidirectional] falls back to [SearchKind::AStar].
+    pub clearance_weight: f32,
+    /// How the start and goals are treated if they are outside the bounds of
+    /// the scene.
+    pub out_of_bounds: BoundsPolicy,
+    /// Points within this distance of the bounds are considered inside, e.g;
+    /// to allow walking around the outermost obstacles.
+    pub bounds_margin: f32,
 }
 
+impl<'a> Default for SearchInfo<'a> {
+    fn default() -> Self {
+        Self {
+            agent_radius: 0.0,
+            allow_partial: false,
+            optimize: Optimize::default(),
+            node_speeds: None,
+            node_costs: None,
+            node_areas: None,
+            allowed_areas: u32::MAX,
+            blocked_nodes: None,
+            heuristic_weight: 1.0,
+            kind: SearchKind::default(),
+            snap_waypoints: false,
+            max_expansions: None,
+            max_duration: None,
+            goal_radius: 0.0,
+            corner_radius: None,
+            edge_cost: None,
+            turn_penalty: 0.0,
+            clearance_weight: 0.0,
+            out_of_bounds: BoundsPolicy::default(),
+            bounds_margin: 0.0,
+        }
+    }
+}
+
+impl<'a> SearchInfo<'a> {
+    /// Returns the cost per unit of distance travelled through `node`
+    fn traversal_cost(&self, node: NodeIndex) -> f32 {
+        let multiplier = self
+            .node_costs
+            .and_then(|costs| costs.get(node).copied())
+            .unwrap_or(1.0);
+
+        match (self.optimize, self.node_speeds) {
+            (Optimize::Time, Some(speeds)) => multiplier / speeds.get(node).copied().unwrap_or(1.0),
+            _ => multiplier,
+        }
+    }
+
+    /// Returns true if the agent may enter `node`
+    fn allows(&self, node: NodeIndex) -> bool {
+        if self
+            .blocked_nodes
+            .is_some_and(|blocked| blocked.contains(&node))
+        {
+            return false;
+        }
+
+        let area = self
+            .node_areas
+            .and_then(|areas| areas.get(node).copied())
+            .unwrap_or(DEFAULT_AREA);
+
+        area & self.allowed_areas != 0
+    }
+
+    /// Returns the cost of crossing `portal`, or None if it may not be
+    /// crossed
+    fn portal_cost(&self, portals: &Portals, portal: Portal) -> Option<f32> {
+        let extra = match self.edge_cost {
+            Some(EdgeCost(cost)) => (cost)(portals, portal)?.max(0.0),
+            None => 0.0,
+        };
+
+        Some(portals.cost(portal.portal_ref()) + extra)
+    }
+
+    /// Returns the cost of turning at `current` towards `next`
+    fn turn_cost(
+        &self,
+        backtraces: &SecondaryMap<NodeIndex, Backtrace>,
+        current: &Backtrace,
+        next: Vec2,
+    ) -> f32 {
+        if self.turn_penalty <= 0.0 {
+            return 0.0;
+        }
+
+        let prev = match current.prev {
+            Some(prev) => backtraces[prev].point,
+            None => return 0.0,
+        };
+
+        let incoming = (current.point - prev).normalize_or_zero();
+        let outgoing = (next - current.point).normalize_or_zero();
+        if incoming == Vec2::ZERO || outgoing == Vec2::ZERO {
+            return 0.0;
+        }
+
+        incoming.angle_between(outgoing).abs() * self.turn_penalty
+    }
+
+    /// Returns the clearance kept from the walls at either end of `portal`
+    fn portal_margin(&self, portal: &Portal) -> f32 {
+        let half = portal.face().length() / 2.0;
+        self.corner_radius()
+            .max(half * self.clearance_weight.clamp(0.0, 1.0))
+    }
+
+    /// Moves `p` along `portal` away from the walls at either end, see
+    /// [Self::clearance_weight]
+    fn keep_clear(&self, portal: &Portal, p: Vec2) -> Vec2 {
+        if self.clearance_weight <= 0.0 {
+            return p;
+        }
+
+        let margin = self.portal_margin(portal).min(portal.face().length() / 2.0);
+        let face = portal.apply_margin(margin);
+        if face.length() < f32::EPSILON {
+            face.vertices[0]
+        } else {
+            face.closest_point(p)
+        }
+    }
+
+    /// Returns the cost of crossing `portal` at `p` due to the proximity of
+    /// walls
+    fn clearance_cost(&self, portal: &Portal, p: Vec2) -> f32 {
+        if self.clearance_weight <= 0.0 {
+            return 0.0;
+        }
+
+        let half = portal.face().length() / 2.0;
+        let [a, b] = portal.face().vertices;
+        let adjacent = portal.adjacent();
+
+        let clearance = [(a, adjacent[0]), (b, adjacent[1])]
+            .iter()
+            .filter(|(_, adjacent)| *adjacent)
+            .map(|(v, _)| v.distance(p))
+            .fold(half, f32::min);
+
+        (half - clearance).max(0.0) * self.clearance_weight
+    }
+
+    /// Applies [Self::out_of_bounds] to `point`, which returns the point to
+    /// search from or to
+    pub fn bound(&self, tree: &BSPTree, point: Vec2) -> Result<Vec2, PathError> {
+        match self.out_of_bounds {
+            BoundsPolicy::Extend => Ok(point),
+            BoundsPolicy::Clamp => Ok(tree.clamp_to_bounds(point, self.bounds_margin)),
+            BoundsPolicy::Error if tree.in_bounds(point, self.bounds_margin) => Ok(point),
+            BoundsPolicy::Error => Err(PathError::OutOfBounds { point }),
+        }
+    }
+
+    /// Returns the clearance kept from corners
+    fn corner_radius(&self) -> f32 {
+        self.corner_radius.unwrap_or(self.agent_radius)
+    }
+
+    /// Returns the scale which keeps a distance heuristic admissible
+    fn heuristic_scale(&self) -> f32 {
+        let multiplier = self
+            .node_costs
+            .map(|costs| costs.values().copied().fold(1.0, f32::min))
+            .unwrap_or(1.0);
+
+        match (self.optimize, self.node_speeds) {
+            (Optimize::Time, Some(speeds)) => {
+                multiplier / speeds.values().copied().fold(1.0, f32::max)
+            }
+            _ => multiplier,
+        }
+    }
+}
+
+/// Finds a path from `start` to `end`.
+///
+/// On success `path` is cleared and refilled in place, reusing its capacity.
+/// On failure `path` is left untouched.
 pub fn astar<'a, F: Fn(Vec2, Vec2) -> f32>(
     tree: &BSPTree,
     portals: &Portals,
@@ -183,15 +676,96 @@ pub fn astar<'a, F: Fn(Vec2, Vec2) -> f32>(
     heuristic: F,
     info: SearchInfo,
     path: &'a mut Option<Path>,
-) -> Option<&'a mut Path> {
+) -> Result<&'a mut Path, PathError> {
+    astar_any(tree, portals, start, &[end], heuristic, info, path).map(|(_, path)| path)
+}
+
+/// Finds a path from `start` to the first reached of several `goals`.
+///
+/// The heuristic is the minimum over all goals, which means the search
+/// terminates at the nearest reachable goal.
+/// Returns the index of the reached goal.
+pub fn astar_any<'a, F: Fn(Vec2, Vec2) -> f32>(
+    tree: &BSPTree,
+    portals: &Portals,
+    start: Vec2,
+    goals: &[Vec2],
+    heuristic: F,
+    info: SearchInfo,
+    path: &'a mut Option<Path>,
+) -> Result<(usize, &'a mut Path), PathError> {
+    astar_observed(tree, portals, start, goals, heuristic, info, path, &mut ())
+}
+
+/// Same as [astar_any], but reports the progress of the search to `observer`.
+#[allow(clippy::too_many_arguments)]
+pub fn astar_observed<'a, F: Fn(Vec2, Vec2) -> f32>(
+    tree: &BSPTree,
+    portals: &Portals,
+    start: Vec2,
+    goals: &[Vec2],
+    heuristic: F,
+    info: SearchInfo,
+    path: &'a mut Option<Path>,
+    observer: &mut impl SearchObserver,
+) -> Result<(usize, &'a mut Path), PathError> {
+    let result = search(tree, portals, start, goals, heuristic, info, path, observer);
+    if let Ok((_, path)) = &result {
+        diagnostic::report(tree, portals, path);
+    }
+
+    result
+}
+
+#[allow(clippy::too_many_arguments)]
+fn search<'a, F: Fn(Vec2, Vec2) -> f32>(
+    tree: &BSPTree,
+    portals: &Portals,
+    start: Vec2,
+    goals: &[Vec2],
+    heuristic: F,
+    info: SearchInfo,
+    path: &'a mut Option<Path>,
+    observer: &mut impl SearchObserver,
+) -> Result<(usize, &'a mut Path), PathError> {
     let mut open = BinaryHeap::new();
+    let start = info.bound(tree, start)?;
+    let goals = goals
+        .iter()
+        .map(|&goal| info.bound(tree, goal))
+        .collect::<Result<SmallVec<[_; 4]>, _>>()?;
+    let goals = &goals[..];
+
     let start_node = tree.locate(start);
-    let end_node = tree.locate(end);
 
-    // // No path if start or end are covered
-    // if start_node.covered() || end_node.covered() {
-    //     return None;
-    // }
+    // No path if start or end are covered
+    if start_node.covered() {
+        return Err(PathError::StartCovered);
+    }
+
+    if goals.is_empty() {
+        return Err(PathError::NoRoute);
+    }
+
+    let goal_nodes = goals
+        .iter()
+        .map(|&goal| {
+            let node = tree.locate(goal);
+            (node.index(), node.covered())
+        })
+        .collect::<SmallVec<[_; 4]>>();
+
+    // Covered goals may still be approached within the goal radius
+    let accepts_near = info.goal_radius > 0.0;
+
+    let all_covered = goal_nodes.iter().all(|(_, covered)| *covered);
+    if all_covered && !info.allow_partial && !accepts_near {
+        return Err(PathError::EndCovered);
+    }
+
+    // Covered goals can not be reached and would only weaken the heuristic,
+    // unless all goals are covered and the closest partial path is wanted.
+    let active = |i: &usize| all_covered || accepts_near || !goal_nodes[*i].1;
 
     // Find matching start node
     // if let Some(p) = path {
@@ -211,17 +785,75 @@ pub fn astar<'a, F: Fn(Vec2, Vec2) -> f32>(
     // }
 
     let start_node = start_node.index();
-    let end_node = end_node.index();
+
+    let budgeted = info.max_expansions.is_some() || info.max_duration.is_some();
+    if info.kind == SearchKind::Bidirectional
+        && goals.len() == 1
+        && !all_covered
+        && !budgeted
+        && !accepts_near
+        && portals.links().is_empty()
+        && info.allows(goal_nodes[0].0)
+        && info.edge_cost.is_none()
+        && info.turn_penalty <= 0.0
+        && info.clearance_weight <= 0.0
+    {
+        let end = (goals[0], goal_nodes[0].0);
+        let found = bidirectional::bidirectional(
+            portals,
+            (start, start_node),
+            end,
+            &heuristic,
+            info,
+            path,
+            observer,
+        );
+
+        if found {
+            return Ok((0, path.as_mut().expect("Path is filled on success")));
+        }
+
+        let info = SearchInfo {
+            kind: SearchKind::AStar,
+            ..info
+        };
+
+        return search(tree, portals, start, goals, heuristic, info, path, observer);
+    }
 
     // Information of how a node was reached
     let mut backtraces: SecondaryMap<_, Backtrace> = SecondaryMap::new();
-    let start = Backtrace::start(start_node, start, (heuristic)(start, end));
+    let heuristic_scale = info.heuristic_scale();
+    let heuristic = |a: Vec2, b: Vec2| (heuristic)(a, b) * heuristic_scale;
+
+    // Returns the goal closest to `p` and the heuristic to it
+    let nearest_goal = |p: Vec2| {
+        (0..goals.len())
+            .filter(active)
+            .map(|i| (i, (heuristic)(p, goals[i])))
+            .min_by(|a, b| a.1.total_cmp(&b.1))
+            .expect("At least one active goal")
+    };
+
+    let weight = info.heuristic_weight;
+    let start = Backtrace::start(start_node, start, nearest_goal(start).1, weight);
 
     // Push the fist node
     open.push(start);
     backtraces.insert(start_node, start);
+    observer.on_push(start_node);
 
-    let mut closed = HashSet::new();
+    let mut closed: HashSet<_> = HashSet::default();
+
+    // The expanded node closest to the end, by heuristic
+    let mut closest = (f32::MAX, start);
+
+    // Whether any portal was skipped due to the agent radius
+    let mut narrow = false;
+
+    let deadline = info.max_duration.map(Deadline::after);
+    let mut expansions = 0;
+    let mut truncated = false;
 
     // Expand the node with the lowest total cost
     while let Some(current) = open.pop() {
@@ -229,46 +861,135 @@ pub fn astar<'a, F: Fn(Vec2, Vec2) -> f32>(
             continue;
         }
 
+        // The budget only stops the search once the goal tests have failed,
+        // so that a goal popped at the limit is still reached
+        let exhausted = info.max_expansions.is_some_and(|max| expansions >= max)
+            || deadline.is_some_and(|deadline| deadline.passed());
+
+        if !exhausted {
+            expansions += 1;
+            observer.on_expand(current.node);
+        }
+
+        let (goal, dist) = nearest_goal(current.point);
+        if dist < closest.0 {
+            closest = (dist, current);
+        }
+
         // End found
         // Generate backtrace and terminate
-        if current.node == end_node {
-            let path = path.get_or_insert_with(|| Default::default());
+        let reached = (0..goals.len())
+            .filter(|i| !goal_nodes[*i].1 && goal_nodes[*i].0 == current.node)
+            .min_by(|a, b| {
+                (heuristic)(current.point, goals[*a])
+                    .total_cmp(&(heuristic)(current.point, goals[*b]))
+            });
+
+        if let Some(reached) = reached {
+            let path = path.get_or_insert_with(Default::default);
+
+            backtrace(goals[reached], current.node, backtraces, path);
+            shorten(portals, path, info.corner_radius());
+            resolve_clip(portals, path, info.corner_radius());
+            path.keep_clear(portals, &info);
+
+            if info.snap_waypoints {
+                path.snap_to_portals(portals, info.corner_radius(), TOLERANCE);
+            }
+
+            return Ok((reached, path));
+        }
+
+        // Close enough to a goal in a neighbouring node
+        let near = (0..goals.len())
+            .filter(|i| accepts_near && active(i))
+            .find(|i| current.point.distance(goals[*i]) <= info.goal_radius);
+
+        if let Some(near) = near {
+            let path = path.get_or_insert_with(Default::default);
+
+            backtrace(current.point, current.node, backtraces, path);
+
+            // The end of the path coincides with the entry to the node
+            if let [.., a, b] = path.points() {
+                if a.point.distance_squared(b.point) < TOLERANCE {
+                    path.points.remove(path.len() - 2);
+                }
+            }
+
+            shorten(portals, path, info.corner_radius());
+            resolve_clip(portals, path, info.corner_radius());
+            path.keep_clear(portals, &info);
+
+            if info.snap_waypoints {
+                path.snap_to_portals(portals, info.corner_radius(), TOLERANCE);
+            }
 
-            backtrace(end, current.node, backtraces, path);
-            shorten(tree, portals, path, info.agent_radius);
-            resolve_clip(portals, path, info.agent_radius);
+            return Ok((near, path));
+        }
 
-            return Some(path);
+        if exhausted {
+            truncated = true;
+            break;
         }
 
+        let end = goals[goal];
         let end_rel = end - current.point;
+        let cost = info.traversal_cost(current.node);
 
         // Add all edges to the open list and update backtraces
-        let portals = portals.get(current.node).filter_map(|portal| {
+        let edges = portals.get(current.node).filter_map(|portal| {
             let face = portal.apply_margin(info.agent_radius);
             if portal.dst() == current.node
-                || face.length() < 2.0 * info.agent_radius
                 || closed.contains(&portal.dst())
+                || !info.allows(portal.dst())
             {
                 return None;
             }
 
+            if portal.clearance() < info.agent_radius {
+                narrow = true;
+                return None;
+            }
+
             assert_eq!(portal.src(), current.node);
 
+            let penalty = info.portal_cost(portals, portal)?;
+
             // Distance to each of the nodes
             let (p1, p2) = face.into_tuple();
-            let p1_dist = (heuristic)(p1, end);
-            let p2_dist = (heuristic)(p2, end);
+            let p1_dist = nearest_goal(p1).1;
+            let p2_dist = nearest_goal(p2).1;
 
             let p = if portal.normal().dot(end_rel) > 0.0 {
-                portal.clip(current.point, end, info.agent_radius)
+                portal.clip(current.point, end, info.portal_margin(&portal))
             } else if p1_dist < p2_dist {
                 p1
             } else {
                 p2
             };
 
-            let backtrace = Backtrace::new(portal, p, &current, (heuristic)(p, end));
+            let p = info.keep_clear(&portal, p);
+            let penalty = penalty
+                + info.turn_cost(&backtraces, &current, p)
+                + info.clearance_cost(&portal, p);
+            let mut backtrace = Backtrace::new(
+                portal,
+                p,
+                &current,
+                nearest_goal(p).1,
+                weight,
+                cost,
+                penalty,
+            );
+
+            // Try to shortcut to the grandparent
+            if info.kind == SearchKind::ThetaStar {
+                let anchor = &backtraces[current.anchor];
+                if line_of_sight(&backtraces, &current, anchor, p, info.corner_radius()) {
+                    backtrace.reanchor(anchor, cost);
+                }
+            }
 
             // Update backtrace
             // If the cost to this node is lower than previosuly found,
@@ -286,19 +1007,186 @@ pub fn astar<'a, F: Fn(Vec2, Vec2) -> f32>(
                 }
             }
 
+            observer.on_push(backtrace.node);
             Some(backtrace)
         });
 
         // Add the edges
-        open.extend(portals);
+        open.extend(edges);
+
+        for (id, link) in portals.links_from(current.node) {
+            if closed.contains(&link.dst) || !info.allows(link.dst) {
+                continue;
+            }
+
+            let backtrace =
+                Backtrace::link(id, link, &current, nearest_goal(link.exit).1, weight, cost);
+
+            match backtraces.entry(backtrace.node).unwrap() {
+                Entry::Occupied(mut val) => {
+                    if val.get().total_cost > backtrace.total_cost {
+                        val.insert(backtrace);
+                    } else {
+                        continue;
+                    }
+                }
+                Entry::Vacant(entry) => {
+                    entry.insert(backtrace);
+                }
+            }
+
+            observer.on_push(backtrace.node);
+            open.push(backtrace);
+        }
 
         // The current node is now done and won't be revisited
         assert!(closed.insert(current.node))
     }
 
+    if info.allow_partial || truncated {
+        let (dist, closest) = closest;
+        let path = path.get_or_insert_with(Default::default);
+
+        backtrace(closest.point, closest.node, backtraces, path);
+
+        // The end of the path coincides with the entry to the closest node
+        if let [.., a, b] = path.points() {
+            if a.point.distance_squared(b.point) < TOLERANCE {
+                path.points.remove(path.len() - 2);
+            }
+        }
+
+        shorten(portals, path, info.corner_radius());
+        resolve_clip(portals, path, info.corner_radius());
+        path.keep_clear(portals, &info);
+        path.partial = true;
+        path.truncated = truncated;
+
+        if info.snap_waypoints {
+            path.snap_to_portals(portals, info.corner_radius(), TOLERANCE);
+        }
+
+        let goal = (0..goals.len())
+            .filter(active)
+            .find(|&i| (heuristic)(closest.point, goals[i]) == dist)
+            .unwrap_or_default();
+
+        return Ok((goal, path));
+    }
+
+    let is_goal = |node| {
+        goal_nodes
+            .iter()
+            .any(|&(goal, covered)| !covered && goal == node)
+    };
+    Err(too_narrow(portals, start_node, is_goal, narrow, &info))
+}
+
+/// Explains a failed search which skipped portals too narrow for the agent.
+///
+/// Returns [PathError::PortalTooNarrow] with the width of the widest route
+/// if the goal is only out of reach due to the agent radius, and
+/// [PathError::NoRoute] otherwise.
+pub(crate) fn too_narrow(
+    portals: &Portals,
+    start: NodeIndex,
+    is_goal: impl Fn(NodeIndex) -> bool,
+    narrow: bool,
+    info: &SearchInfo,
+) -> PathError {
+    if !narrow {
+        return PathError::NoRoute;
+    }
+
+    let needed = 2.0 * info.agent_radius;
+    match widest_route(portals, start, is_goal, info) {
+        Some(found) if found < needed => PathError::PortalTooNarrow { needed, found },
+        _ => PathError::NoRoute,
+    }
+}
+
+/// Returns the width of the widest route from `start` to a goal, which is the
+/// width of its narrowest portal, regardless of the agent radius.
+/// Returns None if no goal can be reached at all.
+fn widest_route(
+    portals: &Portals,
+    start: NodeIndex,
+    is_goal: impl Fn(NodeIndex) -> bool,
+    info: &SearchInfo,
+) -> Option<f32> {
+    let mut widest = SecondaryMap::new();
+    let mut open = BinaryHeap::from([(OrderedFloat(f32::INFINITY), start)]);
+    widest.insert(start, f32::INFINITY);
+
+    while let Some((OrderedFloat(width), node)) = open.pop() {
+        if width < widest[node] {
+            continue;
+        }
+
+        if is_goal(node) {
+            return Some(width);
+        }
+
+        let portals_out = portals
+            .get(node)
+            .filter(|portal| info.portal_cost(portals, *portal).is_some())
+            .map(|portal| (portal.dst(), width.min(2.0 * portal.clearance())));
+        let links = portals.links_from(node).map(|(_, link)| (link.dst, width));
+
+        for (dst, width) in portals_out.chain(links) {
+            if !info.allows(dst) || widest.get(dst).is_some_and(|&v| v >= width) {
+                continue;
+            }
+
+            widest.insert(dst, width);
+            open.push((OrderedFloat(width), dst));
+        }
+    }
+
     None
 }
 
+/// Returns a representative point of the node, the average of its portals'
+/// midpoints
+fn center(portals: &Portals, node: NodeIndex) -> Vec2 {
+    let (sum, count) = portals
+        .get(node)
+        .fold((Vec2::ZERO, 0), |(sum, count), portal| {
+            (sum + portal.face().midpoint(), count + 1)
+        });
+
+    sum / count.max(1) as f32
+}
+
+/// Returns true if the straight line from `anchor` to `to` passes through all
+/// portals between `anchor` and `current`
+fn line_of_sight(
+    backtraces: &SecondaryMap<NodeIndex, Backtrace>,
+    current: &Backtrace,
+    anchor: &Backtrace,
+    to: Vec2,
+    margin: f32,
+) -> bool {
+    let mut node = current;
+    while node.node != anchor.node {
+        let portal = match &node.portal {
+            Some(portal) => portal,
+            None => return false,
+        };
+
+        if portal.try_clip(anchor.point, to, margin).is_none() {
+            return false;
+        }
+
+        node = match node.prev {
+            Some(prev) => &backtraces[prev],
+            None => return false,
+        };
+    }
+
+    true
+}
+
 fn backtrace(
     end: Vec2,
     mut current: NodeIndex,
@@ -322,6 +1210,12 @@ fn backtrace(
 
         prev = node.point;
 
+        // The entry lies in the previous node
+        if let (Some((link, entry)), Some(from)) = (node.link, node.prev) {
+            path.push(WayPoint::new(entry, from, None).with_link(link));
+            prev = entry;
+        }
+
         // Continue up the backtrace
         if let Some(prev) = node.prev {
             current = prev;
@@ -333,7 +1227,7 @@ fn backtrace(
     path.reverse();
 }
 
-fn resolve_clip(portals: &Portals, path: &mut [WayPoint], margin: f32) {
+pub(crate) fn resolve_clip(portals: &Portals, path: &mut [WayPoint], margin: f32) {
     if path.len() < 3 {
         return;
     }
@@ -366,7 +1260,7 @@ fn resolve_clip(portals: &Portals, path: &mut [WayPoint], margin: f32) {
     resolve_clip(portals, &mut path[1..], margin)
 }
 
-fn shorten(tree: &BSPTree, portals: &Portals, path: &mut [WayPoint], agent_radius: f32) -> bool {
+pub(crate) fn shorten(portals: &Portals, path: &mut [WayPoint], margin: f32) -> bool {
     if path.len() < 3 {
         return true;
     }
@@ -378,22 +1272,20 @@ fn shorten(tree: &BSPTree, portals: &Portals, path: &mut [WayPoint], agent_radiu
     if let Some(portal) = b.portal {
         let portal = portals.from_ref(portal);
         // c was directly visible from a
-        if let Some(p) = portal.try_clip(a.point, c.point, agent_radius) {
+        if let Some(p) = portal.try_clip(a.point, c.point, margin) {
             let prev = b.point;
 
             path[1].point = p;
 
             // Try to shorten the next strip.
             // If successful, retry shortening for this strip
-            if shorten(tree, portals, &mut path[1..], agent_radius)
-                && prev.distance_squared(p) > TOLERANCE
-            {
-                shorten(tree, portals, path, agent_radius);
+            if shorten(portals, &mut path[1..], margin) && prev.distance_squared(p) > TOLERANCE {
+                shorten(portals, path, margin);
             }
 
             return true;
         }
     }
 
-    shorten(tree, portals, &mut path[1..], agent_radius)
+    shorten(portals, &mut path[1..], margin)
 }
@@ -0,0 +1,72 @@
+use slotmap::SecondaryMap;
+
+use crate::NodeIndex;
+
+/// Receives events while a search is running.
+///
+/// All methods default to doing nothing. `()` is the observer which ignores
+/// all events.
+pub trait SearchObserver {
+    /// Called when `node` is pushed to the open set
+    fn on_push(&mut self, _node: NodeIndex) {}
+    /// Called when `node` is expanded, I.e; popped from the open set and
+    /// its portals visited
+    fn on_expand(&mut self, _node: NodeIndex) {}
+}
+
+impl SearchObserver for () {}
+
+/// Collects how much work a search performed, and where.
+#[derive(Debug, Clone, Default, PartialEq)]
+pub struct QueryStats {
+    pushes: usize,
+    expansions: usize,
+    heatmap: SecondaryMap<NodeIndex, usize>,
+}
+
+impl QueryStats {
+    pub fn new() -> Self {
+        Self::default()
+    }
+
+    /// Returns the number of nodes pushed to the open set
+    pub fn pushes(&self) -> usize {
+        self.pushes
+    }
+
+    /// Returns the number of expanded nodes
+    pub fn expansions(&self) -> usize {
+        self.expansions
+    }
+
+    /// Returns how many times each node was touched, pushed or expanded, by
+    /// the search.
+    ///
+    /// Nodes never touched are absent.
+    pub fn expansion_heatmap(&self) -> &SecondaryMap<NodeIndex, usize> {
+        &self.heatmap
+    }
+
+    /// Resets the stats to be reused for another query
+    pub fn clear(&mut self) {
+        self.pushes = 0;
+        self.expansions = 0;
+        self.heatmap.clear();
+    }
+
+    fn touch(&mut self, node: NodeIndex) {
+        *self.heatmap.entry(node).unwrap().or_default() += 1;
+    }
+}
+
+impl SearchObserver for QueryStats {
+    fn on_push(&mut self, node: NodeIndex) {
+        self.pushes += 1;
+        self.touch(node);
+    }
+
+    fn on_expand(&mut self, node: NodeIndex) {
+        self.expansions += 1;
+        self.touch(node);
+    }
+}
@@ -0,0 +1,323 @@
+use alloc::{collections::BinaryHeap, vec, vec::Vec};
+
+use glam::Vec2;
+use slotmap::{secondary::Entry, SecondaryMap};
+
+use crate::{util::HashSet, BSPTree, Face, NodeIndex, Portals, TOLERANCE};
+
+use super::{
+    backtrace, center, diagnostic, resolve_clip, shorten, too_narrow, Backtrace, Path, PathError,
+    SearchInfo,
+};
+
+/// The target of a search, which may be an entire region instead of a single
+/// point.
+#[derive(Debug, Clone, PartialEq)]
+pub enum Goal {
+    /// Reach the point exactly.
+    Point(Vec2),
+    /// Reach anywhere inside the node.
+    Node(NodeIndex),
+    /// Reach anywhere inside any of the nodes.
+    Nodes(Vec<NodeIndex>),
+    /// Reach anywhere inside the polygon, e.g; a capture zone.
+    /// The polygon may be concave, and is implicitly closed.
+    Polygon(Vec<Vec2>),
+}
+
+impl Goal {
+    /// Returns the point of the goal closest to `p`.
+    ///
+    /// Node goals are entered through their portals, and the closest point
+    /// on the portals is returned unless `p` lies in `node`, which is then
+    /// already inside the goal.
+    pub fn closest_point(&self, portals: &Portals, node: NodeIndex, p: Vec2) -> Vec2 {
+        match self {
+            Goal::Point(point) => *point,
+            Goal::Node(_) | Goal::Nodes(_) if self.contains_node(node) => p,
+            Goal::Node(goal) => closest_portal_point(portals, core::slice::from_ref(goal), p),
+            Goal::Nodes(goals) => closest_portal_point(portals, goals, p),
+            Goal::Polygon(polygon) if polygon_contains(polygon, p) => p,
+            Goal::Polygon(polygon) => edges(polygon)
+                .map(|edge| edge.closest_point(p))
+                .min_by(|a, b| a.distance_squared(p).total_cmp(&b.distance_squared(p)))
+                .unwrap_or(p),
+        }
+    }
+
+    /// Returns true if `node` is part of a node goal
+    pub fn contains_node(&self, node: NodeIndex) -> bool {
+        match self {
+            Goal::Node(goal) => *goal == node,
+            Goal::Nodes(goals) => goals.contains(&node),
+            _ => false,
+        }
+    }
+
+    /// Returns true if the goal is empty and can never be reached
+    pub fn is_empty(&self) -> bool {
+        match self {
+            Goal::Point(_) | Goal::Node(_) => false,
+            Goal::Nodes(goals) => goals.is_empty(),
+            Goal::Polygon(polygon) => polygon.len() < 3,
+        }
+    }
+
+    /// Returns true if any part of `node` may be inside the goal
+    fn touches(&self, tree: &BSPTree, portals: &Portals, node: NodeIndex) -> bool {
+        match self {
+            Goal::Point(point) => tree.locate(*point).index() == node,
+            Goal::Node(_) | Goal::Nodes(_) => self.contains_node(node),
+            Goal::Polygon(polygon) => {
+                polygon.iter().any(|&p| tree.locate(p).index() == node)
+                    || polygon_contains(polygon, center(portals, node))
+            }
+        }
+    }
+
+    /// Returns the point in `node` at which a search arriving at `p` is
+    /// done, if any.
+    fn reached(&self, tree: &BSPTree, portals: &Portals, node: NodeIndex, p: Vec2) -> Option<Vec2> {
+        let target = self.closest_point(portals, node, p);
+        let located = tree.locate(target);
+
+        if self.contains_node(node) || (located.index() == node && !located.covered()) {
+            Some(target)
+        } else {
+            None
+        }
+    }
+}
+
+impl From<Vec2> for Goal {
+    fn from(point: Vec2) -> Self {
+        Self::Point(point)
+    }
+}
+
+impl From<NodeIndex> for Goal {
+    fn from(node: NodeIndex) -> Self {
+        Self::Node(node)
+    }
+}
+
+/// Finds a path from `start` to anywhere inside of `goal`.
+///
+/// The heuristic is measured to the closest point of the goal region.
+/// Region searches always use [SearchKind::AStar](super::SearchKind::AStar)
+/// and do not respect search budgets.
+pub fn astar_region<'a, F: Fn(Vec2, Vec2) -> f32>(
+    tree: &BSPTree,
+    portals: &Portals,
+    start: Vec2,
+    goal: &Goal,
+    heuristic: F,
+    info: SearchInfo,
+    path: &'a mut Option<Path>,
+) -> Result<&'a mut Path, PathError> {
+    let result = search(tree, portals, start, goal, heuristic, info, path);
+    if let Ok(path) = &result {
+        diagnostic::report(tree, portals, path);
+    }
+
+    result
+}
+
+fn search<'a, F: Fn(Vec2, Vec2) -> f32>(
+    tree: &BSPTree,
+    portals: &Portals,
+    start: Vec2,
+    goal: &Goal,
+    heuristic: F,
+    info: SearchInfo,
+    path: &'a mut Option<Path>,
+) -> Result<&'a mut Path, PathError> {
+    let start = info.bound(tree, start)?;
+    let bounded;
+    let goal = match goal {
+        Goal::Point(point) => {
+            bounded = Goal::Point(info.bound(tree, *point)?);
+            &bounded
+        }
+        goal => goal,
+    };
+
+    let start_node = tree.locate(start);
+    if start_node.covered() {
+        return Err(PathError::StartCovered);
+    }
+
+    if goal.is_empty() {
+        return Err(PathError::NoRoute);
+    }
+
+    if let Goal::Point(point) = goal {
+        if tree.locate(*point).covered() && !info.allow_partial {
+            return Err(PathError::EndCovered);
+        }
+    }
+
+    let start_node = start_node.index();
+
+    let heuristic_scale = info.heuristic_scale();
+    let h = |node: NodeIndex, p: Vec2| {
+        (heuristic)(p, goal.closest_point(portals, node, p)) * heuristic_scale
+    };
+
+    let weight = info.heuristic_weight;
+    let start = Backtrace::start(start_node, start, h(start_node, start), weight);
+
+    let mut open = BinaryHeap::from(vec![start]);
+    let mut backtraces: SecondaryMap<_, Backtrace> = SecondaryMap::new();
+    backtraces.insert(start_node, start);
+
+    let mut closed: HashSet<_> = HashSet::default();
+    let mut closest = (f32::MAX, start);
+    let mut narrow = false;
+
+    while let Some(current) = open.pop() {
+        if !closed.insert(current.node) {
+            continue;
+        }
+
+        let dist = h(current.node, current.point);
+        if dist < closest.0 {
+            closest = (dist, current);
+        }
+
+        if let Some(end) = goal.reached(tree, portals, current.node, current.point) {
+            let path = path.get_or_insert_with(Default::default);
+
+            backtrace(end, current.node, backtraces, path);
+            dedup_end(path);
+            shorten(portals, path, info.corner_radius());
+            resolve_clip(portals, path, info.corner_radius());
+            path.keep_clear(portals, &info);
+
+            if info.snap_waypoints {
+                path.snap_to_portals(portals, info.corner_radius(), TOLERANCE);
+            }
+
+            return Ok(path);
+        }
+
+        let end = goal.closest_point(portals, current.node, current.point);
+        let end_rel = end - current.point;
+        let cost = info.traversal_cost(current.node);
+
+        for portal in portals.get(current.node) {
+            if portal.dst() == current.node
+                || closed.contains(&portal.dst())
+                || !info.allows(portal.dst())
+            {
+                continue;
+            }
+
+            let face = portal.apply_margin(info.agent_radius);
+            if portal.clearance() < info.agent_radius {
+                narrow = true;
+                continue;
+            }
+
+            let penalty = match info.portal_cost(portals, portal) {
+                Some(penalty) => penalty,
+                None => continue,
+            };
+
+            let (p1, p2) = face.into_tuple();
+            let p = if portal.normal().dot(end_rel) > 0.0 {
+                portal.clip(current.point, end, info.portal_margin(&portal))
+            } else if h(portal.dst(), p1) < h(portal.dst(), p2) {
+                p1
+            } else {
+                p2
+            };
+
+            let p = info.keep_clear(&portal, p);
+            let penalty = penalty
+                + info.turn_cost(&backtraces, &current, p)
+                + info.clearance_cost(&portal, p);
+            let backtrace = Backtrace::new(
+                portal,
+                p,
+                &current,
+                h(portal.dst(), p),
+                weight,
+                cost,
+                penalty,
+            );
+
+            match backtraces.entry(backtrace.node).unwrap() {
+                Entry::Occupied(mut val) => {
+                    if val.get().total_cost > backtrace.total_cost {
+                        val.insert(backtrace);
+                    } else {
+                        continue;
+                    }
+                }
+                Entry::Vacant(entry) => {
+                    entry.insert(backtrace);
+                }
+            }
+
+            open.push(backtrace);
+        }
+    }
+
+    if info.allow_partial {
+        let (_, closest) = closest;
+        let path = path.get_or_insert_with(Default::default);
+
+        backtrace(closest.point, closest.node, backtraces, path);
+        dedup_end(path);
+        shorten(portals, path, info.corner_radius());
+        resolve_clip(portals, path, info.corner_radius());
+        path.keep_clear(portals, &info);
+        path.partial = true;
+
+        if info.snap_waypoints {
+            path.snap_to_portals(portals, info.corner_radius(), TOLERANCE);
+        }
+
+        return Ok(path);
+    }
+
+    let is_goal = |node| goal.touches(tree, portals, node);
+    Err(too_narrow(portals, start_node, is_goal, narrow, &info))
+}
+
+/// Removes the entry into the last node if it coincides with the end
+fn dedup_end(path: &mut Path) {
+    if let [.., a, b] = path.points() {
+        if a.point.distance_squared(b.point) < TOLERANCE {
+            path.points.remove(path.len() - 2);
+        }
+    }
+}
+
+fn closest_portal_point(portals: &Portals, nodes: &[NodeIndex], p: Vec2) -> Vec2 {
+    nodes
+        .iter()
+        .flat_map(|&node| portals.incoming(node))
+        .map(|portal| portal.face().closest_point(p))
+        .min_by(|a, b| a.distance_squared(p).total_cmp(&b.distance_squared(p)))
+        .unwrap_or(p)
+}
+
+fn edges(polygon: &[Vec2]) -> impl Iterator<Item = Face> + '_ {
+    polygon
+        .iter()
+        .zip(polygon.iter().cycle().skip(1))
+        .map(|(&a, &b)| Face::new([a, b]))
+}
+
+/// Even-odd point in polygon test
+fn polygon_contains(polygon: &[Vec2], p: Vec2) -> bool {
+    edges(polygon)
+        .map(|edge| edge.into_tuple())
+        .filter(|(a, b)| (a.y > p.y) != (b.y > p.y))
+        .filter(|(a, b)| p.x < a.x + (p.y - a.y) / (b.y - a.y) * (b.x - a.x))
+        .count()
+        % 2
+        == 1
+}
@@ -0,0 +1,68 @@
+use alloc::vec::Vec;
+use core::fmt::Display;
+
+use crate::{NavigationContext, FORMAT_VERSION};
+
+/// Identifies baked navigation data
+const MAGIC: [u8; 4] = *b"BSPN";
+const HEADER_LEN: usize = MAGIC.len() + 4;
+
+/// Describes why baked navigation data could not be loaded
+#[derive(Debug, Clone, Copy, PartialEq)]
+pub enum LoadError {
+    /// The data does not start with the expected header, e.g; it was not
+    /// produced by [NavigationContext::bake]
+    InvalidHeader,
+    /// The data was written with a different [FORMAT_VERSION], or before the
+    /// version was embedded, which is reported as version 0
+    UnsupportedVersion { version: u32 },
+    /// The data following the header is truncated or malformed
+    Corrupt,
+}
+
+impl Display for LoadError {
+    fn fmt(&self, f: &mut core::fmt::Formatter<'_>) -> core::fmt::Result {
+        match self {
+            LoadError::InvalidHeader => write!(f, "Data is not baked navigation data"),
+            LoadError::UnsupportedVersion { version } => write!(
+                f,
+                "Navigation data has version {}, but only {} is supported",
+                version, FORMAT_VERSION
+            ),
+            LoadError::Corrupt => write!(f, "Baked navigation data is corrupt"),
+        }
+    }
+}
+
+impl core::error::Error for LoadError {}
+
+impl NavigationContext {
+    /// Serializes the context into a compact binary form, which is a
+    /// fraction of the size of e.g; JSON and faster to load.
+    ///
+    /// The data starts with a header identifying the format and its
+    /// [FORMAT_VERSION]. Load it with [Self::load].
+    pub fn bake(&self) -> Vec<u8> {
+        let mut bytes = Vec::with_capacity(HEADER_LEN);
+        bytes.extend_from_slice(&MAGIC);
+        bytes.extend_from_slice(&FORMAT_VERSION.to_le_bytes());
+
+        postcard::to_extend(self, bytes).expect("Navigation data is serializable")
+    }
+
+    /// Loads a context baked by [Self::bake] with the same [FORMAT_VERSION].
+    pub fn load(bytes: &[u8]) -> Result<Self, LoadError> {
+        if bytes.len() < HEADER_LEN || bytes[..MAGIC.len()] != MAGIC {
+            return Err(LoadError::InvalidHeader);
+        }
+
+        let mut version = [0; 4];
+        version.copy_from_slice(&bytes[MAGIC.len()..HEADER_LEN]);
+        let version = u32::from_le_bytes(version);
+        if version != FORMAT_VERSION {
+            return Err(LoadError::UnsupportedVersion { version });
+        }
+
+        postcard::from_bytes(&bytes[HEADER_LEN..]).map_err(|_| LoadError::Corrupt)
+    }
+}
@@ -0,0 +1,190 @@
+//! Integrates navigation with the Bevy game engine.
+//!
+//! Add the [NavigationPlugin] and spawn entities with an [Obstacle] and a
+//! transform. The [NavigationContext] resource is rebuilt whenever obstacles
+//! are added, moved or removed, and paths are found for entities with a
+//! [PathRequest], which is replaced by a [PathResult].
+
+use ::bevy::{
+    app::{App, Plugin, PostUpdate, Update},
+    color::Color,
+    ecs::{
+        component::Component,
+        entity::Entity,
+        query::{Changed, Or, With},
+        removal_detection::RemovedComponents,
+        schedule::IntoSystemConfigs,
+        system::{Commands, Query, Res, ResMut, Resource},
+    },
+    gizmos::gizmos::Gizmos,
+    math,
+    transform::{components::GlobalTransform, TransformSystem},
+};
+use glam::{Mat3, Vec2, Vec3};
+
+use crate::{heuristics, NavigationContext, Path, PathError, SearchInfo, Shape};
+
+impl Resource for NavigationContext {}
+
+/// Maintains the [NavigationContext] resource and answers path requests.
+#[derive(Debug, Clone, Copy, Default)]
+pub struct NavigationPlugin {
+    /// Draw the faces, portals and found paths using gizmos.
+    pub debug: bool,
+}
+
+impl Plugin for NavigationPlugin {
+    fn build(&self, app: &mut App) {
+        app.init_resource::<NavigationContext>().add_systems(
+            PostUpdate,
+            (rebuild, find_paths)
+                .chain()
+                .after(TransformSystem::TransformPropagate),
+        );
+
+        if self.debug {
+            app.add_systems(Update, draw);
+        }
+    }
+}
+
+/// An obstacle made of the shape transformed by the entity's
+/// [GlobalTransform].
+#[derive(Component, Debug, Clone, Default)]
+pub struct Obstacle(pub Shape);
+
+/// Requests a path, which is found after the navigation context has been
+/// rebuilt.
+#[derive(Component, Debug, Clone, Copy, PartialEq)]
+pub struct PathRequest {
+    pub start: math::Vec2,
+    pub end: math::Vec2,
+    pub agent_radius: f32,
+    /// See [SearchInfo::allow_partial].
+    pub allow_partial: bool,
+}
+
+impl PathRequest {
+    pub fn new(start: math::Vec2, end: math::Vec2) -> Self {
+        Self {
+            start,
+            end,
+            agent_radius: 0.0,
+            allow_partial: false,
+        }
+    }
+}
+
+/// The path found for a [PathRequest].
+#[derive(Component, Debug, Clone)]
+pub struct PathResult(pub Result<Path, PathError>);
+
+impl PathResult {
+    /// Returns the waypoints of the path, or none if no path was found
+    pub fn points(&self) -> Vec<math::Vec2> {
+        match &self.0 {
+            Ok(path) => path
+                .points()
+                .iter()
+                .map(|waypoint| to_bevy(waypoint.point()))
+                .collect(),
+            Err(_) => Vec::new(),
+        }
+    }
+}
+
+/// Obstacles which were added, changed or moved
+type ObstacleChanged = (
+    With<Obstacle>,
+    Or<(Changed<Obstacle>, Changed<GlobalTransform>)>,
+);
+
+fn rebuild(
+    mut nav: ResMut<NavigationContext>,
+    changed: Query<(), ObstacleChanged>,
+    mut removed: RemovedComponents<Obstacle>,
+    obstacles: Query<(&Obstacle, &GlobalTransform)>,
+) {
+    // Drain all removals to not rebuild again next frame
+    let removed = removed.read().count() > 0;
+    if changed.is_empty() && !removed {
+        return;
+    }
+
+    let faces = obstacles
+        .iter()
+        .flat_map(|(obstacle, transform)| {
+            let transform = to_mat3(transform);
+            obstacle
+                .0
+                .faces()
+                .map(move |face| face.transform_oriented(transform))
+        })
+        .collect::<Vec<_>>();
+
+    *nav = NavigationContext::from_slice(&faces);
+}
+
+fn find_paths(
+    mut commands: Commands,
+    nav: Res<NavigationContext>,
+    requests: Query<(Entity, &PathRequest)>,
+) {
+    for (entity, request) in &requests {
+        let info = SearchInfo {
+            agent_radius: request.agent_radius,
+            allow_partial: request.allow_partial,
+            ..Default::default()
+        };
+
+        let path = nav.find_path(
+            to_glam(request.start),
+            to_glam(request.end),
+            heuristics::euclidiean,
+            info,
+        );
+
+        commands
+            .entity(entity)
+            .remove::<PathRequest>()
+            .insert(PathResult(path));
+    }
+}
+
+fn draw(nav: Res<NavigationContext>, paths: Query<&PathResult>, mut gizmos: Gizmos) {
+    if let Some(tree) = nav.tree() {
+        for face in tree.descendants().flat_map(|(_, node)| node.faces()) {
+            let (a, b) = face.into_tuple();
+            gizmos.line_2d(to_bevy(a), to_bevy(b), Color::WHITE);
+        }
+    }
+
+    for portal in nav.portals().iter().flatten() {
+        let (a, b) = portal.face().into_tuple();
+        gizmos.line_2d(to_bevy(a), to_bevy(b), Color::srgb(0.2, 0.45, 0.95));
+    }
+
+    for path in &paths {
+        for points in path.points().windows(2) {
+            gizmos.line_2d(points[0], points[1], Color::srgb(0.1, 0.8, 0.3));
+        }
+    }
+}
+
+/// Returns the transform in the xy plane
+fn to_mat3(transform: &GlobalTransform) -> Mat3 {
+    let m = transform.compute_matrix();
+    Mat3::from_cols(
+        Vec3::new(m.x_axis.x, m.x_axis.y, 0.0),
+        Vec3::new(m.y_axis.x, m.y_axis.y, 0.0),
+        Vec3::new(m.w_axis.x, m.w_axis.y, 1.0),
+    )
+}
+
+fn to_glam(v: math::Vec2) -> Vec2 {
+    Vec2::new(v.x, v.y)
+}
+
+fn to_bevy(v: Vec2) -> math::Vec2 {
+    math::Vec2::new(v.x, v.y)
+}
@@ -0,0 +1,385 @@
+use alloc::vec::Vec;
+use core::f32::consts::TAU;
+
+use glam::Vec2;
+#[cfg(not(feature = "std"))]
+use num_traits::Float;
+
+use crate::{util::closest_on_segment, Face, Shape, ARC_SEGMENTS, TOLERANCE};
+
+/// The largest sine of the angle between edges for them to be considered
+/// parallel
+const PARALLEL: f32 = 1e-4;
+
+#[derive(Debug, Clone, Copy, PartialEq)]
+enum Op {
+    Union,
+    Intersection,
+    Difference,
+}
+
+/// Where an edge lies relative to the other operand
+#[derive(Debug, Clone, Copy, PartialEq)]
+enum Location {
+    Inside,
+    Outside,
+    /// Coincides with an edge of the other operand, facing the same way or
+    /// not
+    Shared {
+        same: bool,
+    },
+}
+
+impl Shape {
+    /// Returns the outlines of the area covered by either shape, e.g; to merge
+    /// overlapping obstacles without leaving faces inside the solid.
+    ///
+    /// Both shapes are treated as closed polygons in either winding, see
+    /// [Self::polygon]. The resulting outlines are counterclockwise, and holes
+    /// clockwise.
+    pub fn union(&self, other: &Shape) -> Vec<Shape> {
+        boolean(&[self.outline()], &[other.outline()], Op::Union)
+    }
+
+    /// Returns the outlines of the area covered by both shapes. See
+    /// [Self::union].
+    pub fn intersection(&self, other: &Shape) -> Vec<Shape> {
+        boolean(&[self.outline()], &[other.outline()], Op::Intersection)
+    }
+
+    /// Returns the outlines of the area covered by `self` but not by `other`.
+    /// See [Self::union].
+    pub fn difference(&self, other: &Shape) -> Vec<Shape> {
+        boolean(&[self.outline()], &[other.outline()], Op::Difference)
+    }
+
+    /// Merges any number of possibly overlapping shapes into outlines. See
+    /// [Self::union].
+    pub fn union_all<'a>(shapes: impl IntoIterator<Item = &'a Shape>) -> Vec<Shape> {
+        shapes.into_iter().fold(Vec::new(), |acc, shape| {
+            let outlines = acc.iter().map(Shape::vertices).collect::<Vec<_>>();
+            boolean(&outlines, &[shape.outline()], Op::Union)
+        })
+    }
+
+    /// Grows the shape outward by `distance`, or shrinks it for a negative
+    /// `distance`, e.g; to grow obstacles by the radius of an agent.
+    ///
+    /// Corners which open up are rounded by arcs of [ARC_SEGMENTS] per full
+    /// circle, and corners which close are trimmed to where the offset edges
+    /// meet. Parts which vanish when shrinking are removed, which may split
+    /// the shape, and holes may appear when growing concave shapes.
+    ///
+    /// The shape is treated as a closed polygon in either winding, see
+    /// [Self::polygon].
+    pub fn offset(&self, distance: f32) -> Vec<Shape> {
+        let mut points = Shape::polygon(self.outline()).vertices().to_vec();
+        points.dedup_by(|a, b| a.distance(*b) <= TOLERANCE);
+        if points.len() > 1 && points[0].distance(points[points.len() - 1]) <= TOLERANCE {
+            points.pop();
+        }
+
+        if points.len() < 3 {
+            return Vec::new();
+        }
+
+        let normal = |a, b| Face::new([a, b]).normal();
+
+        // The offset outline, which overlaps itself where corners close
+        let mut outline = Vec::new();
+        for (i, &p) in points.iter().enumerate() {
+            let prev = points[(i + points.len() - 1) % points.len()];
+            let next = points[(i + 1) % points.len()];
+            let (a, b) = (normal(prev, p), normal(p, next));
+
+            outline.push(p + a * distance);
+            if (p - prev).perp_dot(next - p) * distance > 0.0 {
+                let angle = a.perp_dot(b).atan2(a.dot(b));
+                let steps = (angle.abs() / TAU * ARC_SEGMENTS as f32).ceil().max(1.0) as usize;
+
+                outline.extend((1..steps).map(|k| {
+                    let (sin, cos) = (angle * k as f32 / steps as f32).sin_cos();
+                    p + Vec2::new(cos * a.x - sin * a.y, sin * a.x + cos * a.y) * distance
+                }));
+            } else {
+                // Passing through the corner keeps the winding of the overlap
+                // positive
+                outline.push(p);
+            }
+
+            outline.push(p + b * distance);
+        }
+
+        let edges = outline
+            .iter()
+            .copied()
+            .zip(outline.iter().copied().cycle().skip(1))
+            .filter(|(a, b)| a.distance(*b) > f32::EPSILON)
+            .collect::<Vec<_>>();
+
+        positive(&edges)
+    }
+
+    /// Returns the outline, or nothing if the shape encloses no area
+    fn outline(&self) -> &[Vec2] {
+        match self.winding() {
+            Some(_) => self.vertices(),
+            None => &[],
+        }
+    }
+}
+
+/// Applies `op` to the areas enclosed by the outlines of `a` and `b`, as by
+/// the nonzero winding rule
+fn boolean(a: &[&[Vec2]], b: &[&[Vec2]], op: Op) -> Vec<Shape> {
+    let a = normalize(a);
+    let b = normalize(b);
+
+    let mut fragments = Vec::new();
+    for (edge, location) in classify(&a, &b) {
+        let keep = match (op, location) {
+            (Op::Union, Location::Outside) => true,
+            (Op::Intersection, Location::Inside) => true,
+            (Op::Difference, Location::Outside) => true,
+            (Op::Union | Op::Intersection, Location::Shared { same }) => same,
+            (Op::Difference, Location::Shared { same }) => !same,
+            _ => false,
+        };
+
+        if keep {
+            fragments.push(edge);
+        }
+    }
+
+    // Shared edges are kept from `a` only
+    for ((p, q), location) in classify(&b, &a) {
+        match (op, location) {
+            (Op::Union, Location::Outside) | (Op::Intersection, Location::Inside) => {
+                fragments.push((p, q))
+            }
+            (Op::Difference, Location::Inside) => fragments.push((q, p)),
+            _ => {}
+        }
+    }
+
+    chain(fragments)
+}
+
+/// Returns the edges of the outlines, wound as they would be by
+/// [Shape::polygon] if alone
+fn normalize(outlines: &[&[Vec2]]) -> Vec<(Vec2, Vec2)> {
+    let mut edges = Vec::new();
+    for &outline in outlines {
+        if outline.len() < 2 {
+            continue;
+        }
+
+        edges.extend(outline.windows(2).map(|w| (w[0], w[1])));
+        if outline.first() != outline.last() {
+            edges.push((outline[outline.len() - 1], outline[0]));
+        }
+    }
+
+    // A single clockwise outline is an authored obstacle in the other winding
+    if outlines.len() == 1 && edges.iter().map(|(a, b)| a.perp_dot(*b)).sum::<f32>() < 0.0 {
+        edges = edges.into_iter().rev().map(|(a, b)| (b, a)).collect();
+    }
+
+    edges
+}
+
+/// Splits the edges where they cross or touch `other`, and locates each
+/// fragment relative to `other`
+fn classify(edges: &[(Vec2, Vec2)], other: &[(Vec2, Vec2)]) -> Vec<((Vec2, Vec2), Location)> {
+    let mut result = Vec::new();
+    for &(p, q) in edges {
+        let mut cuts = cuts(p, q, other);
+        cuts.sort_by(|a, b| a.0.total_cmp(&b.0));
+
+        let mut start = p;
+        for end in cuts.into_iter().map(|v| v.1).chain([q]) {
+            if start.distance(end) <= TOLERANCE {
+                continue;
+            }
+
+            result.push(((start, end), locate(start, end, other)));
+            start = end;
+        }
+    }
+
+    result
+}
+
+/// Returns the points along the edge from `p` to `q`, and their parameters,
+/// where `other` crosses or touches the edge
+fn cuts(p: Vec2, q: Vec2, other: &[(Vec2, Vec2)]) -> Vec<(f32, Vec2)> {
+    let r = q - p;
+    let len = r.length();
+    let interior = |t: f32| t * len > TOLERANCE && (1.0 - t) * len > TOLERANCE;
+
+    let mut cuts = Vec::new();
+    for &(c, d) in other {
+        let s = d - c;
+        let denom = r.perp_dot(s);
+
+        if denom.abs() > PARALLEL * len * s.length() {
+            let t = (c - p).perp_dot(s) / denom;
+            let u = (c - p).perp_dot(r) / denom;
+            let slen = s.length();
+
+            if !interior(t) || u * slen < -TOLERANCE || (u - 1.0) * slen > TOLERANCE {
+                continue;
+            }
+
+            // Reuse the vertex of the other edge when touching it
+            let point = if u * slen <= TOLERANCE {
+                c
+            } else if (1.0 - u) * slen <= TOLERANCE {
+                d
+            } else {
+                p + r * t
+            };
+
+            cuts.push((t, point));
+        } else if closest_on_line(p, r, c).distance(c) <= TOLERANCE {
+            // Collinear edges cut each other at their ends
+            for x in [c, d] {
+                let t = (x - p).dot(r) / (len * len);
+                if interior(t) {
+                    cuts.push((t, x));
+                }
+            }
+        }
+    }
+
+    cuts
+}
+
+fn closest_on_line(p: Vec2, dir: Vec2, point: Vec2) -> Vec2 {
+    p + dir * (point - p).dot(dir) / dir.length_squared()
+}
+
+fn locate(start: Vec2, end: Vec2, other: &[(Vec2, Vec2)]) -> Location {
+    let mid = (start + end) / 2.0;
+    let dir = (end - start).normalize();
+
+    let shared = other.iter().find(|&&(c, d)| {
+        closest_on_segment(c, d, mid).distance(mid) <= TOLERANCE
+            && dir.perp_dot((d - c).normalize()).abs() <= PARALLEL
+    });
+
+    if let Some(&(c, d)) = shared {
+        return Location::Shared {
+            same: dir.dot(d - c) > 0.0,
+        };
+    }
+
+    if winding_number(other, mid) != 0 {
+        Location::Inside
+    } else {
+        Location::Outside
+    }
+}
+
+fn winding_number(edges: &[(Vec2, Vec2)], p: Vec2) -> i32 {
+    let mut winding = 0;
+    for &(a, b) in edges {
+        let side = (b - a).perp_dot(p - a);
+        if a.y <= p.y {
+            if b.y > p.y && side > 0.0 {
+                winding += 1;
+            }
+        } else if b.y <= p.y && side < 0.0 {
+            winding -= 1;
+        }
+    }
+
+    winding
+}
+
+/// Returns the outlines of the area of positive winding number of `edges`,
+/// which may overlap themselves
+fn positive(edges: &[(Vec2, Vec2)]) -> Vec<Shape> {
+    let mut fragments = Vec::new();
+    for &(p, q) in edges {
+        let mut cuts = cuts(p, q, edges);
+        cuts.sort_by(|a, b| a.0.total_cmp(&b.0));
+
+        let mut start = p;
+        for end in cuts.into_iter().map(|v| v.1).chain([q]) {
+            if start.distance(end) <= TOLERANCE {
+                continue;
+            }
+
+            // Compare the winding on either side of the fragment
+            let mid = (start + end) / 2.0;
+            let left = (end - start).normalize().perp() * TOLERANCE / 2.0;
+            let inside = |p| winding_number(edges, p) > 0;
+
+            match (inside(mid + left), inside(mid - left)) {
+                (true, false) => fragments.push((start, end)),
+                (false, true) => fragments.push((end, start)),
+                _ => {}
+            }
+
+            start = end;
+        }
+    }
+
+    chain(fragments)
+}
+
+/// Joins directed edges end to start into closed outlines
+fn chain(mut fragments: Vec<(Vec2, Vec2)>) -> Vec<Shape> {
+    let mut shapes = Vec::new();
+
+    while let Some((start, mut end)) = fragments.pop() {
+        let mut points = Vec::from([start]);
+
+        while end.distance(start) > TOLERANCE {
+            points.push(end);
+
+            let next = fragments
+                .iter()
+                .enumerate()
+                .map(|(i, v)| (i, v.0.distance(end)))
+                .filter(|v| v.1 <= TOLERANCE)
+                .min_by(|a, b| a.1.total_cmp(&b.1));
+
+            match next {
+                Some((i, _)) => end = fragments.swap_remove(i).1,
+                // The outline can not be closed, which only happens for
+                // degenerate input
+                None => break,
+            }
+        }
+
+        let mut points = simplify(points);
+        if points.len() >= 3 {
+            points.push(points[0]);
+            shapes.push(Shape::new(&points));
+        }
+    }
+
+    shapes.reverse();
+    shapes
+}
+
+/// Removes vertices between collinear edges of a closed outline
+fn simplify(mut points: Vec<Vec2>) -> Vec<Vec2> {
+    let mut i = 0;
+    while i < points.len() && points.len() >= 3 {
+        let prev = points[(i + points.len() - 1) % points.len()];
+        let next = points[(i + 1) % points.len()];
+        let (a, b) = (points[i] - prev, next - points[i]);
+
+        if a.normalize().perp_dot(b.normalize()).abs() <= PARALLEL && a.dot(b) > 0.0 {
+            points.remove(i);
+            i = i.saturating_sub(1);
+        } else {
+            i += 1;
+        }
+    }
+
+    points
+}
@@ -0,0 +1,150 @@
+use alloc::{vec, vec::Vec};
+
+use slotmap::SecondaryMap;
+
+use crate::{BSPTree, NodeIndex, Portals};
+
+/// Precomputed connectivity of the nodes of a tree.
+///
+/// Two nodes are connected if there exists a sequence of portals wide enough
+/// for the given clearance, or off-mesh links, between them. Allows quickly
+/// determining that no path exists without running a search.
+///
+/// Connections are undirected, and one-way portals and off-mesh links connect
+/// their nodes both ways. Nodes of the same component may therefore only be
+/// reachable from one another in a single direction.
+#[derive(Default, Debug, Clone)]
+#[cfg_attr(feature = "serialize", derive(serde::Serialize, serde::Deserialize))]
+pub struct Connectivity {
+    components: SecondaryMap<NodeIndex, usize>,
+    count: usize,
+    clearance: f32,
+}
+
+impl Connectivity {
+    /// Computes the connectivity of the tree for agents of radius `clearance`.
+    pub fn new(tree: &BSPTree, portals: &Portals, clearance: f32) -> Self {
+        let mut dense = SecondaryMap::new();
+        tree.nodes().keys().enumerate().for_each(|(i, index)| {
+            dense.insert(index, i);
+        });
+
+        let mut sets = UnionFind::new(dense.len());
+
+        portals
+            .iter()
+            .flatten()
+            .filter(|portal| portal.clearance() >= clearance)
+            .for_each(|portal| {
+                if let (Some(&a), Some(&b)) = (dense.get(portal.src()), dense.get(portal.dst())) {
+                    sets.union(a, b)
+                }
+            });
+
+        portals.links().iter().for_each(|link| {
+            if let (Some(&a), Some(&b)) = (dense.get(link.src), dense.get(link.dst)) {
+                sets.union(a, b)
+            }
+        });
+
+        // Relabel the sets to consecutive component ids
+        let mut labels = vec![usize::MAX; dense.len()];
+        let mut count = 0;
+        let components = dense
+            .iter()
+            .map(|(index, &i)| {
+                let root = sets.find(i);
+                if labels[root] == usize::MAX {
+                    labels[root] = count;
+                    count += 1;
+                }
+
+                (index, labels[root])
+            })
+            .collect();
+
+        Self {
+            components,
+            count,
+            clearance,
+        }
+    }
+
+    /// Returns the component id of a node
+    pub fn component(&self, index: NodeIndex) -> Option<usize> {
+        self.components.get(index).copied()
+    }
+
+    /// Returns the component id of every node.
+    /// Ids are consecutive, starting at 0.
+    pub fn components(&self) -> &SecondaryMap<NodeIndex, usize> {
+        &self.components
+    }
+
+    /// Returns all nodes belonging to `component`
+    pub fn nodes_in(&self, component: usize) -> impl Iterator<Item = NodeIndex> + '_ {
+        self.components
+            .iter()
+            .filter(move |(_, &c)| c == component)
+            .map(|(index, _)| index)
+    }
+
+    /// Returns true if `a` and `b` belong to the same component
+    pub fn is_connected(&self, a: NodeIndex, b: NodeIndex) -> bool {
+        match (self.component(a), self.component(b)) {
+            (Some(a), Some(b)) => a == b,
+            _ => false,
+        }
+    }
+
+    /// Returns the number of distinct components
+    pub fn count(&self) -> usize {
+        self.count
+    }
+
+    /// Returns the clearance the connectivity was computed for
+    pub fn clearance(&self) -> f32 {
+        self.clearance
+    }
+}
+
+struct UnionFind {
+    parents: Vec<usize>,
+    ranks: Vec<u8>,
+}
+
+impl UnionFind {
+    fn new(len: usize) -> Self {
+        Self {
+            parents: (0..len).collect(),
+            ranks: vec![0; len],
+        }
+    }
+
+    fn find(&mut self, mut i: usize) -> usize {
+        while self.parents[i] != i {
+            // Path halving
+            self.parents[i] = self.parents[self.parents[i]];
+            i = self.parents[i];
+        }
+
+        i
+    }
+
+    fn union(&mut self, a: usize, b: usize) {
+        let a = self.find(a);
+        let b = self.find(b);
+        if a == b {
+            return;
+        }
+
+        match self.ranks[a].cmp(&self.ranks[b]) {
+            core::cmp::Ordering::Less => self.parents[a] = b,
+            core::cmp::Ordering::Greater => self.parents[b] = a,
+            core::cmp::Ordering::Equal => {
+                self.parents[b] = a;
+                self.ranks[a] += 1;
+            }
+        }
+    }
+}
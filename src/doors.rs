@@ -0,0 +1,191 @@
+use alloc::{collections::VecDeque, vec::Vec};
+
+use slotmap::{new_key_type, SlotMap};
+use smallvec::SmallVec;
+
+use crate::{util::HashMap, Path, PortalRef};
+
+new_key_type! {
+    pub struct DoorId;
+    pub struct PathId;
+}
+
+#[derive(Debug, Clone)]
+struct Door {
+    // Indices of the portal faces covered by the door
+    faces: SmallVec<[usize; 2]>,
+    open: bool,
+}
+
+/// Tracks doors placed on portals and whether they are open.
+///
+/// State changes are applied immediately, while the invalidation of paths
+/// through changed doors is deferred to a [PathQueue], which spreads the work
+/// over several frames.
+///
+/// Searches do not consult the doors, which only determine the validity of
+/// cached paths. Use [Portals::set_enabled](crate::Portals::set_enabled) to
+/// make searches avoid closed doors.
+#[derive(Debug, Clone, Default)]
+pub struct Doors {
+    doors: SlotMap<DoorId, Door>,
+    // Number of closed doors covering each face
+    closed: HashMap<usize, usize>,
+    // Doors changed since the last time a queue picked up the changes
+    changed: Vec<DoorId>,
+}
+
+impl Doors {
+    pub fn new() -> Self {
+        Self::default()
+    }
+
+    /// Places an open door covering `portals`.
+    /// Both directions of a portal are covered.
+    pub fn insert(&mut self, portals: impl IntoIterator<Item = PortalRef>) -> DoorId {
+        let faces = portals.into_iter().map(|portal| portal.face).collect();
+        self.doors.insert(Door { faces, open: true })
+    }
+
+    /// Removes a door, opening the portals it covered.
+    pub fn remove(&mut self, id: DoorId) -> bool {
+        if !self.doors.contains_key(id) {
+            return false;
+        }
+
+        self.set_open(id, true);
+        self.doors.remove(id);
+        true
+    }
+
+    /// Opens or closes a door.
+    /// Returns true if the state of the door changed.
+    pub fn set_open(&mut self, id: DoorId, open: bool) -> bool {
+        let door = match self.doors.get_mut(id) {
+            Some(door) if door.open != open => door,
+            _ => return false,
+        };
+
+        door.open = open;
+        for &face in &door.faces {
+            let count = self.closed.entry(face).or_default();
+            if open {
+                *count -= 1;
+                if *count == 0 {
+                    self.closed.remove(&face);
+                }
+            } else {
+                *count += 1;
+            }
+        }
+
+        self.changed.push(id);
+        true
+    }
+
+    /// Applies many door changes at once, e.g; when a power outage closes
+    /// every door.
+    ///
+    /// Affected paths are not revalidated synchronously, but queued up for
+    /// the next [PathQueue::tick].
+    /// Returns the number of doors which changed state.
+    pub fn apply_batch(&mut self, changes: impl IntoIterator<Item = (DoorId, bool)>) -> usize {
+        changes
+            .into_iter()
+            .filter(|&(id, open)| self.set_open(id, open))
+            .count()
+    }
+
+    /// Returns true if the door is open, or None if it does not exist
+    pub fn is_open(&self, id: DoorId) -> Option<bool> {
+        self.doors.get(id).map(|door| door.open)
+    }
+
+    /// Returns true if `portal` is covered by a closed door
+    pub fn blocks(&self, portal: PortalRef) -> bool {
+        self.closed.contains_key(&portal.face)
+    }
+
+    /// Returns true if any portal crossed by `path` is covered by a closed door
+    pub fn blocks_path(&self, path: &Path) -> bool {
+        path.iter()
+            .filter_map(|waypoint| waypoint.portal())
+            .any(|portal| self.blocks(portal))
+    }
+
+    /// Returns true if doors have changed since the changes were last taken
+    pub fn has_changes(&self) -> bool {
+        !self.changed.is_empty()
+    }
+
+    /// Takes the doors which have changed state since the last call
+    pub fn take_changes(&mut self) -> Vec<DoorId> {
+        core::mem::take(&mut self.changed)
+    }
+}
+
+/// Caches paths and revalidates them against [Doors] over several frames.
+#[derive(Debug, Clone, Default)]
+pub struct PathQueue {
+    paths: SlotMap<PathId, Path>,
+    // Paths waiting to be revalidated
+    pending: VecDeque<PathId>,
+}
+
+impl PathQueue {
+    pub fn new() -> Self {
+        Self::default()
+    }
+
+    /// Caches a path
+    pub fn insert(&mut self, path: Path) -> PathId {
+        self.paths.insert(path)
+    }
+
+    /// Replaces a cached path, e.g; after replanning an invalidated path.
+    pub fn replace(&mut self, id: PathId, path: Path) -> Option<Path> {
+        self.paths
+            .get_mut(id)
+            .map(|old| core::mem::replace(old, path))
+    }
+
+    pub fn remove(&mut self, id: PathId) -> Option<Path> {
+        self.paths.remove(id)
+    }
+
+    pub fn get(&self, id: PathId) -> Option<&Path> {
+        self.paths.get(id)
+    }
+
+    /// Returns the number of paths waiting to be revalidated
+    pub fn pending(&self) -> usize {
+        self.pending.len()
+    }
+
+    /// Picks up door changes and revalidates at most `budget` cached paths.
+    ///
+    /// Returns the paths found to be blocked by a closed door, which should
+    /// be replanned.
+    pub fn tick(&mut self, doors: &mut Doors, budget: usize) -> Vec<PathId> {
+        // Any change restarts the revalidation of all cached paths
+        if !doors.take_changes().is_empty() {
+            self.pending.clear();
+            self.pending.extend(self.paths.keys());
+        }
+
+        let mut blocked = Vec::new();
+        for _ in 0..budget {
+            let id = match self.pending.pop_front() {
+                Some(id) => id,
+                None => break,
+            };
+
+            match self.paths.get(id) {
+                Some(path) if doors.blocks_path(path) => blocked.push(id),
+                _ => {}
+            }
+        }
+
+        blocked
+    }
+}
@@ -0,0 +1,4 @@
+//! Exports navigation data to formats other tools can display, e.g; to
+//! visualize navigation bugs on a server or in CI.
+
+pub mod svg;
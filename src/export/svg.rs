@@ -0,0 +1,248 @@
+//! Renders a [NavigationContext] and an optional [Path] to an SVG image,
+//! without any rendering dependencies.
+
+use alloc::{string::String, vec::Vec};
+use core::fmt::Write;
+
+use glam::Vec2;
+#[cfg(not(feature = "std"))]
+use num_traits::Float;
+
+use slotmap::SecondaryMap;
+
+use crate::{
+    util::{clip_polygon, HashSet},
+    BSPTree, NavigationContext, NodeIndex, Path, TOLERANCE,
+};
+
+/// Controls what [to_svg] draws, and how.
+#[derive(Debug, Clone, Copy, PartialEq)]
+pub struct SvgOptions<'a> {
+    /// The number of pixels per unit.
+    pub scale: f32,
+    /// The space around the bounds of the tree, in units.
+    pub margin: f32,
+    /// The width of lines, in pixels.
+    pub stroke_width: f32,
+    /// Fill the open leaves.
+    pub cells: bool,
+    /// Draw the splitting plane of each node, clipped to the node's region.
+    pub planes: bool,
+    /// Draw the enabled portals.
+    pub portals: bool,
+    /// Shade the open leaves by how often a search touched them, e.g; the
+    /// [QueryStats::expansion_heatmap](crate::QueryStats::expansion_heatmap)
+    /// of a query.
+    pub heatmap: Option<&'a SecondaryMap<NodeIndex, usize>>,
+}
+
+impl Default for SvgOptions<'_> {
+    fn default() -> Self {
+        Self {
+            scale: 1.0,
+            margin: 10.0,
+            stroke_width: 1.0,
+            cells: true,
+            planes: true,
+            portals: true,
+            heatmap: None,
+        }
+    }
+}
+
+/// Renders the faces, splitting planes, and portals of `nav`, and `path` if
+/// any, to an SVG document.
+///
+/// The y axis points up, as opposed to the SVG convention.
+pub fn to_svg(nav: &NavigationContext, path: Option<&Path>, options: &SvgOptions<'_>) -> String {
+    let (min, max) = match nav.tree() {
+        Some(tree) => tree.bounds(),
+        None => path.into_iter().flatten().fold(
+            (Vec2::splat(f32::MAX), Vec2::splat(f32::MIN)),
+            |(min, max), v| (min.min(v.point()), max.max(v.point())),
+        ),
+    };
+
+    let (min, max) = if min.cmple(max).all() {
+        (
+            min - Vec2::splat(options.margin),
+            max + Vec2::splat(options.margin),
+        )
+    } else {
+        (Vec2::ZERO, Vec2::ZERO)
+    };
+
+    let canvas = Canvas {
+        min,
+        max,
+        scale: options.scale,
+    };
+
+    let size = (max - min) * options.scale;
+    let mut svg = String::new();
+    let _ = writeln!(
+        svg,
+        "<svg xmlns=\"http://www.w3.org/2000/svg\" width=\"{:.0}\" height=\"{:.0}\" viewBox=\"0 0 {:.2} {:.2}\">",
+        size.x.ceil(),
+        size.y.ceil(),
+        size.x,
+        size.y
+    );
+
+    let stroke = options.stroke_width;
+    if let Some(tree) = nav.tree() {
+        if options.cells {
+            svg.push_str("<g id=\"cells\" fill=\"#d8f0d8\" stroke=\"none\">\n");
+            for (_, cell) in tree.cells().filter(|(_, cell)| cell.len() >= 3) {
+                let _ = writeln!(svg, "<polygon points=\"{}\"/>", canvas.points(&cell));
+            }
+            svg.push_str("</g>\n");
+        }
+
+        if let Some(heatmap) = options.heatmap {
+            let max = heatmap.values().copied().max().unwrap_or(0).max(1) as f32;
+            svg.push_str("<g id=\"heatmap\" fill=\"#f03020\" stroke=\"none\">\n");
+            for (index, cell) in tree.cells().filter(|(_, cell)| cell.len() >= 3) {
+                if let Some(&heat) = heatmap.get(index) {
+                    let _ = writeln!(
+                        svg,
+                        "<polygon fill-opacity=\"{:.3}\" points=\"{}\"/>",
+                        heat as f32 / max,
+                        canvas.points(&cell)
+                    );
+                }
+            }
+            svg.push_str("</g>\n");
+        }
+
+        if options.planes {
+            let _ = writeln!(
+                svg,
+                "<g id=\"planes\" stroke=\"#a0a0a0\" stroke-width=\"{}\" stroke-dasharray=\"4 2\">",
+                stroke * 0.5
+            );
+            let (l, r) = tree.bounds();
+            let bounds = [l, Vec2::new(r.x, l.y), r, Vec2::new(l.x, r.y)];
+            planes(tree, tree.root(), &bounds, &canvas, &mut svg);
+            svg.push_str("</g>\n");
+        }
+
+        let _ = writeln!(
+            svg,
+            "<g id=\"faces\" stroke=\"#000000\" stroke-width=\"{}\">",
+            stroke
+        );
+        for (_, node) in tree.descendants() {
+            for face in node.faces() {
+                canvas.line(face.vertices[0], face.vertices[1], &mut svg);
+            }
+        }
+        svg.push_str("</g>\n");
+    }
+
+    if options.portals {
+        let _ = writeln!(
+            svg,
+            "<g id=\"portals\" stroke=\"#3070f0\" stroke-width=\"{}\">",
+            stroke
+        );
+        let mut drawn: HashSet<_> = HashSet::default();
+        for portal in nav.portals().iter().flatten() {
+            if drawn.insert(portal.portal_ref().face) {
+                let (a, b) = portal.face().into_tuple();
+                canvas.line(a, b, &mut svg);
+            }
+        }
+        svg.push_str("</g>\n");
+    }
+
+    if let Some(path) = path {
+        let points = path.iter().map(|v| v.point()).collect::<Vec<_>>();
+        let _ = writeln!(
+            svg,
+            "<g id=\"path\" stroke=\"#f08020\" fill=\"#f08020\" stroke-width=\"{}\">",
+            stroke * 2.0
+        );
+        let _ = writeln!(
+            svg,
+            "<polyline fill=\"none\" points=\"{}\"/>",
+            canvas.points(&points)
+        );
+        for &p in &points {
+            let p = canvas.map(p);
+            let _ = writeln!(
+                svg,
+                "<circle cx=\"{:.2}\" cy=\"{:.2}\" r=\"{}\"/>",
+                p.x,
+                p.y,
+                stroke * 2.0
+            );
+        }
+        svg.push_str("</g>\n");
+    }
+
+    svg.push_str("</svg>\n");
+    svg
+}
+
+/// Maps world space to the image
+struct Canvas {
+    min: Vec2,
+    max: Vec2,
+    scale: f32,
+}
+
+impl Canvas {
+    fn map(&self, p: Vec2) -> Vec2 {
+        Vec2::new(p.x - self.min.x, self.max.y - p.y) * self.scale
+    }
+
+    fn points(&self, points: &[Vec2]) -> String {
+        let mut result = String::new();
+        for (i, &p) in points.iter().enumerate() {
+            let p = self.map(p);
+            let sep = if i > 0 { " " } else { "" };
+            let _ = write!(result, "{}{:.2},{:.2}", sep, p.x, p.y);
+        }
+        result
+    }
+
+    fn line(&self, a: Vec2, b: Vec2, svg: &mut String) {
+        let (a, b) = (self.map(a), self.map(b));
+        let _ = writeln!(
+            svg,
+            "<line x1=\"{:.2}\" y1=\"{:.2}\" x2=\"{:.2}\" y2=\"{:.2}\"/>",
+            a.x, a.y, b.x, b.y
+        );
+    }
+}
+
+/// Draws the splitting plane of each node of the subtree, clipped to the
+/// convex `region` of the subtree
+fn planes(tree: &BSPTree, index: NodeIndex, region: &[Vec2], canvas: &Canvas, svg: &mut String) {
+    let node = &tree.nodes()[index];
+    let (origin, normal) = (node.origin(), node.normal());
+    let dir = normal.perp();
+
+    let front = clip_polygon(region, origin, normal);
+
+    // The extent of the region along the plane
+    let (lo, hi) = front
+        .iter()
+        .filter(|p| (**p - origin).dot(normal).abs() < TOLERANCE)
+        .map(|p| (*p - origin).dot(dir))
+        .fold((f32::MAX, f32::MIN), |(lo, hi), t| (lo.min(t), hi.max(t)));
+
+    if hi - lo > TOLERANCE {
+        canvas.line(origin + dir * lo, origin + dir * hi, svg);
+    }
+
+    if let Some(child) = node.front() {
+        planes(tree, child, &front, canvas, svg);
+    }
+
+    if let Some(child) = node.back() {
+        let back = clip_polygon(region, origin, -normal);
+        planes(tree, child, &back, canvas, svg);
+    }
+}
@@ -0,0 +1,193 @@
+//! A flat C ABI, e.g; for Unity, Unreal or custom C++ engines.
+//!
+//! Build a shared library with
+//! `cargo rustc --release --features ffi --crate-type cdylib` and include
+//! `include/bsp_pathfinding.h`, which is generated by running
+//! `cbindgen --config cbindgen.toml --output include/bsp_pathfinding.h`.
+//!
+//! Points are passed as flat arrays of `x, y` pairs. Null pointers are
+//! reported as [NavStatus::InvalidArgument]. A handle may not be used from
+//! several threads at once.
+
+use alloc::{boxed::Box, vec::Vec};
+use core::slice;
+
+use glam::{Mat3, Vec2};
+
+use crate::{heuristics, NavWorld, PathError, SearchInfo, Shape};
+
+/// The obstacles and navigation data, created by [nav_create] and freed by
+/// [nav_destroy].
+///
+/// The navigation data is rebuilt by the first search after the obstacles
+/// change.
+pub struct Nav {
+    world: NavWorld<u32>,
+    next_id: u32,
+}
+
+/// The result of a call. Negative values are errors.
+#[repr(C)]
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum NavStatus {
+    Ok = 0,
+    /// A pointer was null, or an id or length was invalid
+    InvalidArgument = -1,
+    /// The path did not fit in the output buffer
+    BufferTooSmall = -2,
+    StartCovered = -3,
+    EndCovered = -4,
+    NoRoute = -5,
+    PortalTooNarrow = -6,
+    EmptyScene = -7,
+    OutOfBounds = -8,
+}
+
+impl From<PathError> for NavStatus {
+    fn from(e: PathError) -> Self {
+        match e {
+            PathError::StartCovered => Self::StartCovered,
+            PathError::EndCovered => Self::EndCovered,
+            PathError::NoRoute => Self::NoRoute,
+            PathError::PortalTooNarrow { .. } => Self::PortalTooNarrow,
+            PathError::EmptyScene => Self::EmptyScene,
+            PathError::OutOfBounds { .. } => Self::OutOfBounds,
+        }
+    }
+}
+
+/// Creates an empty navigation handle
+#[no_mangle]
+pub extern "C" fn nav_create() -> *mut Nav {
+    Box::into_raw(Box::new(Nav {
+        world: NavWorld::new(),
+        next_id: 1,
+    }))
+}
+
+/// Frees a handle created by [nav_create]. Null is ignored.
+///
+/// # Safety
+/// `nav` must be null or a handle from [nav_create] which was not yet
+/// destroyed.
+#[no_mangle]
+pub unsafe extern "C" fn nav_destroy(nav: *mut Nav) {
+    if !nav.is_null() {
+        drop(Box::from_raw(nav));
+    }
+}
+
+/// Adds an obstacle of `len` points, wound counterclockwise and implicitly
+/// closed. The id of the obstacle is written to `out_id`.
+///
+/// # Safety
+/// `nav` must be a valid handle, `points` must hold `2 * len` floats, and
+/// `out_id` must be writable.
+#[no_mangle]
+pub unsafe extern "C" fn nav_add_shape(
+    nav: *mut Nav,
+    points: *const f32,
+    len: usize,
+    out_id: *mut u32,
+) -> NavStatus {
+    let nav = match nav.as_mut() {
+        Some(nav) => nav,
+        None => return NavStatus::InvalidArgument,
+    };
+
+    if points.is_null() || out_id.is_null() || len < 2 {
+        return NavStatus::InvalidArgument;
+    }
+
+    let mut vertices = slice::from_raw_parts(points, len * 2)
+        .chunks_exact(2)
+        .map(|p| Vec2::new(p[0], p[1]))
+        .collect::<Vec<_>>();
+    vertices.push(vertices[0]);
+
+    let id = nav.next_id;
+    nav.next_id += 1;
+    nav.world.upsert(id, &Shape::new(&vertices), Mat3::IDENTITY);
+
+    *out_id = id;
+    NavStatus::Ok
+}
+
+/// Removes the obstacle `id`
+///
+/// # Safety
+/// `nav` must be a valid handle.
+#[no_mangle]
+pub unsafe extern "C" fn nav_remove_shape(nav: *mut Nav, id: u32) -> NavStatus {
+    match nav.as_mut().map(|nav| nav.world.remove(&id)) {
+        Some(true) => NavStatus::Ok,
+        _ => NavStatus::InvalidArgument,
+    }
+}
+
+/// Finds a path for an agent of `agent_radius` and writes its waypoints to
+/// `out_points`, which holds room for `capacity` points.
+///
+/// The number of waypoints is written to `out_len`. If the path does not
+/// fit, [NavStatus::BufferTooSmall] is returned and nothing but `out_len` is
+/// written, which allows retrying with a larger buffer.
+///
+/// # Safety
+/// `nav` must be a valid handle, `out_points` must hold `2 * capacity`
+/// floats, and `out_len` must be writable.
+#[no_mangle]
+#[allow(clippy::too_many_arguments)]
+pub unsafe extern "C" fn nav_find_path(
+    nav: *mut Nav,
+    start_x: f32,
+    start_y: f32,
+    end_x: f32,
+    end_y: f32,
+    agent_radius: f32,
+    out_points: *mut f32,
+    capacity: usize,
+    out_len: *mut usize,
+) -> NavStatus {
+    let nav = match nav.as_mut() {
+        Some(nav) => nav,
+        None => return NavStatus::InvalidArgument,
+    };
+
+    if out_len.is_null() || (out_points.is_null() && capacity > 0) {
+        return NavStatus::InvalidArgument;
+    }
+
+    nav.world.commit();
+
+    let info = SearchInfo {
+        agent_radius,
+        ..Default::default()
+    };
+
+    let path = match nav.world.nav().find_path(
+        Vec2::new(start_x, start_y),
+        Vec2::new(end_x, end_y),
+        heuristics::euclidiean,
+        info,
+    ) {
+        Ok(path) => path,
+        Err(e) => return e.into(),
+    };
+
+    *out_len = path.len();
+    if path.len() > capacity {
+        return NavStatus::BufferTooSmall;
+    }
+
+    if path.is_empty() {
+        return NavStatus::Ok;
+    }
+
+    let out = slice::from_raw_parts_mut(out_points, capacity * 2);
+    for (dst, waypoint) in out.chunks_exact_mut(2).zip(path.iter()) {
+        dst[0] = waypoint.point().x;
+        dst[1] = waypoint.point().y;
+    }
+
+    NavStatus::Ok
+}
@@ -0,0 +1,188 @@
+//! Flow fields guide many agents towards a single goal.
+//!
+//! A single Dijkstra search is run outwards from the goal over the portal
+//! graph, after which any agent can look up which way to go from the node it
+//! is in, without running its own search.
+//!
+//! The same search doubles as a distance field, which answers path queries
+//! from any start to the goal in time proportional to the path length.
+use alloc::collections::BinaryHeap;
+use core::cmp::Reverse;
+
+use glam::Vec2;
+use ordered_float::OrderedFloat;
+use slotmap::SecondaryMap;
+
+use crate::{
+    astar::{resolve_clip, shorten},
+    BSPTree, NodeIndex, Path, PathError, Portals, WayPoint,
+};
+
+/// The flow towards the goal from a single node
+#[derive(Debug, Clone, Copy, PartialEq)]
+#[cfg_attr(feature = "serialize", derive(serde::Serialize, serde::Deserialize))]
+pub struct FlowCell {
+    /// The travel distance from `target` to the goal
+    pub cost: f32,
+    /// The point to move towards. Either on the portal to `next` or the goal
+    /// itself.
+    #[cfg_attr(feature = "serialize", serde(with = "crate::serialize::vec2"))]
+    pub target: Vec2,
+    /// The next node towards the goal, None for the goal node
+    pub next: Option<NodeIndex>,
+}
+
+/// Per node directions and costs towards a goal
+#[derive(Debug, Clone)]
+#[cfg_attr(feature = "serialize", derive(serde::Serialize, serde::Deserialize))]
+pub struct FlowField {
+    cells: SecondaryMap<NodeIndex, FlowCell>,
+    #[cfg_attr(feature = "serialize", serde(with = "crate::serialize::vec2"))]
+    goal: Vec2,
+    #[cfg_attr(feature = "serialize", serde(default))]
+    agent_radius: f32,
+}
+
+impl FlowField {
+    /// Generates a flow field towards `goal` for agents of `agent_radius`.
+    pub fn new(tree: &BSPTree, portals: &Portals, goal: Vec2, agent_radius: f32) -> Self {
+        let mut cells = SecondaryMap::new();
+        let mut open = BinaryHeap::new();
+
+        let goal_node = tree.locate(goal).index();
+        cells.insert(
+            goal_node,
+            FlowCell {
+                cost: 0.0,
+                target: goal,
+                next: None,
+            },
+        );
+        open.push((Reverse(OrderedFloat(0.0)), goal_node));
+
+        while let Some((Reverse(OrderedFloat(cost)), current)) = open.pop() {
+            let cell = cells[current];
+            // Outdated entry
+            if cost > cell.cost {
+                continue;
+            }
+
+            // Neighbours which may cross into the current node
+            for portal in portals.incoming(current) {
+                let face = portal.apply_margin(agent_radius);
+                if portal.clearance() < agent_radius {
+                    continue;
+                }
+
+                let target = face.closest_point(cell.target);
+                let cost = cell.cost + target.distance(cell.target);
+
+                match cells.get(portal.dst()) {
+                    Some(v) if v.cost <= cost => continue,
+                    _ => {}
+                }
+
+                cells.insert(
+                    portal.dst(),
+                    FlowCell {
+                        cost,
+                        target,
+                        next: Some(current),
+                    },
+                );
+
+                open.push((Reverse(OrderedFloat(cost)), portal.dst()));
+            }
+        }
+
+        Self {
+            cells,
+            goal,
+            agent_radius,
+        }
+    }
+
+    /// Returns the flow of a node, or None if the goal is unreachable from it
+    pub fn cell(&self, index: NodeIndex) -> Option<&FlowCell> {
+        self.cells.get(index)
+    }
+
+    /// Returns the travel distance from `position` to the goal
+    pub fn cost(&self, tree: &BSPTree, position: Vec2) -> Option<f32> {
+        let cell = self.cell(tree.locate(position).index())?;
+        Some(cell.cost + cell.target.distance(position))
+    }
+
+    /// Returns the normalized direction to move in from `position` to
+    /// approach the goal
+    pub fn direction(&self, tree: &BSPTree, position: Vec2) -> Option<Vec2> {
+        let cell = self.cell(tree.locate(position).index())?;
+        Some((cell.target - position).normalize_or_zero())
+    }
+
+    /// Returns the travel distance from the portal into `index` to the goal,
+    /// or None if the goal is unreachable from it
+    pub fn distance(&self, index: NodeIndex) -> Option<f32> {
+        self.cell(index).map(|cell| cell.cost)
+    }
+
+    /// Returns the path from `start` to the goal by following the field.
+    ///
+    /// No search is performed, which makes repeated queries towards the same
+    /// goal cheap.
+    pub fn path(&self, tree: &BSPTree, portals: &Portals, start: Vec2) -> Result<Path, PathError> {
+        let start_node = tree.locate(start);
+        if start_node.covered() {
+            return Err(PathError::StartCovered);
+        }
+
+        if tree.locate(self.goal).covered() {
+            return Err(PathError::EndCovered);
+        }
+
+        let mut current = start_node.index();
+        let mut cell = self.cell(current).ok_or(PathError::NoRoute)?;
+
+        let mut path = Path::new();
+        path.push(WayPoint::new(start, current, None));
+
+        while let Some(next) = cell.next {
+            // The portal the target was placed on
+            let portal = portals
+                .get(current)
+                .filter(|portal| portal.dst() == next)
+                .min_by_key(|portal| {
+                    let face = portal.apply_margin(self.agent_radius);
+                    OrderedFloat(face.closest_point(cell.target).distance(cell.target))
+                })
+                .map(|portal| portal.portal_ref());
+
+            path.push(WayPoint::new(cell.target, next, portal));
+
+            current = next;
+            cell = self.cell(current).ok_or(PathError::NoRoute)?;
+        }
+
+        path.push(WayPoint::new(self.goal, current, None));
+
+        shorten(portals, &mut path, self.agent_radius);
+        resolve_clip(portals, &mut path, self.agent_radius);
+
+        Ok(path)
+    }
+
+    /// Get the flow field's agent radius.
+    pub fn agent_radius(&self) -> f32 {
+        self.agent_radius
+    }
+
+    /// Get the flow field's goal.
+    pub fn goal(&self) -> Vec2 {
+        self.goal
+    }
+
+    /// Get a reference to the flow of all reachable nodes.
+    pub fn cells(&self) -> &SecondaryMap<NodeIndex, FlowCell> {
+        &self.cells
+    }
+}
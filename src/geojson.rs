@@ -0,0 +1,150 @@
+//! Import of obstacle footprints from GeoJSON, and export of the computed
+//! free space, e.g; to feed building footprints from GIS data into
+//! [NavigationContext::new].
+
+use std::fmt::Display;
+
+use glam::Vec2;
+use serde_json::{json, Value};
+
+use crate::{NavigationContext, Shape};
+
+/// Describes why GeoJSON could not be loaded
+#[derive(Debug, Clone, Copy, PartialEq)]
+pub enum GeoJsonError {
+    /// The input is not valid JSON
+    Syntax { line: usize, column: usize },
+    /// The input is not a GeoJSON object, or has invalid coordinates
+    Malformed,
+}
+
+impl Display for GeoJsonError {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        match self {
+            GeoJsonError::Syntax { line, column } => {
+                write!(f, "Invalid JSON at line {}, column {}", line, column)
+            }
+            GeoJsonError::Malformed => write!(f, "Invalid GeoJSON geometry"),
+        }
+    }
+}
+
+impl std::error::Error for GeoJsonError {}
+
+impl From<serde_json::Error> for GeoJsonError {
+    fn from(e: serde_json::Error) -> Self {
+        Self::Syntax {
+            line: e.line(),
+            column: e.column(),
+        }
+    }
+}
+
+/// Loads the polygons of a GeoJSON object as obstacle shapes, one per ring.
+///
+/// `Polygon` and `MultiPolygon` geometries are supported, in features,
+/// feature collections and geometry collections. Other geometries are
+/// ignored.
+/// The outer ring of each polygon is wound counterclockwise and holes
+/// clockwise, regardless of their winding in the input, which makes the
+/// holes free space inside of the obstacle, e.g; a courtyard.
+pub fn shapes_from_geojson(json: &str) -> Result<Vec<Shape>, GeoJsonError> {
+    let value: Value = serde_json::from_str(json)?;
+    let mut shapes = Vec::new();
+    collect(&value, &mut shapes)?;
+    Ok(shapes)
+}
+
+/// Exports the free space of `nav` as a feature collection of convex
+/// polygons, one for each open leaf, with the area bitmask of the leaf as a
+/// property. See [NavigationContext::to_navmesh].
+pub fn free_space_to_geojson(nav: &NavigationContext) -> String {
+    let mesh = nav.to_navmesh();
+    let features = mesh
+        .polygons
+        .iter()
+        .map(|polygon| {
+            let mut ring = mesh.polygon_vertices(polygon).collect::<Vec<_>>();
+            ring.push(ring[0]);
+
+            json!({
+                "type": "Feature",
+                "geometry": {
+                    "type": "Polygon",
+                    "coordinates": [ring.iter().map(|v| [v.x, v.y]).collect::<Vec<_>>()],
+                },
+                "properties": {
+                    "area": polygon.area,
+                    "neighbours": polygon.neighbours.iter().map(|edge| edge.polygon).collect::<Vec<_>>(),
+                },
+            })
+        })
+        .collect::<Vec<_>>();
+
+    json!({
+        "type": "FeatureCollection",
+        "features": features,
+    })
+    .to_string()
+}
+
+fn collect(value: &Value, shapes: &mut Vec<Shape>) -> Result<(), GeoJsonError> {
+    let kind = value["type"].as_str().ok_or(GeoJsonError::Malformed)?;
+    match kind {
+        "FeatureCollection" => array(&value["features"])?
+            .iter()
+            .try_for_each(|feature| collect(feature, shapes)),
+        "Feature" => match &value["geometry"] {
+            Value::Null => Ok(()),
+            geometry => collect(geometry, shapes),
+        },
+        "GeometryCollection" => array(&value["geometries"])?
+            .iter()
+            .try_for_each(|geometry| collect(geometry, shapes)),
+        "Polygon" => polygon(&value["coordinates"], shapes),
+        "MultiPolygon" => array(&value["coordinates"])?
+            .iter()
+            .try_for_each(|coordinates| polygon(coordinates, shapes)),
+        _ => Ok(()),
+    }
+}
+
+fn polygon(coordinates: &Value, shapes: &mut Vec<Shape>) -> Result<(), GeoJsonError> {
+    for (i, ring) in array(coordinates)?.iter().enumerate() {
+        let mut vertices = array(ring)?
+            .iter()
+            .map(|position| match array(position)?.as_slice() {
+                [x, y, ..] => Ok(Vec2::new(
+                    x.as_f64().ok_or(GeoJsonError::Malformed)? as f32,
+                    y.as_f64().ok_or(GeoJsonError::Malformed)? as f32,
+                )),
+                _ => Err(GeoJsonError::Malformed),
+            })
+            .collect::<Result<Vec<_>, _>>()?;
+
+        if vertices.len() < 3 {
+            return Err(GeoJsonError::Malformed);
+        }
+
+        if vertices.first() != vertices.last() {
+            vertices.push(vertices[0]);
+        }
+
+        // Outer rings counterclockwise, holes clockwise
+        let area = vertices
+            .windows(2)
+            .map(|w| w[0].perp_dot(w[1]))
+            .sum::<f32>();
+        if (area < 0.0) == (i == 0) {
+            vertices.reverse();
+        }
+
+        shapes.push(Shape::new(&vertices));
+    }
+
+    Ok(())
+}
+
+fn array(value: &Value) -> Result<&Vec<Value>, GeoJsonError> {
+    value.as_array().ok_or(GeoJsonError::Malformed)
+}
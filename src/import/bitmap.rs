@@ -0,0 +1,103 @@
+//! Loads obstacles from images, e.g; a map painted in black and white.
+
+use glam::Vec2;
+use image::{DynamicImage, ImageError};
+
+use crate::Shape;
+
+/// Dark pixels with a luminance below this are solid
+const THRESHOLD: u8 = 128;
+
+/// Decodes a PNG and traces the outlines of its dark pixels, see
+/// [shapes_from_image].
+pub fn shapes_from_png(bytes: &[u8], tolerance: f32) -> Result<Vec<Shape>, ImageError> {
+    let image = image::load_from_memory_with_format(bytes, image::ImageFormat::Png)?;
+    Ok(shapes_from_image(&image, tolerance))
+}
+
+/// Traces the outlines of the dark pixels of an image, which become
+/// obstacles.
+///
+/// The outlines are simplified to deviate at most `tolerance` from the
+/// pixel edges, and islands smaller than that are removed. A pixel is one
+/// unit, and the y axis is flipped to point up, which places the image
+/// between `(0, -height)` and `(width, 0)`.
+pub fn shapes_from_image(image: &DynamicImage, tolerance: f32) -> Vec<Shape> {
+    let image = image.to_luma8();
+    let (width, height) = image.dimensions();
+
+    let shapes = Shape::from_grid(width as usize, height as usize, |x, y| {
+        image.get_pixel(x as u32, height - 1 - y as u32)[0] < THRESHOLD
+    });
+
+    let offset = Vec2::new(0.0, height as f32);
+    shapes
+        .iter()
+        .filter_map(|shape| {
+            let points = shape.vertices();
+            let mut points = simplify(&points[..points.len() - 1], tolerance);
+            if points.len() < 3 {
+                return None;
+            }
+
+            points.iter_mut().for_each(|p| *p -= offset);
+            points.push(points[0]);
+            Some(Shape::new(&points))
+        })
+        .collect()
+}
+
+/// Simplifies a closed outline using Douglas-Peucker
+fn simplify(points: &[Vec2], tolerance: f32) -> Vec<Vec2> {
+    if tolerance <= 0.0 || points.len() < 4 {
+        return points.to_vec();
+    }
+
+    // Split the outline at the point farthest from the first
+    let far = (0..points.len())
+        .max_by(|&a, &b| {
+            let (a, b) = (points[a], points[b]);
+            a.distance_squared(points[0])
+                .total_cmp(&b.distance_squared(points[0]))
+        })
+        .expect("Outline is not empty");
+
+    let mut result = Vec::new();
+    simplify_open(&points[..=far], tolerance, &mut result);
+
+    let rest = points[far..]
+        .iter()
+        .copied()
+        .chain([points[0]])
+        .collect::<Vec<_>>();
+    simplify_open(&rest, tolerance, &mut result);
+
+    result
+}
+
+/// Pushes the simplified points, except the last
+fn simplify_open(points: &[Vec2], tolerance: f32, result: &mut Vec<Vec2>) {
+    let (first, last) = (points[0], points[points.len() - 1]);
+
+    let farthest = points
+        .iter()
+        .enumerate()
+        .take(points.len() - 1)
+        .skip(1)
+        .map(|(i, &p)| (i, distance_to_segment(p, first, last)))
+        .max_by(|a, b| a.1.total_cmp(&b.1));
+
+    match farthest {
+        Some((i, dist)) if dist > tolerance => {
+            simplify_open(&points[..=i], tolerance, result);
+            simplify_open(&points[i..], tolerance, result);
+        }
+        _ => result.push(first),
+    }
+}
+
+fn distance_to_segment(p: Vec2, a: Vec2, b: Vec2) -> f32 {
+    let rel = b - a;
+    let t = ((p - a).dot(rel) / rel.length_squared().max(f32::EPSILON)).clamp(0.0, 1.0);
+    p.distance(a + rel * t)
+}
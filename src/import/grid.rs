@@ -0,0 +1,83 @@
+use alloc::{collections::BTreeSet, vec::Vec};
+
+use glam::Vec2;
+
+use crate::Shape;
+
+/// The directions of the edges of the contour, counterclockwise
+const DIRECTIONS: [(i32, i32); 4] = [(1, 0), (0, 1), (-1, 0), (0, -1)];
+
+impl Shape {
+    /// Traces the outlines of the blocked cells of a grid, e.g; an occupancy
+    /// grid or a roguelike map.
+    ///
+    /// The cell `(x, y)` covers `x..x + 1` and `y..y + 1`, and cells outside
+    /// the grid are free. Each connected blocked region becomes a
+    /// counterclockwise outline, and free pockets inside a region become
+    /// clockwise outlines. Cells which only touch at a corner are not
+    /// connected.
+    pub fn from_grid(
+        width: usize,
+        height: usize,
+        mut is_blocked: impl FnMut(usize, usize) -> bool,
+    ) -> Vec<Shape> {
+        let blocked = (0..height)
+            .flat_map(|y| (0..width).map(move |x| (x, y)))
+            .filter(|&(x, y)| is_blocked(x, y))
+            .map(|(x, y)| (x as i32, y as i32))
+            .collect::<BTreeSet<_>>();
+
+        // The edges between blocked and free cells, with the blocked cell to
+        // the left
+        let mut edges = BTreeSet::new();
+        for &(x, y) in &blocked {
+            let corners = [(x, y), (x + 1, y), (x + 1, y + 1), (x, y + 1)];
+            let neighbours = [(x, y - 1), (x + 1, y), (x, y + 1), (x - 1, y)];
+
+            for (dir, (corner, neighbour)) in corners.iter().zip(neighbours).enumerate() {
+                if !blocked.contains(&neighbour) {
+                    edges.insert((*corner, dir));
+                }
+            }
+        }
+
+        // Turn left where possible, which keeps regions touching at a corner
+        // separate
+        let next = |((x, y), dir): ((i32, i32), usize)| {
+            let end = (x + DIRECTIONS[dir].0, y + DIRECTIONS[dir].1);
+            [1, 0, 3]
+                .iter()
+                .map(|turn| (end, (dir + turn) % 4))
+                .find(|edge| edges.contains(edge))
+                .expect("Contours are closed")
+        };
+
+        let mut remaining = edges.clone();
+        let mut shapes = Vec::new();
+        while let Some(&first) = remaining.iter().next() {
+            let mut contour = Vec::new();
+            let mut edge = first;
+            loop {
+                remaining.remove(&edge);
+                contour.push(edge);
+                edge = next(edge);
+                if edge == first {
+                    break;
+                }
+            }
+
+            // Only keep the corners
+            let mut points = contour
+                .iter()
+                .zip(contour.iter().cycle().skip(contour.len() - 1))
+                .filter(|((_, dir), (_, prev))| dir != prev)
+                .map(|(&((x, y), _), _)| Vec2::new(x as f32, y as f32))
+                .collect::<Vec<_>>();
+
+            points.push(points[0]);
+            shapes.push(Shape::new(&points));
+        }
+
+        shapes
+    }
+}
@@ -0,0 +1,37 @@
+//! Loads obstacle geometry authored in other tools, e.g; maps drawn in a
+//! vector editor, painted images, grid maps or physics colliders.
+
+use alloc::vec::Vec;
+
+use glam::Vec2;
+
+use crate::Shape;
+
+#[cfg(feature = "image")]
+pub mod bitmap;
+mod grid;
+#[cfg(feature = "parry2d")]
+mod parry;
+pub mod svg;
+#[cfg(feature = "tiled")]
+mod tiled;
+
+/// Flips the y axis of an outline in screen coordinates. Closed outlines are
+/// oriented counterclockwise, which makes them obstacles.
+pub(crate) fn outline(points: Vec<Vec2>, closed: bool) -> Option<Shape> {
+    let mut points = points
+        .into_iter()
+        .map(|p| Vec2::new(p.x, -p.y))
+        .collect::<Vec<_>>();
+    points.dedup();
+
+    if points.len() < 2 {
+        return None;
+    }
+
+    if closed {
+        Some(Shape::polygon(&points))
+    } else {
+        Some(Shape::new(&points))
+    }
+}
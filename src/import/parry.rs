@@ -0,0 +1,143 @@
+use std::collections::HashMap;
+
+use glam::Vec2;
+use parry2d::{
+    math::{Isometry, Point, Real},
+    shape::{ConvexPolygon, Cuboid, Segment, TriMesh, TypedShape},
+};
+
+use crate::{Face, Shape};
+
+/// The number of segments of flattened balls
+const CIRCLE_SEGMENTS: usize = 16;
+
+impl Shape {
+    /// Converts a parry collider shape at `position` into obstacles, e.g;
+    /// the colliders of a rapier physics world.
+    ///
+    /// Cuboids, convex polygons, triangles, balls, segments and the outlines
+    /// of triangle meshes are supported, and compound shapes are converted
+    /// part by part. Other shapes are ignored.
+    pub fn from_parry(shape: &dyn parry2d::shape::Shape, position: &Isometry<Real>) -> Vec<Shape> {
+        let transform = |points: &[Point<Real>]| {
+            points
+                .iter()
+                .map(|p| to_vec2(position * p))
+                .collect::<Vec<_>>()
+        };
+
+        match shape.as_typed_shape() {
+            TypedShape::Cuboid(cuboid) => vec![polygon(transform(&corners(cuboid)))],
+            TypedShape::ConvexPolygon(polygon) => {
+                vec![self::polygon(transform(polygon.points()))]
+            }
+            TypedShape::Triangle(triangle) => {
+                vec![polygon(transform(triangle.vertices()))]
+            }
+            TypedShape::Ball(ball) => {
+                let points = (0..CIRCLE_SEGMENTS)
+                    .map(|i| {
+                        let angle = std::f32::consts::TAU * i as f32 / CIRCLE_SEGMENTS as f32;
+                        Point::new(angle.cos(), angle.sin()) * ball.radius
+                    })
+                    .collect::<Vec<_>>();
+                vec![polygon(transform(&points))]
+            }
+            TypedShape::Segment(segment) => vec![Shape::new(&transform(&[segment.a, segment.b]))],
+            TypedShape::TriMesh(mesh) => outline(mesh)
+                .into_iter()
+                .map(|points| polygon(transform(&points)))
+                .collect(),
+            TypedShape::Compound(compound) => compound
+                .shapes()
+                .iter()
+                .flat_map(|(local, shape)| Shape::from_parry(shape.as_ref(), &(position * local)))
+                .collect(),
+            _ => Vec::new(),
+        }
+    }
+}
+
+impl From<&Cuboid> for Shape {
+    fn from(cuboid: &Cuboid) -> Self {
+        polygon(corners(cuboid).iter().copied().map(to_vec2).collect())
+    }
+}
+
+impl From<&ConvexPolygon> for Shape {
+    fn from(polygon: &ConvexPolygon) -> Self {
+        self::polygon(polygon.points().iter().copied().map(to_vec2).collect())
+    }
+}
+
+impl From<Segment> for Face {
+    fn from(segment: Segment) -> Self {
+        Face::new([to_vec2(segment.a), to_vec2(segment.b)])
+    }
+}
+
+fn to_vec2(p: Point<Real>) -> Vec2 {
+    Vec2::new(p.x, p.y)
+}
+
+fn corners(cuboid: &Cuboid) -> [Point<Real>; 4] {
+    let he = cuboid.half_extents;
+    [
+        Point::new(-he.x, -he.y),
+        Point::new(he.x, -he.y),
+        Point::new(he.x, he.y),
+        Point::new(-he.x, he.y),
+    ]
+}
+
+/// Closes the polygon and winds it counterclockwise
+fn polygon(points: Vec<Vec2>) -> Shape {
+    Shape::polygon(&points)
+}
+
+/// Returns the boundary loops of the mesh, i.e; the chained edges which only
+/// belong to a single triangle
+fn outline(mesh: &TriMesh) -> Vec<Vec<Point<Real>>> {
+    let vertices = mesh.vertices();
+
+    // Count each undirected edge, keeping the direction of a counterclockwise
+    // triangle
+    let mut edges = HashMap::new();
+    for &[a, b, c] in mesh.indices() {
+        let [pa, pb, pc] = [a, b, c].map(|i| vertices[i as usize]);
+        let ccw = (pb - pa).perp(&(pc - pa)) >= 0.0;
+        let [a, b, c] = if ccw { [a, b, c] } else { [a, c, b] };
+
+        for (from, to) in [(a, b), (b, c), (c, a)] {
+            edges
+                .entry((from.min(to), from.max(to)))
+                .and_modify(|(_, count)| *count += 1)
+                .or_insert(((from, to), 1));
+        }
+    }
+
+    let mut next = edges
+        .into_values()
+        .filter(|(_, count)| *count == 1)
+        .map(|(edge, _)| edge)
+        .collect::<HashMap<_, _>>();
+
+    let mut starts = next.keys().copied().collect::<Vec<_>>();
+    starts.sort_unstable();
+
+    let mut loops = Vec::new();
+    for start in starts {
+        let mut points = Vec::new();
+        let mut current = start;
+        while let Some(to) = next.remove(&current) {
+            points.push(vertices[current as usize]);
+            current = to;
+        }
+
+        if points.len() >= 3 {
+            loops.push(points);
+        }
+    }
+
+    loops
+}
@@ -0,0 +1,426 @@
+//! Loads obstacles from the `<path>`, `<polygon>` and `<polyline>` elements
+//! of an SVG document, e.g; a map drawn in Inkscape.
+//!
+//! The y axis is flipped to point up, matching
+//! [export::svg](crate::export::svg). Transforms and styles are not applied.
+
+use alloc::{vec, vec::Vec};
+use core::{f32::consts::TAU, fmt::Display};
+
+use glam::Vec2;
+#[cfg(not(feature = "std"))]
+use num_traits::Float;
+
+use crate::Shape;
+
+use super::outline;
+
+/// Describes why an SVG document could not be loaded
+#[derive(Debug, Clone, Copy, PartialEq)]
+pub enum SvgError {
+    /// The path data or points of the element at the byte `offset` of the
+    /// document are invalid
+    InvalidElement { offset: usize },
+}
+
+impl Display for SvgError {
+    fn fmt(&self, f: &mut core::fmt::Formatter<'_>) -> core::fmt::Result {
+        match self {
+            SvgError::InvalidElement { offset } => {
+                write!(f, "Invalid path data in element at offset {}", offset)
+            }
+        }
+    }
+}
+
+impl core::error::Error for SvgError {}
+
+/// Loads the outlines of the path, polygon and polyline elements as shapes,
+/// one for each subpath.
+///
+/// Curves and arcs are flattened into line segments deviating at most
+/// `tolerance` from the curve. Closed outlines become obstacles, and are
+/// wound counterclockwise regardless of the direction they were drawn in.
+pub fn shapes_from_svg(svg: &str, tolerance: f32) -> Result<Vec<Shape>, SvgError> {
+    let tolerance = tolerance.max(1e-3);
+    let mut shapes = Vec::new();
+
+    let mut pos = 0;
+    while let Some(start) = svg[pos..].find('<').map(|i| i + pos) {
+        let end = svg[start..].find('>').map_or(svg.len(), |i| start + i);
+        let tag = &svg[start + 1..end];
+        pos = end;
+
+        let name = tag
+            .split(|c: char| c.is_whitespace() || c == '/')
+            .next()
+            .unwrap_or_default();
+
+        let error = SvgError::InvalidElement { offset: start };
+        let outlines = match name {
+            "path" => match attribute(tag, "d") {
+                Some(data) => parse_path(data, tolerance).ok_or(error)?,
+                None => continue,
+            },
+            "polygon" | "polyline" => match attribute(tag, "points") {
+                Some(points) => {
+                    let points = parse_points(points).ok_or(error)?;
+                    vec![(points, name == "polygon")]
+                }
+                None => continue,
+            },
+            _ => continue,
+        };
+
+        shapes.extend(
+            outlines
+                .into_iter()
+                .filter_map(|(points, closed)| outline(points, closed)),
+        );
+    }
+
+    Ok(shapes)
+}
+
+/// Returns the value of the attribute `name` of the tag
+fn attribute<'a>(tag: &'a str, name: &str) -> Option<&'a str> {
+    let mut rest = tag;
+    while let Some(i) = rest.find(name) {
+        let before = rest[..i].chars().next_back();
+        let after = rest[i + name.len()..].trim_start();
+        rest = &rest[i + name.len()..];
+
+        if !before.is_some_and(char::is_whitespace) {
+            continue;
+        }
+
+        let value = match after.strip_prefix('=') {
+            Some(value) => value.trim_start(),
+            None => continue,
+        };
+
+        let quote = value.chars().next()?;
+        if quote != '"' && quote != '\'' {
+            return None;
+        }
+
+        let value = &value[1..];
+        return value.find(quote).map(|end| &value[..end]);
+    }
+
+    None
+}
+
+fn parse_points(points: &str) -> Option<Vec<Vec2>> {
+    let mut tokens = Tokens::new(points);
+    let mut result = Vec::new();
+    while !tokens.is_empty() {
+        result.push(tokens.point()?);
+    }
+
+    Some(result)
+}
+
+/// Returns the flattened subpaths of the path data, and if they are closed
+fn parse_path(data: &str, tolerance: f32) -> Option<Vec<(Vec<Vec2>, bool)>> {
+    let mut tokens = Tokens::new(data);
+    let mut subpaths = Vec::new();
+    let mut points: Vec<Vec2> = Vec::new();
+
+    let mut current = Vec2::ZERO;
+    let mut start = Vec2::ZERO;
+    let mut command = None;
+    // The last control point, for smooth curves
+    let mut control = None;
+
+    while !tokens.is_empty() {
+        let cmd = match tokens.command() {
+            Some(cmd) => cmd,
+            // Repeated arguments of the previous command
+            None => match command? {
+                b'M' => b'L',
+                b'm' => b'l',
+                b'Z' | b'z' => return None,
+                cmd => cmd,
+            },
+        };
+
+        let relative = cmd.is_ascii_lowercase();
+        let offset = if relative { current } else { Vec2::ZERO };
+        let mut next_control = None;
+
+        match cmd.to_ascii_uppercase() {
+            b'M' => {
+                if points.len() > 1 {
+                    subpaths.push((core::mem::take(&mut points), false));
+                }
+                current = tokens.point()? + offset;
+                start = current;
+                points = vec![current];
+            }
+            b'L' => current = tokens.point()? + offset,
+            b'H' => current.x = tokens.number()? + offset.x,
+            b'V' => current.y = tokens.number()? + offset.y,
+            b'C' | b'S' => {
+                let c1 = match cmd.to_ascii_uppercase() {
+                    b'C' => tokens.point()? + offset,
+                    _ => match (command, control) {
+                        (Some(b'C' | b'c' | b'S' | b's'), Some(c)) => current * 2.0 - c,
+                        _ => current,
+                    },
+                };
+                let c2 = tokens.point()? + offset;
+                let end = tokens.point()? + offset;
+
+                cubic(&mut points, [current, c1, c2, end], tolerance);
+                next_control = Some(c2);
+                current = end;
+            }
+            b'Q' | b'T' => {
+                let c = match cmd.to_ascii_uppercase() {
+                    b'Q' => tokens.point()? + offset,
+                    _ => match (command, control) {
+                        (Some(b'Q' | b'q' | b'T' | b't'), Some(c)) => current * 2.0 - c,
+                        _ => current,
+                    },
+                };
+                let end = tokens.point()? + offset;
+
+                quadratic(&mut points, [current, c, end], tolerance);
+                next_control = Some(c);
+                current = end;
+            }
+            b'A' => {
+                let radii = tokens.point()?;
+                let rotation = tokens.number()?.to_radians();
+                let large = tokens.flag()?;
+                let sweep = tokens.flag()?;
+                let end = tokens.point()? + offset;
+
+                arc(
+                    &mut points,
+                    current,
+                    end,
+                    radii,
+                    rotation,
+                    large,
+                    sweep,
+                    tolerance,
+                );
+                current = end;
+            }
+            b'Z' => {
+                current = start;
+                if points.len() > 1 {
+                    subpaths.push((core::mem::take(&mut points), true));
+                }
+                points = vec![start];
+            }
+            _ => return None,
+        }
+
+        if !matches!(cmd, b'M' | b'm' | b'Z' | b'z') {
+            if points.is_empty() {
+                points.push(start);
+            }
+            points.push(current);
+        }
+
+        command = Some(cmd);
+        control = next_control;
+    }
+
+    if points.len() > 1 {
+        subpaths.push((points, false));
+    }
+
+    Some(subpaths)
+}
+
+/// Pushes the points of a cubic bezier, except the start and end
+fn cubic(points: &mut Vec<Vec2>, [p0, p1, p2, p3]: [Vec2; 4], tolerance: f32) {
+    // The deviation of a uniform subdivision is bounded by the second
+    // derivative
+    let dd = (p0 - 2.0 * p1 + p2)
+        .length()
+        .max((p1 - 2.0 * p2 + p3).length());
+    let n = ((6.0 * dd / (8.0 * tolerance)).sqrt().ceil() as usize).clamp(1, 1024);
+
+    points.extend((1..n).map(|i| {
+        let t = i as f32 / n as f32;
+        let u = 1.0 - t;
+        p0 * u * u * u + p1 * 3.0 * u * u * t + p2 * 3.0 * u * t * t + p3 * t * t * t
+    }));
+}
+
+/// Pushes the points of a quadratic bezier, except the start and end
+fn quadratic(points: &mut Vec<Vec2>, [p0, p1, p2]: [Vec2; 3], tolerance: f32) {
+    let dd = (p0 - 2.0 * p1 + p2).length();
+    let n = ((2.0 * dd / (8.0 * tolerance)).sqrt().ceil() as usize).clamp(1, 1024);
+
+    points.extend((1..n).map(|i| {
+        let t = i as f32 / n as f32;
+        let u = 1.0 - t;
+        p0 * u * u + p1 * 2.0 * u * t + p2 * t * t
+    }));
+}
+
+/// Pushes the points of an elliptical arc, except the start and end.
+/// See the SVG specification's endpoint to center parameterization.
+#[allow(clippy::too_many_arguments)]
+fn arc(
+    points: &mut Vec<Vec2>,
+    from: Vec2,
+    to: Vec2,
+    radii: Vec2,
+    rotation: f32,
+    large: bool,
+    sweep: bool,
+    tolerance: f32,
+) {
+    let mut radii = radii.abs();
+    if radii.x < f32::EPSILON || radii.y < f32::EPSILON || from == to {
+        return;
+    }
+
+    let (sin, cos) = rotation.sin_cos();
+    let rotate = |v: Vec2| Vec2::new(cos * v.x - sin * v.y, sin * v.x + cos * v.y);
+    let unrotate = |v: Vec2| Vec2::new(cos * v.x + sin * v.y, -sin * v.x + cos * v.y);
+
+    let p = unrotate((from - to) / 2.0);
+
+    // Scale up radii too small to reach the end
+    let lambda = (p / radii).length_squared();
+    if lambda > 1.0 {
+        radii *= lambda.sqrt();
+    }
+
+    let (rx2, ry2) = (radii.x * radii.x, radii.y * radii.y);
+    let num = rx2 * ry2 - rx2 * p.y * p.y - ry2 * p.x * p.x;
+    let den = rx2 * p.y * p.y + ry2 * p.x * p.x;
+    let mut coef = (num / den).max(0.0).sqrt();
+    if large == sweep {
+        coef = -coef;
+    }
+
+    let c = coef * Vec2::new(radii.x * p.y / radii.y, -radii.y * p.x / radii.x);
+    let center = rotate(c) + (from + to) / 2.0;
+
+    let angle = |v: Vec2| v.y.atan2(v.x);
+    let start = angle((p - c) / radii);
+    let mut delta = angle((-p - c) / radii) - start;
+    if sweep && delta < 0.0 {
+        delta += TAU;
+    } else if !sweep && delta > 0.0 {
+        delta -= TAU;
+    }
+
+    let radius = radii.max_element();
+    let step = 2.0 * (1.0 - tolerance / radius).clamp(-1.0, 1.0).acos();
+    let n = ((delta.abs() / step.max(1e-3)).ceil() as usize).clamp(1, 1024);
+
+    points.extend((1..n).map(|i| {
+        let theta = start + delta * i as f32 / n as f32;
+        center + rotate(Vec2::new(radii.x * theta.cos(), radii.y * theta.sin()))
+    }));
+}
+
+/// Splits path data or points into commands and numbers
+struct Tokens<'a> {
+    s: &'a [u8],
+    pos: usize,
+}
+
+impl<'a> Tokens<'a> {
+    fn new(s: &'a str) -> Self {
+        Self {
+            s: s.as_bytes(),
+            pos: 0,
+        }
+    }
+
+    fn skip_separators(&mut self) {
+        while self
+            .s
+            .get(self.pos)
+            .is_some_and(|c| c.is_ascii_whitespace() || *c == b',')
+        {
+            self.pos += 1;
+        }
+    }
+
+    fn is_empty(&mut self) -> bool {
+        self.skip_separators();
+        self.pos >= self.s.len()
+    }
+
+    fn command(&mut self) -> Option<u8> {
+        self.skip_separators();
+        let c = *self.s.get(self.pos)?;
+        if c.is_ascii_alphabetic() && c != b'e' && c != b'E' {
+            self.pos += 1;
+            Some(c)
+        } else {
+            None
+        }
+    }
+
+    fn number(&mut self) -> Option<f32> {
+        self.skip_separators();
+        let start = self.pos;
+        let digits = |this: &mut Self| {
+            let from = this.pos;
+            while this.s.get(this.pos).is_some_and(u8::is_ascii_digit) {
+                this.pos += 1;
+            }
+            this.pos > from
+        };
+
+        if matches!(self.s.get(self.pos), Some(b'+' | b'-')) {
+            self.pos += 1;
+        }
+
+        let mut any = digits(self);
+        if self.s.get(self.pos) == Some(&b'.') {
+            self.pos += 1;
+            any |= digits(self);
+        }
+
+        if !any {
+            self.pos = start;
+            return None;
+        }
+
+        if matches!(self.s.get(self.pos), Some(b'e' | b'E')) {
+            let mark = self.pos;
+            self.pos += 1;
+            if matches!(self.s.get(self.pos), Some(b'+' | b'-')) {
+                self.pos += 1;
+            }
+            if !digits(self) {
+                self.pos = mark;
+            }
+        }
+
+        core::str::from_utf8(&self.s[start..self.pos])
+            .ok()?
+            .parse()
+            .ok()
+    }
+
+    fn point(&mut self) -> Option<Vec2> {
+        Some(Vec2::new(self.number()?, self.number()?))
+    }
+
+    /// Arc flags may be written without separators, e.g; `a 1 1 0 01 5 5`
+    fn flag(&mut self) -> Option<bool> {
+        self.skip_separators();
+        let flag = match self.s.get(self.pos)? {
+            b'0' => false,
+            b'1' => true,
+            _ => return None,
+        };
+        self.pos += 1;
+        Some(flag)
+    }
+}
@@ -0,0 +1,193 @@
+use std::collections::BTreeSet;
+
+use glam::Vec2;
+use tiled::{
+    ChunkData, Layer, LayerTile, LayerType, Map, Object, ObjectShape, PropertyValue, TileLayer,
+};
+
+use crate::Shape;
+
+use super::outline;
+
+/// The number of segments of flattened ellipses
+const ELLIPSE_SEGMENTS: usize = 16;
+
+impl Shape {
+    /// Converts the obstacles of a Tiled map, e.g; loaded from a TMX file
+    /// using [tiled::Loader].
+    ///
+    /// The shapes of all visible object layers become obstacles, except
+    /// points and text. A tile is blocked if it has collision shapes or a
+    /// `collides` property set to true. Adjacent blocked tiles are merged into
+    /// larger rectangles, which gives fewer faces to partition. Tile layers
+    /// are assumed to be orthogonal.
+    ///
+    /// The y axis is flipped to point up, and a pixel of the map is one unit.
+    pub fn from_tiled(map: &Map) -> Vec<Shape> {
+        let mut shapes = Vec::new();
+        for layer in map.layers() {
+            shapes_from_layer(map, layer, Vec2::ZERO, &mut shapes);
+        }
+
+        shapes
+    }
+}
+
+fn shapes_from_layer(map: &Map, layer: Layer, offset: Vec2, shapes: &mut Vec<Shape>) {
+    if !layer.visible {
+        return;
+    }
+
+    let offset = offset + Vec2::new(layer.offset_x, layer.offset_y);
+    match layer.layer_type() {
+        LayerType::Objects(layer) => shapes.extend(
+            layer
+                .objects()
+                .filter(|object| object.visible)
+                .filter_map(|object| shape_from_object(&object, offset)),
+        ),
+        LayerType::Tiles(layer) => {
+            let tile_size = Vec2::new(map.tile_width as f32, map.tile_height as f32);
+            shapes.extend(
+                merge(&blocked_tiles(&layer))
+                    .into_iter()
+                    .filter_map(|(min, max)| {
+                        let min = min * tile_size + offset;
+                        let max = max * tile_size + offset;
+                        outline(
+                            vec![min, Vec2::new(max.x, min.y), max, Vec2::new(min.x, max.y)],
+                            true,
+                        )
+                    }),
+            )
+        }
+        LayerType::Group(group) => {
+            for layer in group.layers() {
+                shapes_from_layer(map, layer, offset, shapes)
+            }
+        }
+        LayerType::Image(_) => {}
+    }
+}
+
+fn shape_from_object(object: &Object, offset: Vec2) -> Option<Shape> {
+    let (points, closed) = match &object.shape {
+        // Tile objects are anchored at their bottom left corner
+        ObjectShape::Rect { width, height } if object.tile_data().is_some() => (
+            vec![
+                Vec2::new(0.0, -height),
+                Vec2::new(*width, -height),
+                Vec2::new(*width, 0.0),
+                Vec2::ZERO,
+            ],
+            true,
+        ),
+        ObjectShape::Rect { width, height } => (
+            vec![
+                Vec2::ZERO,
+                Vec2::new(*width, 0.0),
+                Vec2::new(*width, *height),
+                Vec2::new(0.0, *height),
+            ],
+            true,
+        ),
+        ObjectShape::Ellipse { width, height } => {
+            let radii = Vec2::new(*width, *height) / 2.0;
+            let points = (0..ELLIPSE_SEGMENTS)
+                .map(|i| {
+                    let (sin, cos) =
+                        (std::f32::consts::TAU * i as f32 / ELLIPSE_SEGMENTS as f32).sin_cos();
+                    radii + radii * Vec2::new(cos, sin)
+                })
+                .collect();
+            (points, true)
+        }
+        ObjectShape::Polygon { points } => (points.iter().map(|&p| p.into()).collect(), true),
+        ObjectShape::Polyline { points } => (points.iter().map(|&p| p.into()).collect(), false),
+        ObjectShape::Point(..) | ObjectShape::Text { .. } => return None,
+    };
+
+    // Objects are rotated clockwise around their position
+    let (sin, cos) = object.rotation.to_radians().sin_cos();
+    let position = Vec2::new(object.x, object.y) + offset;
+    let points = points
+        .into_iter()
+        .map(|p| position + Vec2::new(cos * p.x - sin * p.y, sin * p.x + cos * p.y))
+        .collect();
+
+    outline(points, closed)
+}
+
+fn is_blocked(tile: &LayerTile) -> bool {
+    tile.get_tile().is_some_and(|tile| {
+        tile.collision.is_some()
+            || matches!(
+                tile.properties.get("collides"),
+                Some(PropertyValue::BoolValue(true))
+            )
+    })
+}
+
+/// Returns the blocked tile coordinates, ordered by row
+fn blocked_tiles(layer: &TileLayer) -> BTreeSet<(i32, i32)> {
+    let mut blocked = BTreeSet::new();
+    let mut visit = |x: i32, y: i32, tile: Option<LayerTile>| {
+        if tile.is_some_and(|tile| is_blocked(&tile)) {
+            blocked.insert((y, x));
+        }
+    };
+
+    match layer {
+        TileLayer::Finite(layer) => {
+            for y in 0..layer.height() as i32 {
+                for x in 0..layer.width() as i32 {
+                    visit(x, y, layer.get_tile(x, y));
+                }
+            }
+        }
+        TileLayer::Infinite(layer) => {
+            let (width, height) = (ChunkData::WIDTH as i32, ChunkData::HEIGHT as i32);
+            for ((cx, cy), chunk) in layer.chunks() {
+                for y in 0..height {
+                    for x in 0..width {
+                        visit(cx * width + x, cy * height + y, chunk.get_tile(x, y));
+                    }
+                }
+            }
+        }
+    }
+
+    blocked
+}
+
+/// Greedily merges blocked tiles into rectangles, first along rows and then
+/// downwards. Returns the min and max corners in tiles.
+fn merge(blocked: &BTreeSet<(i32, i32)>) -> Vec<(Vec2, Vec2)> {
+    let mut remaining = blocked.clone();
+    let mut rects = Vec::new();
+
+    while let Some(&(y, x)) = remaining.iter().next() {
+        let mut width = 1;
+        while remaining.contains(&(y, x + width)) {
+            width += 1;
+        }
+
+        let mut height = 1;
+        while (x..x + width).all(|x| remaining.contains(&(y + height, x))) {
+            height += 1;
+        }
+
+        for y in y..y + height {
+            for x in x..x + width {
+                remaining.remove(&(y, x));
+            }
+        }
+
+        rects.push((
+            Vec2::new(x as f32, y as f32),
+            Vec2::new((x + width) as f32, (y + height) as f32),
+        ));
+    }
+
+    rects
+}
@@ -1,11 +1,30 @@
+use alloc::vec::Vec;
+
 use glam::Vec2;
 use itertools::Itertools;
+#[cfg(not(feature = "std"))]
+use num_traits::Float;
 use ordered_float::NotNan;
 
 use crate::{
-    astar, BSPNode, BSPTree, Face, NodeIndex, NodePayload, Path, Portals, SearchInfo, TOLERANCE,
+    astar, BSPNode, BSPTree, Face, NodeIndex, NodePayload, Path, PathError, Portals, SearchInfo,
+    TOLERANCE,
 };
 
+/// Identifies a single layer of a [LayeredNavigationContext].
+/// Obtained from [LayeredNavigationContext::layer_of] or
+/// [LayeredNavigationContext::layer_ids].
+#[derive(Clone, Copy, Debug, PartialEq, Eq, PartialOrd, Ord, Hash)]
+#[cfg_attr(feature = "serialize", derive(serde::Serialize, serde::Deserialize))]
+pub struct LayerId(usize);
+
+impl LayerId {
+    /// Returns the index of the layer, ordered by ascending height
+    pub fn index(&self) -> usize {
+        self.0
+    }
+}
+
 /// Contains a layered graph and edges necessary for path finding
 #[derive(Default)]
 #[cfg_attr(feature = "serialize", derive(serde::Serialize, serde::Deserialize))]
@@ -19,7 +38,7 @@ impl LayeredNavigationContext {
             .into_iter()
             .map(|(k, v)| (NotNan::new(k).unwrap(), v))
             .sorted_by_key(|(k, _)| *k)
-            .group_by(|(k, _)| (k / TOLERANCE).round() as i32)
+            .group_by(|(k, _)| bucket(k.into_inner()))
             .into_iter()
             .flat_map(|(k, faces)| {
                 let tree = BSPTree::new(faces.map(|(_, v)| v).collect_vec())?;
@@ -36,56 +55,73 @@ impl LayeredNavigationContext {
         self.layers.iter()
     }
 
-    pub fn locate(&self, layer: f32, point: Vec2) -> Option<NodePayload> {
-        self.layer(layer).map(|v| v.1.locate(point))
+    /// Returns the ids of all layers, ordered by ascending height
+    pub fn layer_ids(&self) -> impl Iterator<Item = LayerId> {
+        (0..self.layers.len()).map(LayerId)
     }
 
-    pub fn layer(&self, layer: f32) -> Option<&(NotNan<f32>, BSPTree, Portals)> {
-        let layer = NotNan::new(layer).ok()?;
-        let mut slice = &self.layers[..];
-        loop {
-            let i = slice.len() / 2;
-            let mid = slice.get(i)?;
-
-            if slice.len() == 1 {
-                return Some(mid);
-            }
-
-            match layer.cmp(&mid.0) {
-                std::cmp::Ordering::Less => slice = &slice[0..i],
-                std::cmp::Ordering::Equal => return Some(mid),
-                std::cmp::Ordering::Greater => slice = &slice[i..],
-            }
+    /// Returns the layer at or directly below `height`.
+    /// Heights are rounded to layers in the same way as when constructing, so
+    /// a height belongs to the layer its faces would have been placed in.
+    /// If `height` is below all layers, the lowest layer is returned.
+    pub fn layer_of(&self, height: f32) -> Option<LayerId> {
+        if !height.is_finite() || self.layers.is_empty() {
+            return None;
         }
+
+        let key = bucket(height);
+        let index = self
+            .layers
+            .partition_point(|(k, _, _)| bucket(k.into_inner()) <= key);
+
+        Some(LayerId(index.saturating_sub(1)))
     }
 
-    pub fn node(&self, layer: f32, index: NodeIndex) -> Option<&BSPNode> {
+    /// Returns the height of the layer
+    pub fn height(&self, layer: LayerId) -> Option<f32> {
+        self.layer(layer).map(|v| v.0.into_inner())
+    }
+
+    pub fn locate(&self, layer: LayerId, point: Vec2) -> Option<NodePayload<'_>> {
+        self.layer(layer).map(|v| v.1.locate(point))
+    }
+
+    pub fn layer(&self, layer: LayerId) -> Option<&(NotNan<f32>, BSPTree, Portals)> {
+        self.layers.get(layer.0)
+    }
+
+    pub fn node(&self, layer: LayerId, index: NodeIndex) -> Option<&BSPNode> {
         self.layer(layer).and_then(|v| v.1.node(index))
     }
 
     pub fn find_path_inc<'a>(
         &self,
-        layer: f32,
+        layer: LayerId,
         start: Vec2,
         end: Vec2,
         heuristic: impl Fn(Vec2, Vec2) -> f32,
         info: SearchInfo,
         path: &'a mut Option<Path>,
-    ) -> Option<&'a mut Path> {
-        let (_, tree, portals) = self.layer(layer)?;
+    ) -> Result<&'a mut Path, PathError> {
+        let (_, tree, portals) = self.layer(layer).ok_or(PathError::EmptyScene)?;
         astar(tree, portals, start, end, heuristic, info, path)
     }
 
     pub fn find_path(
         &self,
-        layer: f32,
+        layer: LayerId,
         start: Vec2,
         end: Vec2,
         heuristic: impl Fn(Vec2, Vec2) -> f32,
         info: SearchInfo,
-    ) -> Option<Path> {
+    ) -> Result<Path, PathError> {
         let mut path = None;
-        self.find_path_inc(layer, start, end, heuristic, info, &mut path);
-        path
+        self.find_path_inc(layer, start, end, heuristic, info, &mut path)?;
+        Ok(path.expect("Path is filled on success"))
     }
 }
+
+/// Returns the key of the layer a height belongs to
+fn bucket(height: f32) -> i32 {
+    (height / TOLERANCE).round() as i32
+}
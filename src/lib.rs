@@ -30,18 +30,83 @@
 //!     .expect("Failed to find a path");
 //! ```
 //!
+//! # no_std
+//! The crate depends only on `alloc` when the default `std` feature is
+//! disabled. The `libm` feature then provides the float math.
+#![cfg_attr(not(feature = "std"), no_std)]
+
+extern crate alloc;
+
+#[cfg(feature = "rkyv")]
+mod archive;
 pub mod astar;
+#[cfg(feature = "serialize")]
+mod bake;
+#[cfg(feature = "bevy")]
+pub mod bevy_plugin;
+mod boolean;
+mod connectivity;
+mod doors;
+pub mod export;
+#[cfg(feature = "ffi")]
+pub mod ffi;
+pub mod flow_field;
+#[cfg(feature = "geojson")]
+pub mod geojson;
 pub mod heuristics;
+pub mod import;
 mod layered_context;
+#[cfg(feature = "nalgebra")]
+pub mod nalgebra_interop;
 mod navigation_context;
+mod navigator;
+mod navmesh;
+mod patch;
+mod preprocess;
+#[cfg(feature = "python")]
+pub mod python;
+mod raster;
+mod rrt;
+mod scalar;
+#[cfg(feature = "serialize")]
+mod serialize;
 mod shape;
+mod shared;
+mod side_table;
+pub mod sim;
+#[cfg(all(feature = "std", not(target_arch = "wasm32")))]
+mod trace;
 mod tree;
 mod util;
+mod world;
 
+#[cfg(feature = "rkyv")]
+pub use archive::*;
 pub use astar::*;
+#[cfg(feature = "serialize")]
+pub use bake::*;
+pub use connectivity::*;
+pub use doors::*;
 pub use layered_context::*;
 pub use navigation_context::*;
+pub use navigator::*;
+pub use navmesh::*;
+pub use patch::*;
+pub use preprocess::*;
+pub use raster::*;
+pub use rrt::*;
+pub use scalar::*;
+#[cfg(feature = "serialize")]
+pub use serialize::FORMAT_VERSION;
 pub use shape::*;
+pub use shared::*;
+pub use side_table::PortalTable;
+#[cfg(all(feature = "std", not(target_arch = "wasm32")))]
+pub use trace::*;
 pub use tree::*;
+pub use world::*;
+
+#[cfg(not(feature = "std"))]
+pub use util::{HashMap, HashSet};
 
 pub const TOLERANCE: f32 = 0.1;
@@ -0,0 +1,78 @@
+//! Conversions between the [glam] based API and [nalgebra], e.g; for
+//! robotics stacks built on nalgebra.
+//!
+//! Neither vector type belongs to this crate, so points and vectors are
+//! converted through [IntoGlam] and [IntoNalgebra], while shapes, faces and
+//! paths implement [From].
+
+use glam::Vec2;
+use nalgebra::{Point2, Vector2};
+
+use crate::{Face, Path, Shape};
+
+/// Converts a nalgebra point or vector into a [Vec2].
+pub trait IntoGlam {
+    fn into_glam(self) -> Vec2;
+}
+
+impl IntoGlam for Point2<f32> {
+    fn into_glam(self) -> Vec2 {
+        Vec2::new(self.x, self.y)
+    }
+}
+
+impl IntoGlam for Vector2<f32> {
+    fn into_glam(self) -> Vec2 {
+        Vec2::new(self.x, self.y)
+    }
+}
+
+/// Converts a [Vec2] into a nalgebra point or vector.
+pub trait IntoNalgebra {
+    fn into_point(self) -> Point2<f32>;
+    fn into_vector(self) -> Vector2<f32>;
+}
+
+impl IntoNalgebra for Vec2 {
+    fn into_point(self) -> Point2<f32> {
+        Point2::new(self.x, self.y)
+    }
+
+    fn into_vector(self) -> Vector2<f32> {
+        Vector2::new(self.x, self.y)
+    }
+}
+
+impl From<&[Point2<f32>]> for Shape {
+    fn from(points: &[Point2<f32>]) -> Self {
+        Shape::new(&points.iter().map(|p| p.into_glam()).collect::<Vec<_>>())
+    }
+}
+
+impl From<[Point2<f32>; 2]> for Face {
+    fn from([a, b]: [Point2<f32>; 2]) -> Self {
+        Face::new([a.into_glam(), b.into_glam()])
+    }
+}
+
+impl From<Face> for [Point2<f32>; 2] {
+    fn from(face: Face) -> Self {
+        face.vertices().map(IntoNalgebra::into_point)
+    }
+}
+
+impl From<&Shape> for Vec<Point2<f32>> {
+    fn from(shape: &Shape) -> Self {
+        shape.vertices().iter().map(|p| p.into_point()).collect()
+    }
+}
+
+/// Returns the points of the waypoints
+impl From<&Path> for Vec<Point2<f32>> {
+    fn from(path: &Path) -> Self {
+        path.points()
+            .iter()
+            .map(|waypoint| waypoint.point().into_point())
+            .collect()
+    }
+}
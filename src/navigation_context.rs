@@ -1,55 +1,207 @@
+use alloc::vec::Vec;
+
 use crate::{
-    astar::{astar, Path, SearchInfo},
-    BSPNode, BSPTree, NodeIndex, NodePayload, PortalIter,
+    astar::{
+        astar, astar_any, astar_observed, astar_region, escort, escort_open, EscortPath, Goal,
+        Path, PathError, SearchInfo, SearchObserver, WayPoint, DEFAULT_AREA,
+    },
+    flow_field::FlowField,
+    patch::Canonical,
+    rrt,
+    side_table::SideTables,
+    BSPNode, BSPTree, Connectivity, LinkId, MovingObstacle, NodeIndex, NodePayload, OffMeshLink,
+    PortalIter, PortalRef, PortalTable, RrtSettings, TOLERANCE,
 };
-use glam::Vec2;
+#[cfg(all(feature = "std", not(target_arch = "wasm32")))]
+use crate::{QueryStats, Tracer};
+use core::any::Any;
+use glam::{Mat3, Vec2};
 use itertools::Itertools;
 use rand::Rng;
+use slotmap::{Key, SecondaryMap};
 
-use crate::{Face, Portals};
+use crate::{Face, FaceError, Portals};
 
 /// Contains the graph and edges necessary for path finding
-#[derive(Default)]
-#[cfg_attr(feature = "serialize", derive(serde::Serialize, serde::Deserialize))]
+///
+/// The context is `Send + Sync`, and all queries take `&self`, which allows
+/// querying from several threads at once. See [SharedNav].
+///
+/// Serialized contexts carry a [FORMAT_VERSION](crate::FORMAT_VERSION).
+#[derive(Default, Clone)]
 pub struct NavigationContext {
-    tree: Option<BSPTree>,
-    portals: Portals,
+    pub(crate) tree: Option<BSPTree>,
+    pub(crate) portals: Portals,
+    pub(crate) connectivity: Connectivity,
+    pub(crate) areas: SecondaryMap<NodeIndex, u32>,
+    pub(crate) tables: SideTables,
 }
 
 impl NavigationContext {
-    /// Creates a new navigation context
+    /// Creates a new navigation context.
+    /// The faces are collected once, in a single allocation for exact size
+    /// iterators. Prefer [Self::from_slice] if the faces are already stored.
     pub fn new(faces: impl IntoIterator<Item = Face>) -> Self {
-        let tree = BSPTree::new(faces.into_iter().collect_vec());
-        let mut portals = Portals::new();
-        if let Some(tree) = tree.as_ref() {
-            portals.generate(&tree);
-        }
+        Self::from_tree(BSPTree::from_slice(&faces.into_iter().collect_vec()))
+    }
+
+    /// Creates a new navigation context, rejecting degenerate faces.
+    /// See [BSPTree::try_from_slice].
+    pub fn try_new(faces: impl IntoIterator<Item = Face>) -> Result<Self, Vec<FaceError>> {
+        let faces = faces.into_iter().collect_vec();
+        BSPTree::try_from_slice(&faces).map(Self::from_tree)
+    }
+
+    /// Creates a new navigation context from borrowed faces, avoiding any
+    /// intermediate collection of the input.
+    pub fn from_slice(faces: &[Face]) -> Self {
+        Self::from_tree(BSPTree::from_slice(faces))
+    }
+
+    /// Creates a new navigation context for agents of `radius`, by inflating
+    /// the faces, see [BSPTree::new_inflated].
+    ///
+    /// Search with an agent radius of 0, as the clearance is already part of
+    /// the geometry.
+    pub fn new_inflated(faces: impl IntoIterator<Item = Face>, radius: f32) -> Self {
+        let faces = faces.into_iter().collect_vec();
+        Self::from_tree(BSPTree::new_inflated(&faces, radius))
+    }
 
-        Self { tree, portals }
+    /// Creates a new navigation context after removing duplicated and
+    /// overlapping walls, see [dedup_faces](crate::dedup_faces).
+    pub fn new_deduplicated(faces: impl IntoIterator<Item = Face>) -> Self {
+        let faces = faces.into_iter().collect_vec();
+        Self::from_tree(BSPTree::from_slice(&crate::dedup_faces(&faces)))
     }
 
     /// Creates a new navigation context.
     /// Shuffles the input which usually reduces the depth of the final tree.
     pub fn new_shuffle(faces: impl IntoIterator<Item = Face>, rng: &mut impl Rng) -> Self {
-        let tree = BSPTree::new_shuffle(faces.into_iter(), rng);
+        Self::from_tree(BSPTree::new_shuffle(faces.into_iter(), rng))
+    }
+
+    /// Creates a new navigation context while recording the duration of each
+    /// construction phase to `tracer`.
+    #[cfg(all(feature = "std", not(target_arch = "wasm32")))]
+    pub fn new_traced(faces: impl IntoIterator<Item = Face>, tracer: &mut Tracer) -> Self {
+        let faces = faces.into_iter().collect_vec();
+        let tree = tracer.span("build tree", "build", || BSPTree::from_slice(&faces));
+        Self::build(tree, |name, phase| tracer.span(name, "build", phase))
+    }
+
+    fn from_tree(tree: Option<BSPTree>) -> Self {
+        Self::build(tree, |_, phase| phase())
+    }
+
+    /// Builds the context, running each phase through `span`
+    fn build(tree: Option<BSPTree>, mut span: impl FnMut(&'static str, &mut dyn FnMut())) -> Self {
         let mut portals = Portals::new();
+        let mut connectivity = Connectivity::default();
         if let Some(tree) = tree.as_ref() {
-            portals.generate(&tree);
+            span("generate portals", &mut || portals.generate(tree));
+            span("connectivity", &mut || {
+                connectivity = Connectivity::new(tree, &portals, 0.0)
+            });
         }
 
-        Self { tree, portals }
+        Self {
+            tree,
+            portals,
+            connectivity,
+            areas: SecondaryMap::new(),
+            tables: SideTables::default(),
+        }
     }
+
+    /// Assembles a context from already generated parts
+    ///
+    /// Leaf clearances are not part of the compact portals, and are baked
+    /// again from the tree.
+    pub(crate) fn from_parts(
+        tree: Option<BSPTree>,
+        mut portals: Portals,
+        areas: SecondaryMap<NodeIndex, u32>,
+        clearance: f32,
+    ) -> Self {
+        let connectivity = match &tree {
+            Some(tree) => {
+                portals.bake_leaf_clearances(tree);
+                Connectivity::new(tree, &portals, clearance)
+            }
+            None => Connectivity::default(),
+        };
+
+        Self {
+            tree,
+            portals,
+            connectivity,
+            areas,
+            tables: SideTables::default(),
+        }
+    }
+
+    /// Returns a copy of the context with an affine transform applied to the
+    /// tree and portals, without rebuilding from the raw faces.
+    ///
+    /// Rotations, translations, scales and mirroring are supported, allowing
+    /// prefab chunks to be placed anywhere.
+    pub fn transformed(&self, transform: Mat3) -> Self {
+        let tree = self.tree.as_ref().map(|tree| tree.transformed(transform));
+        let portals = self.portals.transformed(transform);
+
+        // Scaling changes portal widths
+        let connectivity = match &tree {
+            Some(tree) => Connectivity::new(tree, &portals, self.connectivity.clearance()),
+            None => Connectivity::default(),
+        };
+
+        Self {
+            tree,
+            portals,
+            connectivity,
+            areas: self.areas.clone(),
+            tables: self.tables.clone(),
+        }
+    }
+
     pub fn node(&self, index: NodeIndex) -> Option<&BSPNode> {
         self.tree.as_ref()?.node(index)
     }
 
     /// Locate a position in the tree.
     /// Return None if there are no faces in the scene
-    pub fn locate(&self, point: Vec2) -> Option<NodePayload> {
-        match &self.tree {
-            Some(tree) => Some(tree.locate(point)),
-            None => None,
+    pub fn locate(&self, point: Vec2) -> Option<NodePayload<'_>> {
+        self.tree.as_ref().map(|tree| tree.locate(point))
+    }
+
+    /// Moves `point` out of obstacles to the closest position which is not
+    /// covered and at least `radius` away from all faces.
+    ///
+    /// Resolves against all nearby faces iteratively, which correctly handles
+    /// points inside corners.
+    /// Returns None if no free position could be found, e.g; the radius does
+    /// not fit in the surrounding space.
+    pub fn project_to_navigable(&self, point: Vec2, radius: f32) -> Option<Vec2> {
+        let tree = match &self.tree {
+            Some(tree) => tree,
+            None => return Some(point),
+        };
+
+        if let Some(point) = resolve_free(tree, point, radius) {
+            return Some(point);
         }
+
+        // Overlapping obstacles can make the nearest face push the point into
+        // another obstacle. Try pushing out of every face, closest first.
+        tree.descendants()
+            .flat_map(|(_, node)| node.faces().iter())
+            .map(|face| face.closest_point(point) + face.normal() * (radius + TOLERANCE))
+            .sorted_by(|a, b| {
+                a.distance_squared(point)
+                    .total_cmp(&b.distance_squared(point))
+            })
+            .find_map(|candidate| resolve_free(tree, candidate, radius))
     }
 
     /// Get a reference to the navigation context's tree.
@@ -62,13 +214,250 @@ impl NavigationContext {
         &self.portals
     }
 
+    /// Sets the extra cost of crossing `portal`. See [Portals::set_cost].
+    pub fn set_portal_cost(&mut self, portal: PortalRef, cost: f32) {
+        self.portals.set_cost(portal, cost)
+    }
+
+    /// Enables or disables `portal` without rebuilding the tree, e.g; when a
+    /// door is opened or closed. See [Portals::set_enabled].
+    ///
+    /// The connectivity is recomputed if the state changed.
+    pub fn set_portal_enabled(&mut self, portal: PortalRef, enabled: bool) {
+        if self.portals.is_enabled(portal) == enabled {
+            return;
+        }
+
+        self.portals.set_enabled(portal, enabled);
+        if let Some(tree) = self.tree.as_ref() {
+            self.connectivity =
+                Connectivity::new(tree, &self.portals, self.connectivity.clearance());
+        }
+    }
+
+    /// Attaches arbitrary user data to `portal`. See [Portals::set_data].
+    pub fn set_portal_data<T: Any + Send + Sync>(&mut self, portal: PortalRef, data: T) {
+        self.portals.set_data(portal, data)
+    }
+
+    /// Makes `portal` only crossable from its src to its dst.
+    /// See [Portals::set_one_way].
+    pub fn set_one_way(&mut self, portal: PortalRef, one_way: bool) {
+        self.portals.set_one_way(portal, one_way)
+    }
+
+    /// Adds a one way off-mesh link from `entry` to `exit` which costs `cost`
+    /// to traverse, e.g; a jump, ladder or teleporter. See
+    /// [Portals::add_link].
+    ///
+    /// Returns None if either point is covered, or there are no faces in the
+    /// scene.
+    pub fn add_link(&mut self, entry: Vec2, exit: Vec2, cost: f32) -> Option<LinkId> {
+        let tree = self.tree.as_ref()?;
+        let (src, dst) = (tree.locate(entry), tree.locate(exit));
+        if src.covered() || dst.covered() {
+            return None;
+        }
+
+        let link = OffMeshLink::new(src.index(), entry, dst.index(), exit, cost);
+        let id = self.portals.add_link(link);
+        self.connectivity = Connectivity::new(tree, &self.portals, self.connectivity.clearance());
+
+        Some(id)
+    }
+
+    /// Sets the area bitmask of `node`, e.g; water or road.
+    /// Searches only enter nodes whose area is allowed by
+    /// [SearchInfo::allowed_areas].
+    pub fn set_area(&mut self, node: NodeIndex, area: u32) {
+        self.areas.insert(node, area);
+    }
+
+    /// Sets the area bitmask of the node containing `point`.
+    /// Returns the node, or None if the point is covered or there are no
+    /// faces in the scene.
+    pub fn set_area_at(&mut self, point: Vec2, area: u32) -> Option<NodeIndex> {
+        let payload = self.tree.as_ref()?.locate(point);
+        if payload.covered() {
+            return None;
+        }
+
+        let node = payload.index();
+        self.set_area(node, area);
+        Some(node)
+    }
+
+    /// Returns the area bitmask of `node`
+    pub fn area(&self, node: NodeIndex) -> u32 {
+        self.areas.get(node).copied().unwrap_or(DEFAULT_AREA)
+    }
+
+    /// Returns the area bitmask of every node which was assigned one
+    pub fn areas(&self) -> &SecondaryMap<NodeIndex, u32> {
+        &self.areas
+    }
+
+    /// Returns the table of user data of type `T` attached to nodes, e.g;
+    /// room names or spawn weights. Returns None if no such table was
+    /// created by [Self::node_data_mut].
+    ///
+    /// Tables are not serialized, and are dropped when the context is
+    /// patched. Carry them over rebuilds with [Self::remap_regions].
+    pub fn node_data<T: Any>(&self) -> Option<&SecondaryMap<NodeIndex, T>> {
+        self.tables.get()
+    }
+
+    /// Returns the table of user data of type `T` attached to nodes,
+    /// creating an empty table if there is none.
+    pub fn node_data_mut<T: Any + Clone + Send + Sync>(
+        &mut self,
+    ) -> &mut SecondaryMap<NodeIndex, T> {
+        self.tables.get_mut()
+    }
+
+    /// Removes and returns the table of user data of type `T` attached to
+    /// nodes
+    pub fn take_node_data<T: Any>(&mut self) -> Option<SecondaryMap<NodeIndex, T>> {
+        self.tables.remove()
+    }
+
+    /// Returns the table of user data of type `T` attached to portals.
+    /// Returns None if no such table was created by [Self::portal_table_mut].
+    ///
+    /// Unlike [Self::set_portal_data], a portal may have data of several
+    /// types, which is however not available to searches.
+    pub fn portal_table<T: Any>(&self) -> Option<&PortalTable<T>> {
+        self.tables.get()
+    }
+
+    /// Returns the table of user data of type `T` attached to portals,
+    /// creating an empty table if there is none.
+    pub fn portal_table_mut<T: Any + Clone + Send + Sync>(&mut self) -> &mut PortalTable<T> {
+        self.tables.get_mut()
+    }
+
+    /// Carries data keyed by the nodes of `old`, e.g; the context before a
+    /// rebuild, over to the nodes of this context through their [RegionId](crate::RegionId)s.
+    ///
+    /// Entries whose region is now covered are dropped. If several regions
+    /// merged into one, the entry of any of them is kept.
+    pub fn remap_regions<T: Clone>(
+        &self,
+        old: &NavigationContext,
+        data: &SecondaryMap<NodeIndex, T>,
+    ) -> SecondaryMap<NodeIndex, T> {
+        let (old, tree) = match (old.tree(), self.tree()) {
+            (Some(old), Some(tree)) => (old, tree),
+            _ => return SecondaryMap::new(),
+        };
+
+        data.iter()
+            .filter_map(|(node, value)| {
+                let node = tree.region(old.region_id(node)?)?;
+                Some((node, value.clone()))
+            })
+            .collect()
+    }
+
+    /// Returns a stable hash of the tree, portals, links and areas, e.g; to
+    /// detect desyncs between peers in lockstep multiplayer.
+    ///
+    /// Node indices are canonicalized, which means contexts hash the same if
+    /// their navigation data is identical, regardless of how they were built
+    /// or loaded. User data of portals is not hashed.
+    pub fn hash_state(&self) -> u64 {
+        Canonical::new(self).checksum()
+    }
+
+    /// Fills in the areas of the context unless given by the search
+    fn search_info<'b>(&'b self, info: SearchInfo<'b>) -> SearchInfo<'b> {
+        SearchInfo {
+            node_areas: info.node_areas.or(Some(&self.areas)),
+            ..info
+        }
+    }
+
+    /// Get a reference to the navigation context's connectivity.
+    /// The connectivity is computed for point sized agents, use
+    /// [Connectivity::new] for a larger clearance.
+    pub fn connectivity(&self) -> &Connectivity {
+        &self.connectivity
+    }
+
+    /// Returns the connected component id of every node.
+    /// Nodes with different ids can not reach each other. See
+    /// [Connectivity] for one-way connections.
+    pub fn components(&self) -> &SecondaryMap<NodeIndex, usize> {
+        self.connectivity.components()
+    }
+
+    /// Returns the component id of the node containing `point`, or None if the
+    /// point is covered.
+    pub fn component_of(&self, point: Vec2) -> Option<usize> {
+        let payload = self.tree.as_ref()?.locate(point);
+        if payload.covered() {
+            None
+        } else {
+            self.connectivity.component(payload.index())
+        }
+    }
+
+    /// Returns false if no path exists from `a` to `b`, without running a
+    /// search.
+    ///
+    /// One-way portals and off-mesh links are treated as two way, which means
+    /// `b` is only known to be reachable in at least one direction when true
+    /// is returned, while false is definitive.
+    pub fn is_reachable(&self, a: Vec2, b: Vec2) -> bool {
+        let tree = match &self.tree {
+            Some(tree) => tree,
+            None => return true,
+        };
+
+        let a = tree.locate(a);
+        let b = tree.locate(b);
+
+        !a.covered() && !b.covered() && self.connectivity.is_connected(a.index(), b.index())
+    }
+
+    /// Generates a flow field towards `goal` for agents of `agent_radius`.
+    ///
+    /// Any number of path queries towards the goal can be answered through
+    /// [FlowField::path] without further searching, e.g; for rally points.
+    /// Returns None if there are no faces in the scene.
+    pub fn flow_field(&self, goal: Vec2, agent_radius: f32) -> Option<FlowField> {
+        let tree = self.tree.as_ref()?;
+        Some(FlowField::new(tree, &self.portals, goal, agent_radius))
+    }
+
+    /// Runs a single Dijkstra search from `goal`, yielding the travel distance
+    /// to the goal of every node it can be reached from.
+    ///
+    /// Unreachable nodes are absent. Use [Self::flow_field] to also extract
+    /// paths.
+    /// Returns None if there are no faces in the scene.
+    pub fn distance_field(
+        &self,
+        goal: Vec2,
+        agent_radius: f32,
+    ) -> Option<SecondaryMap<NodeIndex, f32>> {
+        let field = self.flow_field(goal, agent_radius)?;
+        Some(
+            field
+                .cells()
+                .iter()
+                .map(|(index, cell)| (index, cell.cost))
+                .collect(),
+        )
+    }
+
     /// Get the portals associated to a node
-    pub fn get(&self, index: NodeIndex) -> PortalIter {
+    pub fn get(&self, index: NodeIndex) -> PortalIter<'_> {
         self.portals.get(index)
     }
 
     /// Find a path from `start` to `end`
-    /// Returns None if no path was found.
+    /// Returns an error describing why if no path was found.
     /// If there are no faces in the scene, a straight path will be returned.
     pub fn find_path(
         &self,
@@ -76,22 +465,118 @@ impl NavigationContext {
         end: Vec2,
         heuristic: impl Fn(Vec2, Vec2) -> f32,
         info: SearchInfo,
-    ) -> Option<Path> {
+    ) -> Result<Path, PathError> {
         let mut path = None;
         match &self.tree {
             Some(tree) => {
-                astar(&tree, &self.portals, start, end, heuristic, info, &mut path);
-                path
+                astar(
+                    tree,
+                    &self.portals,
+                    start,
+                    end,
+                    heuristic,
+                    self.search_info(info),
+                    &mut path,
+                )?;
+                Ok(path.expect("Path is filled on success"))
             }
-            None => Some(Path::euclidian(start, end)),
+            None => Ok(Path::euclidian(start, end)),
         }
     }
 
+    /// Find a path from `start` to `end` while reporting the search's progress
+    /// to `observer`, e.g; a [QueryStats](crate::QueryStats).
+    /// Returns an error describing why if no path was found.
+    pub fn find_path_observed(
+        &self,
+        start: Vec2,
+        end: Vec2,
+        heuristic: impl Fn(Vec2, Vec2) -> f32,
+        info: SearchInfo,
+        observer: &mut impl SearchObserver,
+    ) -> Result<Path, PathError> {
+        let mut path = None;
+        match &self.tree {
+            Some(tree) => {
+                astar_observed(
+                    tree,
+                    &self.portals,
+                    start,
+                    &[end],
+                    heuristic,
+                    self.search_info(info),
+                    &mut path,
+                    observer,
+                )?;
+                Ok(path.expect("Path is filled on success"))
+            }
+            None => Ok(Path::euclidian(start, end)),
+        }
+    }
+
+    /// Find a path from `start` to `end` for a leader, along with the path of
+    /// a follower walking at a lateral `offset` beside it, e.g; an escort.
+    /// See [escort](crate::escort).
+    /// Returns an error describing why if no path was found.
+    pub fn find_path_escort(
+        &self,
+        start: Vec2,
+        end: Vec2,
+        heuristic: impl Fn(Vec2, Vec2) -> f32,
+        info: SearchInfo,
+        offset: f32,
+    ) -> Result<EscortPath, PathError> {
+        let leader = self.find_path(start, end, heuristic, info)?;
+        match &self.tree {
+            Some(tree) => Ok(escort(
+                tree,
+                &self.portals,
+                leader,
+                offset,
+                &self.search_info(info),
+            )),
+            None => Ok(escort_open(leader, offset)),
+        }
+    }
+
+    /// Find a path from `start` to `end` while recording the query's duration,
+    /// number of expansions, and outcome to `tracer`.
+    /// Returns an error describing why if no path was found.
+    #[cfg(all(feature = "std", not(target_arch = "wasm32")))]
+    pub fn find_path_traced(
+        &self,
+        start: Vec2,
+        end: Vec2,
+        heuristic: impl Fn(Vec2, Vec2) -> f32,
+        info: SearchInfo,
+        tracer: &mut Tracer,
+    ) -> Result<Path, PathError> {
+        let mut stats = QueryStats::new();
+        let time = std::time::Instant::now();
+        let result = self.find_path_observed(start, end, heuristic, info, &mut stats);
+
+        let args = vec![
+            ("expansions", stats.expansions() as f64),
+            ("pushes", stats.pushes() as f64),
+            ("success", result.is_ok() as u8 as f64),
+            (
+                "waypoints",
+                result.as_ref().map(|path| path.len()).unwrap_or(0) as f64,
+            ),
+        ];
+
+        tracer.record("find_path", "query", time, args);
+        result
+    }
+
     /// Find a path from `start` to `end`
-    /// Returns None if no path was found.
+    /// Returns an error describing why if no path was found.
     /// If there are no faces in the scene, a straight path will be returned.
-    /// Uses an already allocated path to fill and will attempt to only update
-    /// parts of the path
+    ///
+    /// Uses an already allocated path to fill. The path is cleared and refilled
+    /// in place, which means its capacity is reused and no allocation is made
+    /// for the waypoints unless the new path is longer than any previous path.
+    /// The path is left untouched on failure.
     pub fn find_path_inc<'a>(
         &self,
         start: Vec2,
@@ -99,13 +584,130 @@ impl NavigationContext {
         heuristic: impl Fn(Vec2, Vec2) -> f32,
         info: SearchInfo,
         path: &'a mut Option<Path>,
-    ) -> Option<&'a mut Path> {
+    ) -> Result<&'a mut Path, PathError> {
         match &self.tree {
-            Some(tree) => astar(&tree, &self.portals, start, end, heuristic, info, path),
+            Some(tree) => {
+                let info = self.search_info(info);
+                astar(tree, &self.portals, start, end, heuristic, info, path)
+            }
             None => {
-                *path = Some(Path::euclidian(start, end));
-                path.as_mut()
+                let path = path.get_or_insert_with(Default::default);
+                path.clear();
+                path.push(WayPoint::new(start, NodeIndex::null(), None));
+                path.push(WayPoint::new(end, NodeIndex::null(), None));
+                Ok(path)
             }
         }
     }
+
+    /// Find a path from `start` to anywhere inside of `goal`, e.g; a node or
+    /// capture zone.
+    /// If there are no faces in the scene, a straight path to the closest
+    /// point of the goal will be returned.
+    pub fn find_path_region(
+        &self,
+        start: Vec2,
+        goal: &Goal,
+        heuristic: impl Fn(Vec2, Vec2) -> f32,
+        info: SearchInfo,
+    ) -> Result<Path, PathError> {
+        let mut path = None;
+        match &self.tree {
+            Some(tree) => {
+                astar_region(
+                    tree,
+                    &self.portals,
+                    start,
+                    goal,
+                    heuristic,
+                    self.search_info(info),
+                    &mut path,
+                )?;
+                Ok(path.expect("Path is filled on success"))
+            }
+            None if goal.is_empty() => Err(PathError::NoRoute),
+            None => {
+                let end = goal.closest_point(&self.portals, NodeIndex::null(), start);
+                Ok(Path::euclidian(start, end))
+            }
+        }
+    }
+
+    /// Find a path from `start` to `end` using the sampling based planner,
+    /// for agents with constraints the graph search can't express.
+    /// See [rrt].
+    pub fn find_path_rrt(
+        &self,
+        start: Vec2,
+        end: Vec2,
+        settings: &RrtSettings,
+        obstacles: &[MovingObstacle],
+        rng: &mut impl Rng,
+    ) -> Result<Path, PathError> {
+        match &self.tree {
+            Some(tree) => rrt(tree, start, end, settings, obstacles, rng),
+            None => Ok(Path::euclidian(start, end)),
+        }
+    }
+
+    /// Find a path from `start` to the nearest reachable of `goals`.
+    /// Returns the index of the reached goal along with the path.
+    /// If there are no faces in the scene, a straight path to the closest goal
+    /// will be returned.
+    pub fn find_path_to_any(
+        &self,
+        start: Vec2,
+        goals: &[Vec2],
+        heuristic: impl Fn(Vec2, Vec2) -> f32,
+        info: SearchInfo,
+    ) -> Result<(usize, Path), PathError> {
+        let mut path = None;
+        match &self.tree {
+            Some(tree) => {
+                let (goal, _) = astar_any(
+                    tree,
+                    &self.portals,
+                    start,
+                    goals,
+                    heuristic,
+                    self.search_info(info),
+                    &mut path,
+                )?;
+                Ok((goal, path.expect("Path is filled on success")))
+            }
+            None => {
+                let (goal, end) = goals
+                    .iter()
+                    .enumerate()
+                    .min_by(|a, b| (heuristic)(start, *a.1).total_cmp(&(heuristic)(start, *b.1)))
+                    .ok_or(PathError::NoRoute)?;
+                Ok((goal, Path::euclidian(start, *end)))
+            }
+        }
+    }
+}
+
+/// Iteratively pushes `point` out of the closest face until it is free
+fn resolve_free(tree: &BSPTree, mut point: Vec2, radius: f32) -> Option<Vec2> {
+    const MAX_ITERATIONS: usize = 8;
+
+    for _ in 0..MAX_ITERATIONS {
+        let covered = tree.locate(point).covered();
+        let (face, closest) = tree.closest_face(point)?;
+        let dist = closest.distance(point);
+
+        if !covered && dist >= radius - TOLERANCE {
+            return Some(point);
+        }
+
+        let dir = if covered || dist < TOLERANCE {
+            face.normal()
+        } else {
+            (point - closest) / dist
+        };
+
+        point = closest + dir * (radius + TOLERANCE);
+    }
+
+    None
 }
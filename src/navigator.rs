@@ -0,0 +1,355 @@
+use alloc::vec::Vec;
+use core::time::Duration;
+
+use glam::Vec2;
+use slotmap::{new_key_type, SlotMap};
+
+use crate::{
+    heuristics, util::Deadline, NavigationContext, NodeIndex, PathError, PathFollower, SearchInfo,
+};
+
+new_key_type! {
+    pub struct AgentId;
+}
+
+/// Controls when the [Navigator] replans paths.
+#[derive(Debug, Clone, Copy, PartialEq)]
+pub struct NavigatorSettings {
+    /// Seconds between unconditional replans of a moving agent.
+    pub replan_interval: f32,
+    /// Distance at which a waypoint is considered reached.
+    pub waypoint_radius: f32,
+    /// Allow agents to move as close as possible to unreachable targets.
+    pub allow_partial: bool,
+    /// The time spent replanning each tick. At least one agent is replanned
+    /// per tick, and the remaining agents keep following their stale paths
+    /// until their turn, see [Navigator::staleness].
+    /// None replans every agent immediately, as does the lack of a clock
+    /// without the `std` feature or on wasm32.
+    pub replan_budget: Option<Duration>,
+    /// Decides which agents are replanned first when the budget runs out.
+    pub policy: RepathPolicy,
+}
+
+impl Default for NavigatorSettings {
+    fn default() -> Self {
+        Self {
+            replan_interval: 1.0,
+            waypoint_radius: 1.0,
+            allow_partial: false,
+            replan_budget: None,
+            policy: RepathPolicy::default(),
+        }
+    }
+}
+
+/// Decides the order in which agents waiting for a new path are replanned,
+/// see [NavigatorSettings::replan_budget].
+#[derive(Default, Debug, Clone, Copy, PartialEq)]
+pub enum RepathPolicy {
+    /// Agents take turns, continuing after the agent last replanned.
+    #[default]
+    RoundRobin,
+    /// Agents closest to the point are replanned first, e.g; the player or
+    /// camera, as distant agents running on stale paths are less noticeable.
+    Nearest(Vec2),
+    /// Agents which have waited the longest are replanned first.
+    Stalest,
+}
+
+/// The movement state of an agent.
+#[derive(Debug, Clone, Copy, PartialEq)]
+pub enum AgentStatus {
+    /// The agent has no target.
+    Idle,
+    /// The agent is moving towards its target.
+    Moving,
+    /// The agent has reached its target.
+    Arrived,
+    /// No path to the target could be found.
+    /// A new path will be attempted at the next replan.
+    Failed(PathError),
+}
+
+#[derive(Debug, Clone)]
+struct Agent {
+    position: Vec2,
+    radius: f32,
+    node: Option<NodeIndex>,
+    target: Option<Vec2>,
+    follower: Option<PathFollower>,
+    steering: Option<Vec2>,
+    since_replan: f32,
+    needs_replan: bool,
+    // Seconds the agent has waited for a replan
+    staleness: f32,
+    status: AgentStatus,
+}
+
+impl Agent {
+    fn new(position: Vec2, radius: f32) -> Self {
+        Self {
+            position,
+            radius,
+            node: None,
+            target: None,
+            follower: None,
+            steering: None,
+            since_replan: 0.0,
+            needs_replan: false,
+            staleness: 0.0,
+            status: AgentStatus::Idle,
+        }
+    }
+
+    /// Returns true if the agent has a target it has not yet arrived at
+    fn is_moving(&self) -> bool {
+        self.target.is_some() && self.status != AgentStatus::Arrived
+    }
+}
+
+/// High level facade for moving agents around a [NavigationContext].
+///
+/// Agents are given targets through [Navigator::move_to]. Each
+/// [Navigator::tick] replans stale or invalidated paths and yields the point
+/// each agent should steer towards. The caller moves the agents and reports
+/// back their positions using [Navigator::set_position].
+pub struct Navigator {
+    nav: NavigationContext,
+    agents: SlotMap<AgentId, Agent>,
+    settings: NavigatorSettings,
+    // The agent last replanned, for [RepathPolicy::RoundRobin]
+    last_replanned: Option<AgentId>,
+}
+
+impl Navigator {
+    pub fn new(nav: NavigationContext, settings: NavigatorSettings) -> Self {
+        Self {
+            nav,
+            agents: SlotMap::with_key(),
+            settings,
+            last_replanned: None,
+        }
+    }
+
+    /// Adds an agent of `radius` at `position`.
+    pub fn add_agent(&mut self, position: Vec2, radius: f32) -> AgentId {
+        self.agents.insert(Agent::new(position, radius))
+    }
+
+    /// Removes an agent. Returns false if the agent did not exist.
+    pub fn remove_agent(&mut self, id: AgentId) -> bool {
+        self.agents.remove(id).is_some()
+    }
+
+    /// Updates the current position of an agent.
+    pub fn set_position(&mut self, id: AgentId, position: Vec2) {
+        if let Some(agent) = self.agents.get_mut(id) {
+            agent.position = position;
+        }
+    }
+
+    /// Gives the agent a new target, replanning at the next tick.
+    pub fn move_to(&mut self, id: AgentId, target: Vec2) {
+        if let Some(agent) = self.agents.get_mut(id) {
+            agent.target = Some(target);
+            agent.needs_replan = true;
+            agent.status = AgentStatus::Moving;
+        }
+    }
+
+    /// Clears the target of the agent.
+    pub fn stop(&mut self, id: AgentId) {
+        if let Some(agent) = self.agents.get_mut(id) {
+            agent.target = None;
+            agent.follower = None;
+            agent.steering = None;
+            agent.needs_replan = false;
+            agent.staleness = 0.0;
+            agent.status = AgentStatus::Idle;
+        }
+    }
+
+    /// Returns the movement state of an agent.
+    pub fn status(&self, id: AgentId) -> Option<AgentStatus> {
+        self.agents.get(id).map(|agent| agent.status)
+    }
+
+    /// Returns the path currently followed by an agent.
+    pub fn follower(&self, id: AgentId) -> Option<&PathFollower> {
+        self.agents.get(id)?.follower.as_ref()
+    }
+
+    /// Returns the number of seconds the agent has been waiting for a replan,
+    /// while following its previous path, e.g; to visually mask agents
+    /// running on stale paths.
+    /// Returns 0 if the agent is up to date, or None if it does not exist.
+    pub fn staleness(&self, id: AgentId) -> Option<f32> {
+        self.agents.get(id).map(|agent| agent.staleness)
+    }
+
+    /// Returns true if the agent is waiting for a replan
+    pub fn is_stale(&self, id: AgentId) -> bool {
+        self.agents
+            .get(id)
+            .is_some_and(|agent| agent.needs_replan && agent.is_moving())
+    }
+
+    /// Returns the point the agent should currently steer towards.
+    pub fn steering(&self, id: AgentId) -> Option<Vec2> {
+        self.agents.get(id)?.steering
+    }
+
+    /// Advances the navigator by `dt` seconds and returns the steering target
+    /// of every moving agent.
+    pub fn tick(&mut self, dt: f32) -> impl Iterator<Item = (AgentId, Vec2)> + '_ {
+        let settings = self.settings;
+        let nav = &self.nav;
+
+        for (_, agent) in &mut self.agents {
+            if !agent.is_moving() {
+                agent.steering = None;
+                continue;
+            }
+
+            agent.since_replan += dt;
+
+            // Leaving the node the path was planned from warrants a validity
+            // check of the current segment.
+            let node = nav.tree().map(|tree| tree.locate(agent.position).index());
+            let moved_node = node != agent.node;
+            agent.node = node;
+
+            if agent.since_replan >= settings.replan_interval
+                || moved_node && !segment_clear(nav, agent)
+            {
+                agent.needs_replan = true;
+            }
+        }
+
+        self.replan_pending(dt);
+
+        for (_, agent) in &mut self.agents {
+            if !agent.is_moving() {
+                continue;
+            }
+
+            agent.steering = agent
+                .follower
+                .as_mut()
+                .and_then(|follower| follower.advance(agent.position, settings.waypoint_radius));
+
+            if agent.steering.is_none() && agent.follower.is_some() {
+                agent.status = AgentStatus::Arrived;
+                agent.follower = None;
+            }
+        }
+
+        self.agents
+            .iter()
+            .filter_map(|(id, agent)| Some((id, agent.steering?)))
+    }
+
+    /// Replans the waiting agents in the order of the policy, until the budget
+    /// runs out
+    fn replan_pending(&mut self, dt: f32) {
+        let settings = self.settings;
+        let deadline = settings.replan_budget.map(Deadline::after);
+
+        let mut pending = self
+            .agents
+            .iter()
+            .filter(|(_, agent)| agent.needs_replan && agent.is_moving())
+            .map(|(id, agent)| (id, agent.position, agent.staleness))
+            .collect::<Vec<_>>();
+
+        match settings.policy {
+            RepathPolicy::RoundRobin => {
+                // Continue after the last replanned agent
+                let last = self.last_replanned;
+                pending.sort_by_key(|&(id, _, _)| (Some(id) <= last, id));
+            }
+            RepathPolicy::Nearest(point) => pending.sort_by(|a, b| {
+                a.1.distance_squared(point)
+                    .total_cmp(&b.1.distance_squared(point))
+            }),
+            RepathPolicy::Stalest => pending.sort_by(|a, b| b.2.total_cmp(&a.2)),
+        }
+
+        for (i, &(id, _, _)) in pending.iter().enumerate() {
+            let agent = &mut self.agents[id];
+            let out_of_budget = deadline.is_some_and(|deadline| i > 0 && deadline.passed());
+
+            match agent.target {
+                Some(target) if !out_of_budget => {
+                    replan(&self.nav, agent, target, settings);
+                    self.last_replanned = Some(id);
+                }
+                _ => agent.staleness += dt,
+            }
+        }
+    }
+
+    /// Get a reference to the navigator's navigation context.
+    pub fn nav(&self) -> &NavigationContext {
+        &self.nav
+    }
+
+    /// Replaces the navigation context and replans all moving agents.
+    pub fn set_nav(&mut self, nav: NavigationContext) {
+        self.nav = nav;
+        for (_, agent) in &mut self.agents {
+            agent.needs_replan = agent.target.is_some();
+            agent.node = None;
+        }
+    }
+
+    /// Get a reference to the navigator's settings.
+    pub fn settings(&self) -> &NavigatorSettings {
+        &self.settings
+    }
+
+    /// Get a mutable reference to the navigator's settings.
+    pub fn settings_mut(&mut self) -> &mut NavigatorSettings {
+        &mut self.settings
+    }
+}
+
+/// Returns true if nothing obstructs the line to the current steering target
+fn segment_clear(nav: &NavigationContext, agent: &Agent) -> bool {
+    match (nav.tree(), agent.steering) {
+        (Some(tree), Some(steering)) => {
+            let dir = steering - agent.position;
+            tree.raycast(agent.position, dir, dir.length()).is_none()
+        }
+        _ => true,
+    }
+}
+
+fn replan(nav: &NavigationContext, agent: &mut Agent, target: Vec2, settings: NavigatorSettings) {
+    agent.needs_replan = false;
+    agent.since_replan = 0.0;
+    agent.staleness = 0.0;
+
+    // Agents pushed into geometry are planned from the closest free point
+    let start = nav
+        .project_to_navigable(agent.position, agent.radius)
+        .unwrap_or(agent.position);
+
+    let info = SearchInfo {
+        agent_radius: agent.radius,
+        allow_partial: settings.allow_partial,
+        ..Default::default()
+    };
+
+    match nav.find_path(start, target, heuristics::euclidiean, info) {
+        Ok(path) => {
+            agent.follower = Some(PathFollower::new(path));
+            agent.status = AgentStatus::Moving;
+        }
+        Err(e) => {
+            agent.follower = None;
+            agent.status = AgentStatus::Failed(e);
+        }
+    }
+}
@@ -0,0 +1,181 @@
+use alloc::vec::Vec;
+
+use glam::Vec2;
+#[cfg(not(feature = "std"))]
+use num_traits::Float;
+use slotmap::SecondaryMap;
+
+use crate::{util::HashMap, Face, NavigationContext, NodeIndex, TOLERANCE};
+
+/// The free space of a context as convex polygons and their adjacency, for
+/// use by other tools or engines. See [NavigationContext::to_navmesh].
+///
+/// Vertices within [TOLERANCE] of each other are welded. Leaves of the tree
+/// do not necessarily line up, which means a vertex of one polygon may lie on
+/// the edge of another; use [NavPolygon::neighbours] rather than shared edges
+/// for adjacency.
+#[derive(Debug, Clone, Default, PartialEq)]
+#[cfg_attr(feature = "serialize", derive(serde::Serialize, serde::Deserialize))]
+pub struct NavMesh {
+    #[cfg_attr(feature = "serialize", serde(with = "crate::serialize::vec2_vec"))]
+    pub vertices: Vec<Vec2>,
+    pub polygons: Vec<NavPolygon>,
+    pub links: Vec<NavLink>,
+}
+
+/// A convex polygon of a [NavMesh]
+#[derive(Debug, Clone, PartialEq)]
+#[cfg_attr(feature = "serialize", derive(serde::Serialize, serde::Deserialize))]
+pub struct NavPolygon {
+    /// Indices into [NavMesh::vertices], in counterclockwise order.
+    pub vertices: Vec<usize>,
+    /// The leaf of the tree the polygon was created from.
+    pub node: NodeIndex,
+    /// The area bitmask of the leaf, see [NavigationContext::area].
+    pub area: u32,
+    /// The polygons reachable through an enabled portal.
+    pub neighbours: Vec<NavEdge>,
+}
+
+/// A portal from one [NavPolygon] to another
+#[derive(Debug, Clone, Copy, PartialEq)]
+#[cfg_attr(feature = "serialize", derive(serde::Serialize, serde::Deserialize))]
+pub struct NavEdge {
+    /// The index of the neighbouring polygon.
+    pub polygon: usize,
+    /// The segment shared by the polygons.
+    pub portal: Face,
+    /// The radius of the largest agent which can cross the portal.
+    pub clearance: f32,
+    /// The extra cost of crossing the portal, see
+    /// [Portals::cost](crate::Portals::cost).
+    pub cost: f32,
+}
+
+/// An off-mesh link between two polygons of a [NavMesh], see
+/// [OffMeshLink](crate::OffMeshLink)
+#[derive(Debug, Clone, Copy, PartialEq)]
+#[cfg_attr(feature = "serialize", derive(serde::Serialize, serde::Deserialize))]
+pub struct NavLink {
+    pub src: usize,
+    pub dst: usize,
+    #[cfg_attr(feature = "serialize", serde(with = "crate::serialize::vec2"))]
+    pub entry: Vec2,
+    #[cfg_attr(feature = "serialize", serde(with = "crate::serialize::vec2"))]
+    pub exit: Vec2,
+    pub cost: f32,
+}
+
+impl NavMesh {
+    /// Returns the positions of the polygon's vertices
+    pub fn polygon_vertices<'a>(
+        &'a self,
+        polygon: &'a NavPolygon,
+    ) -> impl Iterator<Item = Vec2> + 'a {
+        polygon.vertices.iter().map(move |&i| self.vertices[i])
+    }
+}
+
+impl NavigationContext {
+    /// Exports the free space as convex polygons for every open leaf, with
+    /// adjacency through the portals, e.g; to bake with this crate and run
+    /// queries elsewhere.
+    ///
+    /// Polygons are clipped to the bounds of the tree. Disabled portals are
+    /// left out.
+    pub fn to_navmesh(&self) -> NavMesh {
+        let tree = match self.tree() {
+            Some(tree) => tree,
+            None => return NavMesh::default(),
+        };
+
+        let mut welder = Welder::default();
+        let mut mesh = NavMesh::default();
+        let mut indices = SecondaryMap::new();
+
+        for (node, cell) in tree.cells().filter(|(_, cell)| cell.len() >= 3) {
+            let mut vertices = cell
+                .iter()
+                .map(|&v| welder.weld(&mut mesh.vertices, v))
+                .collect::<Vec<_>>();
+            vertices.dedup();
+            if vertices.len() > 1 && vertices.first() == vertices.last() {
+                vertices.pop();
+            }
+
+            // Slivers thinner than the tolerance collapse when welded
+            if vertices.len() < 3 {
+                continue;
+            }
+
+            indices.insert(node, mesh.polygons.len());
+            mesh.polygons.push(NavPolygon {
+                vertices,
+                node,
+                area: self.area(node),
+                neighbours: Vec::new(),
+            });
+        }
+
+        let portals = self.portals();
+        for polygon in &mut mesh.polygons {
+            polygon.neighbours = portals
+                .get(polygon.node)
+                .filter_map(|portal| {
+                    Some(NavEdge {
+                        polygon: *indices.get(portal.dst())?,
+                        portal: *portal.face(),
+                        clearance: portal.clearance(),
+                        cost: portals.cost(portal.portal_ref()),
+                    })
+                })
+                .collect();
+        }
+
+        mesh.links = portals
+            .links()
+            .iter()
+            .filter_map(|link| {
+                Some(NavLink {
+                    src: *indices.get(link.src)?,
+                    dst: *indices.get(link.dst)?,
+                    entry: link.entry,
+                    exit: link.exit,
+                    cost: link.cost,
+                })
+            })
+            .collect();
+
+        mesh
+    }
+}
+
+/// Merges vertices within [TOLERANCE] of each other by bucketing them in a
+/// grid of that size
+#[derive(Default)]
+struct Welder {
+    grid: HashMap<(i64, i64), Vec<usize>>,
+}
+
+impl Welder {
+    fn weld(&mut self, vertices: &mut Vec<Vec2>, v: Vec2) -> usize {
+        let cell = |v: f32| (v / TOLERANCE).floor() as i64;
+        let (x, y) = (cell(v.x), cell(v.y));
+
+        let existing = (x - 1..=x + 1)
+            .flat_map(|x| (y - 1..=y + 1).map(move |y| (x, y)))
+            .filter_map(|key| self.grid.get(&key))
+            .flatten()
+            .copied()
+            .find(|&i| vertices[i].distance_squared(v) < TOLERANCE * TOLERANCE);
+
+        if let Some(index) = existing {
+            return index;
+        }
+
+        let index = vertices.len();
+        vertices.push(v);
+        self.grid.entry((x, y)).or_default().push(index);
+        index
+    }
+}
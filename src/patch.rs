@@ -0,0 +1,329 @@
+use alloc::{vec, vec::Vec};
+use core::{
+    fmt::Display,
+    hash::{Hash, Hasher},
+};
+
+use slotmap::{SecondaryMap, SlotMap};
+use smallvec::SmallVec;
+
+use crate::{
+    hash_portals, util::StableHasher, BSPTree, CompactPortals, Face, NavigationContext, NodeIndex,
+    OffMeshLink, PortalRecord, Portals, PrebuiltError, PrebuiltNode,
+};
+
+/// A compact delta between two navigation contexts, e.g; before and after a
+/// level patch. See [NavigationContext::diff].
+///
+/// The tree is compared in a depth first order, which means rebaking a
+/// single region of the level results in a small patch, while the patch
+/// grows when the partitioning changes throughout the level.
+#[derive(Debug, Clone, PartialEq)]
+#[cfg_attr(feature = "serialize", derive(serde::Serialize, serde::Deserialize))]
+pub struct NavPatch {
+    // Checksum of the context the patch applies to
+    base: u64,
+    nodes: Splice<NodeRecord>,
+    portals: Splice<PortalRecord>,
+    links: Splice<OffMeshLink>,
+    areas: Splice<(NodeIndex, u32)>,
+    clearance: f32,
+}
+
+impl NavPatch {
+    /// Returns true if the patch does not change the navigation data
+    pub fn is_empty(&self) -> bool {
+        self.nodes.is_empty()
+            && self.portals.is_empty()
+            && self.links.is_empty()
+            && self.areas.is_empty()
+    }
+
+    /// Returns the number of replaced nodes, portals, links and areas
+    pub fn len(&self) -> usize {
+        self.nodes.items.len()
+            + self.portals.items.len()
+            + self.links.items.len()
+            + self.areas.items.len()
+    }
+}
+
+/// Describes why a patch could not be applied
+#[derive(Debug, Clone, Copy, PartialEq)]
+pub enum PatchError {
+    /// The patch was made against a different context
+    BaseMismatch { expected: u64, found: u64 },
+    /// The patched hierarchy does not form a tree
+    Malformed,
+    /// The patched tree is invalid
+    Tree(PrebuiltError),
+}
+
+impl Display for PatchError {
+    fn fmt(&self, f: &mut core::fmt::Formatter<'_>) -> core::fmt::Result {
+        match self {
+            PatchError::BaseMismatch { expected, found } => write!(
+                f,
+                "Patch expects context {:016x}, but was applied to {:016x}",
+                expected, found
+            ),
+            PatchError::Malformed => write!(f, "Patched hierarchy is not a tree"),
+            PatchError::Tree(e) => write!(f, "Patched tree is invalid: {}", e),
+        }
+    }
+}
+
+impl core::error::Error for PatchError {}
+
+impl From<PrebuiltError> for PatchError {
+    fn from(e: PrebuiltError) -> Self {
+        Self::Tree(e)
+    }
+}
+
+impl NavigationContext {
+    /// Returns the patch which turns `self` into `other`, e.g; to ship a
+    /// small delta instead of the whole rebaked context.
+    ///
+    /// User data of portals is not included. See [Self::apply_patch].
+    pub fn diff(&self, other: &NavigationContext) -> NavPatch {
+        let (before, after) = (Canonical::new(self), Canonical::new(other));
+
+        NavPatch {
+            base: before.checksum(),
+            nodes: Splice::new(&before.nodes, &after.nodes),
+            portals: Splice::new(&before.portals, &after.portals),
+            links: Splice::new(&before.links, &after.links),
+            areas: Splice::new(&before.areas, &after.areas),
+            clearance: other.connectivity().clearance(),
+        }
+    }
+
+    /// Applies a patch made by [Self::diff] against this context.
+    ///
+    /// Node indices are not preserved, and are renumbered in the same way as
+    /// the context the patch was made towards.
+    /// Returns an error and leaves self untouched if the patch was made
+    /// against a different context, or is invalid.
+    pub fn apply_patch(&mut self, patch: &NavPatch) -> Result<(), PatchError> {
+        let mut base = Canonical::new(self);
+        let found = base.checksum();
+        if found != patch.base {
+            return Err(PatchError::BaseMismatch {
+                expected: patch.base,
+                found,
+            });
+        }
+
+        patch.nodes.apply(&mut base.nodes)?;
+        patch.portals.apply(&mut base.portals)?;
+        patch.links.apply(&mut base.links)?;
+        patch.areas.apply(&mut base.areas)?;
+
+        let tree = if base.nodes.is_empty() {
+            None
+        } else {
+            Some(BSPTree::from_prebuilt(prebuilt(&base.nodes)?)?)
+        };
+
+        let portals = Portals::from(CompactPortals {
+            portals: base.portals,
+            links: base.links,
+        });
+
+        *self = Self::from_parts(
+            tree,
+            portals,
+            base.areas.into_iter().collect(),
+            patch.clearance,
+        );
+
+        Ok(())
+    }
+}
+
+/// A node of the tree in depth first order, which stores which children
+/// follow rather than their indices
+#[derive(Debug, Clone, PartialEq)]
+#[cfg_attr(feature = "serialize", derive(serde::Serialize, serde::Deserialize))]
+struct NodeRecord {
+    faces: SmallVec<[Face; 2]>,
+    front: bool,
+    back: bool,
+}
+
+/// Replaces `start..end` of the old items by `items`
+#[derive(Debug, Clone, PartialEq)]
+#[cfg_attr(feature = "serialize", derive(serde::Serialize, serde::Deserialize))]
+struct Splice<T> {
+    start: usize,
+    end: usize,
+    items: Vec<T>,
+}
+
+impl<T: Clone + PartialEq> Splice<T> {
+    /// Replaces everything between the common prefix and suffix
+    fn new(before: &[T], after: &[T]) -> Self {
+        let prefix = before.iter().zip(after).take_while(|(a, b)| a == b).count();
+
+        let suffix = before[prefix..]
+            .iter()
+            .rev()
+            .zip(after[prefix..].iter().rev())
+            .take_while(|(a, b)| a == b)
+            .count();
+
+        Self {
+            start: prefix,
+            end: before.len() - suffix,
+            items: after[prefix..after.len() - suffix].to_vec(),
+        }
+    }
+
+    fn is_empty(&self) -> bool {
+        self.start == self.end && self.items.is_empty()
+    }
+
+    fn apply(&self, base: &mut Vec<T>) -> Result<(), PatchError> {
+        if self.start > self.end || self.end > base.len() {
+            return Err(PatchError::Malformed);
+        }
+
+        base.splice(self.start..self.end, self.items.iter().cloned());
+        Ok(())
+    }
+}
+
+/// The navigation data of a context independent of how it was built.
+///
+/// Node indices are renumbered to those of [BSPTree::from_prebuilt], which
+/// makes contexts comparable and patched contexts identical to their target.
+#[derive(Default)]
+pub(crate) struct Canonical {
+    // See [BSPTree::hash_state]
+    tree: u64,
+    nodes: Vec<NodeRecord>,
+    portals: Vec<PortalRecord>,
+    links: Vec<OffMeshLink>,
+    areas: Vec<(NodeIndex, u32)>,
+}
+
+impl Canonical {
+    pub(crate) fn new(nav: &NavigationContext) -> Self {
+        let tree = match nav.tree() {
+            Some(tree) => tree,
+            None => return Self::default(),
+        };
+
+        let order = tree.preorder();
+        let nodes = order
+            .iter()
+            .map(|&index| {
+                let node = &tree.nodes()[index];
+                NodeRecord {
+                    faces: SmallVec::from_slice(node.faces()),
+                    front: node.front().is_some(),
+                    back: node.back().is_some(),
+                }
+            })
+            .collect::<Vec<_>>();
+
+        let mut map = SecondaryMap::new();
+        for (&old, new) in order.iter().zip(keys(&nodes)) {
+            map.insert(old, new);
+        }
+
+        let mut compact = CompactPortals::from(nav.portals());
+        compact.remap(&map);
+
+        let mut areas = nav
+            .areas()
+            .iter()
+            .filter_map(|(node, &area)| Some((*map.get(node)?, area)))
+            .collect::<Vec<_>>();
+        areas.sort_by_key(|&(node, _)| node);
+
+        Self {
+            tree: tree.hash_state(),
+            nodes,
+            portals: compact.portals,
+            links: compact.links,
+            areas,
+        }
+    }
+
+    /// See [NavigationContext::hash_state]
+    pub(crate) fn checksum(&self) -> u64 {
+        let mut state = StableHasher::default();
+        state.write_u64(self.tree);
+        hash_portals(&self.portals, &self.links, &mut state);
+        self.areas.iter().for_each(|(node, area)| {
+            node.hash(&mut state);
+            state.write_u32(*area);
+        });
+        state.finish()
+    }
+}
+
+/// Returns the index each node will receive from [BSPTree::from_prebuilt],
+/// which inserts the children of a node before the node itself
+fn keys(nodes: &[NodeRecord]) -> Vec<NodeIndex> {
+    fn insert(
+        nodes: &[NodeRecord],
+        pos: &mut usize,
+        slots: &mut SlotMap<NodeIndex, ()>,
+        keys: &mut [NodeIndex],
+    ) {
+        let index = *pos;
+        *pos += 1;
+
+        if nodes[index].front {
+            insert(nodes, pos, slots, keys);
+        }
+        if nodes[index].back {
+            insert(nodes, pos, slots, keys);
+        }
+
+        keys[index] = slots.insert(());
+    }
+
+    let mut keys = vec![NodeIndex::default(); nodes.len()];
+    if !nodes.is_empty() {
+        insert(nodes, &mut 0, &mut SlotMap::with_key(), &mut keys);
+    }
+
+    keys
+}
+
+/// Converts the depth first records to a hierarchy with explicit indices
+fn prebuilt(nodes: &[NodeRecord]) -> Result<Vec<PrebuiltNode>, PatchError> {
+    fn insert(
+        nodes: &[NodeRecord],
+        pos: &mut usize,
+        result: &mut Vec<PrebuiltNode>,
+    ) -> Result<usize, PatchError> {
+        let v = nodes.get(*pos).ok_or(PatchError::Malformed)?;
+        let index = result.len();
+        *pos += 1;
+
+        result.push(PrebuiltNode::new(v.faces.to_vec(), None, None));
+        if v.front {
+            result[index].front = Some(insert(nodes, pos, result)?);
+        }
+        if v.back {
+            result[index].back = Some(insert(nodes, pos, result)?);
+        }
+
+        Ok(index)
+    }
+
+    let mut result = Vec::with_capacity(nodes.len());
+    let mut pos = 0;
+    insert(nodes, &mut pos, &mut result)?;
+
+    if pos != nodes.len() {
+        return Err(PatchError::Malformed);
+    }
+
+    Ok(result)
+}
@@ -0,0 +1,245 @@
+use alloc::{vec, vec::Vec};
+
+use glam::Vec2;
+
+use crate::{util::plane_distance, Face, TOLERANCE};
+
+/// The largest deviation of normals, as `1 - dot`, for faces to be
+/// considered parallel
+const PARALLEL: f32 = 1e-4;
+
+/// Removes duplicated and overlapping walls, e.g; where adjacent shapes
+/// share an edge.
+///
+/// Collinear faces facing the same way are merged where they overlap, and
+/// collinear faces facing opposite ways are cut where they overlap, as the
+/// overlap lies inside the combined solid. Faces which do not overlap any
+/// other face are kept as is, which means faces merely touching end to end
+/// are not joined.
+///
+/// Left in, duplicated walls deepen the tree and create regions of zero
+/// width.
+pub fn dedup_faces(faces: &[Face]) -> Vec<Face> {
+    let mut result = Vec::with_capacity(faces.len());
+
+    for group in collinear_groups(faces, TOLERANCE) {
+        if let [index] = group[..] {
+            result.push(faces[index]);
+            continue;
+        }
+
+        let (forward, backward) = spans(faces, &group);
+        let forward = merge(forward, -TOLERANCE);
+        let backward = merge(backward, -TOLERANCE);
+
+        result.extend(to_faces(&subtract(&forward, &backward), false));
+        result.extend(to_faces(&subtract(&backward, &forward), true));
+    }
+
+    result
+}
+
+impl Face {
+    /// Merges chains of collinear faces facing the same way, which touch or
+    /// overlap within `tolerance`, into single faces, e.g; the walls of
+    /// adjacent tiles.
+    ///
+    /// Faces which are not joined with another face are kept as is. Welding
+    /// before construction reduces the number of nodes of tile derived
+    /// geometry considerably.
+    pub fn weld(faces: &[Face], tolerance: f32) -> Vec<Face> {
+        let mut result = Vec::with_capacity(faces.len());
+
+        for group in collinear_groups(faces, tolerance) {
+            if let [index] = group[..] {
+                result.push(faces[index]);
+                continue;
+            }
+
+            let (forward, backward) = spans(faces, &group);
+            result.extend(to_faces(&merge(forward, tolerance), false));
+            result.extend(to_faces(&merge(backward, tolerance), true));
+        }
+
+        result
+    }
+}
+
+/// Groups the indices of faces which are collinear within `tolerance`,
+/// ordered by their first face
+fn collinear_groups(faces: &[Face], tolerance: f32) -> Vec<Vec<usize>> {
+    let lines = faces.iter().map(Line::new).collect::<Vec<_>>();
+
+    let mut order = (0..faces.len()).collect::<Vec<_>>();
+    order.sort_by(|&a, &b| lines[a].offset.total_cmp(&lines[b].offset));
+
+    let mut parents = (0..faces.len()).collect::<Vec<_>>();
+    for (i, &a) in order.iter().enumerate() {
+        for &b in order[i + 1..]
+            .iter()
+            .take_while(|&&b| lines[b].offset - lines[a].offset <= tolerance)
+        {
+            if lines[a].normal.dot(lines[b].normal) >= 1.0 - PARALLEL
+                && faces[b].vertices.iter().all(|&v| {
+                    plane_distance(v, faces[a].vertices[0], lines[a].normal).abs() <= tolerance
+                })
+            {
+                let (a, b) = (find(&mut parents, a), find(&mut parents, b));
+                parents[a.max(b)] = a.min(b);
+            }
+        }
+    }
+
+    let mut groups: Vec<Vec<usize>> = Vec::new();
+    let mut group_of = vec![usize::MAX; faces.len()];
+    for index in 0..faces.len() {
+        let root = find(&mut parents, index);
+        if group_of[root] == usize::MAX {
+            group_of[root] = groups.len();
+            groups.push(Vec::new());
+        }
+
+        groups[group_of[root]].push(index);
+    }
+
+    groups
+}
+
+fn find(parents: &mut [usize], mut i: usize) -> usize {
+    while parents[i] != i {
+        parents[i] = parents[parents[i]];
+        i = parents[i];
+    }
+
+    i
+}
+
+/// The line of a face, with the normal flipped to a canonical direction so
+/// that opposite facing faces share a line
+#[derive(Debug, Clone, Copy)]
+struct Line {
+    normal: Vec2,
+    offset: f32,
+}
+
+impl Line {
+    fn new(face: &Face) -> Self {
+        let normal = face.normal();
+        let flip = if normal.x.abs() <= PARALLEL {
+            normal.y < 0.0
+        } else {
+            normal.x < 0.0
+        };
+
+        let normal = if flip { -normal } else { normal };
+        Self {
+            normal,
+            offset: normal.dot(face.vertices[0]),
+        }
+    }
+
+    /// Returns the direction along the line
+    fn tangent(&self) -> Vec2 {
+        self.normal.perp()
+    }
+
+    /// Returns the span of `face` along the line, and whether it runs
+    /// against the tangent
+    fn span(&self, face: &Face) -> (Span, bool) {
+        let [a, b] = face.vertices.map(|p| Bound {
+            t: self.tangent().dot(p),
+            p,
+        });
+
+        if a.t <= b.t {
+            ((a, b), false)
+        } else {
+            ((b, a), true)
+        }
+    }
+}
+
+/// An end of a span, which keeps the original vertex to not introduce
+/// rounding errors
+#[derive(Debug, Clone, Copy)]
+struct Bound {
+    t: f32,
+    p: Vec2,
+}
+
+type Span = (Bound, Bound);
+
+/// Returns the spans of the collinear faces of `group` along their line,
+/// split by the direction of the faces
+fn spans(faces: &[Face], group: &[usize]) -> (Vec<Span>, Vec<Span>) {
+    let line = Line::new(&faces[group[0]]);
+    let (mut forward, mut backward) = (Vec::new(), Vec::new());
+    for &index in group {
+        let (span, reversed) = line.span(&faces[index]);
+        if reversed {
+            backward.push(span);
+        } else {
+            forward.push(span);
+        }
+    }
+
+    (forward, backward)
+}
+
+fn to_faces(spans: &[Span], reversed: bool) -> impl Iterator<Item = Face> + '_ {
+    spans.iter().map(move |&(a, b)| {
+        if reversed {
+            Face::new([b.p, a.p])
+        } else {
+            Face::new([a.p, b.p])
+        }
+    })
+}
+
+/// Merges spans separated by at most `gap`. A negative gap requires spans to
+/// overlap by more than its magnitude.
+fn merge(mut spans: Vec<Span>, gap: f32) -> Vec<Span> {
+    spans.sort_by(|a, b| a.0.t.total_cmp(&b.0.t));
+
+    let mut result: Vec<Span> = Vec::with_capacity(spans.len());
+    for span in spans {
+        match result.last_mut() {
+            Some(last) if span.0.t <= last.1.t + gap => {
+                if span.1.t > last.1.t {
+                    last.1 = span.1;
+                }
+            }
+            _ => result.push(span),
+        }
+    }
+
+    result
+}
+
+/// Returns the parts of `spans` not overlapped by `other`, dropping parts
+/// shorter than the tolerance. Both must be sorted.
+fn subtract(spans: &[Span], other: &[Span]) -> Vec<Span> {
+    let mut result = Vec::new();
+    for &(start, end) in spans {
+        let mut start = start;
+        for &(a, b) in other {
+            if b.t <= start.t + TOLERANCE || a.t >= end.t - TOLERANCE {
+                continue;
+            }
+
+            if a.t - start.t > TOLERANCE {
+                result.push((start, a));
+            }
+
+            if b.t > start.t {
+                start = b;
+            }
+        }
+
+        if end.t - start.t > TOLERANCE {
+            result.push((start, end));
+        }
+    }
+
+    result
+}
@@ -0,0 +1,127 @@
+//! Python bindings, e.g; to script scenarios and plot paths with matplotlib.
+//!
+//! Build a wheel with `maturin build`, which enables the `python` feature
+//! as configured in `pyproject.toml`.
+//!
+//! ```python
+//! from bsp_pathfinding import NavigationContext, Shape
+//!
+//! nav = NavigationContext([Shape.rect((50, 50), (0, 0)), ...])
+//! xs, ys = zip(*nav.find_path((-100, 0), (100, 10), agent_radius=5))
+//! ```
+
+use glam::Vec2;
+use pyo3::{create_exception, exceptions::PyException, prelude::*};
+
+use crate::{heuristics, NavigationContext, SearchInfo, Shape};
+
+create_exception!(
+    bsp_pathfinding,
+    PathError,
+    PyException,
+    "Raised when no path was found"
+);
+
+type Point = (f32, f32);
+
+fn to_vec2((x, y): Point) -> Vec2 {
+    Vec2::new(x, y)
+}
+
+fn to_point(v: Vec2) -> Point {
+    (v.x, v.y)
+}
+
+/// A polygonal obstacle, see [Shape].
+#[pyclass(name = "Shape")]
+#[derive(Clone)]
+pub struct PyShape(pub Shape);
+
+#[pymethods]
+impl PyShape {
+    /// Creates a polygon from points wound counterclockwise, which is
+    /// implicitly closed
+    #[new]
+    fn new(vertices: Vec<Point>) -> Self {
+        let mut vertices = vertices.into_iter().map(to_vec2).collect::<Vec<_>>();
+        vertices.extend(vertices.first().copied());
+        Self(Shape::new(&vertices))
+    }
+
+    #[staticmethod]
+    fn rect(size: Point, origin: Point) -> Self {
+        Self(Shape::rect(to_vec2(size), to_vec2(origin)))
+    }
+
+    #[staticmethod]
+    fn regular_polygon(sides: usize, radius: f32, origin: Point) -> Self {
+        Self(Shape::regular_polygon(sides, radius, to_vec2(origin)))
+    }
+
+    /// The closed outline, e.g; to plot the obstacle
+    #[getter]
+    fn vertices(&self) -> Vec<Point> {
+        self.0.vertices().iter().copied().map(to_point).collect()
+    }
+}
+
+/// The navigation data of a scene, see [NavigationContext].
+#[pyclass(name = "NavigationContext")]
+pub struct PyNavigationContext(pub NavigationContext);
+
+#[pymethods]
+impl PyNavigationContext {
+    #[new]
+    fn new(shapes: Vec<PyShape>) -> Self {
+        Self(NavigationContext::new(
+            shapes.iter().flat_map(|shape| shape.0.faces()),
+        ))
+    }
+
+    /// Returns the waypoints of the path from `start` to `end`.
+    /// Raises [PathError] if there is none.
+    #[pyo3(signature = (start, end, agent_radius = 0.0, allow_partial = false))]
+    fn find_path(
+        &self,
+        start: Point,
+        end: Point,
+        agent_radius: f32,
+        allow_partial: bool,
+    ) -> PyResult<Vec<Point>> {
+        let info = SearchInfo {
+            agent_radius,
+            allow_partial,
+            ..Default::default()
+        };
+
+        self.0
+            .find_path(to_vec2(start), to_vec2(end), heuristics::euclidiean, info)
+            .map(|path| {
+                path.iter()
+                    .map(|waypoint| to_point(waypoint.point()))
+                    .collect()
+            })
+            .map_err(|e| PathError::new_err(e.to_string()))
+    }
+
+    /// Returns the portals between the navigable regions as pairs of points
+    fn portals(&self) -> Vec<(Point, Point)> {
+        self.0
+            .portals()
+            .iter()
+            .flatten()
+            .map(|portal| {
+                let [a, b] = portal.face().vertices();
+                (to_point(a), to_point(b))
+            })
+            .collect()
+    }
+}
+
+#[pymodule]
+pub fn bsp_pathfinding(m: &Bound<'_, PyModule>) -> PyResult<()> {
+    m.add_class::<PyShape>()?;
+    m.add_class::<PyNavigationContext>()?;
+    m.add("PathError", m.py().get_type::<PathError>())?;
+    Ok(())
+}
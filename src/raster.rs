@@ -0,0 +1,150 @@
+use alloc::vec::Vec;
+
+use glam::Vec2;
+
+use crate::NavigationContext;
+
+/// A grid of free and blocked cells sampled from a navigation context, e.g;
+/// for fog of war or GPU simulations. See [NavigationContext::rasterize].
+///
+/// Cells are stored row by row, starting at the minimum corner of the
+/// scene.
+#[derive(Debug, Clone, Default, PartialEq)]
+#[cfg_attr(feature = "serialize", derive(serde::Serialize, serde::Deserialize))]
+pub struct OccupancyGrid {
+    #[cfg_attr(feature = "serialize", serde(with = "crate::serialize::vec2"))]
+    origin: Vec2,
+    cell_size: f32,
+    width: usize,
+    height: usize,
+    blocked: Vec<bool>,
+    distances: Option<Vec<f32>>,
+}
+
+impl OccupancyGrid {
+    /// Get the occupancy grid's minimum corner.
+    pub fn origin(&self) -> Vec2 {
+        self.origin
+    }
+
+    /// Get the occupancy grid's cell size.
+    pub fn cell_size(&self) -> f32 {
+        self.cell_size
+    }
+
+    /// Get the occupancy grid's width in cells.
+    pub fn width(&self) -> usize {
+        self.width
+    }
+
+    /// Get the occupancy grid's height in cells.
+    pub fn height(&self) -> usize {
+        self.height
+    }
+
+    /// Returns true if the cell is blocked. Cells outside the grid are
+    /// blocked.
+    pub fn is_blocked(&self, x: usize, y: usize) -> bool {
+        self.index(x, y).is_none_or(|i| self.blocked[i])
+    }
+
+    /// Returns whether each cell is blocked, row by row
+    pub fn cells(&self) -> &[bool] {
+        &self.blocked
+    }
+
+    /// Returns the distance from the center of the cell to the closest wall,
+    /// which is negative inside obstacles.
+    /// Returns None if the distances were not computed, or the cell is
+    /// outside the grid.
+    pub fn distance(&self, x: usize, y: usize) -> Option<f32> {
+        Some(self.distances.as_ref()?[self.index(x, y)?])
+    }
+
+    /// Returns the distance of each cell if computed, row by row
+    pub fn distances(&self) -> Option<&[f32]> {
+        self.distances.as_deref()
+    }
+
+    /// Returns the center of the cell
+    pub fn cell_center(&self, x: usize, y: usize) -> Vec2 {
+        self.origin + Vec2::new(x as f32 + 0.5, y as f32 + 0.5) * self.cell_size
+    }
+
+    /// Returns the cell containing `point`, if inside the grid
+    pub fn cell_at(&self, point: Vec2) -> Option<(usize, usize)> {
+        let rel = (point - self.origin) / self.cell_size;
+        if rel.x < 0.0 || rel.y < 0.0 {
+            return None;
+        }
+
+        let (x, y) = (rel.x as usize, rel.y as usize);
+        (x < self.width && y < self.height).then_some((x, y))
+    }
+
+    fn index(&self, x: usize, y: usize) -> Option<usize> {
+        (x < self.width && y < self.height).then_some(y * self.width + x)
+    }
+}
+
+impl NavigationContext {
+    /// Samples the center of each cell of a grid covering the scene to
+    /// determine if it is blocked.
+    pub fn rasterize(&self, cell_size: f32) -> OccupancyGrid {
+        self.rasterize_grid(cell_size, false)
+    }
+
+    /// Rasterizes like [Self::rasterize], and stores the distance from each
+    /// cell to the closest wall, see [OccupancyGrid::distance].
+    pub fn rasterize_with_distances(&self, cell_size: f32) -> OccupancyGrid {
+        self.rasterize_grid(cell_size, true)
+    }
+
+    fn rasterize_grid(&self, cell_size: f32, distances: bool) -> OccupancyGrid {
+        assert!(cell_size > 0.0, "Cell size must be positive");
+
+        let tree = match self.tree() {
+            Some(tree) => tree,
+            None => return OccupancyGrid::default(),
+        };
+
+        let (min, max) = tree.bounds();
+        let size = ((max - min) / cell_size).ceil();
+        let mut grid = OccupancyGrid {
+            origin: min,
+            cell_size,
+            width: size.x as usize,
+            height: size.y as usize,
+            blocked: Vec::new(),
+            distances: None,
+        };
+
+        let centers = (0..grid.height)
+            .flat_map(|y| (0..grid.width).map(move |x| (x, y)))
+            .map(|(x, y)| grid.cell_center(x, y))
+            .collect::<Vec<_>>();
+
+        grid.blocked = centers.iter().map(|&p| tree.locate(p).covered()).collect();
+
+        if distances {
+            grid.distances = Some(
+                centers
+                    .iter()
+                    .zip(&grid.blocked)
+                    .map(|(&p, &blocked)| {
+                        let dist = tree
+                            .closest_face(p)
+                            .map_or(f32::INFINITY, |(_, closest)| closest.distance(p));
+                        if blocked {
+                            -dist
+                        } else {
+                            dist
+                        }
+                    })
+                    .collect(),
+            );
+        }
+
+        grid
+    }
+}
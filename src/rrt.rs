@@ -0,0 +1,256 @@
+use alloc::{vec, vec::Vec};
+
+use glam::Vec2;
+#[cfg(not(feature = "std"))]
+use num_traits::Float;
+use rand::Rng;
+
+use crate::{BSPTree, Path, PathError, WayPoint};
+
+/// An obstacle moving at a constant velocity, which is not part of the tree.
+#[derive(Debug, Clone, Copy, PartialEq)]
+pub struct MovingObstacle {
+    /// The position at time 0.
+    pub position: Vec2,
+    pub velocity: Vec2,
+    pub radius: f32,
+}
+
+impl MovingObstacle {
+    pub fn new(position: Vec2, velocity: Vec2, radius: f32) -> Self {
+        Self {
+            position,
+            velocity,
+            radius,
+        }
+    }
+
+    /// Returns the position of the obstacle at `time`
+    pub fn position_at(&self, time: f32) -> Vec2 {
+        self.position + self.velocity * time
+    }
+}
+
+/// Controls the sampling based planner, see [rrt].
+#[derive(Debug, Clone, Copy, PartialEq)]
+pub struct RrtSettings {
+    pub agent_radius: f32,
+    /// The speed of the agent, used to determine where moving obstacles are
+    /// when the agent passes.
+    pub speed: f32,
+    /// The maximum length of each branch.
+    pub step: f32,
+    /// The probability of sampling the goal instead of a random point.
+    pub goal_bias: f32,
+    /// The distance from which a connection to the goal is attempted.
+    pub goal_radius: f32,
+    /// The number of samples before giving up.
+    pub max_iterations: usize,
+    /// The minimum turning radius of the agent, e.g; a vehicle.
+    /// Branches turn at most `step / turn_radius` radians from their parent.
+    pub turn_radius: Option<f32>,
+}
+
+impl Default for RrtSettings {
+    fn default() -> Self {
+        Self {
+            agent_radius: 0.0,
+            speed: 1.0,
+            step: 10.0,
+            goal_bias: 0.1,
+            goal_radius: 20.0,
+            max_iterations: 5000,
+            turn_radius: None,
+        }
+    }
+}
+
+#[derive(Debug, Clone, Copy)]
+struct Sample {
+    point: Vec2,
+    parent: Option<usize>,
+    // The direction the sample was reached in
+    heading: Vec2,
+    // The time at which the agent reaches the sample
+    time: f32,
+}
+
+/// Finds a path from `start` to `goal` using a goal biased Rapidly-exploring
+/// Random Tree.
+///
+/// Unlike [astar](crate::astar()), no portals are needed, and constraints
+/// which a graph search can't express are supported, such as a turning
+/// radius and moving obstacles. Collisions are checked against the tree using
+/// [BSPTree::locate] and [BSPTree::raycast].
+///
+/// The resulting path is feasible, but neither optimal nor deterministic
+/// unless `rng` is seeded.
+pub fn rrt(
+    tree: &BSPTree,
+    start: Vec2,
+    goal: Vec2,
+    settings: &RrtSettings,
+    obstacles: &[MovingObstacle],
+    rng: &mut impl Rng,
+) -> Result<Path, PathError> {
+    if tree.locate(start).covered() {
+        return Err(PathError::StartCovered);
+    }
+
+    if tree.locate(goal).covered() {
+        return Err(PathError::EndCovered);
+    }
+
+    let (min, max) = tree.bounds();
+    let mut samples = vec![Sample {
+        point: start,
+        parent: None,
+        heading: Vec2::ZERO,
+        time: 0.0,
+    }];
+
+    for _ in 0..settings.max_iterations {
+        let target = if rng.gen::<f32>() < settings.goal_bias {
+            goal
+        } else {
+            Vec2::new(rng.gen_range(min.x..=max.x), rng.gen_range(min.y..=max.y))
+        };
+
+        let (index, nearest) = samples
+            .iter()
+            .enumerate()
+            .min_by(|a, b| {
+                a.1.point
+                    .distance_squared(target)
+                    .total_cmp(&b.1.point.distance_squared(target))
+            })
+            .expect("Samples are not empty");
+
+        let nearest = *nearest;
+        let dir = match steer(&nearest, target, settings) {
+            Some(dir) => dir,
+            None => continue,
+        };
+
+        let point = nearest.point + dir * settings.step.min(nearest.point.distance(target));
+        let sample = match extend(tree, &nearest, index, point, settings, obstacles) {
+            Some(sample) => sample,
+            None => continue,
+        };
+
+        samples.push(sample);
+
+        if point.distance(goal) > settings.goal_radius {
+            continue;
+        }
+
+        // Try to connect straight to the goal
+        let connected = steer(&sample, goal, settings)
+            .filter(|dir| dir.dot((goal - point).normalize_or_zero()) > 1.0 - 1e-4)
+            .and_then(|_| extend(tree, &sample, samples.len() - 1, goal, settings, obstacles));
+
+        if let Some(end) = connected {
+            samples.push(end);
+            return Ok(backtrace(tree, &samples));
+        }
+    }
+
+    Err(PathError::NoRoute)
+}
+
+/// Returns the direction to extend `from` towards `target`, limited by the
+/// turning radius
+fn steer(from: &Sample, target: Vec2, settings: &RrtSettings) -> Option<Vec2> {
+    let dir = (target - from.point).normalize_or_zero();
+    if dir == Vec2::ZERO {
+        return None;
+    }
+
+    let max_turn = match settings.turn_radius {
+        Some(radius) if from.heading != Vec2::ZERO && radius > 0.0 => settings.step / radius,
+        _ => return Some(dir),
+    };
+
+    let angle = from.heading.perp_dot(dir).atan2(from.heading.dot(dir));
+    if angle.abs() <= max_turn {
+        return Some(dir);
+    }
+
+    let (sin, cos) = (max_turn * angle.signum()).sin_cos();
+    let h = from.heading;
+    Some(Vec2::new(cos * h.x - sin * h.y, sin * h.x + cos * h.y))
+}
+
+/// Returns the sample reached by moving from `from` to `to`, if the movement
+/// is free of collisions
+fn extend(
+    tree: &BSPTree,
+    from: &Sample,
+    parent: usize,
+    to: Vec2,
+    settings: &RrtSettings,
+    obstacles: &[MovingObstacle],
+) -> Option<Sample> {
+    let rel = to - from.point;
+    let length = rel.length();
+    if length < f32::EPSILON || tree.locate(to).covered() {
+        return None;
+    }
+
+    if tree.raycast(from.point, rel, length).is_some() {
+        return None;
+    }
+
+    if settings.agent_radius > 0.0 {
+        let clearance = tree
+            .closest_face(to)
+            .map(|(_, p)| p.distance(to))
+            .unwrap_or(f32::INFINITY);
+
+        if clearance < settings.agent_radius {
+            return None;
+        }
+    }
+
+    let time = from.time + length / settings.speed;
+
+    // Check the moving obstacles along the movement
+    let steps = (length / settings.agent_radius.max(1.0)).ceil() as usize;
+    let blocked = (0..=steps).any(|i| {
+        let t = i as f32 / steps as f32;
+        let p = from.point + rel * t;
+        let time = from.time + (time - from.time) * t;
+
+        obstacles.iter().any(|obstacle| {
+            obstacle.position_at(time).distance(p) < obstacle.radius + settings.agent_radius
+        })
+    });
+
+    if blocked {
+        return None;
+    }
+
+    Some(Sample {
+        point: to,
+        parent: Some(parent),
+        heading: rel / length,
+        time,
+    })
+}
+
+/// Builds the path from the start to the last sample
+fn backtrace(tree: &BSPTree, samples: &[Sample]) -> Path {
+    let mut points = Vec::new();
+    let mut current = samples.len().checked_sub(1);
+    while let Some(index) = current {
+        points.push(samples[index].point);
+        current = samples[index].parent;
+    }
+
+    let mut path = Path::new();
+    for point in points.into_iter().rev() {
+        path.push(WayPoint::new(point, tree.locate(point).index(), None));
+    }
+
+    path
+}
@@ -0,0 +1,250 @@
+use core::{
+    fmt::Debug,
+    ops::{Add, Div, Mul, Neg, Sub},
+};
+
+use glam::{DVec2, Vec2};
+
+use crate::util::{vec2_plane_distance, vec2_plane_intersect};
+
+/// The number type of the geometry core, e.g; `f32`, `f64` or a fixed point
+/// type.
+pub trait Scalar:
+    Copy
+    + Debug
+    + Default
+    + PartialOrd
+    + Add<Output = Self>
+    + Sub<Output = Self>
+    + Mul<Output = Self>
+    + Div<Output = Self>
+    + Neg<Output = Self>
+{
+    const ZERO: Self;
+    const ONE: Self;
+
+    /// Converts a constant, e.g; [TOLERANCE](crate::TOLERANCE)
+    fn from_f32(v: f32) -> Self;
+
+    fn sqrt(self) -> Self;
+
+    fn abs(self) -> Self {
+        if self < Self::ZERO {
+            -self
+        } else {
+            self
+        }
+    }
+
+    fn min(self, other: Self) -> Self {
+        if other < self {
+            other
+        } else {
+            self
+        }
+    }
+
+    fn max(self, other: Self) -> Self {
+        if other > self {
+            other
+        } else {
+            self
+        }
+    }
+
+    fn clamp(self, min: Self, max: Self) -> Self {
+        self.max(min).min(max)
+    }
+}
+
+impl Scalar for f32 {
+    const ZERO: Self = 0.0;
+    const ONE: Self = 1.0;
+
+    fn from_f32(v: f32) -> Self {
+        v
+    }
+
+    fn sqrt(self) -> Self {
+        #[cfg(feature = "std")]
+        return f32::sqrt(self);
+        #[cfg(not(feature = "std"))]
+        return num_traits::Float::sqrt(self);
+    }
+}
+
+impl Scalar for f64 {
+    const ZERO: Self = 0.0;
+    const ONE: Self = 1.0;
+
+    fn from_f32(v: f32) -> Self {
+        v as f64
+    }
+
+    fn sqrt(self) -> Self {
+        #[cfg(feature = "std")]
+        return f64::sqrt(self);
+        #[cfg(not(feature = "std"))]
+        return num_traits::Float::sqrt(self);
+    }
+}
+
+/// The vector type of the geometry core, which [Face] is generic over.
+///
+/// Only the constructor and components are required, which allows
+/// implementing it for fixed point vectors. Implementations for glam vectors
+/// forward to glam.
+///
+/// The crate's internal geometry helpers are generic over it as well. The
+/// tree, portals and searches however only work with [glam::Vec2]; use the
+/// `f64` feature to evaluate their plane tests in double precision.
+///
+/// [Face]: crate::Face
+pub trait Vector2:
+    Copy
+    + Debug
+    + Default
+    + PartialEq
+    + Add<Output = Self>
+    + Sub<Output = Self>
+    + Neg<Output = Self>
+    + Mul<Self::Scalar, Output = Self>
+    + Div<Self::Scalar, Output = Self>
+{
+    type Scalar: Scalar;
+
+    fn new(x: Self::Scalar, y: Self::Scalar) -> Self;
+    fn x(self) -> Self::Scalar;
+    fn y(self) -> Self::Scalar;
+
+    fn dot(self, other: Self) -> Self::Scalar {
+        self.x() * other.x() + self.y() * other.y()
+    }
+
+    fn perp_dot(self, other: Self) -> Self::Scalar {
+        self.x() * other.y() - self.y() * other.x()
+    }
+
+    /// Rotates the vector a quarter turn counterclockwise
+    fn perp(self) -> Self {
+        Self::new(-self.y(), self.x())
+    }
+
+    fn length_squared(self) -> Self::Scalar {
+        self.dot(self)
+    }
+
+    fn length(self) -> Self::Scalar {
+        self.length_squared().sqrt()
+    }
+
+    fn distance(self, other: Self) -> Self::Scalar {
+        (self - other).length()
+    }
+
+    fn normalize(self) -> Self {
+        self / self.length()
+    }
+
+    /// Returns the signed distance from the plane through `p` to self
+    fn plane_distance(self, p: Self, normal: Self) -> Self::Scalar {
+        (self - p).dot(normal)
+    }
+
+    /// Returns where the line from self along `dir` crosses the plane through
+    /// `p`, and how far along `dir` that is, as a multiple of its length
+    fn plane_intersect(self, dir: Self, p: Self, normal: Self) -> (Self, Self::Scalar) {
+        let l = p.plane_distance(self, normal) / dir.dot(normal);
+        (self + dir * l, l)
+    }
+}
+
+impl Vector2 for Vec2 {
+    type Scalar = f32;
+
+    fn new(x: f32, y: f32) -> Self {
+        Vec2::new(x, y)
+    }
+
+    fn x(self) -> f32 {
+        self.x
+    }
+
+    fn y(self) -> f32 {
+        self.y
+    }
+
+    fn dot(self, other: Self) -> f32 {
+        Vec2::dot(self, other)
+    }
+
+    fn perp_dot(self, other: Self) -> f32 {
+        Vec2::perp_dot(self, other)
+    }
+
+    fn perp(self) -> Self {
+        Vec2::perp(self)
+    }
+
+    fn length_squared(self) -> f32 {
+        Vec2::length_squared(self)
+    }
+
+    fn length(self) -> f32 {
+        Vec2::length(self)
+    }
+
+    fn distance(self, other: Self) -> f32 {
+        Vec2::distance(self, other)
+    }
+
+    fn normalize(self) -> Self {
+        Vec2::normalize(self)
+    }
+
+    /// Respects the precision of the `f64` feature
+    fn plane_distance(self, p: Self, normal: Self) -> f32 {
+        vec2_plane_distance(self, p, normal)
+    }
+
+    /// Respects the precision of the `f64` feature
+    fn plane_intersect(self, dir: Self, p: Self, normal: Self) -> (Self, f32) {
+        vec2_plane_intersect(self, dir, p, normal)
+    }
+}
+
+impl Vector2 for DVec2 {
+    type Scalar = f64;
+
+    fn new(x: f64, y: f64) -> Self {
+        DVec2::new(x, y)
+    }
+
+    fn x(self) -> f64 {
+        self.x
+    }
+
+    fn y(self) -> f64 {
+        self.y
+    }
+
+    fn dot(self, other: Self) -> f64 {
+        DVec2::dot(self, other)
+    }
+
+    fn perp_dot(self, other: Self) -> f64 {
+        DVec2::perp_dot(self, other)
+    }
+
+    fn perp(self) -> Self {
+        DVec2::perp(self)
+    }
+
+    fn length(self) -> f64 {
+        DVec2::length(self)
+    }
+
+    fn normalize(self) -> Self {
+        DVec2::normalize(self)
+    }
+}
@@ -0,0 +1,175 @@
+//! Self-contained serialization of the glam types used by the crate.
+//!
+//! Vectors are written as plain `f32` pairs with explicit field names, which
+//! keeps baked navigation data independent of glam's own serde support and
+//! version.
+
+use alloc::vec::Vec;
+
+use glam::Vec2;
+use serde::{de::Error, Deserialize, Deserializer, Serialize, Serializer};
+use slotmap::SecondaryMap;
+
+use crate::{
+    BSPTree, CompactPortals, Connectivity, Face, LoadError, NavigationContext, NodeIndex, Portals,
+};
+
+#[derive(Serialize, Deserialize)]
+#[serde(rename = "Vec2")]
+struct Vec2Repr {
+    x: f32,
+    y: f32,
+}
+
+impl From<Vec2> for Vec2Repr {
+    fn from(v: Vec2) -> Self {
+        Self { x: v.x, y: v.y }
+    }
+}
+
+impl From<Vec2Repr> for Vec2 {
+    fn from(v: Vec2Repr) -> Self {
+        Vec2::new(v.x, v.y)
+    }
+}
+
+/// Serializes a [glam::Vec2] as `{ x, y }`.
+/// Use with `#[serde(with = "crate::serialize::vec2")]`
+pub(crate) mod vec2 {
+    use super::*;
+
+    pub fn serialize<S: Serializer>(v: &Vec2, serializer: S) -> Result<S::Ok, S::Error> {
+        Vec2Repr::from(*v).serialize(serializer)
+    }
+
+    pub fn deserialize<'de, D: Deserializer<'de>>(deserializer: D) -> Result<Vec2, D::Error> {
+        Vec2Repr::deserialize(deserializer).map(Into::into)
+    }
+}
+
+/// Serializes a pair of [glam::Vec2] as `[{ x, y }, { x, y }]`.
+pub(crate) mod vec2_pair {
+    use super::*;
+
+    pub fn serialize<S: Serializer>(v: &[Vec2; 2], serializer: S) -> Result<S::Ok, S::Error> {
+        [Vec2Repr::from(v[0]), Vec2Repr::from(v[1])].serialize(serializer)
+    }
+
+    pub fn deserialize<'de, D: Deserializer<'de>>(deserializer: D) -> Result<[Vec2; 2], D::Error> {
+        let [a, b] = <[Vec2Repr; 2]>::deserialize(deserializer)?;
+        Ok([a.into(), b.into()])
+    }
+}
+
+/// Serializes a list of [glam::Vec2] as `[{ x, y }, ..]`.
+pub(crate) mod vec2_vec {
+    use super::*;
+
+    pub fn serialize<S: Serializer>(v: &[Vec2], serializer: S) -> Result<S::Ok, S::Error> {
+        serializer.collect_seq(v.iter().map(|&v| Vec2Repr::from(v)))
+    }
+
+    pub fn deserialize<'de, D: Deserializer<'de>>(deserializer: D) -> Result<Vec<Vec2>, D::Error> {
+        let v = Vec::<Vec2Repr>::deserialize(deserializer)?;
+        Ok(v.into_iter().map(Into::into).collect())
+    }
+}
+
+/// The version of serialized navigation data, bumped on incompatible
+/// changes.
+///
+/// Only data of this exact version is read. Other versions, including data
+/// written before the version was embedded, fail with
+/// [LoadError::UnsupportedVersion].
+pub const FORMAT_VERSION: u32 = 1;
+
+/// The format of [NavigationContext], with the portals in their compact form
+#[derive(Serialize)]
+#[serde(rename = "NavigationContext")]
+struct ContextRef<'a> {
+    version: u32,
+    tree: &'a Option<BSPTree>,
+    portals: CompactPortals,
+    connectivity: &'a Connectivity,
+    areas: &'a SecondaryMap<NodeIndex, u32>,
+}
+
+#[derive(Deserialize)]
+#[serde(rename = "NavigationContext")]
+struct ContextRepr {
+    // Missing from data written before the version was embedded
+    #[serde(default)]
+    version: u32,
+    tree: Option<BSPTree>,
+    portals: CompactPortals,
+    connectivity: Connectivity,
+    #[serde(default)]
+    areas: SecondaryMap<NodeIndex, u32>,
+}
+
+impl Serialize for NavigationContext {
+    fn serialize<S: Serializer>(&self, serializer: S) -> Result<S::Ok, S::Error> {
+        ContextRef {
+            version: FORMAT_VERSION,
+            tree: &self.tree,
+            portals: CompactPortals::from(&self.portals),
+            connectivity: &self.connectivity,
+            areas: &self.areas,
+        }
+        .serialize(serializer)
+    }
+}
+
+impl<'de> Deserialize<'de> for NavigationContext {
+    fn deserialize<D: Deserializer<'de>>(deserializer: D) -> Result<Self, D::Error> {
+        let repr = ContextRepr::deserialize(deserializer)?;
+
+        if repr.version != FORMAT_VERSION {
+            return Err(D::Error::custom(LoadError::UnsupportedVersion {
+                version: repr.version,
+            }));
+        }
+
+        // Leaf clearances are not part of the compact portals
+        let mut portals = Portals::from(repr.portals);
+        if let Some(tree) = repr.tree.as_ref() {
+            portals.bake_leaf_clearances(tree);
+        }
+
+        Ok(NavigationContext {
+            tree: repr.tree,
+            portals,
+            connectivity: repr.connectivity,
+            areas: repr.areas,
+            tables: Default::default(),
+        })
+    }
+}
+
+/// The format of [Face](crate::Face), which is generic over the vector type
+/// and only serializable for [glam::Vec2].
+#[derive(Serialize, Deserialize)]
+#[serde(rename = "Face")]
+struct FaceRepr {
+    #[serde(with = "vec2")]
+    normal: Vec2,
+    #[serde(with = "vec2_pair")]
+    vertices: [Vec2; 2],
+}
+
+impl Serialize for Face {
+    fn serialize<S: Serializer>(&self, serializer: S) -> Result<S::Ok, S::Error> {
+        FaceRepr {
+            normal: self.normal,
+            vertices: self.vertices,
+        }
+        .serialize(serializer)
+    }
+}
+
+impl<'de> Deserialize<'de> for Face {
+    fn deserialize<D: Deserializer<'de>>(deserializer: D) -> Result<Self, D::Error> {
+        let FaceRepr { normal, vertices } = FaceRepr::deserialize(deserializer)?;
+        Ok(Face { normal, vertices })
+    }
+}
@@ -1,9 +1,12 @@
-use std::{array, f32::consts::TAU};
+use alloc::{vec, vec::Vec};
+use core::{array, f32::consts::TAU, fmt::Display};
 
 use glam::{Mat3, Mat4, Vec2, Vec3Swizzles};
+#[cfg(not(feature = "std"))]
+use num_traits::Float;
 use smallvec::{smallvec, SmallVec};
 
-use crate::TOLERANCE;
+use crate::{Scalar, Vector2, TOLERANCE};
 
 /// Defines a collection of faces.
 /// This struct is not neccesary to use, but helps in constructing squares and
@@ -47,7 +50,157 @@ impl Shape {
         Self { vertices }
     }
 
-    pub fn faces(&self) -> Faces {
+    /// Creates a circle approximated by a regular polygon with `segments`
+    /// sides, e.g; for a pillar.
+    ///
+    /// The polygon is circumscribed, so that it fully encloses the circle.
+    pub fn circle(radius: f32, origin: Vec2, segments: usize) -> Self {
+        let segments = segments.max(3);
+        Self::regular_polygon(segments, circumscribed(radius, segments), origin)
+    }
+
+    /// Creates a capsule around the segment from `a` to `b`, where each end
+    /// is approximated by `segments` sides.
+    ///
+    /// As for [Self::circle], the polygon fully encloses the capsule.
+    pub fn capsule(a: Vec2, b: Vec2, radius: f32, segments: usize) -> Self {
+        let segments = segments.max(1);
+        let dir = if a.distance(b) > TOLERANCE {
+            (b - a).normalize()
+        } else {
+            Vec2::X
+        };
+        let radius = circumscribed(radius, segments * 2);
+
+        // Half turns around each end, counterclockwise
+        let turn = TAU / 2.0 / segments as f32;
+        let mut vertices = [(b, -TAU / 4.0), (a, TAU / 4.0)]
+            .iter()
+            .flat_map(|&(end, start)| {
+                (0..=segments).map(move |i| {
+                    let angle = start + turn * i as f32;
+                    end + (dir * angle.cos() + dir.perp() * angle.sin()) * radius
+                })
+            })
+            .collect::<SmallVec<_>>();
+
+        vertices.push(vertices[0]);
+        Self { vertices }
+    }
+
+    /// Creates a thin obstacle of `thickness` along an open chain of points,
+    /// e.g; a fence or a river, with faces on both sides.
+    ///
+    /// An open chain passed to [Self::new] only has faces on one side, which
+    /// covers everything behind it. Joins are mitered, or beveled where the
+    /// miter would extend further than `thickness`, and the ends are cut
+    /// square. Chains which cross themselves, or turn sharply compared to
+    /// `thickness`, overlap themselves.
+    pub fn polyline(points: &[Vec2], thickness: f32) -> Self {
+        let mut points = points.to_vec();
+        points.dedup_by(|a, b| a.distance(*b) <= TOLERANCE);
+
+        if points.len() < 2 {
+            return Self::default();
+        }
+
+        // Along the right side, and back along the left
+        let mut vertices = polyline_side(&points, thickness / 2.0)
+            .into_iter()
+            .chain(polyline_side(&points, -thickness / 2.0).into_iter().rev())
+            .collect::<SmallVec<_>>();
+
+        vertices.push(vertices[0]);
+        Self { vertices }
+    }
+
+    /// Creates a solid polygon from vertices in either winding, which is
+    /// implicitly closed.
+    ///
+    /// The winding is detected from the signed area, and reversed if
+    /// necessary so that normals point outward. Use [Self::with_winding] to
+    /// override the winding, or [Self::new] to keep it as is.
+    pub fn polygon(vertices: &[Vec2]) -> Self {
+        let mut shape = Self::new(vertices);
+        if shape.vertices.len() > 2 && shape.vertices.first() != shape.vertices.last() {
+            shape.vertices.push(shape.vertices[0]);
+        }
+
+        shape.with_winding(Winding::CounterClockwise)
+    }
+
+    /// Returns the signed area enclosed by the outline, which is positive for
+    /// counterclockwise outlines. Open outlines are implicitly closed.
+    pub fn signed_area(&self) -> f32 {
+        self.vertices
+            .iter()
+            .zip(self.vertices.iter().cycle().skip(1))
+            .map(|(a, b)| a.perp_dot(*b))
+            .sum::<f32>()
+            / 2.0
+    }
+
+    /// Returns the winding of the outline, or None if it encloses no area
+    pub fn winding(&self) -> Option<Winding> {
+        let area = self.signed_area();
+        if area > 0.0 {
+            Some(Winding::CounterClockwise)
+        } else if area < 0.0 {
+            Some(Winding::Clockwise)
+        } else {
+            None
+        }
+    }
+
+    /// Reverses the outline if it does not have `winding`, e.g; to make the
+    /// inside of a room walkable.
+    pub fn with_winding(mut self, winding: Winding) -> Self {
+        if self.winding().is_some_and(|v| v != winding) {
+            self.vertices.reverse();
+        }
+
+        self
+    }
+
+    /// Transforms the shape, e.g; to place an instance of a prefab obstacle.
+    ///
+    /// Mirroring transforms reverse the vertices to keep the winding, see
+    /// [Face::transform_oriented].
+    pub fn transform(&self, transform: Mat3) -> Self {
+        let mut vertices = self
+            .vertices
+            .iter()
+            .map(|&v| transform.transform_point2(v))
+            .collect::<SmallVec<_>>();
+
+        if transform.determinant() < 0.0 {
+            vertices.reverse();
+        }
+
+        Self { vertices }
+    }
+
+    pub fn translated(&self, translation: Vec2) -> Self {
+        self.transform(Mat3::from_translation(translation))
+    }
+
+    /// Rotates the shape counterclockwise by `angle` radians around the origin
+    pub fn rotated(&self, angle: f32) -> Self {
+        self.transform(Mat3::from_angle(angle))
+    }
+
+    /// Scales the shape relative to the origin. Negative scales mirror the
+    /// shape.
+    pub fn scaled(&self, scale: Vec2) -> Self {
+        self.transform(Mat3::from_scale(scale))
+    }
+
+    /// Get the shape's vertices.
+    pub fn vertices(&self) -> &[Vec2] {
+        &self.vertices
+    }
+
+    pub fn faces(&self) -> Faces<'_> {
         Faces {
             vertices: &self.vertices,
             current: 0,
@@ -56,80 +209,185 @@ impl Shape {
     }
 }
 
+/// Offsets an open chain by `offset` along the face normals
+fn polyline_side(points: &[Vec2], offset: f32) -> Vec<Vec2> {
+    let normals = points
+        .windows(2)
+        .map(|w| Face::new([w[0], w[1]]).normal() * offset)
+        .collect::<Vec<_>>();
+
+    let mut result = Vec::from([points[0] + normals[0]]);
+    for (i, w) in normals.windows(2).enumerate() {
+        let (p, a, b) = (points[i + 1], w[0], w[1]);
+        let turn = (p - points[i]).perp_dot(points[i + 2] - p);
+
+        let cos = (a + b).normalize().dot(a.normalize());
+        if turn * offset > 0.0 && cos < 0.5 || !cos.is_finite() {
+            // Bevel
+            result.extend([p + a, p + b]);
+        } else {
+            result.push(p + (a + b).normalize() * offset.abs() / cos);
+        }
+    }
+
+    result.push(points[points.len() - 1] + normals[normals.len() - 1]);
+    result
+}
+
+/// Returns the distance to the corners of a regular polygon with `sides`
+/// sides, for its edges to touch a circle of `radius`
+fn circumscribed(radius: f32, sides: usize) -> f32 {
+    radius / (TAU / 2.0 / sides as f32).cos()
+}
+
+/// The orientation of a closed outline, which determines the side normals
+/// point to
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum Winding {
+    /// Normals point outward, which makes the inside solid
+    CounterClockwise,
+    /// Normals point inward, which makes the inside walkable and the
+    /// outside solid
+    Clockwise,
+}
+
+/// A sloped connection between two floors, e.g; stairs or a ramp, for use
+/// with a [LayeredNavigationContext](crate::LayeredNavigationContext).
+#[derive(Debug, Copy, Clone, PartialEq)]
+pub struct Ramp {
+    /// The center of the low end of the footprint.
+    pub bottom: Vec2,
+    /// The center of the high end of the footprint.
+    pub top: Vec2,
+    /// The width of the footprint.
+    pub width: f32,
+    /// The heights of the low and high floors.
+    pub heights: (f32, f32),
+    /// The number of layers the ramp is sliced into.
+    pub steps: usize,
+}
+
+impl Ramp {
+    pub fn new(bottom: Vec2, top: Vec2, width: f32, heights: (f32, f32), steps: usize) -> Self {
+        Self {
+            bottom,
+            top,
+            width,
+            heights,
+            steps,
+        }
+    }
+
+    /// Slices the ramp into `steps` layers strictly between the two floors.
+    ///
+    /// Each layer holds an equally long section of the footprint, walled in
+    /// by the sides of the ramp, and open towards the neighbouring layers.
+    pub fn layered_faces(&self) -> Vec<(f32, Face)> {
+        let (low, high) = self.heights;
+        let side = (self.top - self.bottom).normalize_or_zero().perp() * self.width / 2.0;
+
+        (0..self.steps)
+            .flat_map(|i| {
+                let height = low + (high - low) * (i + 1) as f32 / (self.steps + 1) as f32;
+                let a = self.bottom.lerp(self.top, i as f32 / self.steps as f32);
+                let b = self
+                    .bottom
+                    .lerp(self.top, (i + 1) as f32 / self.steps as f32);
+
+                // Normals face into the ramp
+                [
+                    (height, Face::new([a + side, b + side])),
+                    (height, Face::new([b - side, a - side])),
+                ]
+            })
+            .collect()
+    }
+}
+
 #[derive(Default, Debug, Copy, Clone, PartialEq)]
 /// A two dimensional face of two vertices.
 /// Uses counterclockwise winding order to calculate a normal
-#[cfg_attr(feature = "serialize", derive(serde::Serialize, serde::Deserialize))]
-pub struct Face {
-    pub(crate) normal: Vec2,
-    pub vertices: [Vec2; 2],
+///
+/// The vector type defaults to [glam::Vec2], which the rest of the crate
+/// uses. See [Vector2] for other scalar types.
+pub struct Face<V = Vec2> {
+    pub(crate) normal: V,
+    pub vertices: [V; 2],
 }
 
-impl Face {
-    pub fn new(vertices: [Vec2; 2]) -> Self {
+impl<V: Vector2> Face<V> {
+    pub fn new(vertices: [V; 2]) -> Self {
         let dir = (vertices[1] - vertices[0]).normalize();
-        let normal = Vec2::new(dir.y, -dir.x);
+        let normal = V::new(dir.y(), -dir.x());
         Self { normal, vertices }
     }
 
+    /// Creates a face, or returns why the vertices do not form a usable face.
+    /// Unlike [Self::new], identical vertices are rejected instead of
+    /// producing a NaN normal.
+    pub fn try_new(vertices: [V; 2]) -> Result<Self, FaceDefect> {
+        let face = Self::new(vertices);
+        match face.defect() {
+            Some(defect) => Err(defect),
+            None => Ok(face),
+        }
+    }
+
+    /// Returns why the face is unusable for navigation, if it is.
+    pub fn defect(&self) -> Option<FaceDefect> {
+        // NaN and infinities are the only values which are not 0 when
+        // subtracted from themselves
+        let finite = |v: V| v.x() - v.x() == V::Scalar::ZERO && v.y() - v.y() == V::Scalar::ZERO;
+
+        if !self.vertices.iter().all(|&v| finite(v)) {
+            Some(FaceDefect::NonFinite)
+        } else if self.vertices[0] == self.vertices[1] {
+            Some(FaceDefect::ZeroLength)
+        } else if !finite(self.normal) {
+            Some(FaceDefect::NonFinite)
+        } else if self.length() < tolerance() {
+            Some(FaceDefect::TooShort)
+        } else {
+            None
+        }
+    }
+
     // Return the length of the face
-    pub fn length(&self) -> f32 {
+    pub fn length(&self) -> V::Scalar {
         (self.vertices[0] - self.vertices[1]).length()
     }
 
-    pub fn length_squared(&self) -> f32 {
+    pub fn length_squared(&self) -> V::Scalar {
         (self.vertices[0] - self.vertices[1]).length_squared()
     }
 
     /// Get the face's vertices.
-    pub fn vertices(&self) -> [Vec2; 2] {
+    pub fn vertices(&self) -> [V; 2] {
         self.vertices
     }
 
-    pub fn into_tuple(&self) -> (Vec2, Vec2) {
+    pub fn into_tuple(&self) -> (V, V) {
         (self.vertices[0], self.vertices[1])
     }
 
     /// Get the face's normal.
     #[inline]
-    pub fn normal(&self) -> Vec2 {
+    pub fn normal(&self) -> V {
         self.normal
     }
 
-    /// Transforms the face
-    pub fn transform(&self, transform: Mat3) -> Self {
-        let [a, b] = self.vertices;
-        Face::new([transform.transform_point2(a), transform.transform_point2(b)])
-    }
-
-    /// Transforms the face using 3d space using xz plane
-    pub fn transform_3d(&self, transform: Mat4) -> Self {
-        let a = transform.transform_point3(self.vertices[0].extend(0.0).xzy());
-        let b = transform.transform_point3(self.vertices[1].extend(0.0).xzy());
-
-        Self::new([a.xz(), b.xz()])
-    }
-
     /// Returns the side self is in respect to a point and normal
-    pub fn side_of(&self, p: Vec2, normal: Vec2) -> Side {
-        let a = (self.vertices[0] - p).dot(normal);
-        let b = (self.vertices[1] - p).dot(normal);
+    pub fn side_of(&self, p: V, normal: V) -> Side {
+        let a = Side::of_distance(self.vertices[0].plane_distance(p, normal));
+        let b = Side::of_distance(self.vertices[1].plane_distance(p, normal));
 
-        if a.abs() < TOLERANCE && b.abs() < TOLERANCE {
-            Side::Coplanar
-        } else if a >= -TOLERANCE && b >= -TOLERANCE {
-            Side::Front
-        } else if a <= TOLERANCE && b <= TOLERANCE {
-            Side::Back
-        } else {
-            Side::Intersecting
-        }
+        a.merge(b)
     }
 
     /// Splits the face around `p`
-    pub fn split(&self, p: Vec2, normal: Vec2) -> [Self; 2] {
-        let a = (self.vertices[0] - p).dot(normal);
-        if a >= -TOLERANCE {
+    pub fn split(&self, p: V, normal: V) -> [Self; 2] {
+        let a = self.vertices[0].plane_distance(p, normal);
+        if a >= -tolerance::<V::Scalar>() {
             [
                 Face::new([self.vertices[0], p]),
                 Face::new([p, self.vertices[1]]),
@@ -143,17 +401,18 @@ impl Face {
     }
 
     /// Returns true if the face is touching the other face
-    pub fn adjacent(&self, other: Face) -> bool {
+    pub fn adjacent(&self, other: Self) -> bool {
         let p = other.midpoint();
-        let a = (self.vertices[0] - p).dot(other.normal);
-        let b = (self.vertices[1] - p).dot(other.normal);
+        let a = self.vertices[0].plane_distance(p, other.normal);
+        let b = self.vertices[1].plane_distance(p, other.normal);
+        let tolerance = tolerance::<V::Scalar>();
 
         // a.signum() != b.signum()
-        (a < -TOLERANCE && b > TOLERANCE) || (b < -TOLERANCE && a > TOLERANCE)
+        (a < -tolerance && b > tolerance) || (b < -tolerance && a > tolerance)
     }
 
-    pub fn midpoint(&self) -> Vec2 {
-        (self.vertices[0] + self.vertices[1]) / 2.0
+    pub fn midpoint(&self) -> V {
+        (self.vertices[0] + self.vertices[1]) / V::Scalar::from_f32(2.0)
     }
 
     /// Returns true if `other` overlaps self
@@ -166,7 +425,7 @@ impl Face {
         let b = (other.vertices[1]).dot(dir);
 
         // a -- b in the direction of self
-        let (a, b) = if dir.dot(other.dir()) > 0.0 {
+        let (a, b) = if dir.dot(other.dir()) > V::Scalar::ZERO {
             (a, b)
         } else {
             (b, a)
@@ -177,22 +436,136 @@ impl Face {
 
         let overlap = la.min(lb);
 
-        overlap > TOLERANCE
+        overlap > tolerance()
     }
 
-    pub fn contains_point(&self, p: Vec2) -> bool {
+    /// Returns the point on the face closest to `p`
+    pub fn closest_point(&self, p: V) -> V {
+        let dir = self.dir();
+        let d = (p - self.vertices[0])
+            .dot(dir)
+            .clamp(V::Scalar::ZERO, self.length());
+
+        self.vertices[0] + dir * d
+    }
+
+    pub fn contains_point(&self, p: V) -> bool {
         let dir = self.dir();
 
         let d = (p - self.vertices[0]).dot(dir);
+        let tolerance = tolerance::<V::Scalar>();
 
-        d > -TOLERANCE && d < self.length() + TOLERANCE
+        d > -tolerance && d < self.length() + tolerance
     }
 
-    pub fn dir(&self) -> Vec2 {
+    pub fn dir(&self) -> V {
         (self.vertices[1] - self.vertices[0]).normalize()
     }
 }
 
+impl Face {
+    /// Transforms the face
+    pub fn transform(&self, transform: Mat3) -> Self {
+        let [a, b] = self.vertices;
+        Face::new([transform.transform_point2(a), transform.transform_point2(b)])
+    }
+
+    /// Transforms the face, keeping the normal on the same side of the
+    /// geometry.
+    ///
+    /// Mirroring transforms reverse the winding, which would otherwise flip
+    /// the normal into the solid side.
+    pub fn transform_oriented(&self, transform: Mat3) -> Self {
+        let [a, b] = self.vertices;
+        let (a, b) = (transform.transform_point2(a), transform.transform_point2(b));
+
+        if transform.determinant() < 0.0 {
+            Face::new([b, a])
+        } else {
+            Face::new([a, b])
+        }
+    }
+
+    pub fn translated(&self, translation: Vec2) -> Self {
+        self.transform_oriented(Mat3::from_translation(translation))
+    }
+
+    /// Rotates the face counterclockwise by `angle` radians around the origin
+    pub fn rotated(&self, angle: f32) -> Self {
+        self.transform_oriented(Mat3::from_angle(angle))
+    }
+
+    /// Scales the face relative to the origin, keeping the normal on the
+    /// same side of the geometry
+    pub fn scaled(&self, scale: Vec2) -> Self {
+        self.transform_oriented(Mat3::from_scale(scale))
+    }
+
+    /// Transforms the face using 3d space using xz plane
+    pub fn transform_3d(&self, transform: Mat4) -> Self {
+        let a = transform.transform_point3(self.vertices[0].extend(0.0).xzy());
+        let b = transform.transform_point3(self.vertices[1].extend(0.0).xzy());
+
+        Self::new([a.xz(), b.xz()])
+    }
+}
+
+/// Describes why a face is unusable for navigation, see [Face::defect]
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum FaceDefect {
+    /// A vertex or the normal is NaN or infinite
+    NonFinite,
+    /// The vertices are identical, which leaves the normal undefined
+    ZeroLength,
+    /// The face is shorter than [TOLERANCE], and is lost when partitioning
+    TooShort,
+}
+
+impl Display for FaceDefect {
+    fn fmt(&self, f: &mut core::fmt::Formatter<'_>) -> core::fmt::Result {
+        match self {
+            FaceDefect::NonFinite => write!(f, "is not finite"),
+            FaceDefect::ZeroLength => write!(f, "has zero length"),
+            FaceDefect::TooShort => write!(f, "is shorter than the tolerance"),
+        }
+    }
+}
+
+/// A degenerate face of the input to [BSPTree::try_from_slice](crate::BSPTree::try_from_slice)
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub struct FaceError {
+    /// The index of the face in the input
+    pub index: usize,
+    pub defect: FaceDefect,
+}
+
+impl Display for FaceError {
+    fn fmt(&self, f: &mut core::fmt::Formatter<'_>) -> core::fmt::Result {
+        write!(f, "Face {} {}", self.index, self.defect)
+    }
+}
+
+impl core::error::Error for FaceError {}
+
+/// Returns every degenerate face of `faces`
+pub fn degenerate_faces<V: Vector2>(faces: &[Face<V>]) -> Vec<FaceError> {
+    faces
+        .iter()
+        .enumerate()
+        .filter_map(|(index, face)| {
+            Some(FaceError {
+                index,
+                defect: face.defect()?,
+            })
+        })
+        .collect()
+}
+
+/// [TOLERANCE] in the scalar type
+fn tolerance<S: Scalar>() -> S {
+    S::from_f32(TOLERANCE)
+}
+
 impl<'a> IntoIterator for &'a Shape {
     type Item = Face;
 
@@ -203,20 +576,20 @@ impl<'a> IntoIterator for &'a Shape {
     }
 }
 
-impl<'a> IntoIterator for Face {
-    type Item = Vec2;
+impl<V> IntoIterator for Face<V> {
+    type Item = V;
 
-    type IntoIter = array::IntoIter<Vec2, 2>;
+    type IntoIter = array::IntoIter<V, 2>;
 
     fn into_iter(self) -> Self::IntoIter {
         self.vertices.into_iter()
     }
 }
 
-impl<'a> IntoIterator for &'a Face {
-    type Item = Vec2;
+impl<V: Copy> IntoIterator for &Face<V> {
+    type Item = V;
 
-    type IntoIter = array::IntoIter<Vec2, 2>;
+    type IntoIter = array::IntoIter<V, 2>;
 
     fn into_iter(self) -> Self::IntoIter {
         self.vertices.into_iter()
@@ -235,36 +608,125 @@ impl<'a> Iterator for Faces<'a> {
 
     // Generate normals from winding
     fn next(&mut self) -> Option<Self::Item> {
-        if self.current + 1 == self.len {
+        if self.current + 1 >= self.len {
             return None;
         }
 
         let a = self.vertices[self.current];
-        let b = self.vertices[(self.current + 1)];
+        let b = self.vertices[self.current + 1];
 
         self.current += 1;
         Some(Face::new([a, b]))
     }
 }
 
-#[cfg(test)]
-mod tests {
-    use glam::Vec2;
-
-    use super::Shape;
-
-    #[test]
-    fn shape_rect() {
-        let rect = Shape::rect(Vec2::new(2.0, 1.0), Vec2::new(1.0, 0.0));
-
-        let faces = rect.faces();
-
-        let normals = [-Vec2::Y, Vec2::X, Vec2::Y, -Vec2::X];
+/// The number of segments a full circle is approximated by in [inflate]
+pub const ARC_SEGMENTS: usize = 16;
+
+/// Inflates the obstacles outlined by `faces` outward by `radius`, i.e; the
+/// Minkowski sum with a circle.
+///
+/// Faces are connected into outlines by their shared vertices. Convex corners
+/// are rounded by arcs of [ARC_SEGMENTS] per full circle, and concave corners
+/// are trimmed to where the inflated faces meet. Open ends of outlines are
+/// extended by `radius`.
+///
+/// Obstacles closer than `2 * radius` overlap once inflated, which the tree
+/// does not support. Closed shapes can be inflated with [Shape::offset]
+/// instead, which resolves overlaps.
+pub fn inflate(faces: &[Face], radius: f32) -> Vec<Face> {
+    if radius <= 0.0 {
+        return faces.to_vec();
+    }
 
-        assert!(faces.map(|val| val.normal).eq(normals));
+    // The face continuing from the end of each face
+    let next = faces
+        .iter()
+        .enumerate()
+        .map(|(i, a)| {
+            faces.iter().enumerate().position(|(j, b)| {
+                i != j && a.vertices[1].distance_squared(b.vertices[0]) < TOLERANCE * TOLERANCE
+            })
+        })
+        .collect::<Vec<_>>();
+
+    let mut has_prev = vec![false; faces.len()];
+    next.iter().flatten().for_each(|&j| has_prev[j] = true);
+
+    let mut ends = faces
+        .iter()
+        .zip(&has_prev)
+        .zip(&next)
+        .map(|((face, has_prev), next)| {
+            let [a, b] = face.vertices.map(|v| v + face.normal * radius);
+            let extend = face.dir() * radius;
+            [
+                if *has_prev { a } else { a - extend },
+                if next.is_some() { b } else { b + extend },
+            ]
+        })
+        .collect::<Vec<_>>();
+
+    let mut arcs = Vec::new();
+    for (i, j) in next
+        .iter()
+        .enumerate()
+        .filter_map(|(i, j)| Some((i, (*j)?)))
+    {
+        let (a, b) = (&faces[i], &faces[j]);
+        let turn = a.dir().perp_dot(b.dir());
+
+        if turn < -f32::EPSILON {
+            // Concave, trim both faces to where they meet
+            let (p, q) = (ends[i][0], ends[j][0]);
+            let t = (q - p).perp_dot(b.dir()) / a.dir().perp_dot(b.dir());
+            ends[i][1] = p + a.dir() * t;
+            ends[j][0] = ends[i][1];
+        } else if turn > f32::EPSILON {
+            // Convex, round the corner
+            let angle = a.normal.perp_dot(b.normal).atan2(a.normal.dot(b.normal));
+            let steps = (angle / TAU * ARC_SEGMENTS as f32).ceil().max(1.0) as usize;
+            let center = a.vertices[1];
+
+            let points = (0..=steps).map(|k| {
+                let (sin, cos) = (angle * k as f32 / steps as f32).sin_cos();
+                let n = a.normal;
+                center + Vec2::new(cos * n.x - sin * n.y, sin * n.x + cos * n.y) * radius
+            });
+
+            arcs.extend(
+                points
+                    .clone()
+                    .zip(points.skip(1))
+                    .map(|(p, q)| Face::new([p, q])),
+            );
+        }
     }
+
+    // Faces which were trimmed away entirely are reversed
+    faces
+        .iter()
+        .zip(ends)
+        .filter(|(face, [a, b])| (*b - *a).dot(face.dir()) > TOLERANCE)
+        .map(|(_, vertices)| Face::new(vertices))
+        .chain(arcs)
+        .collect()
 }
 
+/// Describes where a point or face lies in respect to a plane.
+///
+/// A point further than [crate::TOLERANCE] from the plane is [Side::Front] or
+/// [Side::Back], and [Side::Coplanar] otherwise, see [Side::of_distance].
+///
+/// A face is the [Side::merge] of its vertices:
+/// - [Side::Front]: no vertex behind the plane, and at least one in front.
+///   The face may touch the plane.
+/// - [Side::Back]: the mirror of [Side::Front]
+/// - [Side::Coplanar]: every vertex on the plane
+/// - [Side::Intersecting]: vertices both in front of and behind the plane
+///
+/// [Side::min_side] and [Side::max_side] instead pick out the furthest back
+/// or forward part, and never yield [Side::Intersecting].
 #[derive(Clone, Copy, PartialEq, Eq, PartialOrd, Ord, Debug)]
 pub enum Side {
     Front,
@@ -274,10 +736,63 @@ pub enum Side {
 }
 
 impl Side {
+    /// Classifies a signed distance from a plane
+    pub fn of_distance<S: Scalar>(distance: S) -> Self {
+        if distance.abs() < tolerance() {
+            Side::Coplanar
+        } else if distance <= S::ZERO {
+            Side::Back
+        } else {
+            Side::Front
+        }
+    }
+
+    /// Returns the side of the furthest back part of `self` and `other`.
+    ///
+    /// Sides are ordered [Side::Back], [Side::Coplanar], [Side::Front] along
+    /// the plane normal, and the lowest of the two is returned.
+    /// [Side::Intersecting] reaches behind the plane and counts as
+    /// [Side::Back], e.g; `Front.min_side(Intersecting)` is [Side::Back], and
+    /// `Front.min_side(Coplanar)` is [Side::Coplanar].
+    ///
+    /// This is commutative and associative. [Side::Front] leaves every side
+    /// but [Side::Intersecting] unchanged.
     pub fn min_side(&self, other: Self) -> Self {
-        match (self, other) {
-            (Side::Back, _) | (_, Side::Back) => Side::Back,
-            _ => *self,
+        match (*self, other) {
+            (Side::Back | Side::Intersecting, _) | (_, Side::Back | Side::Intersecting) => {
+                Side::Back
+            }
+            (Side::Coplanar, _) | (_, Side::Coplanar) => Side::Coplanar,
+            _ => Side::Front,
+        }
+    }
+
+    /// Returns the side of the furthest forward part of `self` and `other`.
+    ///
+    /// The mirror of [Side::min_side]: the highest of the two is returned,
+    /// and [Side::Intersecting] counts as [Side::Front]. [Side::Back] leaves
+    /// every side but [Side::Intersecting] unchanged.
+    pub fn max_side(&self, other: Self) -> Self {
+        match (*self, other) {
+            (Side::Front | Side::Intersecting, _) | (_, Side::Front | Side::Intersecting) => {
+                Side::Front
+            }
+            (Side::Coplanar, _) | (_, Side::Coplanar) => Side::Coplanar,
+            _ => Side::Back,
+        }
+    }
+
+    /// Returns the side of the union of `self` and `other`, e.g; the side of a
+    /// face given the sides of its vertices.
+    ///
+    /// [Side::Coplanar] is the identity, [Side::Intersecting] absorbs
+    /// everything, and [Side::Front] merged with [Side::Back] is
+    /// [Side::Intersecting].
+    pub fn merge(&self, other: Self) -> Self {
+        match (*self, other) {
+            (Side::Coplanar, side) | (side, Side::Coplanar) => side,
+            (a, b) if a == b => a,
+            _ => Side::Intersecting,
         }
     }
 }
@@ -0,0 +1,64 @@
+use alloc::sync::Arc;
+use core::ops::Deref;
+
+use crate::{BSPTree, NavigationContext, Portals};
+
+/// Fails to compile if the navigation data can not be shared between threads.
+/// Only `Rc` free data may be stored, e.g; `rpds` collections are kept to
+/// portal generation.
+const _: () = {
+    const fn assert_send_sync<T: Send + Sync>() {}
+
+    assert_send_sync::<NavigationContext>();
+    assert_send_sync::<BSPTree>();
+    assert_send_sync::<Portals>();
+    assert_send_sync::<SharedNav>();
+};
+
+/// A [NavigationContext] shared between threads, e.g; the workers of a job
+/// system.
+///
+/// Cloning is cheap and shares the same context. Queries are made through
+/// [Deref].
+#[derive(Default, Clone)]
+pub struct SharedNav {
+    inner: Arc<NavigationContext>,
+}
+
+impl SharedNav {
+    pub fn new(nav: NavigationContext) -> Self {
+        Self {
+            inner: Arc::new(nav),
+        }
+    }
+
+    /// Returns a mutable reference to the context, cloning it first if it is
+    /// shared. Existing clones keep the previous context.
+    pub fn make_mut(&mut self) -> &mut NavigationContext {
+        Arc::make_mut(&mut self.inner)
+    }
+
+    /// Replaces the context. Existing clones keep the previous context.
+    pub fn replace(&mut self, nav: NavigationContext) {
+        self.inner = Arc::new(nav);
+    }
+
+    /// Returns true if both refer to the same context
+    pub fn ptr_eq(&self, other: &Self) -> bool {
+        Arc::ptr_eq(&self.inner, &other.inner)
+    }
+}
+
+impl Deref for SharedNav {
+    type Target = NavigationContext;
+
+    fn deref(&self) -> &Self::Target {
+        &self.inner
+    }
+}
+
+impl From<NavigationContext> for SharedNav {
+    fn from(nav: NavigationContext) -> Self {
+        Self::new(nav)
+    }
+}
@@ -0,0 +1,134 @@
+use alloc::{boxed::Box, vec::Vec};
+use core::any::{Any, TypeId};
+
+use crate::{util::HashMap, PortalRef};
+
+/// Typed tables of user data, one for each type of table
+#[derive(Default)]
+pub(crate) struct SideTables {
+    tables: HashMap<TypeId, Box<dyn Table>>,
+}
+
+impl SideTables {
+    pub(crate) fn get<T: Any>(&self) -> Option<&T> {
+        let table: &dyn Table = self.tables.get(&TypeId::of::<T>())?.as_ref();
+        table.as_any().downcast_ref()
+    }
+
+    /// Returns the table of type `T`, inserting an empty table if there is
+    /// none
+    pub(crate) fn get_mut<T: Table + Default>(&mut self) -> &mut T {
+        self.tables
+            .entry(TypeId::of::<T>())
+            .or_insert_with(|| Box::<T>::default())
+            .as_mut()
+            .as_any_mut()
+            .downcast_mut()
+            .expect("Table is stored by its type")
+    }
+
+    pub(crate) fn remove<T: Any>(&mut self) -> Option<T> {
+        let table = self.tables.remove(&TypeId::of::<T>())?;
+        table.into_any().downcast().ok().map(|v| *v)
+    }
+}
+
+impl Clone for SideTables {
+    fn clone(&self) -> Self {
+        Self {
+            tables: self
+                .tables
+                .iter()
+                .map(|(&id, table)| (id, table.as_ref().clone_table()))
+                .collect(),
+        }
+    }
+}
+
+pub(crate) trait Table: Any + Send + Sync {
+    fn clone_table(&self) -> Box<dyn Table>;
+    fn as_any(&self) -> &dyn Any;
+    fn as_any_mut(&mut self) -> &mut dyn Any;
+    fn into_any(self: Box<Self>) -> Box<dyn Any>;
+}
+
+impl<T: Any + Clone + Send + Sync> Table for T {
+    fn clone_table(&self) -> Box<dyn Table> {
+        Box::new(self.clone())
+    }
+
+    fn as_any(&self) -> &dyn Any {
+        self
+    }
+
+    fn as_any_mut(&mut self) -> &mut dyn Any {
+        self
+    }
+
+    fn into_any(self: Box<Self>) -> Box<dyn Any> {
+        self
+    }
+}
+
+/// User data of type `T` for portals, where both directions of a portal
+/// share the same entry. See [NavigationContext::portal_table](crate::NavigationContext::portal_table).
+#[derive(Debug, Clone)]
+pub struct PortalTable<T> {
+    values: Vec<Option<T>>,
+    len: usize,
+}
+
+impl<T> Default for PortalTable<T> {
+    fn default() -> Self {
+        Self {
+            values: Vec::new(),
+            len: 0,
+        }
+    }
+}
+
+impl<T> PortalTable<T> {
+    pub fn get(&self, portal: PortalRef) -> Option<&T> {
+        self.values.get(portal.face)?.as_ref()
+    }
+
+    pub fn get_mut(&mut self, portal: PortalRef) -> Option<&mut T> {
+        self.values.get_mut(portal.face)?.as_mut()
+    }
+
+    /// Sets the entry of `portal`, returning the previous entry
+    pub fn insert(&mut self, portal: PortalRef, value: T) -> Option<T> {
+        if portal.face >= self.values.len() {
+            self.values.resize_with(portal.face + 1, || None);
+        }
+
+        let old = self.values[portal.face].replace(value);
+        if old.is_none() {
+            self.len += 1;
+        }
+
+        old
+    }
+
+    pub fn remove(&mut self, portal: PortalRef) -> Option<T> {
+        let old = self.values.get_mut(portal.face)?.take();
+        if old.is_some() {
+            self.len -= 1;
+        }
+
+        old
+    }
+
+    pub fn contains(&self, portal: PortalRef) -> bool {
+        self.get(portal).is_some()
+    }
+
+    /// Returns the number of portals with an entry
+    pub fn len(&self) -> usize {
+        self.len
+    }
+
+    pub fn is_empty(&self) -> bool {
+        self.len == 0
+    }
+}
@@ -0,0 +1,254 @@
+//! A deterministic fixed timestep simulation of agents moving around a
+//! [NavigationContext], e.g; for headless testing or authoritative server
+//! side movement.
+
+use alloc::{vec, vec::Vec};
+
+use glam::Vec2;
+use slotmap::SecondaryMap;
+
+use crate::{AgentId, AgentStatus, NavigationContext, Navigator, NavigatorSettings, TOLERANCE};
+
+/// Controls the [Simulation]
+#[derive(Debug, Clone, Copy, PartialEq)]
+pub struct SimSettings {
+    /// The duration of each step, in seconds.
+    pub timestep: f32,
+    /// The maximum number of steps run by a single [Simulation::update], which
+    /// drops time rather than spiralling when the simulation can not keep up.
+    pub max_steps: usize,
+    /// Push overlapping agents apart.
+    pub avoidance: bool,
+    /// Controls replanning. The replan budget is measured in wall clock time
+    /// and is ignored to keep the simulation deterministic.
+    pub navigator: NavigatorSettings,
+}
+
+impl Default for SimSettings {
+    fn default() -> Self {
+        Self {
+            timestep: 1.0 / 60.0,
+            max_steps: 8,
+            avoidance: true,
+            navigator: NavigatorSettings::default(),
+        }
+    }
+}
+
+#[derive(Debug, Clone, Copy)]
+struct SimAgent {
+    position: Vec2,
+    velocity: Vec2,
+    radius: f32,
+    speed: f32,
+}
+
+/// Advances agents along their paths in fixed steps using a [Navigator].
+///
+/// Agents move towards their steering target at their speed, are pushed
+/// apart when overlapping, and stop at walls. The same inputs always produce
+/// the same movement, regardless of the frame rate [Self::update] is called
+/// at.
+pub struct Simulation {
+    navigator: Navigator,
+    agents: SecondaryMap<AgentId, SimAgent>,
+    settings: SimSettings,
+    accumulator: f32,
+    steps: u64,
+}
+
+impl Simulation {
+    pub fn new(nav: NavigationContext, mut settings: SimSettings) -> Self {
+        settings.navigator.replan_budget = None;
+        Self {
+            navigator: Navigator::new(nav, settings.navigator),
+            agents: SecondaryMap::new(),
+            settings,
+            accumulator: 0.0,
+            steps: 0,
+        }
+    }
+
+    /// Adds an agent of `radius` at `position`, which moves at `speed` units
+    /// per second.
+    pub fn add_agent(&mut self, position: Vec2, radius: f32, speed: f32) -> AgentId {
+        let id = self.navigator.add_agent(position, radius);
+        self.agents.insert(
+            id,
+            SimAgent {
+                position,
+                velocity: Vec2::ZERO,
+                radius,
+                speed,
+            },
+        );
+        id
+    }
+
+    /// Removes an agent. Returns false if the agent did not exist.
+    pub fn remove_agent(&mut self, id: AgentId) -> bool {
+        self.agents.remove(id);
+        self.navigator.remove_agent(id)
+    }
+
+    /// Gives the agent a new target. See [Navigator::move_to].
+    pub fn move_to(&mut self, id: AgentId, target: Vec2) {
+        self.navigator.move_to(id, target)
+    }
+
+    /// Clears the target of the agent. See [Navigator::stop].
+    pub fn stop(&mut self, id: AgentId) {
+        self.navigator.stop(id)
+    }
+
+    /// Teleports the agent to `position`.
+    pub fn set_position(&mut self, id: AgentId, position: Vec2) {
+        if let Some(agent) = self.agents.get_mut(id) {
+            agent.position = position;
+            agent.velocity = Vec2::ZERO;
+            self.navigator.set_position(id, position);
+        }
+    }
+
+    /// Returns the current position of an agent.
+    pub fn position(&self, id: AgentId) -> Option<Vec2> {
+        self.agents.get(id).map(|agent| agent.position)
+    }
+
+    /// Returns the velocity of an agent during the last step.
+    pub fn velocity(&self, id: AgentId) -> Option<Vec2> {
+        self.agents.get(id).map(|agent| agent.velocity)
+    }
+
+    /// Returns the movement state of an agent.
+    pub fn status(&self, id: AgentId) -> Option<AgentStatus> {
+        self.navigator.status(id)
+    }
+
+    /// Advances the simulation by `dt` seconds of real time, running as many
+    /// whole steps as fit, and returns the number of steps run.
+    /// The remainder is carried over to the next update.
+    pub fn update(&mut self, dt: f32) -> usize {
+        self.accumulator += dt;
+
+        let mut steps = 0;
+        while self.accumulator >= self.settings.timestep && steps < self.settings.max_steps {
+            self.step();
+            self.accumulator -= self.settings.timestep;
+            steps += 1;
+        }
+
+        // Drop the time which could not be caught up with
+        if steps == self.settings.max_steps {
+            self.accumulator = self.accumulator.min(self.settings.timestep);
+        }
+
+        steps
+    }
+
+    /// Advances the simulation by exactly one timestep
+    pub fn step(&mut self) {
+        let dt = self.settings.timestep;
+        let steering = self.navigator.tick(dt).collect::<Vec<_>>();
+
+        let mut targets = SecondaryMap::<AgentId, Vec2>::new();
+        for (id, agent) in &self.agents {
+            targets.insert(id, agent.position);
+        }
+
+        for (id, target) in steering {
+            let agent = &self.agents[id];
+            let rel = target - agent.position;
+            let step = (agent.speed * dt).min(rel.length());
+            targets[id] = agent.position + rel.normalize_or_zero() * step;
+        }
+
+        if self.settings.avoidance {
+            separate(&self.agents, &mut targets);
+        }
+
+        let nav = self.navigator.nav();
+        for (id, agent) in &mut self.agents {
+            let target = stop_at_walls(nav, agent.position, targets[id]);
+            agent.velocity = (target - agent.position) / dt;
+            agent.position = target;
+        }
+
+        for (id, agent) in &self.agents {
+            self.navigator.set_position(id, agent.position);
+        }
+
+        self.steps += 1;
+    }
+
+    /// Returns the number of steps run
+    pub fn steps(&self) -> u64 {
+        self.steps
+    }
+
+    /// Returns the simulated time, in seconds
+    pub fn time(&self) -> f64 {
+        self.steps as f64 * self.settings.timestep as f64
+    }
+
+    /// Returns how far into the next step the accumulated time is, for
+    /// interpolating rendered positions between steps
+    pub fn alpha(&self) -> f32 {
+        self.accumulator / self.settings.timestep
+    }
+
+    /// Get a reference to the simulation's navigator.
+    pub fn navigator(&self) -> &Navigator {
+        &self.navigator
+    }
+
+    /// Get a reference to the simulation's settings.
+    pub fn settings(&self) -> &SimSettings {
+        &self.settings
+    }
+}
+
+/// Pushes overlapping agents apart, each by half the overlap
+fn separate(agents: &SecondaryMap<AgentId, SimAgent>, targets: &mut SecondaryMap<AgentId, Vec2>) {
+    let ids = agents.keys().collect::<Vec<_>>();
+    let mut pushes = vec![Vec2::ZERO; ids.len()];
+
+    for i in 0..ids.len() {
+        for j in i + 1..ids.len() {
+            let (a, b) = (ids[i], ids[j]);
+            let rel = targets[b] - targets[a];
+            let overlap = agents[a].radius + agents[b].radius - rel.length();
+            if overlap <= 0.0 {
+                continue;
+            }
+
+            // Agents on top of each other are separated along a fixed axis
+            let dir = match rel.normalize_or_zero() {
+                dir if dir == Vec2::ZERO => Vec2::X,
+                dir => dir,
+            };
+
+            pushes[i] -= dir * overlap * 0.5;
+            pushes[j] += dir * overlap * 0.5;
+        }
+    }
+
+    for (id, push) in ids.into_iter().zip(pushes) {
+        targets[id] += push;
+    }
+}
+
+/// Returns how far along the movement from `from` to `to` the agent gets
+/// before hitting a wall
+fn stop_at_walls(nav: &NavigationContext, from: Vec2, to: Vec2) -> Vec2 {
+    let tree = match nav.tree() {
+        Some(tree) => tree,
+        None => return to,
+    };
+
+    let rel = to - from;
+    match tree.raycast(from, rel, rel.length()) {
+        Some(hit) => from + rel.normalize_or_zero() * (hit.distance - TOLERANCE).max(0.0),
+        None => to,
+    }
+}
@@ -0,0 +1,165 @@
+use std::{
+    borrow::Cow,
+    io::{self, Write},
+    time::{Duration, Instant},
+};
+
+/// A timed span or instant recorded by a [Tracer].
+#[derive(Debug, Clone, PartialEq)]
+pub struct TraceEvent {
+    pub name: Cow<'static, str>,
+    /// Groups events, e.g; `build` or `query`.
+    pub category: &'static str,
+    /// The time since the tracer was created.
+    pub start: Duration,
+    /// None for instant events, such as frame markers.
+    pub duration: Option<Duration>,
+    /// Additional values shown for the event, e.g; the number of expansions.
+    pub args: Vec<(&'static str, f64)>,
+}
+
+/// Records the timings of context construction and queries, which can be
+/// written in the Chrome trace event format and opened in chrome://tracing
+/// or Perfetto.
+///
+/// Frame markers from [Self::mark] allow correlating navigation spikes with
+/// the frames they occurred in.
+/// See [NavigationContext::new_traced](crate::NavigationContext::new_traced)
+/// and [NavigationContext::find_path_traced](crate::NavigationContext::find_path_traced).
+#[derive(Debug, Clone)]
+pub struct Tracer {
+    origin: Instant,
+    events: Vec<TraceEvent>,
+}
+
+impl Default for Tracer {
+    fn default() -> Self {
+        Self::new()
+    }
+}
+
+impl Tracer {
+    pub fn new() -> Self {
+        Self {
+            origin: Instant::now(),
+            events: Vec::new(),
+        }
+    }
+
+    /// Runs `f` and records its duration
+    pub fn span<T>(
+        &mut self,
+        name: impl Into<Cow<'static, str>>,
+        category: &'static str,
+        f: impl FnOnce() -> T,
+    ) -> T {
+        let start = Instant::now();
+        let result = f();
+        self.record(name, category, start, Vec::new());
+        result
+    }
+
+    /// Records a span from `start` until now
+    pub fn record(
+        &mut self,
+        name: impl Into<Cow<'static, str>>,
+        category: &'static str,
+        start: Instant,
+        args: Vec<(&'static str, f64)>,
+    ) {
+        let end = Instant::now();
+        self.events.push(TraceEvent {
+            name: name.into(),
+            category,
+            start: start.saturating_duration_since(self.origin),
+            duration: Some(end.saturating_duration_since(start)),
+            args,
+        })
+    }
+
+    /// Records an instant event, e.g; the start of a frame
+    pub fn mark(&mut self, name: impl Into<Cow<'static, str>>) {
+        self.events.push(TraceEvent {
+            name: name.into(),
+            category: "mark",
+            start: self.origin.elapsed(),
+            duration: None,
+            args: Vec::new(),
+        })
+    }
+
+    /// Get the tracer's events, in the order they finished.
+    pub fn events(&self) -> &[TraceEvent] {
+        &self.events
+    }
+
+    /// Removes all events, e.g; after they have been written
+    pub fn clear(&mut self) {
+        self.events.clear()
+    }
+
+    /// Writes the events as a Chrome trace JSON object
+    pub fn write_json(&self, mut writer: impl Write) -> io::Result<()> {
+        write!(writer, "{{\"traceEvents\":[")?;
+
+        for (i, event) in self.events.iter().enumerate() {
+            if i > 0 {
+                write!(writer, ",")?;
+            }
+
+            write!(writer, "{{\"name\":")?;
+            write_str(&mut writer, &event.name)?;
+            write!(writer, ",\"cat\":")?;
+            write_str(&mut writer, event.category)?;
+            write!(writer, ",\"ts\":{}", micros(event.start))?;
+
+            match event.duration {
+                Some(duration) => write!(writer, ",\"ph\":\"X\",\"dur\":{}", micros(duration))?,
+                None => write!(writer, ",\"ph\":\"i\",\"s\":\"g\"")?,
+            }
+
+            write!(writer, ",\"pid\":1,\"tid\":1,\"args\":{{")?;
+            for (j, (key, value)) in event.args.iter().enumerate() {
+                if j > 0 {
+                    write!(writer, ",")?;
+                }
+                write_str(&mut writer, key)?;
+                // JSON has no representation of non finite numbers
+                if value.is_finite() {
+                    write!(writer, ":{}", value)?;
+                } else {
+                    write!(writer, ":null")?;
+                }
+            }
+            write!(writer, "}}}}")?;
+        }
+
+        write!(writer, "]}}")
+    }
+
+    /// Returns the events as a Chrome trace JSON object
+    pub fn to_json(&self) -> String {
+        let mut buf = Vec::new();
+        self.write_json(&mut buf)
+            .expect("Writing to a Vec does not fail");
+        String::from_utf8(buf).expect("JSON is valid UTF-8")
+    }
+}
+
+fn micros(duration: Duration) -> f64 {
+    duration.as_secs_f64() * 1e6
+}
+
+fn write_str(writer: &mut impl Write, s: &str) -> io::Result<()> {
+    write!(writer, "\"")?;
+    for c in s.chars() {
+        match c {
+            '"' => write!(writer, "\\\"")?,
+            '\\' => write!(writer, "\\\\")?,
+            '\n' => write!(writer, "\\n")?,
+            c if c.is_control() => write!(writer, "\\u{:04x}", c as u32)?,
+            c => write!(writer, "{}", c)?,
+        }
+    }
+    write!(writer, "\"")
+}
@@ -0,0 +1,146 @@
+use alloc::{vec, vec::Vec};
+
+use glam::Vec2;
+#[cfg(not(feature = "std"))]
+use num_traits::Float;
+
+use crate::Face;
+
+/// The largest number of cells along each axis
+const MAX_CELLS: usize = 256;
+
+/// Buckets faces in a uniform grid, so that the distance to the closest face
+/// can be found for many points without comparing against every face.
+pub(crate) struct FaceGrid<'a> {
+    faces: Vec<&'a Face>,
+    origin: Vec2,
+    cell_size: f32,
+    width: usize,
+    height: usize,
+    /// The faces overlapping each cell, row by row
+    cells: Vec<Vec<u32>>,
+}
+
+impl<'a> FaceGrid<'a> {
+    pub(crate) fn new(faces: Vec<&'a Face>) -> Self {
+        let (min, max) = faces
+            .iter()
+            .flat_map(|face| face.vertices)
+            .fold((Vec2::splat(f32::MAX), Vec2::splat(f32::MIN)), |acc, v| {
+                (acc.0.min(v), acc.1.max(v))
+            });
+
+        if faces.is_empty() {
+            return Self {
+                faces,
+                origin: Vec2::ZERO,
+                cell_size: 1.0,
+                width: 0,
+                height: 0,
+                cells: Vec::new(),
+            };
+        }
+
+        // Aim for about one face per cell
+        let extent = (max - min).max(Vec2::splat(f32::EPSILON));
+        let cell_size = (extent.x * extent.y / faces.len() as f32)
+            .sqrt()
+            .max(extent.x.max(extent.y) / MAX_CELLS as f32)
+            .max(f32::EPSILON);
+
+        let width = ((extent.x / cell_size).ceil() as usize).clamp(1, MAX_CELLS);
+        let height = ((extent.y / cell_size).ceil() as usize).clamp(1, MAX_CELLS);
+
+        let mut grid = Self {
+            faces,
+            origin: min,
+            cell_size,
+            width,
+            height,
+            cells: vec![Vec::new(); width * height],
+        };
+
+        for (i, face) in grid.faces.iter().enumerate() {
+            let [a, b] = face.vertices;
+            let (l, r) = (grid.clamped(a.min(b)), grid.clamped(a.max(b)));
+            for y in l.1..=r.1 {
+                for x in l.0..=r.0 {
+                    grid.cells[y * width + x].push(i as u32);
+                }
+            }
+        }
+
+        grid
+    }
+
+    /// Returns the distance from `p` to the closest face, or `f32::MAX` if
+    /// there are no faces
+    pub(crate) fn distance(&self, p: Vec2) -> f32 {
+        if self.faces.is_empty() {
+            return f32::MAX;
+        }
+
+        let cell = (p - self.origin) / self.cell_size;
+        let (cx, cy) = (cell.x.floor() as i64, cell.y.floor() as i64);
+        let (w, h) = (self.width as i64, self.height as i64);
+
+        // Rings closer than the grid contain no cells
+        let start = (-cx).max(cx - w + 1).max(-cy).max(cy - h + 1).max(0);
+        let end = (cx.max(w - 1 - cx)).max(cy.max(h - 1 - cy)).max(start);
+
+        let mut best = f32::MAX;
+        for ring in start..=end {
+            // Faces in further rings are at least this far away
+            if best <= (ring - 1).max(0) as f32 * self.cell_size {
+                break;
+            }
+
+            self.ring(cx, cy, ring, |index| {
+                for &face in &self.cells[index] {
+                    let face = self.faces[face as usize];
+                    best = best.min(face.closest_point(p).distance(p));
+                }
+            });
+        }
+
+        best
+    }
+
+    /// Visits the cells in the grid at a chessboard distance of `ring` from
+    /// the cell `(cx, cy)`
+    fn ring(&self, cx: i64, cy: i64, ring: i64, mut f: impl FnMut(usize)) {
+        let (w, h) = (self.width as i64, self.height as i64);
+        let mut visit = |x: i64, y: i64| {
+            if (0..w).contains(&x) && (0..h).contains(&y) {
+                f((y * w + x) as usize)
+            }
+        };
+
+        if ring == 0 {
+            visit(cx, cy);
+            return;
+        }
+
+        let xs = (cx - ring).max(0)..=(cx + ring).min(w - 1);
+        for y in [cy - ring, cy + ring] {
+            if (0..h).contains(&y) {
+                xs.clone().for_each(|x| visit(x, y));
+            }
+        }
+
+        let ys = (cy - ring + 1).max(0)..=(cy + ring - 1).min(h - 1);
+        for x in [cx - ring, cx + ring] {
+            if (0..w).contains(&x) {
+                ys.clone().for_each(|y| visit(x, y));
+            }
+        }
+    }
+
+    fn clamped(&self, p: Vec2) -> (usize, usize) {
+        let cell = (p - self.origin) / self.cell_size;
+        (
+            (cell.x.max(0.0) as usize).min(self.width - 1),
+            (cell.y.max(0.0) as usize).min(self.height - 1),
+        )
+    }
+}
@@ -0,0 +1,31 @@
+use petgraph::Graph;
+use slotmap::SecondaryMap;
+
+use crate::{BSPTree, NodeIndex, PortalRef, Portals};
+
+impl Portals {
+    /// Returns the navigation connectivity as a directed graph, e.g; to run
+    /// centrality, max-flow, or custom algorithms with `petgraph`.
+    ///
+    /// There is a graph node for each open leaf of `tree`, and an edge for
+    /// each enabled portal in the direction it can be crossed. Off-mesh links
+    /// are left out.
+    pub fn to_graph(&self, tree: &BSPTree) -> Graph<NodeIndex, PortalRef> {
+        let mut graph = Graph::new();
+        let mut indices = SecondaryMap::new();
+
+        for (node, _) in tree.cells() {
+            indices.insert(node, graph.add_node(node));
+        }
+
+        for (node, &src) in &indices {
+            for portal in self.get(node) {
+                if let Some(&dst) = indices.get(portal.dst()) {
+                    graph.add_edge(src, dst, portal.portal_ref());
+                }
+            }
+        }
+
+        graph
+    }
+}
@@ -0,0 +1,46 @@
+use glam::Vec2;
+
+use crate::NodeIndex;
+
+/// Identifies an [OffMeshLink] of [Portals](crate::Portals)
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Hash, PartialOrd, Ord)]
+#[cfg_attr(feature = "serialize", derive(serde::Serialize, serde::Deserialize))]
+pub struct LinkId(pub(crate) usize);
+
+impl LinkId {
+    /// Returns the index of the link, in the order the links were added
+    pub fn index(&self) -> usize {
+        self.0
+    }
+}
+
+/// A custom connection between two arbitrary nodes which does not follow the
+/// geometry, e.g; a jump, ladder or teleporter.
+///
+/// Links are one way, from `entry` in `src` to `exit` in `dst`. Add a second
+/// link for the way back.
+#[derive(Debug, Clone, Copy, PartialEq)]
+#[cfg_attr(feature = "serialize", derive(serde::Serialize, serde::Deserialize))]
+pub struct OffMeshLink {
+    pub src: NodeIndex,
+    pub dst: NodeIndex,
+    #[cfg_attr(feature = "serialize", serde(with = "crate::serialize::vec2"))]
+    pub entry: Vec2,
+    #[cfg_attr(feature = "serialize", serde(with = "crate::serialize::vec2"))]
+    pub exit: Vec2,
+    /// The cost of traversing the link, in units of distance. Replaces the
+    /// distance between entry and exit.
+    pub cost: f32,
+}
+
+impl OffMeshLink {
+    pub fn new(src: NodeIndex, entry: Vec2, dst: NodeIndex, exit: Vec2, cost: f32) -> Self {
+        Self {
+            src,
+            dst,
+            entry,
+            exit,
+            cost,
+        }
+    }
+}
@@ -1,18 +1,37 @@
-use std::ops::Index;
+use alloc::{collections::BTreeSet, string::String, vec, vec::Vec};
+use core::{f32::consts::TAU, fmt::Write, hash::Hasher, ops::Index};
 
-use glam::Vec2;
+use glam::{Mat3, Vec2};
+#[cfg(not(feature = "std"))]
+use num_traits::Float;
 use rand::{prelude::SliceRandom, Rng};
 use slotmap::*;
 
-use crate::Face;
+use crate::{
+    degenerate_faces, inflate,
+    util::{cell_distance, hash_vec2, plane_distance, StableHasher},
+    Face, FaceError, TOLERANCE,
+};
 
+use face_grid::FaceGrid;
+pub use link::*;
 pub use node::*;
 pub use portal::*;
 pub use portals::*;
-
+pub use prebuilt::*;
+pub use region::*;
+pub use validate::*;
+
+mod face_grid;
+#[cfg(feature = "petgraph")]
+mod graph;
+mod link;
 mod node;
 mod portal;
 mod portals;
+mod prebuilt;
+mod region;
+mod validate;
 
 type Nodes = SlotMap<NodeIndex, BSPNode>;
 
@@ -20,13 +39,24 @@ new_key_type! {
     /// Represent the index of a [crate::BSPNode]
     pub struct NodeIndex;
 }
-/// Defines the tree used for navigation
+
+/// The maximum number of points a segment is sampled at by
+/// [BSPTree::faces_facing]
+pub const FACING_SAMPLES: usize = 32;
+/// The number of segments each portal is sampled at by
+/// [BSPTree::clearances]
+pub const CLEARANCE_SAMPLES: usize = 16;
+/// Defines the tree used for navigation.
+/// The tree is `Send + Sync`.
+#[derive(Clone)]
 #[cfg_attr(feature = "serialize", derive(serde::Serialize, serde::Deserialize))]
 pub struct BSPTree {
     nodes: Nodes,
     root: NodeIndex,
     // Bounds
+    #[cfg_attr(feature = "serialize", serde(with = "crate::serialize::vec2"))]
     l: Vec2,
+    #[cfg_attr(feature = "serialize", serde(with = "crate::serialize::vec2"))]
     r: Vec2,
 }
 
@@ -34,17 +64,20 @@ impl BSPTree {
     /// Constructs a new tree.
     /// Returns None if there are not faces, and root construction was not possible
     pub fn new(faces: Vec<Face>) -> Option<Self> {
-        Self::new_inner(faces)
+        Self::from_slice(&faces)
     }
 
     pub fn new_shuffle(faces: impl Iterator<Item = Face>, rng: &mut impl Rng) -> Option<Self> {
         let mut faces: Vec<_> = faces.collect();
         faces.shuffle(rng);
 
-        Self::new_inner(faces)
+        Self::from_slice(&faces)
     }
 
-    fn new_inner(faces: Vec<Face>) -> Option<Self> {
+    /// Constructs a new tree from borrowed faces without copying them into
+    /// an intermediate collection.
+    /// Returns None if there are not faces, and root construction was not possible
+    pub fn from_slice(faces: &[Face]) -> Option<Self> {
         let mut l = Vec2::new(f32::MAX, f32::MAX);
         let mut r = Vec2::new(f32::MIN, f32::MIN);
 
@@ -54,15 +87,73 @@ impl BSPTree {
         });
 
         let mut nodes = SlotMap::with_key();
-        let root = BSPNode::from_faces(&mut nodes, &faces, 0)?;
+        let root = BSPNode::from_faces(&mut nodes, faces, 0)?;
 
         Some(Self { nodes, root, l, r })
     }
 
+    /// Constructs a new tree like [Self::from_slice], but rejects degenerate
+    /// faces, which would otherwise silently corrupt the partitioning.
+    ///
+    /// Returns every degenerate face along with its index, see
+    /// [Face::defect].
+    pub fn try_from_slice(faces: &[Face]) -> Result<Option<Self>, Vec<FaceError>> {
+        let errors = degenerate_faces(faces);
+        if !errors.is_empty() {
+            return Err(errors);
+        }
+
+        Ok(Self::from_slice(faces))
+    }
+
+    /// Constructs a new tree from the faces inflated by `radius`, see
+    /// [inflate].
+    ///
+    /// Searches in the tree with an agent radius of 0 are then exact for
+    /// agents of `radius`, including around corners.
+    /// Returns None if there are not faces, and root construction was not possible
+    pub fn new_inflated(faces: &[Face], radius: f32) -> Option<Self> {
+        Self::from_slice(&inflate(faces, radius))
+    }
+
     pub fn node(&self, index: NodeIndex) -> Option<&BSPNode> {
         self.nodes.get(index)
     }
 
+    /// Returns a copy of the tree with an affine transform applied to all
+    /// nodes and the bounds.
+    ///
+    /// Node indices are preserved, which means portals and other data keyed
+    /// by [NodeIndex] remain valid for the transformed tree.
+    pub fn transformed(&self, transform: Mat3) -> Self {
+        let mut nodes = self.nodes.clone();
+        nodes
+            .iter_mut()
+            .for_each(|(_, node)| *node = node.transformed(transform));
+
+        let mut l = Vec2::new(f32::MAX, f32::MAX);
+        let mut r = Vec2::new(f32::MIN, f32::MIN);
+        [
+            self.l,
+            self.r,
+            Vec2::new(self.l.x, self.r.y),
+            Vec2::new(self.r.x, self.l.y),
+        ]
+        .iter()
+        .for_each(|&p| {
+            let p = transform.transform_point2(p);
+            l = l.min(p);
+            r = r.max(p);
+        });
+
+        Self {
+            nodes,
+            root: self.root,
+            l,
+            r,
+        }
+    }
+
     /// Returns the root index
     pub fn root(&self) -> NodeIndex {
         self.root
@@ -73,18 +164,17 @@ impl BSPTree {
         self.node(self.root).expect("Root is always present")
     }
 
-    pub fn descendants(&self) -> Descendants {
+    pub fn descendants(&self) -> Descendants<'_> {
         BSPNode::descendants(self.root, &self.nodes)
     }
 
     /// Returns the containing node and if the point is covered
-    pub fn locate(&self, point: Vec2) -> NodePayload {
+    pub fn locate(&self, point: Vec2) -> NodePayload<'_> {
         let mut index = self.root;
 
         loop {
             let node = &self.nodes[index];
-            let rel = point - node.origin();
-            let dot = rel.dot(node.normal());
+            let dot = plane_distance(point, node.origin(), node.normal());
 
             let (next, covered) = if dot >= 0.0 {
                 (node.front(), false)
@@ -109,6 +199,160 @@ impl BSPTree {
         }
     }
 
+    /// Returns the face closest to `point` and the closest point on that face
+    pub fn closest_face(&self, point: Vec2) -> Option<(Face, Vec2)> {
+        self.descendants()
+            .flat_map(|(_, node)| node.faces().iter())
+            .map(|face| (*face, face.closest_point(point)))
+            .min_by(|a, b| {
+                a.1.distance_squared(point)
+                    .total_cmp(&b.1.distance_squared(point))
+            })
+    }
+
+    /// Returns the open leaf closest to `point` along with the distance to
+    /// it, e.g; for picking with the mouse.
+    /// The distance is 0 if `point` is not covered.
+    pub fn pick_node(&self, point: Vec2) -> Option<(NodeIndex, f32)> {
+        let located = self.locate(point);
+        if !located.covered() {
+            return Some((located.index(), 0.0));
+        }
+
+        self.cells()
+            .filter(|(_, cell)| cell.len() >= 3)
+            .map(|(index, cell)| (index, cell_distance(&cell, point)))
+            .min_by(|a, b| a.1.total_cmp(&b.1))
+    }
+
+    /// Returns the face closest to `point` along with the distance to it, or
+    /// None if no face is within `max_dist`.
+    pub fn pick_face(&self, point: Vec2, max_dist: f32) -> Option<(Face, f32)> {
+        let (face, closest) = self.closest_face(point)?;
+        let dist = closest.distance(point);
+        (dist <= max_dist).then_some((face, dist))
+    }
+
+    /// Returns a stable hash of the partitioning and faces of the tree, e.g;
+    /// to detect desyncs between peers in lockstep multiplayer.
+    ///
+    /// Nodes are hashed in depth first order rather than by index, which means
+    /// trees of identical structure hash the same regardless of how they were
+    /// built.
+    pub fn hash_state(&self) -> u64 {
+        let mut state = StableHasher::default();
+        for index in self.preorder() {
+            let node = &self.nodes[index];
+            node.faces()
+                .iter()
+                .flatten()
+                .for_each(|v| hash_vec2(v, &mut state));
+            state.write_u8(node.front().is_some() as u8 | (node.back().is_some() as u8) << 1);
+        }
+
+        state.finish()
+    }
+
+    /// Returns the node hierarchy in the Graphviz DOT format, e.g; to debug
+    /// why a construction produced a lopsided tree.
+    ///
+    /// Each node is labelled with its index, depth, splitting plane and number
+    /// of faces. Open leaves in front of a node and covered space behind it
+    /// are drawn as separate green and grey boxes.
+    pub fn to_dot(&self) -> String {
+        self.dot(None)
+    }
+
+    /// Returns the node hierarchy in the Graphviz DOT format, like
+    /// [Self::to_dot], with the nodes of `heatmap` filled by how often a
+    /// search touched them, e.g; the
+    /// [QueryStats::expansion_heatmap](crate::QueryStats::expansion_heatmap)
+    /// of a query.
+    pub fn to_dot_heatmap(&self, heatmap: &SecondaryMap<NodeIndex, usize>) -> String {
+        self.dot(Some(heatmap))
+    }
+
+    fn dot(&self, heatmap: Option<&SecondaryMap<NodeIndex, usize>>) -> String {
+        let max = heatmap
+            .and_then(|v| v.values().copied().max())
+            .unwrap_or(0)
+            .max(1);
+
+        let order = self.preorder();
+        let mut ids = SecondaryMap::new();
+        for (i, &index) in order.iter().enumerate() {
+            ids.insert(index, i);
+        }
+
+        let mut dot = String::from("digraph BSPTree {\n    node [shape=ellipse];\n");
+        for (i, &index) in order.iter().enumerate() {
+            let node = &self.nodes[index];
+            let (origin, normal) = (node.origin(), node.normal());
+            let _ = writeln!(
+                dot,
+                "    n{} [label=\"{:?}\\ndepth {}\\norigin ({:.2}, {:.2})\\nnormal ({:.2}, {:.2})\\n{} faces\"];",
+                i,
+                index,
+                node.depth(),
+                origin.x,
+                origin.y,
+                normal.x,
+                normal.y,
+                node.faces().len()
+            );
+
+            if let Some(&heat) = heatmap.and_then(|v| v.get(index)) {
+                // White for untouched, to red for the most touched
+                let shade = 255 - (heat * 255 / max) as u8;
+                let _ = writeln!(
+                    dot,
+                    "    n{} [style=filled, fillcolor=\"#ff{:02x}{:02x}\", xlabel=\"{} touches\"];",
+                    i, shade, shade, heat
+                );
+            }
+
+            let children = [
+                (node.front(), "front", "open", "palegreen"),
+                (node.back(), "back", "covered", "gray"),
+            ];
+
+            for (child, side, label, color) in children {
+                match child {
+                    Some(child) => {
+                        let _ = writeln!(dot, "    n{} -> n{} [label={}];", i, ids[child], side);
+                    }
+                    None => {
+                        let _ = writeln!(
+                            dot,
+                            "    n{i}_{side} [label={label}, shape=box, style=filled, fillcolor={color}];\n    n{i} -> n{i}_{side} [label={side}];",
+                            i = i,
+                            side = side,
+                            label = label,
+                            color = color
+                        );
+                    }
+                }
+            }
+        }
+
+        dot.push_str("}\n");
+        dot
+    }
+
+    /// Returns the nodes in depth first order, front first
+    pub(crate) fn preorder(&self) -> Vec<NodeIndex> {
+        let mut result = Vec::with_capacity(self.nodes.len());
+        let mut stack = vec![self.root];
+        while let Some(index) = stack.pop() {
+            let node = &self.nodes[index];
+            stack.extend(node.back());
+            stack.extend(node.front());
+            result.push(index);
+        }
+
+        result
+    }
+
     /// Get a mutable reference to the bsptree's root.
     pub fn root_mut(&mut self) -> &mut NodeIndex {
         &mut self.root
@@ -119,6 +363,34 @@ impl BSPTree {
         &self.nodes
     }
 
+    /// Returns the minimum and maximum corners of the scene's bounding box
+    pub fn bounds(&self) -> (Vec2, Vec2) {
+        (self.l, self.r)
+    }
+
+    /// Returns true if `point` is inside the scene's bounding box grown by
+    /// `margin`
+    pub fn in_bounds(&self, point: Vec2, margin: f32) -> bool {
+        point.cmpge(self.l - Vec2::splat(margin)).all()
+            && point.cmple(self.r + Vec2::splat(margin)).all()
+    }
+
+    /// Returns the closest point inside the scene's bounding box grown by
+    /// `margin`
+    pub fn clamp_to_bounds(&self, point: Vec2, margin: f32) -> Vec2 {
+        point.clamp(self.l - Vec2::splat(margin), self.r + Vec2::splat(margin))
+    }
+
+    /// Returns the containing node like [Self::locate], or None if the point
+    /// is outside the scene's bounding box grown by `margin`.
+    pub fn locate_checked(&self, point: Vec2, margin: f32) -> Option<NodePayload<'_>> {
+        if self.in_bounds(point, margin) {
+            Some(self.locate(point))
+        } else {
+            None
+        }
+    }
+
     /// Returns clipping planes which contain the scene
     pub fn clipping_planes(&self) -> [Face; 4] {
         [
@@ -129,6 +401,273 @@ impl BSPTree {
         ]
     }
 
+    /// Casts a ray from `origin` along `dir` and returns the first face hit
+    /// within `max_dist`.
+    pub fn raycast(&self, origin: Vec2, dir: Vec2, max_dist: f32) -> Option<RayHit> {
+        let dir = dir.normalize_or_zero();
+        if dir == Vec2::ZERO {
+            return None;
+        }
+
+        BSPNode::raycast(self.root, &self.nodes, origin, dir, 0.0, max_dist)
+    }
+
+    /// Visits all nodes in front to back order as seen from `point`, e.g; the
+    /// faces of a node can only be occluded by faces of previously visited
+    /// nodes.
+    /// Stops early if `f` returns false.
+    pub fn front_to_back(&self, point: Vec2, mut f: impl FnMut(NodeIndex, &BSPNode) -> bool) {
+        BSPNode::front_to_back(self.root, &self.nodes, point, &mut f);
+    }
+
+    /// Computes which cells of a grid are visible from `origin`.
+    ///
+    /// The grid covers `grid_bounds` (min, max) with square cells of
+    /// `cell_size`, and is returned row major with
+    /// `((max - min) / cell_size).ceil()` columns and rows.
+    /// Returns an empty grid if `cell_size` is not positive and finite.
+    ///
+    /// Faces are traversed front to back and rasterized into an angular
+    /// occlusion buffer around the origin, which is then used to classify each
+    /// cell. This is considerably faster than casting a ray per cell.
+    pub fn rasterize_visibility(
+        &self,
+        origin: Vec2,
+        grid_bounds: (Vec2, Vec2),
+        cell_size: f32,
+    ) -> Vec<bool> {
+        if !(cell_size > 0.0 && cell_size.is_finite()) {
+            return Vec::new();
+        }
+
+        let (min, max) = grid_bounds;
+        let width = ((max.x - min.x) / cell_size).ceil().max(0.0) as usize;
+        let height = ((max.y - min.y) / cell_size).ceil().max(0.0) as usize;
+
+        // Choose the angular resolution such that a bin is at most half a cell
+        // wide at the furthest cell
+        let max_dist = [min, max, Vec2::new(min.x, max.y), Vec2::new(max.x, min.y)]
+            .iter()
+            .map(|v| v.distance(origin))
+            .fold(0.0, f32::max);
+
+        let bins = ((TAU * max_dist / (cell_size * 0.5)).ceil() as usize).clamp(64, 1 << 16);
+        let bin_angle = TAU / bins as f32;
+
+        let mut depths = vec![f32::INFINITY; bins];
+        let mut filled = 0;
+
+        let bin_of = |dir: Vec2| {
+            let angle = heading(dir);
+            ((angle / bin_angle) as usize).min(bins - 1)
+        };
+
+        self.front_to_back(origin, |_, node| {
+            for face in node.faces() {
+                let a = face.vertices[0] - origin;
+                let b = face.vertices[1] - origin;
+
+                // Face is seen edge on
+                if a.perp_dot(b).abs() < f32::EPSILON {
+                    continue;
+                }
+
+                // Walk the bins counterclockwise over the smaller arc
+                let (a, b) = if a.perp_dot(b) > 0.0 { (a, b) } else { (b, a) };
+                let start = bin_of(a);
+                let end = bin_of(b);
+                let count = (end + bins - start) % bins + 1;
+
+                for i in 0..count {
+                    let bin = (start + i) % bins;
+                    if depths[bin].is_finite() {
+                        continue;
+                    }
+
+                    let angle = (bin as f32 + 0.5) * bin_angle;
+                    let dir = Vec2::new(angle.cos(), angle.sin());
+                    // Bin center ray may miss the face at the edge bins
+                    if a.perp_dot(dir) < 0.0 || dir.perp_dot(b) < 0.0 {
+                        continue;
+                    }
+
+                    let t = a.perp_dot(b - a) / dir.perp_dot(b - a);
+                    if t >= 0.0 {
+                        depths[bin] = t;
+                        filled += 1;
+                    }
+                }
+            }
+
+            // Everything is occluded, further faces can't be seen
+            filled < bins
+        });
+
+        (0..height)
+            .flat_map(|y| (0..width).map(move |x| (x, y)))
+            .map(|(x, y)| {
+                let center = min + (Vec2::new(x as f32, y as f32) + Vec2::splat(0.5)) * cell_size;
+                let rel = center - origin;
+                let dist = rel.length();
+
+                dist < TOLERANCE || dist <= depths[bin_of(rel)]
+            })
+            .collect()
+    }
+
+    /// Returns the faces whose front side faces the segment from `a` to `b`
+    /// and which are not occluded by other faces, as seen from some point on
+    /// the segment.
+    ///
+    /// The segment is sampled at up to [FACING_SAMPLES] points, from which
+    /// faces are traversed front to back into an angular occlusion buffer.
+    pub fn faces_facing(&self, a: Vec2, b: Vec2) -> Vec<Face> {
+        const BINS: usize = 1024;
+        let bin_angle = TAU / BINS as f32;
+        let bin_of = |dir: Vec2| {
+            let angle = heading(dir);
+            ((angle / bin_angle) as usize).min(BINS - 1)
+        };
+
+        let samples = (a.distance(b).ceil() as usize).clamp(1, FACING_SAMPLES - 1);
+        let mut visible = BTreeSet::new();
+
+        for i in 0..=samples {
+            let origin = a.lerp(b, i as f32 / samples as f32);
+            let mut occluded = vec![false; BINS];
+            let mut filled = 0;
+
+            self.front_to_back(origin, |index, node| {
+                for (i, face) in node.faces().iter().enumerate() {
+                    let a = face.vertices[0] - origin;
+                    let b = face.vertices[1] - origin;
+
+                    // Face is seen edge on
+                    if a.perp_dot(b).abs() < f32::EPSILON {
+                        continue;
+                    }
+
+                    let facing = plane_distance(origin, face.vertices[0], face.normal()) > 0.0;
+
+                    // Walk the bins counterclockwise over the smaller arc
+                    let (a, b) = if a.perp_dot(b) > 0.0 { (a, b) } else { (b, a) };
+                    let start = bin_of(a);
+                    let count = (bin_of(b) + BINS - start) % BINS + 1;
+
+                    let bins = (0..count).map(|i| (start + i) % BINS);
+                    if facing && bins.clone().any(|bin| !occluded[bin]) {
+                        visible.insert((index, i));
+                    }
+
+                    for bin in bins {
+                        let angle = (bin as f32 + 0.5) * bin_angle;
+                        let dir = Vec2::new(angle.cos(), angle.sin());
+                        // Only occlude bins fully covered by the face
+                        if !occluded[bin] && a.perp_dot(dir) >= 0.0 && dir.perp_dot(b) >= 0.0 {
+                            occluded[bin] = true;
+                            filled += 1;
+                        }
+                    }
+                }
+
+                filled < BINS
+            });
+        }
+
+        visible
+            .into_iter()
+            .map(|(node, i)| self.nodes[node].faces()[i])
+            .collect()
+    }
+
+    /// Returns the open leaves which touch at least one face, along with the
+    /// touching faces, e.g; cells next to walls.
+    pub fn boundary_leaves(&self) -> impl Iterator<Item = (NodeIndex, Vec<Face>)> {
+        let cell = self.bounds_polygon();
+
+        let mut result = Vec::new();
+        BSPNode::boundary_leaves(self.root, &self.nodes, &cell, &mut Vec::new(), &mut result);
+        result.into_iter()
+    }
+
+    /// Returns the corners of the bounds in counterclockwise order
+    fn bounds_polygon(&self) -> [Vec2; 4] {
+        [
+            self.l,
+            Vec2::new(self.r.x, self.l.y),
+            self.r,
+            Vec2::new(self.l.x, self.r.y),
+        ]
+    }
+
+    /// Returns the convex region of the open leaf `index` within the bounds,
+    /// in counterclockwise order.
+    /// Returns None if `index` has a front child, and is thereby not a leaf
+    /// which can be located.
+    pub fn cell(&self, index: NodeIndex) -> Option<Vec<Vec2>> {
+        BSPNode::cell(self.root, &self.nodes, index, &self.bounds_polygon())
+    }
+
+    /// Returns the convex region of every open leaf within the bounds.
+    /// See [Self::cell].
+    pub fn cells(&self) -> impl Iterator<Item = (NodeIndex, Vec<Vec2>)> {
+        let mut result = Vec::new();
+        BSPNode::cells(self.root, &self.nodes, &self.bounds_polygon(), &mut result);
+        result.into_iter()
+    }
+
+    /// Returns the radius of the largest agent which can stand in each open
+    /// leaf without overlapping a face.
+    ///
+    /// Approximated by the clearance at a few points spread over each leaf.
+    /// Faces are bucketed in a grid, so each point is only compared against
+    /// nearby faces.
+    pub fn leaf_clearances(&self) -> SecondaryMap<NodeIndex, f32> {
+        let grid = FaceGrid::new(self.all_faces());
+        let clearance = |p| grid.distance(p);
+
+        self.cells()
+            .filter(|(_, cell)| !cell.is_empty())
+            .map(|(index, cell)| {
+                let center = cell.iter().fold(Vec2::ZERO, |acc, &p| acc + p) / cell.len() as f32;
+                let best = cell
+                    .iter()
+                    .map(|&v| center.lerp(v, 0.5))
+                    .chain([center])
+                    .map(clearance)
+                    .fold(0.0, f32::max);
+
+                (index, best)
+            })
+            .collect()
+    }
+
+    /// Returns the radius of the largest agent which can cross each of
+    /// `portals` without overlapping a face.
+    ///
+    /// Approximated by the clearance at [CLEARANCE_SAMPLES] evenly spaced
+    /// points along each portal. See [Self::leaf_clearances].
+    pub fn clearances(&self, portals: &[Face]) -> Vec<f32> {
+        let grid = FaceGrid::new(self.all_faces());
+
+        portals
+            .iter()
+            .map(|portal| {
+                let [a, b] = portal.vertices;
+                (0..=CLEARANCE_SAMPLES)
+                    .map(|i| a.lerp(b, i as f32 / CLEARANCE_SAMPLES as f32))
+                    .map(|p| grid.distance(p))
+                    .fold(0.0, f32::max)
+            })
+            .collect()
+    }
+
+    fn all_faces(&self) -> Vec<&Face> {
+        self.descendants()
+            .flat_map(|(_, node)| node.faces().iter())
+            .collect()
+    }
+
     pub fn generate_portals(&self) -> Vec<ClippedFace> {
         let clipping_planes = self.clipping_planes().into_iter().collect();
 
@@ -138,6 +677,16 @@ impl BSPTree {
     }
 }
 
+/// Returns the angle of `dir` in `0..TAU`
+fn heading(dir: Vec2) -> f32 {
+    let angle = dir.y.atan2(dir.x);
+    if angle < 0.0 {
+        angle + TAU
+    } else {
+        angle
+    }
+}
+
 /// Represents the result of [crate::BSPTree::locate]
 #[derive(Clone, Debug)]
 pub struct NodePayload<'a> {
@@ -169,6 +718,43 @@ impl<'a> NodePayload<'a> {
     }
 }
 
+/// Represents the result of [crate::BSPTree::raycast]
+#[derive(Clone, Copy, Debug, PartialEq)]
+pub struct RayHit {
+    pub face: Face,
+    pub node: NodeIndex,
+    pub point: Vec2,
+    pub normal: Vec2,
+    pub distance: f32,
+}
+
+impl RayHit {
+    /// Get the ray hit's face.
+    pub fn face(&self) -> Face {
+        self.face
+    }
+
+    /// Get the node containing the hit face.
+    pub fn node(&self) -> NodeIndex {
+        self.node
+    }
+
+    /// Get the ray hit's point.
+    pub fn point(&self) -> Vec2 {
+        self.point
+    }
+
+    /// Get the normal of the hit face.
+    pub fn normal(&self) -> Vec2 {
+        self.normal
+    }
+
+    /// Get the distance along the ray to the hit.
+    pub fn distance(&self) -> f32 {
+        self.distance
+    }
+}
+
 impl Index<NodeIndex> for BSPTree {
     type Output = BSPNode;
 
@@ -1,16 +1,18 @@
-use glam::Vec2;
+use alloc::{vec, vec::Vec};
+
+use glam::{Mat3, Vec2};
 use rpds::Vector;
 use smallvec::{smallvec, SmallVec};
 
 use crate::{
-    util::{face_intersect, face_intersect_dir, Intersect},
-    ClippedFace, Face, Side, TOLERANCE,
+    util::{clip_polygon, face_intersect, face_intersect_dir, plane_distance, Intersect},
+    ClippedFace, Face, RayHit, Side, TOLERANCE,
 };
 
 use super::{NodeIndex, Nodes};
 
 #[cfg_attr(feature = "serialize", derive(serde::Serialize, serde::Deserialize))]
-#[derive(Debug)]
+#[derive(Debug, Clone)]
 /// Represents  a single node in the binary tree.
 /// The node constitutes of a splitting plane and children behind and in front
 /// of the plane.
@@ -18,7 +20,9 @@ use super::{NodeIndex, Nodes};
 /// A node can be double planar, which means that the partitioning plane
 /// contains two faces with opposite facing normals.
 pub struct BSPNode {
+    #[cfg_attr(feature = "serialize", serde(with = "crate::serialize::vec2"))]
     origin: Vec2,
+    #[cfg_attr(feature = "serialize", serde(with = "crate::serialize::vec2"))]
     normal: Vec2,
 
     front: Option<NodeIndex>,
@@ -30,6 +34,44 @@ pub struct BSPNode {
 }
 
 impl BSPNode {
+    /// Returns a copy of the node with all faces and the partitioning plane
+    /// transformed. See [Face::transform_oriented].
+    pub fn transformed(&self, transform: Mat3) -> Self {
+        let faces: SmallVec<[Face; 2]> = self
+            .faces
+            .iter()
+            .map(|face| face.transform_oriented(transform))
+            .collect();
+
+        Self {
+            origin: transform.transform_point2(self.origin),
+            // The partitioning plane is defined by the first face
+            normal: faces[0].normal,
+            front: self.front,
+            back: self.back,
+            faces,
+            depth: self.depth,
+        }
+    }
+
+    /// Creates a node from already partitioned parts.
+    /// The partitioning plane is defined by the first face.
+    pub(crate) fn from_parts(
+        faces: SmallVec<[Face; 2]>,
+        front: Option<NodeIndex>,
+        back: Option<NodeIndex>,
+        depth: usize,
+    ) -> Self {
+        Self {
+            origin: faces[0].midpoint(),
+            normal: faces[0].normal,
+            front,
+            back,
+            faces,
+            depth,
+        }
+    }
+
     /// Creates a new BSPNode and inserts it into nodes.
     /// Returns None if there were not faces to create a node from
     pub fn from_faces(nodes: &mut Nodes, faces: &[Face], depth: usize) -> Option<NodeIndex> {
@@ -98,15 +140,7 @@ impl BSPNode {
     }
 
     pub fn get_side(&self, point: Vec2) -> Side {
-        let dot = (point - self.origin).dot(self.normal());
-
-        if dot.abs() < TOLERANCE {
-            Side::Coplanar
-        } else if dot <= 0.0 {
-            Side::Back
-        } else {
-            Side::Front
-        }
+        Side::of_distance(plane_distance(point, self.origin, self.normal()))
     }
 
     /// Get the bspnode's front.
@@ -131,7 +165,7 @@ impl BSPNode {
         self.origin
     }
 
-    pub fn descendants(index: NodeIndex, nodes: &Nodes) -> Descendants {
+    pub fn descendants(index: NodeIndex, nodes: &Nodes) -> Descendants<'_> {
         Descendants {
             nodes,
             stack: vec![index],
@@ -171,8 +205,8 @@ impl BSPNode {
 
         let side = portal.side_of(node.origin, node.normal);
         // Allow back faces to override front
-        let a = (portal.vertices[0] - node.origin).dot(node.normal);
-        let b = (portal.vertices[1] - node.origin).dot(node.normal);
+        let a = plane_distance(portal.vertices[0], node.origin, node.normal);
+        let b = plane_distance(portal.vertices[1], node.origin, node.normal);
 
         // a is touching the plane
         if a.abs() < TOLERANCE {
@@ -206,8 +240,7 @@ impl BSPNode {
                 // portal.src = NodeIndex::null();
                 Self::clip(front, nodes, portal, Side::Front)
                     .into_iter()
-                    .map(|val| Self::clip(back, nodes, val, Side::Back))
-                    .flatten()
+                    .flat_map(|val| Self::clip(back, nodes, val, Side::Back))
                     .collect()
             }
             (Side::Coplanar, Some(front), _) => Self::clip(front, nodes, portal, Side::Front),
@@ -303,6 +336,198 @@ impl BSPNode {
         }
     }
 
+    /// Collects the open leaves of the subtree which touch at least one face,
+    /// along with the touching faces.
+    ///
+    /// `cell` is the convex region covered by the subtree, and `path` the
+    /// ancestors of the subtree and which side of them it lies on.
+    pub fn boundary_leaves(
+        index: NodeIndex,
+        nodes: &Nodes,
+        cell: &[Vec2],
+        path: &mut Vec<(NodeIndex, bool)>,
+        result: &mut Vec<(NodeIndex, Vec<Face>)>,
+    ) {
+        let node = &nodes[index];
+
+        let front = clip_polygon(cell, node.origin, node.normal);
+        path.push((index, true));
+        match node.front {
+            Some(child) => Self::boundary_leaves(child, nodes, &front, path, result),
+            None => {
+                let faces = Self::touching_faces(nodes, &front, path);
+                if !faces.is_empty() {
+                    result.push((index, faces));
+                }
+            }
+        }
+        path.pop();
+
+        // Leaves behind a plane are solid
+        if let Some(child) = node.back {
+            let back = clip_polygon(cell, node.origin, -node.normal);
+            path.push((index, false));
+            Self::boundary_leaves(child, nodes, &back, path, result);
+            path.pop();
+        }
+    }
+
+    /// Collects the open region of every open leaf of the subtree.
+    ///
+    /// `cell` is the convex region covered by the subtree.
+    pub fn cells(
+        index: NodeIndex,
+        nodes: &Nodes,
+        cell: &[Vec2],
+        result: &mut Vec<(NodeIndex, Vec<Vec2>)>,
+    ) {
+        let node = &nodes[index];
+
+        let front = clip_polygon(cell, node.origin, node.normal);
+        match node.front {
+            Some(child) => Self::cells(child, nodes, &front, result),
+            None => result.push((index, front)),
+        }
+
+        if let Some(child) = node.back {
+            let back = clip_polygon(cell, node.origin, -node.normal);
+            Self::cells(child, nodes, &back, result);
+        }
+    }
+
+    /// Returns the open region in front of `target`, if it is part of the
+    /// subtree.
+    ///
+    /// `cell` is the convex region covered by the subtree.
+    pub fn cell(
+        index: NodeIndex,
+        nodes: &Nodes,
+        target: NodeIndex,
+        cell: &[Vec2],
+    ) -> Option<Vec<Vec2>> {
+        let node = &nodes[index];
+        let front = clip_polygon(cell, node.origin, node.normal);
+
+        if index == target {
+            return match node.front {
+                Some(_) => None,
+                None => Some(front),
+            };
+        }
+
+        if let Some(child) = node.front {
+            if let Some(cell) = Self::cell(child, nodes, target, &front) {
+                return Some(cell);
+            }
+        }
+
+        let back = clip_polygon(cell, node.origin, -node.normal);
+        Self::cell(node.back?, nodes, target, &back)
+    }
+
+    /// Returns the faces of the bounding planes in `path` which face into,
+    /// and overlap an edge of `cell`.
+    fn touching_faces(nodes: &Nodes, cell: &[Vec2], path: &[(NodeIndex, bool)]) -> Vec<Face> {
+        let mut faces = Vec::new();
+
+        for &(index, front) in path {
+            let node = &nodes[index];
+            let inward = if front { node.normal } else { -node.normal };
+            let dir = node.normal.perp();
+
+            // The extent of the cell's edge on the plane
+            let (min, max) = cell
+                .iter()
+                .filter(|p| plane_distance(**p, node.origin, node.normal).abs() < TOLERANCE)
+                .map(|p| (*p - node.origin).dot(dir))
+                .fold((f32::MAX, f32::MIN), |(min, max), t| {
+                    (min.min(t), max.max(t))
+                });
+
+            if max - min < TOLERANCE {
+                continue;
+            }
+
+            faces.extend(node.faces.iter().filter(|face| {
+                let a = (face.vertices[0] - node.origin).dot(dir);
+                let b = (face.vertices[1] - node.origin).dot(dir);
+
+                face.normal().dot(inward) > 0.0 && max.min(a.max(b)) - min.max(a.min(b)) > TOLERANCE
+            }));
+        }
+
+        faces
+    }
+
+    /// Traces the segment `start + dir * [t0, t1]` through the subtree front to
+    /// back and returns the first face hit.
+    pub fn raycast(
+        index: NodeIndex,
+        nodes: &Nodes,
+        start: Vec2,
+        dir: Vec2,
+        t0: f32,
+        t1: f32,
+    ) -> Option<RayHit> {
+        let node = &nodes[index];
+
+        let a = plane_distance(start + dir * t0, node.origin, node.normal);
+        let b = plane_distance(start + dir * t1, node.origin, node.normal);
+
+        let (near, far) = if a >= 0.0 {
+            (node.front, node.back)
+        } else {
+            (node.back, node.front)
+        };
+
+        // Entirely on one side of the plane
+        if (a >= 0.0) == (b >= 0.0) {
+            return near.and_then(|near| Self::raycast(near, nodes, start, dir, t0, t1));
+        }
+
+        let t = face_intersect_dir(start, dir, node.origin, node.normal).distance;
+
+        if let Some(hit) = near.and_then(|near| Self::raycast(near, nodes, start, dir, t0, t)) {
+            return Some(hit);
+        }
+
+        let point = start + dir * t;
+        if let Some(face) = node.faces.iter().find(|face| face.contains_point(point)) {
+            return Some(RayHit {
+                face: *face,
+                node: index,
+                point,
+                normal: face.normal(),
+                distance: t,
+            });
+        }
+
+        far.and_then(|far| Self::raycast(far, nodes, start, dir, t, t1))
+    }
+
+    /// Visits the subtree in front to back order as seen from `point`.
+    /// Stops early and returns false if `f` returns false.
+    pub fn front_to_back(
+        index: NodeIndex,
+        nodes: &Nodes,
+        point: Vec2,
+        f: &mut impl FnMut(NodeIndex, &BSPNode) -> bool,
+    ) -> bool {
+        let node = &nodes[index];
+        let (near, far) = if plane_distance(point, node.origin, node.normal) >= 0.0 {
+            (node.front, node.back)
+        } else {
+            (node.back, node.front)
+        };
+
+        near.map(|near| Self::front_to_back(near, nodes, point, f))
+            .unwrap_or(true)
+            && f(index, node)
+            && far
+                .map(|far| Self::front_to_back(far, nodes, point, f))
+                .unwrap_or(true)
+    }
+
     pub fn is_leaf(&self) -> bool {
         self.front.is_none() && self.back.is_none()
     }
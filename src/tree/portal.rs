@@ -1,4 +1,4 @@
-use std::ops::Deref;
+use core::ops::Deref;
 
 use glam::Vec2;
 
@@ -10,6 +10,7 @@ pub struct Portal<'a> {
     pub(crate) face: &'a Face,
 
     pub(crate) portal_ref: PortalRef,
+    pub(crate) clearance: f32,
 }
 
 impl<'a> Portal<'a> {
@@ -37,8 +38,18 @@ impl<'a> Portal<'a> {
         self.face
     }
 
+    /// Returns the radius of the largest agent which can pass the portal.
+    ///
+    /// Baked by [Portals::generate](crate::Portals::generate) from the
+    /// distance to the surrounding faces. Portals pushed manually assume
+    /// half their length.
+    pub fn clearance(&self) -> f32 {
+        self.clearance
+    }
+
     // Returns true if the line is contained on the surface of the portal
     pub(crate) fn try_clip(&self, start: Vec2, end: Vec2, margin: f32) -> Option<Vec2> {
+        let margin = margin.min(self.face.length() / 2.0);
         let (l, r) = self.apply_margin(margin).into_tuple();
         let p = face_intersect((l, r), start, (end - start).perp());
 
@@ -51,7 +62,15 @@ impl<'a> Portal<'a> {
     }
 
     pub(crate) fn clip(&self, start: Vec2, end: Vec2, margin: f32) -> Vec2 {
+        // Never clip past the middle of the portal
+        let margin = margin.min(self.face.length() / 2.0);
         let (l, r) = self.apply_margin(margin).into_tuple();
+
+        // Only the middle remains
+        if l.distance_squared(r) < f32::EPSILON {
+            return l;
+        }
+
         let p = face_intersect((l, r), start, (end - start).perp());
 
         // let rel = (p - self.vertices[0]).dot(self.vertices[1] - self.vertices[0]);
@@ -90,10 +109,37 @@ pub struct PortalRef {
     pub(crate) adjacent: [bool; 2],
     // Normal may be different than the face due to the normal pointing through
     // the portal
+    #[cfg_attr(feature = "serialize", serde(with = "crate::serialize::vec2"))]
     pub(crate) normal: Vec2,
+    #[cfg_attr(feature = "serialize", serde(default))]
+    pub(crate) direction: PortalDirection,
+}
+
+/// Determines which ways a portal may be crossed
+#[derive(Default, Debug, Clone, Copy, PartialEq, Eq)]
+#[cfg_attr(feature = "serialize", derive(serde::Serialize, serde::Deserialize))]
+pub enum PortalDirection {
+    /// The portal may be crossed both ways
+    #[default]
+    Both,
+    /// The portal may only be crossed from src to dst
+    Forward,
+    /// The portal may only be crossed from dst to src, and is hidden from
+    /// [Portals::get](crate::Portals::get)
+    Backward,
 }
 
 impl PortalRef {
+    /// Returns which ways the portal may be crossed
+    pub fn direction(&self) -> PortalDirection {
+        self.direction
+    }
+
+    /// Returns true if the portal may only be crossed one way
+    pub fn is_one_way(&self) -> bool {
+        self.direction != PortalDirection::Both
+    }
+
     /// Returns the normal which points into the portal
     pub fn normal(&self) -> Vec2 {
         self.normal
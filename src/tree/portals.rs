@@ -1,11 +1,23 @@
-use core::slice;
-use std::ops::Deref;
-
-use glam::Vec2;
-use slotmap::{secondary::Iter, SecondaryMap};
+use alloc::{sync::Arc, vec, vec::Vec};
+use core::{
+    any::Any,
+    hash::{Hash, Hasher},
+    ops::Deref,
+    slice,
+};
+
+use glam::{Mat3, Vec2};
+use itertools::Itertools;
+#[cfg(not(feature = "std"))]
+use num_traits::Float;
+use slotmap::{secondary::Iter, Key, SecondaryMap};
 use smallvec::SmallVec;
 
-use crate::{util::face_intersect, BSPTree, Face, NodeIndex, Portal, PortalRef, Side};
+use crate::{
+    util::{face_intersect, hash_vec2, plane_distance, StableHasher},
+    BSPTree, Face, LinkId, NodeIndex, OffMeshLink, Portal, PortalDirection, PortalRef, Side,
+    TOLERANCE,
+};
 
 #[derive(Copy, Debug, Clone, PartialEq)]
 #[doc(hidden)]
@@ -14,6 +26,7 @@ pub struct ClippedFace {
     // Used to determine if a face is completely inside
     pub(crate) sides: [Side; 2],
     pub(crate) adjacent: [bool; 2],
+    pub(crate) one_way: bool,
 
     pub src: NodeIndex,
     pub dst: NodeIndex,
@@ -31,11 +44,24 @@ impl ClippedFace {
             face: Face::new(vertices),
             sides,
             adjacent,
+            one_way: false,
             src,
             dst,
         }
     }
 
+    /// Makes the portal only crossable from src to dst, e.g; a drop-down or
+    /// one-way gate.
+    pub fn with_one_way(mut self, one_way: bool) -> Self {
+        self.one_way = one_way;
+        self
+    }
+
+    /// Returns true if the portal may only be crossed from src to dst
+    pub fn one_way(&self) -> bool {
+        self.one_way
+    }
+
     pub(crate) fn split(&self, p: Vec2, normal: Vec2) -> [Self; 2] {
         let intersection = face_intersect(self.into_tuple(), p, normal);
 
@@ -88,13 +114,35 @@ impl Deref for ClippedFace {
 }
 
 type NodePortals = SmallVec<[PortalRef; 4]>;
+type PortalData = Arc<dyn Any + Send + Sync>;
 
 /// Declares portals which are surfaces connecting two partitioning planes,
 /// [crate::BSPNode].
+/// The portals are `Send + Sync`.
+#[derive(Clone)]
 #[cfg_attr(feature = "serialize", derive(serde::Serialize, serde::Deserialize))]
 pub struct Portals {
     inner: SecondaryMap<NodeIndex, NodePortals>,
     faces: Vec<Face>,
+    // The radius of the largest agent which can cross each face
+    #[cfg_attr(feature = "serialize", serde(default))]
+    clearances: Vec<f32>,
+    // Extra cost of crossing each face
+    #[cfg_attr(feature = "serialize", serde(default))]
+    costs: Vec<f32>,
+    // Faces which may not be crossed in either direction
+    #[cfg_attr(feature = "serialize", serde(default))]
+    disabled: Vec<bool>,
+    #[cfg_attr(feature = "serialize", serde(default))]
+    links: Vec<OffMeshLink>,
+    // The clearance of each leaf, see [Self::leaf_clearance]
+    #[cfg_attr(feature = "serialize", serde(default))]
+    leaf_clearance: SecondaryMap<NodeIndex, f32>,
+    // User data of each face, see [Self::set_data]
+    #[cfg_attr(feature = "serialize", serde(skip))]
+    data: Vec<Option<PortalData>>,
+    #[cfg_attr(feature = "serialize", serde(skip))]
+    diagnostics: Vec<PortalDiagnostic>,
 }
 
 impl Portals {
@@ -102,19 +150,101 @@ impl Portals {
         Self {
             inner: SecondaryMap::new(),
             faces: Vec::new(),
+            clearances: Vec::new(),
+            costs: Vec::new(),
+            disabled: Vec::new(),
+            links: Vec::new(),
+            leaf_clearance: SecondaryMap::new(),
+            data: Vec::new(),
+            diagnostics: Vec::new(),
         }
     }
 
+    /// Generates the portals for the tree, and bakes the clearance of each
+    /// portal and leaf.
+    /// Malformed portals are skipped and reported in [Self::diagnostics].
     pub fn generate(&mut self, tree: &BSPTree) {
-        self.extend(tree.generate_portals())
+        let portals = tree.generate_portals();
+        self.inner.set_capacity(self.inner.len() + portals.len());
+        for portal in portals {
+            if let Err(diagnostic) = self.push_checked(tree, portal) {
+                self.diagnostics.push(diagnostic)
+            }
+        }
+
+        self.bake_leaf_clearances(tree);
+        self.clearances = tree.clearances(&self.faces);
+    }
+
+    /// Bakes the clearance of each leaf of `tree`, e.g; when the portals were
+    /// restored from a compact form which does not carry them.
+    pub(crate) fn bake_leaf_clearances(&mut self, tree: &BSPTree) {
+        self.leaf_clearance = tree.leaf_clearances();
+    }
+
+    /// Returns the radius of the largest agent which can stand in the leaf
+    /// `index`, see [BSPTree::leaf_clearances].
+    /// Returns None if the clearance was not baked by [Self::generate].
+    pub fn leaf_clearance(&self, index: NodeIndex) -> Option<f32> {
+        self.leaf_clearance.get(index).copied()
+    }
+
+    /// Adds a new portal for both src and dst, like [Self::push], after
+    /// checking that both are nodes of `tree`.
+    /// Returns an error and leaves self untouched if the portal is malformed.
+    pub fn push_checked(
+        &mut self,
+        tree: &BSPTree,
+        portal: ClippedFace,
+    ) -> Result<(), PortalDiagnostic> {
+        if let Some(node) = [portal.src, portal.dst]
+            .into_iter()
+            .find(|&node| !tree.nodes().contains_key(node))
+        {
+            return Err(PortalDiagnostic::StaleNode {
+                node,
+                face: portal.face,
+            });
+        }
+
+        self.push(portal)
     }
 
-    /// Adds a new portal for both src and dst
-    pub fn push(&mut self, portal: ClippedFace) {
+    /// Adds a new portal for both src and dst.
+    /// One-way portals are only crossed from src to dst, see
+    /// [ClippedFace::with_one_way].
+    /// Returns an error and leaves self untouched if the portal is malformed.
+    ///
+    /// Nodes are only checked against the portals, which means nodes which
+    /// never existed are accepted. Use [Self::push_checked] to check them
+    /// against the tree.
+    pub fn push(&mut self, portal: ClippedFace) -> Result<(), PortalDiagnostic> {
+        if portal.src == portal.dst {
+            return Err(PortalDiagnostic::SelfPortal {
+                node: portal.src,
+                face: portal.face,
+            });
+        }
+
+        for node in [portal.src, portal.dst] {
+            if node.is_null() || self.inner.entry(node).is_none() {
+                return Err(PortalDiagnostic::StaleNode {
+                    node,
+                    face: portal.face,
+                });
+            }
+        }
+
         let face = self.faces.len();
         self.faces.push(portal.face);
+        self.costs.push(0.0);
+        self.disabled.push(false);
 
-        assert_ne!(portal.src, portal.dst);
+        let (forward, backward) = if portal.one_way {
+            (PortalDirection::Forward, PortalDirection::Backward)
+        } else {
+            (PortalDirection::Both, PortalDirection::Both)
+        };
 
         self.inner
             .entry(portal.src)
@@ -126,6 +256,7 @@ impl Portals {
                 adjacent: portal.adjacent,
                 normal: -portal.normal(),
                 face,
+                direction: forward,
             });
         self.inner
             .entry(portal.dst)
@@ -137,51 +268,356 @@ impl Portals {
                 adjacent: portal.adjacent,
                 normal: portal.normal(),
                 face,
+                direction: backward,
             });
+
+        Ok(())
+    }
+
+    /// Sets the extra cost of crossing `portal`, in units of distance, e.g;
+    /// for doors, narrow gaps or ladders.
+    /// Both directions of the portal are affected. Negative costs are
+    /// clamped to 0.
+    pub fn set_cost(&mut self, portal: PortalRef, cost: f32) {
+        if portal.face < self.faces.len() {
+            self.costs.resize(self.faces.len(), 0.0);
+            self.costs[portal.face] = cost.max(0.0);
+        }
+    }
+
+    /// Returns the extra cost of crossing `portal`
+    pub fn cost(&self, portal: PortalRef) -> f32 {
+        self.costs.get(portal.face).copied().unwrap_or_default()
+    }
+
+    /// Enables or disables `portal` in both directions, e.g; when a door is
+    /// opened or closed. Disabled portals are hidden from [Self::get] and
+    /// [Self::incoming], and thereby skipped by all searches.
+    ///
+    /// The tree and [Connectivity](crate::Connectivity) are not rebuilt.
+    pub fn set_enabled(&mut self, portal: PortalRef, enabled: bool) {
+        if portal.face < self.faces.len() {
+            self.disabled.resize(self.faces.len(), false);
+            self.disabled[portal.face] = !enabled;
+        }
     }
 
-    pub fn get(&self, index: NodeIndex) -> PortalIter {
+    /// Returns true if `portal` may be crossed
+    pub fn is_enabled(&self, portal: PortalRef) -> bool {
+        !self.disabled.get(portal.face).copied().unwrap_or_default()
+    }
+
+    /// Attaches arbitrary user data to `portal`, e.g; a faction lock, toll or
+    /// noise level, replacing any previous data.
+    /// Both directions of the portal share the data.
+    ///
+    /// The data is available to [SearchInfo::edge_cost](crate::SearchInfo::edge_cost)
+    /// during searches and through [WayPoint::data](crate::WayPoint::data)
+    /// afterwards. User data is not serialized.
+    pub fn set_data<T: Any + Send + Sync>(&mut self, portal: PortalRef, data: T) {
+        if portal.face < self.faces.len() {
+            self.data.resize(self.faces.len(), None);
+            self.data[portal.face] = Some(Arc::new(data));
+        }
+    }
+
+    /// Returns the user data of `portal`, if any data of type `T` was
+    /// attached
+    pub fn data<T: Any>(&self, portal: PortalRef) -> Option<&T> {
+        self.data.get(portal.face)?.as_ref()?.downcast_ref::<T>()
+    }
+
+    /// Removes the user data of `portal`
+    pub fn clear_data(&mut self, portal: PortalRef) {
+        if let Some(data) = self.data.get_mut(portal.face) {
+            *data = None;
+        }
+    }
+
+    /// Makes `portal` only crossable in its direction, from src to dst, or
+    /// crossable both ways again.
+    pub fn set_one_way(&mut self, portal: PortalRef, one_way: bool) {
+        for (node, direction) in [
+            (portal.src, PortalDirection::Forward),
+            (portal.dst, PortalDirection::Backward),
+        ] {
+            let refs = self.inner.get_mut(node).into_iter().flatten();
+            for side in refs.filter(|side| side.face == portal.face) {
+                side.direction = if one_way {
+                    direction
+                } else {
+                    PortalDirection::Both
+                };
+            }
+        }
+    }
+
+    /// Adds an off-mesh link, e.g; a jump or ladder.
+    /// Links are followed by [astar](crate::astar()) and
+    /// [Connectivity](crate::Connectivity), and marked on the entry waypoint
+    /// of the path, see [WayPoint::link](crate::WayPoint::link).
+    pub fn add_link(&mut self, link: OffMeshLink) -> LinkId {
+        self.links.push(link);
+        LinkId(self.links.len() - 1)
+    }
+
+    /// Returns the link identified by `id`
+    pub fn link(&self, id: LinkId) -> Option<&OffMeshLink> {
+        self.links.get(id.0)
+    }
+
+    /// Returns all off-mesh links
+    pub fn links(&self) -> &[OffMeshLink] {
+        &self.links
+    }
+
+    /// Returns the off-mesh links leaving `index`
+    pub fn links_from(&self, index: NodeIndex) -> impl Iterator<Item = (LinkId, &OffMeshLink)> {
+        self.links
+            .iter()
+            .enumerate()
+            .filter(move |(_, link)| link.src == index)
+            .map(|(i, link)| (LinkId(i), link))
+    }
+
+    /// Returns the portal closest to `point` along with the distance to it, or
+    /// None if no portal is within `max_dist`.
+    ///
+    /// Disabled portals are included. The returned side of the portal is the
+    /// one leading away from `point`.
+    pub fn pick_portal(&self, point: Vec2, max_dist: f32) -> Option<(Portal<'_>, f32)> {
+        self.inner
+            .values()
+            .flatten()
+            .filter(|portal| {
+                plane_distance(point, self.faces[portal.face].vertices[0], portal.normal) <= 0.0
+            })
+            .map(|portal| {
+                let closest = self.faces[portal.face].closest_point(point);
+                (portal, closest.distance(point))
+            })
+            .filter(|(_, dist)| *dist <= max_dist)
+            .min_by(|a, b| a.1.total_cmp(&b.1))
+            .map(|(portal, dist)| (self.from_ref(*portal), dist))
+    }
+
+    /// Returns a stable hash of the portals, their state and the off-mesh
+    /// links, e.g; to detect desyncs between peers in lockstep multiplayer.
+    ///
+    /// Node indices are hashed as is, use
+    /// [NavigationContext::hash_state](crate::NavigationContext::hash_state)
+    /// to compare portals of trees which were built differently.
+    /// User data is not hashed.
+    pub fn hash_state(&self) -> u64 {
+        let compact = CompactPortals::from(self);
+        let mut state = StableHasher::default();
+        hash_portals(&compact.portals, &compact.links, &mut state);
+        state.finish()
+    }
+
+    /// Returns the portals which were skipped during generation
+    pub fn diagnostics(&self) -> &[PortalDiagnostic] {
+        &self.diagnostics
+    }
+
+    /// Checks that every portal is consistent with `tree` and its opposite
+    /// direction, e.g; in tests or after mutating portals by hand.
+    ///
+    /// Each portal must connect two distinct nodes of the tree, reference an
+    /// existing face, and have a unit normal pointing into its dst. The
+    /// opposite direction must be stored in dst with the same face.
+    pub fn validate(&self, tree: &BSPTree) -> Vec<PortalIssue> {
+        let mut issues = Vec::new();
+
+        for (node, portals) in self.inner.iter() {
+            for &portal in portals {
+                let mut issue = |issue: fn(PortalRef) -> PortalIssue| issues.push(issue(portal));
+
+                if portal.src != node {
+                    issue(PortalIssue::Misplaced);
+                }
+
+                if portal.src == portal.dst {
+                    issue(PortalIssue::SelfPortal);
+                }
+
+                if tree.node(portal.src).is_none() || tree.node(portal.dst).is_none() {
+                    issue(PortalIssue::StaleNode);
+                    continue;
+                }
+
+                let face = match self.faces.get(portal.face) {
+                    Some(face) => face,
+                    None => {
+                        issue(PortalIssue::MissingFace);
+                        continue;
+                    }
+                };
+
+                let opposite = match portal.direction {
+                    PortalDirection::Both => PortalDirection::Both,
+                    PortalDirection::Forward => PortalDirection::Backward,
+                    PortalDirection::Backward => PortalDirection::Forward,
+                };
+
+                let symmetric = self.inner.get(portal.dst).is_some_and(|v| {
+                    v.iter().any(|other| {
+                        other.face == portal.face
+                            && other.dst == portal.src
+                            && other.direction == opposite
+                            && other.normal.abs_diff_eq(-portal.normal, 1e-4)
+                    })
+                });
+
+                if !symmetric {
+                    issue(PortalIssue::Asymmetric);
+                }
+
+                let inside = face.midpoint() + portal.normal * TOLERANCE;
+                if !portal.normal.is_normalized() || tree.locate(inside).index() != portal.dst {
+                    issue(PortalIssue::WrongNormal);
+                }
+            }
+        }
+
+        issues
+    }
+
+    /// Returns the portals which may be crossed out of `index`
+    pub fn get(&self, index: NodeIndex) -> PortalIter<'_> {
         PortalIter {
             faces: &self.faces,
+            clearances: &self.clearances,
+            disabled: &self.disabled,
             iter: self
                 .inner
                 .get(index)
                 .map(|val| val.as_ref())
                 .unwrap_or_default()
                 .iter(),
+            skip: PortalDirection::Backward,
         }
     }
 
-    pub fn iter(&self) -> PortalsIter {
+    /// Returns the portals out of `index` whose neighbours may cross them
+    /// into `index`, for searching backwards from a goal.
+    pub fn incoming(&self, index: NodeIndex) -> PortalIter<'_> {
+        PortalIter {
+            faces: &self.faces,
+            clearances: &self.clearances,
+            disabled: &self.disabled,
+            iter: self
+                .inner
+                .get(index)
+                .map(|val| val.as_ref())
+                .unwrap_or_default()
+                .iter(),
+            skip: PortalDirection::Forward,
+        }
+    }
+
+    pub fn iter(&self) -> PortalsIter<'_> {
         PortalsIter {
             faces: &self.faces,
+            clearances: &self.clearances,
+            disabled: &self.disabled,
             inner: self.inner.iter(),
         }
     }
 
-    pub fn from_ref(&self, portal: PortalRef) -> Portal {
+    /// Returns a copy of the portals with an affine transform applied.
+    /// Mirroring is handled by reversing the winding of each face, see
+    /// [Face::transform_oriented].
+    pub fn transformed(&self, transform: Mat3) -> Self {
+        let mirror = transform.determinant() < 0.0;
+
+        let faces = self
+            .faces
+            .iter()
+            .map(|face| face.transform_oriented(transform))
+            .collect_vec();
+
+        let mut inner = self.inner.clone();
+        inner.values_mut().flatten().for_each(|portal| {
+            let old = &self.faces[portal.face];
+            let new = &faces[portal.face];
+
+            // Keep pointing through the same side of the face
+            let sign = portal.normal.dot(old.normal).signum();
+            portal.normal = new.normal * sign;
+
+            if mirror {
+                portal.adjacent.reverse();
+            }
+        });
+
+        // Uniform scales scale the clearance
+        let scale = transform.determinant().abs().sqrt();
+        let mut leaf_clearance = self.leaf_clearance.clone();
+        leaf_clearance.values_mut().for_each(|v| *v *= scale);
+        let clearances = self.clearances.iter().map(|v| v * scale).collect();
+
+        Self {
+            inner,
+            faces,
+            clearances,
+            costs: self.costs.clone(),
+            disabled: self.disabled.clone(),
+            data: self.data.clone(),
+            leaf_clearance,
+            links: self
+                .links
+                .iter()
+                .map(|link| OffMeshLink {
+                    entry: transform.transform_point2(link.entry),
+                    exit: transform.transform_point2(link.exit),
+                    ..*link
+                })
+                .collect(),
+            diagnostics: self.diagnostics.clone(),
+        }
+    }
+
+    pub fn from_ref(&self, portal: PortalRef) -> Portal<'_> {
         Portal {
             face: &self.faces[portal.face],
             portal_ref: portal,
+            clearance: clearance(&self.faces, &self.clearances, portal.face),
         }
     }
 }
 
+/// Returns the baked clearance of `face`, or half its length if not baked
+fn clearance(faces: &[Face], clearances: &[f32], face: usize) -> f32 {
+    clearances
+        .get(face)
+        .copied()
+        .unwrap_or_else(|| faces[face].length() / 2.0)
+}
+
 #[doc(hidden)]
 pub struct PortalIter<'a> {
     faces: &'a [Face],
+    clearances: &'a [f32],
+    disabled: &'a [bool],
     iter: slice::Iter<'a, PortalRef>,
+    // Portals of this direction are not yielded
+    skip: PortalDirection,
 }
 
 impl<'a> Iterator for PortalIter<'a> {
     type Item = Portal<'a>;
 
     fn next(&mut self) -> Option<Self::Item> {
-        let portal = self.iter.next()?;
+        let (skip, disabled) = (self.skip, self.disabled);
+        let portal = self.iter.find(|portal| {
+            portal.direction != skip && !disabled.get(portal.face).copied().unwrap_or_default()
+        })?;
 
         Some(Portal {
             face: &self.faces[portal.face],
             portal_ref: *portal,
+            clearance: clearance(self.faces, self.clearances, portal.face),
         })
     }
 }
@@ -199,6 +635,8 @@ impl<'a> IntoIterator for &'a Portals {
 #[doc(hidden)]
 pub struct PortalsIter<'a> {
     faces: &'a [Face],
+    clearances: &'a [f32],
+    disabled: &'a [bool],
     inner: Iter<'a, NodeIndex, NodePortals>,
 }
 
@@ -209,7 +647,10 @@ impl<'a> Iterator for PortalsIter<'a> {
         let (_, portals) = self.inner.next()?;
         Some(PortalIter {
             faces: self.faces,
+            clearances: self.clearances,
+            disabled: self.disabled,
             iter: portals.iter(),
+            skip: PortalDirection::Backward,
         })
     }
 }
@@ -226,6 +667,207 @@ impl Extend<ClippedFace> for Portals {
         let cap = self.inner.len() + iter.size_hint().0;
 
         self.inner.set_capacity(cap);
-        iter.for_each(|val| self.push(val))
+        iter.for_each(|val| {
+            if let Err(diagnostic) = self.push(val) {
+                self.diagnostics.push(diagnostic)
+            }
+        })
+    }
+}
+
+/// A minimal representation of [Portals] storing each portal once, without
+/// the data which can be derived from the face.
+///
+/// Used to shrink serialized navigation data.
+#[derive(Debug, Clone, Default, PartialEq)]
+#[cfg_attr(feature = "serialize", derive(serde::Serialize, serde::Deserialize))]
+pub struct CompactPortals {
+    pub(crate) portals: Vec<PortalRecord>,
+    #[cfg_attr(feature = "serialize", serde(default))]
+    pub(crate) links: Vec<OffMeshLink>,
+}
+
+impl CompactPortals {
+    /// Replaces every node index by its entry in `map`
+    pub(crate) fn remap(&mut self, map: &SecondaryMap<NodeIndex, NodeIndex>) {
+        let get = |node: NodeIndex| map.get(node).copied().unwrap_or_default();
+        self.portals.iter_mut().for_each(|v| {
+            v.src = get(v.src);
+            v.dst = get(v.dst);
+        });
+        self.links.iter_mut().for_each(|v| {
+            v.src = get(v.src);
+            v.dst = get(v.dst);
+        });
+    }
+}
+
+#[derive(Debug, Clone, Copy, PartialEq)]
+#[cfg_attr(feature = "serialize", derive(serde::Serialize, serde::Deserialize))]
+pub(crate) struct PortalRecord {
+    #[cfg_attr(feature = "serialize", serde(with = "crate::serialize::vec2_pair"))]
+    pub(crate) vertices: [Vec2; 2],
+    pub(crate) src: NodeIndex,
+    pub(crate) dst: NodeIndex,
+    pub(crate) adjacent: [bool; 2],
+    #[cfg_attr(feature = "serialize", serde(default))]
+    pub(crate) cost: f32,
+    // The direction of the src side
+    #[cfg_attr(feature = "serialize", serde(default))]
+    pub(crate) direction: PortalDirection,
+    #[cfg_attr(feature = "serialize", serde(default))]
+    pub(crate) disabled: bool,
+    #[cfg_attr(feature = "serialize", serde(default))]
+    pub(crate) clearance: Option<f32>,
+}
+
+impl From<&Portals> for CompactPortals {
+    fn from(portals: &Portals) -> Self {
+        let mut records: Vec<Option<PortalRecord>> = vec![None; portals.faces.len()];
+
+        // The side of the portal whose normal opposes the face is the original
+        // src
+        portals
+            .inner
+            .values()
+            .flatten()
+            .filter(|portal| portal.normal.dot(portals.faces[portal.face].normal()) < 0.0)
+            .for_each(|portal| {
+                records[portal.face] = Some(PortalRecord {
+                    vertices: portals.faces[portal.face].vertices,
+                    src: portal.src,
+                    dst: portal.dst,
+                    adjacent: portal.adjacent,
+                    cost: portals.cost(*portal),
+                    direction: portal.direction,
+                    disabled: !portals.is_enabled(*portal),
+                    clearance: portals.clearances.get(portal.face).copied(),
+                })
+            });
+
+        Self {
+            portals: records.into_iter().flatten().collect(),
+            links: portals.links.clone(),
+        }
+    }
+}
+
+impl From<CompactPortals> for Portals {
+    fn from(compact: CompactPortals) -> Self {
+        let mut portals = Portals::new();
+        for v in compact.portals {
+            let face = ClippedFace::new(v.vertices, [Side::Front; 2], v.adjacent, v.src, v.dst)
+                .with_one_way(v.direction == PortalDirection::Forward);
+
+            if let Err(diagnostic) = portals.push(face) {
+                portals.diagnostics.push(diagnostic);
+                continue;
+            }
+
+            let length = Face::new(v.vertices).length();
+            portals.clearances.push(v.clearance.unwrap_or(length / 2.0));
+            *portals.costs.last_mut().expect("Portal was pushed") = v.cost;
+            *portals.disabled.last_mut().expect("Portal was pushed") = v.disabled;
+
+            // Only crossable from dst to src
+            if v.direction == PortalDirection::Backward {
+                let reverse = *portals.inner[v.dst].last().expect("Portal was pushed");
+                portals.set_one_way(reverse, true);
+            }
+        }
+
+        portals.links = compact.links;
+        portals
+    }
+}
+
+/// Hashes the compact portals and links, without any platform dependent
+/// sizes
+pub(crate) fn hash_portals(
+    portals: &[PortalRecord],
+    links: &[OffMeshLink],
+    state: &mut impl Hasher,
+) {
+    for v in portals {
+        v.vertices.iter().for_each(|&p| hash_vec2(p, state));
+        (v.src, v.dst, v.adjacent, v.disabled).hash(state);
+        state.write_u8(v.direction as u8);
+        state.write_u32(v.cost.to_bits());
+        state.write_u32(v.clearance.map_or(u32::MAX, f32::to_bits));
+    }
+
+    for v in links {
+        hash_vec2(v.entry, state);
+        hash_vec2(v.exit, state);
+        (v.src, v.dst).hash(state);
+        state.write_u32(v.cost.to_bits());
+    }
+}
+
+/// Describes a portal which could not be added
+#[derive(Debug, Clone, Copy, PartialEq)]
+pub enum PortalDiagnostic {
+    /// The portal connects a node to itself
+    SelfPortal { node: NodeIndex, face: Face },
+    /// The portal references a node which was removed or never existed
+    StaleNode { node: NodeIndex, face: Face },
+}
+
+impl core::fmt::Display for PortalDiagnostic {
+    fn fmt(&self, f: &mut core::fmt::Formatter<'_>) -> core::fmt::Result {
+        match self {
+            PortalDiagnostic::SelfPortal { node, face } => {
+                write!(
+                    f,
+                    "Portal {:?} connects {:?} to itself",
+                    face.vertices, node
+                )
+            }
+            PortalDiagnostic::StaleNode { node, face } => {
+                write!(
+                    f,
+                    "Portal {:?} references stale node {:?}",
+                    face.vertices, node
+                )
+            }
+        }
+    }
+}
+
+impl core::error::Error for PortalDiagnostic {}
+
+/// An inconsistent portal, see [Portals::validate]
+#[derive(Debug, Clone, Copy, PartialEq)]
+pub enum PortalIssue {
+    /// The portal is stored for a node other than its src
+    Misplaced(PortalRef),
+    /// The portal connects a node to itself
+    SelfPortal(PortalRef),
+    /// The portal references a node which is not part of the tree
+    StaleNode(PortalRef),
+    /// The face of the portal does not exist
+    MissingFace(PortalRef),
+    /// The opposite direction of the portal is missing from its dst
+    Asymmetric(PortalRef),
+    /// The normal is not of unit length, or does not point into dst
+    WrongNormal(PortalRef),
+}
+
+impl core::fmt::Display for PortalIssue {
+    fn fmt(&self, f: &mut core::fmt::Formatter<'_>) -> core::fmt::Result {
+        let (portal, issue) = match self {
+            PortalIssue::Misplaced(portal) => (portal, "is stored for another node"),
+            PortalIssue::SelfPortal(portal) => (portal, "connects a node to itself"),
+            PortalIssue::StaleNode(portal) => (portal, "references a stale node"),
+            PortalIssue::MissingFace(portal) => (portal, "references a missing face"),
+            PortalIssue::Asymmetric(portal) => (portal, "has no opposite direction"),
+            PortalIssue::WrongNormal(portal) => (portal, "has a normal not pointing into dst"),
+        };
+
+        write!(
+            f,
+            "Portal {} from {:?} to {:?} {}",
+            portal.face, portal.src, portal.dst, issue
+        )
     }
 }
@@ -0,0 +1,180 @@
+use alloc::{vec, vec::Vec};
+use core::fmt::Display;
+
+use glam::Vec2;
+use slotmap::SlotMap;
+use smallvec::SmallVec;
+
+use crate::{Face, Side};
+
+use super::{BSPNode, BSPTree, NodeIndex};
+
+/// A node of an already partitioned hierarchy, e.g; produced by an editor or
+/// an offline baker. See [BSPTree::from_prebuilt].
+#[derive(Debug, Clone, PartialEq)]
+#[cfg_attr(feature = "serialize", derive(serde::Serialize, serde::Deserialize))]
+pub struct PrebuiltNode {
+    /// The coplanar faces of the node. The first face defines the
+    /// partitioning plane.
+    pub faces: Vec<Face>,
+    /// Index of the node in front of the plane
+    pub front: Option<usize>,
+    /// Index of the node behind the plane
+    pub back: Option<usize>,
+}
+
+impl PrebuiltNode {
+    pub fn new(faces: Vec<Face>, front: Option<usize>, back: Option<usize>) -> Self {
+        Self { faces, front, back }
+    }
+}
+
+/// Describes why a prebuilt hierarchy was rejected
+#[derive(Debug, Clone, Copy, PartialEq)]
+pub enum PrebuiltError {
+    /// There are no nodes
+    Empty,
+    /// A node has no faces to define its plane
+    NoFaces { node: usize },
+    /// A face does not lie in the plane of its node
+    NotCoplanar { node: usize },
+    /// A node references a child which does not exist
+    InvalidChild { node: usize, child: usize },
+    /// A node is the child of several nodes, or of itself
+    SharedChild { node: usize },
+    /// A node can not be reached from the root
+    Unreachable { node: usize },
+    /// A face of `node` lies on the wrong side of the plane of `ancestor`
+    WrongSide { node: usize, ancestor: usize },
+}
+
+impl Display for PrebuiltError {
+    fn fmt(&self, f: &mut core::fmt::Formatter<'_>) -> core::fmt::Result {
+        match self {
+            PrebuiltError::Empty => write!(f, "Hierarchy has no nodes"),
+            PrebuiltError::NoFaces { node } => write!(f, "Node {} has no faces", node),
+            PrebuiltError::NotCoplanar { node } => {
+                write!(f, "Faces of node {} are not coplanar", node)
+            }
+            PrebuiltError::InvalidChild { node, child } => {
+                write!(f, "Node {} references missing child {}", node, child)
+            }
+            PrebuiltError::SharedChild { node } => {
+                write!(f, "Node {} has more than one parent", node)
+            }
+            PrebuiltError::Unreachable { node } => {
+                write!(f, "Node {} is not reachable from the root", node)
+            }
+            PrebuiltError::WrongSide { node, ancestor } => write!(
+                f,
+                "Node {} lies on the wrong side of ancestor {}",
+                node, ancestor
+            ),
+        }
+    }
+}
+
+impl core::error::Error for PrebuiltError {}
+
+impl BSPTree {
+    /// Wraps an already partitioned hierarchy, rooted at the first node.
+    ///
+    /// The hierarchy is validated to be a tree where every face lies in the
+    /// plane of its node, and on the correct side of all its ancestors.
+    pub fn from_prebuilt(prebuilt: Vec<PrebuiltNode>) -> Result<Self, PrebuiltError> {
+        if prebuilt.is_empty() {
+            return Err(PrebuiltError::Empty);
+        }
+
+        // Validate the structure before descending
+        let mut parents = vec![false; prebuilt.len()];
+        parents[0] = true;
+        for (node, v) in prebuilt.iter().enumerate() {
+            let first = v.faces.first().ok_or(PrebuiltError::NoFaces { node })?;
+            if v.faces
+                .iter()
+                .any(|face| face.side_of(first.vertices[0], first.normal()) != Side::Coplanar)
+            {
+                return Err(PrebuiltError::NotCoplanar { node });
+            }
+
+            for child in v.front.into_iter().chain(v.back) {
+                match parents.get_mut(child) {
+                    None => return Err(PrebuiltError::InvalidChild { node, child }),
+                    Some(true) => return Err(PrebuiltError::SharedChild { node: child }),
+                    Some(parent) => *parent = true,
+                }
+            }
+        }
+
+        if let Some(node) = parents.iter().position(|v| !v) {
+            return Err(PrebuiltError::Unreachable { node });
+        }
+
+        let mut l = Vec2::splat(f32::MAX);
+        let mut r = Vec2::splat(f32::MIN);
+        prebuilt
+            .iter()
+            .flat_map(|v| &v.faces)
+            .flatten()
+            .for_each(|v| {
+                l = l.min(v);
+                r = r.max(v);
+            });
+
+        let mut nodes = SlotMap::with_key();
+        let mut ancestors = Vec::new();
+        let root = insert(&prebuilt, 0, 0, &mut ancestors, &mut nodes)?;
+
+        Ok(Self { nodes, root, l, r })
+    }
+}
+
+/// Inserts the subtree of `index`, checking its faces against the planes of
+/// `ancestors`, and which side of them the subtree lies on
+fn insert(
+    prebuilt: &[PrebuiltNode],
+    index: usize,
+    depth: usize,
+    ancestors: &mut Vec<(usize, bool)>,
+    nodes: &mut super::Nodes,
+) -> Result<NodeIndex, PrebuiltError> {
+    let v = &prebuilt[index];
+
+    for &(ancestor, front) in ancestors.iter() {
+        let plane = prebuilt[ancestor].faces[0];
+        for face in &v.faces {
+            let side = face.side_of(plane.vertices[0], plane.normal());
+            let wrong = match side {
+                Side::Coplanar => false,
+                Side::Front => !front,
+                Side::Back => front,
+                Side::Intersecting => true,
+            };
+
+            if wrong {
+                return Err(PrebuiltError::WrongSide {
+                    node: index,
+                    ancestor,
+                });
+            }
+        }
+    }
+
+    let mut child = |child: Option<usize>, front: bool| {
+        child
+            .map(|child| {
+                ancestors.push((index, front));
+                let result = insert(prebuilt, child, depth + 1, ancestors, nodes);
+                ancestors.pop();
+                result
+            })
+            .transpose()
+    };
+
+    let front = child(v.front, true)?;
+    let back = child(v.back, false)?;
+
+    let faces = SmallVec::from_slice(&v.faces);
+    Ok(nodes.insert(BSPNode::from_parts(faces, front, back, depth)))
+}
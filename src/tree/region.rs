@@ -0,0 +1,87 @@
+use glam::Vec2;
+#[cfg(not(feature = "std"))]
+use num_traits::Float;
+
+use super::{BSPTree, NodeIndex};
+use crate::TOLERANCE;
+
+/// Identifies an open leaf by its geometry rather than its [NodeIndex], which
+/// changes each time the tree is rebuilt.
+///
+/// The id is the centroid of the leaf, snapped to [TOLERANCE]. Rebuilding
+/// the same faces yields the same ids, which makes them suitable as keys of
+/// gameplay data, e.g; tags and labels of regions.
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Hash, PartialOrd, Ord)]
+#[cfg_attr(feature = "serialize", derive(serde::Serialize, serde::Deserialize))]
+pub struct RegionId {
+    x: i32,
+    y: i32,
+}
+
+impl RegionId {
+    fn new(point: Vec2) -> Self {
+        Self {
+            x: (point.x / TOLERANCE).round() as i32,
+            y: (point.y / TOLERANCE).round() as i32,
+        }
+    }
+
+    /// Returns the snapped centroid of the region
+    pub fn point(&self) -> Vec2 {
+        Vec2::new(self.x as f32, self.y as f32) * TOLERANCE
+    }
+}
+
+impl BSPTree {
+    /// Returns the stable id of the open leaf `index`.
+    /// Returns None if `index` is not a leaf which can be located.
+    pub fn region_id(&self, index: NodeIndex) -> Option<RegionId> {
+        self.cell(index)
+            .and_then(|cell| centroid(&cell))
+            .map(RegionId::new)
+    }
+
+    /// Returns the open leaf identified by `id`.
+    ///
+    /// If the partitioning changed since the id was taken, the leaf which now
+    /// contains the centroid of the region is returned. Returns None if the
+    /// centroid is now covered.
+    pub fn region(&self, id: RegionId) -> Option<NodeIndex> {
+        let located = self.locate(id.point());
+        if located.covered() {
+            None
+        } else {
+            Some(located.index())
+        }
+    }
+
+    /// Returns the stable id of every open leaf. See [Self::region_id].
+    pub fn region_ids(&self) -> impl Iterator<Item = (NodeIndex, RegionId)> {
+        self.cells()
+            .filter_map(|(index, cell)| Some((index, RegionId::new(centroid(&cell)?))))
+    }
+}
+
+/// Returns the area weighted centroid of a convex polygon, or the mean of the
+/// vertices if it is degenerate
+fn centroid(polygon: &[Vec2]) -> Option<Vec2> {
+    let first = *polygon.first()?;
+
+    let (area, sum) = polygon.iter().zip(polygon.iter().cycle().skip(1)).fold(
+        (0.0, Vec2::ZERO),
+        |(area, sum), (&a, &b)| {
+            // Relative to the first vertex to keep precision far from the
+            // origin
+            let (a, b) = (a - first, b - first);
+            let cross = a.perp_dot(b);
+            (area + cross, sum + (a + b) * cross)
+        },
+    );
+
+    if area.abs() > f32::EPSILON {
+        Some(first + sum / (3.0 * area))
+    } else {
+        let sum = polygon.iter().fold(Vec2::ZERO, |acc, &v| acc + v);
+        Some(sum / polygon.len() as f32)
+    }
+}
@@ -0,0 +1,239 @@
+use alloc::vec::Vec;
+use core::fmt::Display;
+
+use slotmap::SecondaryMap;
+
+use super::{BSPTree, NodeIndex};
+use crate::Side;
+
+/// A violated structural invariant of a [BSPTree]. See [BSPTree::validate].
+#[derive(Debug, Clone, Copy, PartialEq)]
+pub enum TreeIssue {
+    /// The root is not a node of the tree
+    MissingRoot,
+    /// A node has no faces to define its plane
+    NoFaces { node: NodeIndex },
+    /// The normal of the plane, or of a face, is not of unit length
+    UnnormalizedNormal { node: NodeIndex },
+    /// The plane of the node does not match its first face
+    PlaneMismatch { node: NodeIndex },
+    /// A face does not lie in the plane of its node
+    NotCoplanar { node: NodeIndex, face: usize },
+    /// A face lies on the wrong side of the plane of an ancestor
+    WrongSide {
+        node: NodeIndex,
+        face: usize,
+        ancestor: NodeIndex,
+    },
+    /// The stored depth differs from the distance to the root
+    Depth {
+        node: NodeIndex,
+        expected: usize,
+        found: usize,
+    },
+    /// A node references a child which does not exist
+    MissingChild { node: NodeIndex, child: NodeIndex },
+    /// A node is the child of several nodes, or of its own subtree
+    SharedChild { node: NodeIndex },
+    /// A node can not be reached from the root
+    Unreachable { node: NodeIndex },
+}
+
+impl Display for TreeIssue {
+    fn fmt(&self, f: &mut core::fmt::Formatter<'_>) -> core::fmt::Result {
+        match self {
+            TreeIssue::MissingRoot => write!(f, "Root is not a node of the tree"),
+            TreeIssue::NoFaces { node } => write!(f, "Node {:?} has no faces", node),
+            TreeIssue::UnnormalizedNormal { node } => {
+                write!(f, "Node {:?} has a normal of non unit length", node)
+            }
+            TreeIssue::PlaneMismatch { node } => {
+                write!(f, "Plane of node {:?} does not match its first face", node)
+            }
+            TreeIssue::NotCoplanar { node, face } => {
+                write!(f, "Face {} of node {:?} is not coplanar", face, node)
+            }
+            TreeIssue::WrongSide {
+                node,
+                face,
+                ancestor,
+            } => write!(
+                f,
+                "Face {} of node {:?} lies on the wrong side of ancestor {:?}",
+                face, node, ancestor
+            ),
+            TreeIssue::Depth {
+                node,
+                expected,
+                found,
+            } => write!(
+                f,
+                "Node {:?} has depth {}, but lies at depth {}",
+                node, found, expected
+            ),
+            TreeIssue::MissingChild { node, child } => {
+                write!(f, "Node {:?} references missing child {:?}", node, child)
+            }
+            TreeIssue::SharedChild { node } => {
+                write!(f, "Node {:?} has more than one parent", node)
+            }
+            TreeIssue::Unreachable { node } => {
+                write!(f, "Node {:?} is not reachable from the root", node)
+            }
+        }
+    }
+}
+
+/// The result of [BSPTree::validate]
+#[derive(Debug, Clone, Default, PartialEq)]
+pub struct ValidationReport {
+    /// Every violated invariant, in depth first order
+    pub issues: Vec<TreeIssue>,
+    /// The number of nodes reachable from the root
+    pub reachable: usize,
+    /// The greatest distance from the root to a node
+    pub max_depth: usize,
+}
+
+impl ValidationReport {
+    pub fn is_valid(&self) -> bool {
+        self.issues.is_empty()
+    }
+}
+
+impl Display for ValidationReport {
+    fn fmt(&self, f: &mut core::fmt::Formatter<'_>) -> core::fmt::Result {
+        write!(
+            f,
+            "{} nodes, max depth {}, {} issues",
+            self.reachable,
+            self.max_depth,
+            self.issues.len()
+        )?;
+
+        for issue in &self.issues {
+            write!(f, "\n  {}", issue)?;
+        }
+
+        Ok(())
+    }
+}
+
+impl BSPTree {
+    /// Checks the structural invariants of the tree, e.g; to localize
+    /// construction bugs with near-degenerate input.
+    ///
+    /// Checks that the nodes form a tree, that normals are normalized, that
+    /// faces lie in the plane of their node and on the correct side of every
+    /// ancestor, and that the stored depths are correct.
+    ///
+    /// Faces of descendants are compared against all ancestors, which is
+    /// quadratic in the depth of the tree.
+    pub fn validate(&self) -> ValidationReport {
+        let mut report = ValidationReport::default();
+
+        if !self.nodes.contains_key(self.root) {
+            report.issues.push(TreeIssue::MissingRoot);
+            return report;
+        }
+
+        let mut visited = SecondaryMap::new();
+        let mut ancestors = Vec::new();
+        self.validate_node(self.root, 0, &mut ancestors, &mut visited, &mut report);
+
+        report.issues.extend(
+            self.nodes
+                .keys()
+                .filter(|&node| !visited.contains_key(node))
+                .map(|node| TreeIssue::Unreachable { node }),
+        );
+
+        report
+    }
+
+    fn validate_node(
+        &self,
+        index: NodeIndex,
+        depth: usize,
+        ancestors: &mut Vec<(NodeIndex, bool)>,
+        visited: &mut SecondaryMap<NodeIndex, ()>,
+        report: &mut ValidationReport,
+    ) {
+        if visited.insert(index, ()).is_some() {
+            report.issues.push(TreeIssue::SharedChild { node: index });
+            return;
+        }
+
+        let node = &self.nodes[index];
+        report.reachable += 1;
+        report.max_depth = report.max_depth.max(depth);
+
+        if node.depth() != depth {
+            report.issues.push(TreeIssue::Depth {
+                node: index,
+                expected: depth,
+                found: node.depth(),
+            });
+        }
+
+        let faces = node.faces();
+        match faces.first() {
+            None => report.issues.push(TreeIssue::NoFaces { node: index }),
+            Some(first) if first.normal() != node.normal() => {
+                report.issues.push(TreeIssue::PlaneMismatch { node: index })
+            }
+            Some(_) => {}
+        }
+
+        if !node.normal().is_normalized() || faces.iter().any(|v| !v.normal().is_normalized()) {
+            report
+                .issues
+                .push(TreeIssue::UnnormalizedNormal { node: index });
+        }
+
+        for (i, face) in faces.iter().enumerate() {
+            if face.side_of(node.origin(), node.normal()) != Side::Coplanar {
+                report.issues.push(TreeIssue::NotCoplanar {
+                    node: index,
+                    face: i,
+                });
+            }
+
+            for &(ancestor, front) in ancestors.iter() {
+                let plane = &self.nodes[ancestor];
+                let wrong = match face.side_of(plane.origin(), plane.normal()) {
+                    Side::Coplanar => false,
+                    Side::Front => !front,
+                    Side::Back => front,
+                    Side::Intersecting => true,
+                };
+
+                if wrong {
+                    report.issues.push(TreeIssue::WrongSide {
+                        node: index,
+                        face: i,
+                        ancestor,
+                    });
+                }
+            }
+        }
+
+        for (child, front) in [(node.front(), true), (node.back(), false)] {
+            let child = match child {
+                Some(child) => child,
+                None => continue,
+            };
+
+            if !self.nodes.contains_key(child) {
+                report
+                    .issues
+                    .push(TreeIssue::MissingChild { node: index, child });
+                continue;
+            }
+
+            ancestors.push((index, front));
+            self.validate_node(child, depth + 1, ancestors, visited, report);
+            ancestors.pop();
+        }
+    }
+}
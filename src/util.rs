@@ -1,31 +1,226 @@
-use std::ops::Deref;
+use alloc::vec::Vec;
+use core::{hash::Hasher, ops::Deref, time::Duration};
 
 use glam::Vec2;
 
-pub(crate) fn face_intersect(a: (Vec2, Vec2), p: Vec2, normal: Vec2) -> Intersect {
+use crate::{Scalar, Vector2, TOLERANCE};
+
+pub(crate) fn face_intersect<V: Vector2>(a: (V, V), p: V, normal: V) -> Intersect<V> {
     let dir = a.1 - a.0;
     face_intersect_dir(a.0, dir, p, normal)
 }
 
-pub(crate) fn face_intersect_dir(a: Vec2, dir: Vec2, p: Vec2, normal: Vec2) -> Intersect {
-    let l = (p - a).dot(normal) / (dir.dot(normal));
-    Intersect::new(a + dir * l, l)
+pub(crate) fn face_intersect_dir<V: Vector2>(a: V, dir: V, p: V, normal: V) -> Intersect<V> {
+    let (point, distance) = a.plane_intersect(dir, p, normal);
+    Intersect::new(point, distance)
+}
+
+/// See [Vector2::plane_intersect]
+pub(crate) fn vec2_plane_intersect(a: Vec2, dir: Vec2, p: Vec2, normal: Vec2) -> (Vec2, f32) {
+    let l = real_distance(p, a, normal)
+        / (dir.x as Real * normal.x as Real + dir.y as Real * normal.y as Real);
+    let point = Vec2::new(
+        (a.x as Real + dir.x as Real * l) as f32,
+        (a.y as Real + dir.y as Real * l) as f32,
+    );
+
+    (point, l as f32)
+}
+
+/// The precision of plane distances and intersections
+#[cfg(feature = "f64")]
+type Real = f64;
+#[cfg(not(feature = "f64"))]
+type Real = f32;
+
+/// Returns the signed distance from the plane through `p` to `point`, see
+/// [Vector2::plane_distance].
+#[inline]
+pub(crate) fn plane_distance<V: Vector2>(point: V, p: V, normal: V) -> V::Scalar {
+    point.plane_distance(p, normal)
+}
+
+/// With the `f64` feature the distance is computed in double precision,
+/// which keeps the comparisons against [TOLERANCE] reliable for geometry far
+/// from the origin.
+#[inline]
+pub(crate) fn vec2_plane_distance(point: Vec2, p: Vec2, normal: Vec2) -> f32 {
+    real_distance(point, p, normal) as f32
+}
+
+#[inline]
+fn real_distance(point: Vec2, p: Vec2, normal: Vec2) -> Real {
+    let (x, y) = (point.x as Real - p.x as Real, point.y as Real - p.y as Real);
+    x * normal.x as Real + y * normal.y as Real
+}
+
+/// Clips a convex polygon to the front side of the plane through `p`
+pub(crate) fn clip_polygon<V: Vector2>(polygon: &[V], p: V, normal: V) -> Vec<V> {
+    let mut result = Vec::with_capacity(polygon.len() + 1);
+    let zero = V::Scalar::ZERO;
+
+    for (i, &a) in polygon.iter().enumerate() {
+        let b = polygon[(i + 1) % polygon.len()];
+        let (da, db) = (plane_distance(a, p, normal), plane_distance(b, p, normal));
+
+        if da >= zero {
+            result.push(a);
+        }
+
+        if (da >= zero) != (db >= zero) {
+            result.push(a + (b - a) * (da / (da - db)));
+        }
+    }
+
+    result
+}
+
+/// Returns the edges of a closed polygon
+pub(crate) fn edges<V: Copy>(cell: &[V]) -> impl Iterator<Item = (V, V)> + '_ {
+    cell.iter()
+        .copied()
+        .zip(cell.iter().copied().cycle().skip(1))
+}
+
+/// Returns true if `point` is inside the convex, counterclockwise `cell`
+pub(crate) fn cell_contains<V: Vector2>(cell: &[V], point: V) -> bool {
+    let epsilon = V::Scalar::from_f32(f32::EPSILON);
+    let tolerance = V::Scalar::from_f32(TOLERANCE);
+
+    cell.len() >= 3
+        && edges(cell).all(|(a, b)| {
+            let len = a.distance(b);
+            len < epsilon || plane_distance(point, a, (b - a).perp()) / len > -tolerance
+        })
+}
+
+/// Returns the closest point to `p` on the segment from `a` to `b`
+pub(crate) fn closest_on_segment<V: Vector2>(a: V, b: V, p: V) -> V {
+    let ab = b - a;
+    let t = (p - a).dot(ab) / ab.length_squared().max(V::Scalar::from_f32(f32::EPSILON));
+    a + ab * t.clamp(V::Scalar::ZERO, V::Scalar::ONE)
+}
+
+/// Returns the distance from `point` to the convex, counterclockwise `cell`,
+/// which is 0 inside
+pub(crate) fn cell_distance<V: Vector2>(cell: &[V], point: V) -> V::Scalar {
+    if cell_contains(cell, point) {
+        return V::Scalar::ZERO;
+    }
+
+    edges(cell)
+        .map(|(a, b)| closest_on_segment(a, b, point).distance(point))
+        .reduce(Scalar::min)
+        .unwrap_or_else(|| V::Scalar::from_f32(f32::INFINITY))
+}
+
+/// FNV-1a, which unlike the std hasher is stable across releases and
+/// platforms, as hashes are compared between peers and stored in patches.
+///
+/// Integers are fed as little endian bytes, and `usize` as 64 bits, so that
+/// derived `Hash` impls agree between 32 and 64 bit and big endian targets.
+pub struct StableHasher(u64);
+
+impl Default for StableHasher {
+    fn default() -> Self {
+        Self(0xcbf29ce484222325)
+    }
+}
+
+impl Hasher for StableHasher {
+    fn finish(&self) -> u64 {
+        self.0
+    }
+
+    fn write(&mut self, bytes: &[u8]) {
+        for &b in bytes {
+            self.0 = (self.0 ^ b as u64).wrapping_mul(0x100000001b3);
+        }
+    }
+
+    fn write_u8(&mut self, i: u8) {
+        self.write(&[i])
+    }
+
+    fn write_u16(&mut self, i: u16) {
+        self.write(&i.to_le_bytes())
+    }
+
+    fn write_u32(&mut self, i: u32) {
+        self.write(&i.to_le_bytes())
+    }
+
+    fn write_u64(&mut self, i: u64) {
+        self.write(&i.to_le_bytes())
+    }
+
+    fn write_u128(&mut self, i: u128) {
+        self.write(&i.to_le_bytes())
+    }
+
+    fn write_usize(&mut self, i: usize) {
+        self.write_u64(i as u64)
+    }
+}
+
+#[cfg(feature = "std")]
+pub(crate) use std::collections::{HashMap, HashSet};
+
+/// Without std, hashbrown is used with the stable hasher, as there is no
+/// source of random keys. Construct with `HashMap::default()`.
+#[cfg(not(feature = "std"))]
+pub type HashMap<K, V> = hashbrown::HashMap<K, V, core::hash::BuildHasherDefault<StableHasher>>;
+#[cfg(not(feature = "std"))]
+pub type HashSet<T> = hashbrown::HashSet<T, core::hash::BuildHasherDefault<StableHasher>>;
+
+/// The point in time at which a time budget runs out.
+/// Without std, or on wasm32 where `std::time::Instant` panics, there is no
+/// clock, and budgets never run out.
+#[derive(Debug, Clone, Copy)]
+pub(crate) struct Deadline {
+    #[cfg(all(feature = "std", not(target_arch = "wasm32")))]
+    instant: std::time::Instant,
+}
+
+impl Deadline {
+    pub(crate) fn after(budget: Duration) -> Self {
+        #[cfg(not(all(feature = "std", not(target_arch = "wasm32"))))]
+        let _ = budget;
+
+        Self {
+            #[cfg(all(feature = "std", not(target_arch = "wasm32")))]
+            instant: std::time::Instant::now() + budget,
+        }
+    }
+
+    pub(crate) fn passed(&self) -> bool {
+        #[cfg(all(feature = "std", not(target_arch = "wasm32")))]
+        return std::time::Instant::now() >= self.instant;
+        #[cfg(not(all(feature = "std", not(target_arch = "wasm32"))))]
+        return false;
+    }
+}
+
+/// Hashes the exact bits of `v`
+pub(crate) fn hash_vec2(v: Vec2, state: &mut impl Hasher) {
+    state.write_u32(v.x.to_bits());
+    state.write_u32(v.y.to_bits());
 }
 
 #[derive(Default, Debug, Clone, Copy, PartialEq)]
-pub(crate) struct Intersect {
-    pub point: Vec2,
-    pub distance: f32,
+pub(crate) struct Intersect<V: Vector2 = Vec2> {
+    pub point: V,
+    pub distance: V::Scalar,
 }
 
-impl Intersect {
-    pub fn new(point: Vec2, distance: f32) -> Self {
+impl<V: Vector2> Intersect<V> {
+    pub fn new(point: V, distance: V::Scalar) -> Self {
         Self { point, distance }
     }
 }
 
-impl Deref for Intersect {
-    type Target = Vec2;
+impl<V: Vector2> Deref for Intersect<V> {
+    type Target = V;
 
     fn deref(&self) -> &Self::Target {
         &self.point
@@ -0,0 +1,204 @@
+use alloc::vec::Vec;
+use core::hash::Hash;
+
+use glam::Mat3;
+
+use crate::{util::HashMap, Face, NavigationContext, Shape};
+
+/// A handle to an obstacle of a [NavWorld], see [NavWorld::insert]
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Hash, PartialOrd, Ord)]
+pub struct ObstacleId(u32);
+
+/// A scene of obstacles, each a shape placed by a transform, which rebuilds
+/// the navigation context only when they change.
+///
+/// Obstacles are identified either by handles from [Self::insert], or by
+/// keys of the user, e.g; the entities of an ECS driving the obstacles with
+/// [Self::upsert].
+///
+/// Obstacles are rebuilt in the order they were first inserted, which makes
+/// the result deterministic for the same sequence of changes.
+#[derive(Clone)]
+pub struct NavWorld<K = ObstacleId> {
+    ids: HashMap<K, usize>,
+    obstacles: Vec<Obstacle<K>>,
+    nav: NavigationContext,
+    dirty: bool,
+    next_id: u32,
+}
+
+#[derive(Clone)]
+struct Obstacle<K> {
+    id: K,
+    shape: Shape,
+    transform: Mat3,
+    /// The transformed faces of the shape
+    faces: Vec<Face>,
+}
+
+impl<K> Default for NavWorld<K> {
+    fn default() -> Self {
+        Self {
+            ids: HashMap::default(),
+            obstacles: Vec::new(),
+            nav: NavigationContext::default(),
+            dirty: false,
+            next_id: 0,
+        }
+    }
+}
+
+impl NavWorld<ObstacleId> {
+    /// Adds an obstacle made of `shape` transformed by `transform`, and
+    /// returns a handle to it
+    pub fn insert(&mut self, shape: &Shape, transform: Mat3) -> ObstacleId {
+        let id = ObstacleId(self.next_id);
+        self.next_id += 1;
+        self.upsert(id, shape, transform);
+        id
+    }
+}
+
+fn transformed(shape: &Shape, transform: Mat3) -> Vec<Face> {
+    shape
+        .faces()
+        .map(|face| face.transform_oriented(transform))
+        .collect()
+}
+
+impl<K: Hash + Eq + Clone> NavWorld<K> {
+    pub fn new() -> Self {
+        Self::default()
+    }
+
+    /// Inserts or updates the obstacle `id`, made of `shape` transformed by
+    /// `transform`. The world is only marked dirty if the obstacle changed.
+    pub fn upsert(&mut self, id: K, shape: &Shape, transform: Mat3) {
+        let faces = transformed(shape, transform);
+
+        match self.ids.get(&id) {
+            Some(&index) => {
+                let obstacle = &mut self.obstacles[index];
+                self.dirty |= obstacle.faces != faces;
+                obstacle.shape = shape.clone();
+                obstacle.transform = transform;
+                obstacle.faces = faces;
+            }
+            None => {
+                self.ids.insert(id.clone(), self.obstacles.len());
+                self.obstacles.push(Obstacle {
+                    id,
+                    shape: shape.clone(),
+                    transform,
+                    faces,
+                });
+                self.dirty = true;
+            }
+        }
+    }
+
+    /// Moves the obstacle `id`, keeping its shape. Returns false if it does
+    /// not exist.
+    pub fn set_transform(&mut self, id: &K, transform: Mat3) -> bool {
+        let obstacle = match self.ids.get(id) {
+            Some(&index) => &mut self.obstacles[index],
+            None => return false,
+        };
+
+        let faces = transformed(&obstacle.shape, transform);
+        self.dirty |= obstacle.faces != faces;
+        obstacle.transform = transform;
+        obstacle.faces = faces;
+        true
+    }
+
+    /// Returns the untransformed shape of the obstacle `id`
+    pub fn shape(&self, id: &K) -> Option<&Shape> {
+        Some(&self.obstacles[*self.ids.get(id)?].shape)
+    }
+
+    pub fn transform(&self, id: &K) -> Option<Mat3> {
+        Some(self.obstacles[*self.ids.get(id)?].transform)
+    }
+
+    /// Iterates the obstacles in insertion order, e.g; to draw the scene
+    pub fn iter(&self) -> impl Iterator<Item = (&K, &Shape, Mat3)> {
+        self.obstacles
+            .iter()
+            .map(|v| (&v.id, &v.shape, v.transform))
+    }
+
+    /// Removes the obstacle `id`. Returns true if it existed.
+    pub fn remove(&mut self, id: &K) -> bool {
+        let index = match self.ids.remove(id) {
+            Some(index) => index,
+            None => return false,
+        };
+
+        // Shift the following obstacles to preserve the order
+        self.obstacles.remove(index);
+        for obstacle in &self.obstacles[index..] {
+            *self.ids.get_mut(&obstacle.id).expect("Obstacle is tracked") -= 1;
+        }
+
+        self.dirty = true;
+        true
+    }
+
+    /// Removes all obstacles
+    pub fn clear(&mut self) {
+        self.dirty |= !self.obstacles.is_empty();
+        self.ids.clear();
+        self.obstacles.clear();
+    }
+
+    /// Rebuilds the navigation context if any obstacle changed since the last
+    /// commit. Returns true if it was rebuilt.
+    pub fn commit(&mut self) -> bool {
+        if !self.dirty {
+            return false;
+        }
+
+        self.nav = self.build_navigation();
+        self.dirty = false;
+        true
+    }
+
+    /// Builds a navigation context from the current obstacles, regardless of
+    /// whether they changed, e.g; to build on another thread while the scene
+    /// keeps changing.
+    pub fn build_navigation(&self) -> NavigationContext {
+        let faces = self
+            .obstacles
+            .iter()
+            .flat_map(|v| v.faces.iter().copied())
+            .collect::<Vec<_>>();
+
+        NavigationContext::from_slice(&faces)
+    }
+
+    /// Returns true if the obstacle is tracked
+    pub fn contains(&self, id: &K) -> bool {
+        self.ids.contains_key(id)
+    }
+
+    /// Returns true if obstacles changed since the last commit
+    pub fn is_dirty(&self) -> bool {
+        self.dirty
+    }
+
+    /// Returns the number of obstacles
+    pub fn len(&self) -> usize {
+        self.obstacles.len()
+    }
+
+    /// Returns true if there are no obstacles
+    pub fn is_empty(&self) -> bool {
+        self.obstacles.is_empty()
+    }
+
+    /// Get the nav world's navigation context, as of the last commit.
+    pub fn nav(&self) -> &NavigationContext {
+        &self.nav
+    }
+}
@@ -3,7 +3,6 @@
 fn serialize() {
     use bsp_pathfinding::*;
     use glam::*;
-    use serde_json::*;
     // Define a simple scene
     let square = Shape::rect(Vec2::new(50.0, 50.0), Vec2::new(0.0, 0.0));
     let left = Shape::rect(Vec2::new(10.0, 200.0), Vec2::new(-200.0, 10.0));
@@ -36,3 +35,311 @@ fn serialize() {
         end,
     ]));
 }
+
+#[test]
+#[cfg(feature = "serialize")]
+fn serialize_vec2_fields() {
+    use bsp_pathfinding::*;
+    use glam::*;
+
+    let face = Face::new([Vec2::new(1.0, 2.0), Vec2::new(3.0, 2.0)]);
+
+    let json = serde_json::to_value(face).unwrap();
+
+    assert_eq!(json["vertices"][0]["x"], 1.0);
+    assert_eq!(json["vertices"][1]["y"], 2.0);
+    assert_eq!(json["normal"]["y"], -1.0);
+
+    let face2: Face = serde_json::from_value(json).unwrap();
+    assert_eq!(face, face2);
+}
+
+#[test]
+#[cfg(feature = "serialize")]
+fn serialize_compact_portals() {
+    use bsp_pathfinding::*;
+    use glam::*;
+
+    let square = Shape::rect(Vec2::new(50.0, 50.0), Vec2::new(0.0, 0.0));
+    let left = Shape::rect(Vec2::new(10.0, 200.0), Vec2::new(-200.0, 10.0));
+    let right = Shape::rect(Vec2::new(10.0, 200.0), Vec2::new(200.0, 10.0));
+
+    let mut nav = NavigationContext::new([square, left, right].iter().flatten());
+
+    // Directions and costs survive compaction
+    let portal = nav.portals().iter().flatten().nth(3).unwrap().portal_ref();
+    nav.set_one_way(portal, true);
+    nav.set_portal_cost(portal, 5.0);
+    let link = nav
+        .add_link(Vec2::new(-100.0, 0.0), Vec2::new(100.0, 0.0), 1.0)
+        .unwrap();
+
+    let portals = nav.portals();
+
+    let compact = CompactPortals::from(portals);
+
+    let full_json = serde_json::to_string(portals).unwrap();
+    let compact_json = serde_json::to_string(&compact).unwrap();
+    assert!(compact_json.len() * 2 < full_json.len());
+
+    let compact: CompactPortals = serde_json::from_str(&compact_json).unwrap();
+    let restored = Portals::from(compact);
+
+    for (index, _) in nav.tree().unwrap().descendants() {
+        assert!(portals.get(index).eq(restored.get(index)));
+        assert!(portals.incoming(index).eq(restored.incoming(index)));
+    }
+
+    assert_eq!(restored.cost(portal), 5.0);
+    assert_eq!(restored.link(link), portals.link(link));
+}
+
+#[test]
+#[cfg(feature = "serialize")]
+fn serialize_patch() {
+    use bsp_pathfinding::*;
+    use glam::*;
+
+    let square = Shape::rect(Vec2::new(50.0, 50.0), Vec2::new(0.0, 0.0));
+    let left = Shape::rect(Vec2::new(10.0, 200.0), Vec2::new(-200.0, 10.0));
+    let right = Shape::rect(Vec2::new(10.0, 200.0), Vec2::new(200.0, 10.0));
+    let obstacle = Shape::rect(Vec2::new(20.0, 20.0), Vec2::new(0.0, 60.0));
+
+    let shapes = [square, left, right];
+    let before = NavigationContext::new(shapes.iter().flatten());
+    let after = NavigationContext::new(shapes.iter().chain([&obstacle]).flatten());
+
+    // Ship both the base and the patch serialized
+    let base = serde_json::to_string(&before).unwrap();
+    let patch = serde_json::to_string(&before.diff(&after)).unwrap();
+
+    let mut nav: NavigationContext = serde_json::from_str(&base).unwrap();
+    let patch: NavPatch = serde_json::from_str(&patch).unwrap();
+    nav.apply_patch(&patch).unwrap();
+
+    assert!(nav.diff(&after).is_empty());
+    assert!(!patch.is_empty());
+}
+
+#[test]
+#[cfg(feature = "serialize")]
+fn serialize_hash_state() {
+    use bsp_pathfinding::*;
+    use glam::*;
+
+    let square = Shape::rect(Vec2::new(50.0, 50.0), Vec2::new(0.0, 0.0));
+    let left = Shape::rect(Vec2::new(10.0, 200.0), Vec2::new(-200.0, 10.0));
+
+    let nav = NavigationContext::new([square, left].iter().flatten());
+    let json = serde_json::to_string(&nav).unwrap();
+    let loaded: NavigationContext = serde_json::from_str(&json).unwrap();
+
+    assert_eq!(nav.hash_state(), loaded.hash_state());
+
+    let clearance = |nav: &NavigationContext, p: Vec2| {
+        let node = nav.tree().unwrap().locate(p).index();
+        nav.portals().leaf_clearance(node)
+    };
+    for p in [Vec2::new(-100.0, 0.0), Vec2::new(100.0, 100.0)] {
+        assert!(clearance(&loaded, p).is_some());
+        assert_eq!(clearance(&loaded, p), clearance(&nav, p));
+    }
+}
+
+#[test]
+#[cfg(feature = "serialize")]
+fn serialize_navmesh() {
+    use bsp_pathfinding::*;
+    use glam::*;
+
+    let square = Shape::rect(Vec2::new(50.0, 50.0), Vec2::new(0.0, 0.0));
+    let left = Shape::rect(Vec2::new(10.0, 200.0), Vec2::new(-200.0, 10.0));
+    let right = Shape::rect(Vec2::new(10.0, 200.0), Vec2::new(200.0, 10.0));
+
+    let nav = NavigationContext::new([square, left, right].iter().flatten());
+    let mesh = nav.to_navmesh();
+
+    let json = serde_json::to_string(&mesh).unwrap();
+    let loaded: NavMesh = serde_json::from_str(&json).unwrap();
+    assert_eq!(loaded, mesh);
+}
+
+#[test]
+#[cfg(feature = "serialize")]
+fn serialize_occupancy_grid() {
+    use bsp_pathfinding::*;
+    use glam::*;
+
+    let square = Shape::rect(Vec2::new(50.0, 50.0), Vec2::new(0.0, 0.0));
+    let nav = NavigationContext::new(square.faces());
+    let grid = nav.rasterize_with_distances(5.0);
+
+    let json = serde_json::to_string(&grid).unwrap();
+    let loaded: OccupancyGrid = serde_json::from_str(&json).unwrap();
+    assert_eq!(loaded, grid);
+}
+
+#[test]
+#[cfg(feature = "serialize")]
+fn bake_and_load() {
+    use bsp_pathfinding::*;
+    use glam::*;
+
+    let square = Shape::rect(Vec2::new(50.0, 50.0), Vec2::new(0.0, 0.0));
+    let left = Shape::rect(Vec2::new(10.0, 200.0), Vec2::new(-200.0, 10.0));
+    let right = Shape::rect(Vec2::new(10.0, 200.0), Vec2::new(200.0, 10.0));
+    let bottom = Shape::rect(Vec2::new(200.0, 10.0), Vec2::new(10.0, -200.0));
+    let top = Shape::rect(Vec2::new(200.0, 10.0), Vec2::new(10.0, 200.0));
+
+    let nav = NavigationContext::new([square, left, right, top, bottom].iter().flatten());
+
+    let bytes = nav.bake();
+    assert!(bytes.starts_with(b"BSPN"));
+    assert!(bytes.len() * 4 < serde_json::to_vec(&nav).unwrap().len());
+
+    let loaded = NavigationContext::load(&bytes).unwrap();
+    assert_eq!(loaded.portals().hash_state(), nav.portals().hash_state());
+
+    let clearance = |nav: &NavigationContext, p: Vec2| {
+        let node = nav.tree().unwrap().locate(p).index();
+        nav.portals().leaf_clearance(node)
+    };
+    for p in [Vec2::new(-100.0, 0.0), Vec2::new(100.0, 100.0)] {
+        assert!(clearance(&loaded, p).is_some());
+        assert_eq!(clearance(&loaded, p), clearance(&nav, p));
+    }
+
+    let start = Vec2::new(-100.0, 0.0);
+    let end = Vec2::new(100.0, 30.0);
+    let find = |nav: &NavigationContext| {
+        nav.find_path(start, end, heuristics::euclidiean, SearchInfo::default())
+            .unwrap()
+            .iter()
+            .map(|v| v.point())
+            .collect::<Vec<_>>()
+    };
+
+    assert_eq!(find(&loaded), find(&nav));
+
+    assert_eq!(
+        NavigationContext::load(b"{}").err(),
+        Some(LoadError::InvalidHeader)
+    );
+    assert_eq!(
+        NavigationContext::load(&bytes[..bytes.len() / 2]).err(),
+        Some(LoadError::Corrupt)
+    );
+}
+
+#[test]
+#[cfg(feature = "serialize")]
+fn format_version() {
+    use bsp_pathfinding::*;
+    use glam::*;
+
+    let square = Shape::rect(Vec2::new(50.0, 50.0), Vec2::new(0.0, 0.0));
+    let nav = NavigationContext::new(square.faces());
+
+    let mut json = serde_json::to_value(&nav).unwrap();
+    assert_eq!(json["version"], FORMAT_VERSION);
+
+    let loaded: NavigationContext = serde_json::from_value(json.clone()).unwrap();
+    assert_eq!(loaded.portals().hash_state(), nav.portals().hash_state());
+
+    // Data from before the version was embedded is rejected
+    let mut legacy = json.clone();
+    legacy.as_object_mut().unwrap().remove("version");
+    let err = serde_json::from_value::<NavigationContext>(legacy)
+        .err()
+        .unwrap();
+    assert!(err.to_string().contains("version 0"), "{}", err);
+
+    json["version"] = (FORMAT_VERSION + 1).into();
+    let err = serde_json::from_value::<NavigationContext>(json)
+        .err()
+        .unwrap();
+    assert!(err.to_string().contains("version 2"), "{}", err);
+
+    let mut bytes = nav.bake();
+    bytes[4..8].copy_from_slice(&(FORMAT_VERSION + 1).to_le_bytes());
+    assert_eq!(
+        NavigationContext::load(&bytes).err(),
+        Some(LoadError::UnsupportedVersion {
+            version: FORMAT_VERSION + 1
+        })
+    );
+
+    bytes[4..8].copy_from_slice(&0u32.to_le_bytes());
+    assert_eq!(
+        NavigationContext::load(&bytes).err(),
+        Some(LoadError::UnsupportedVersion { version: 0 })
+    );
+}
+
+#[test]
+#[cfg(feature = "serialize")]
+fn validate_tampered_tree() {
+    use bsp_pathfinding::*;
+    use glam::*;
+
+    let square = Shape::rect(Vec2::new(50.0, 50.0), Vec2::new(0.0, 0.0));
+    let left = Shape::rect(Vec2::new(10.0, 200.0), Vec2::new(-200.0, 10.0));
+
+    let nav = NavigationContext::new([square, left].iter().flatten());
+    let tree = nav.tree().unwrap();
+    assert!(tree.validate().is_valid());
+
+    let json = serde_json::to_value(tree).unwrap();
+    let tamper = |f: &dyn Fn(&mut serde_json::Value)| {
+        let mut json = json.clone();
+        f(&mut json["nodes"]);
+        let tree: BSPTree = serde_json::from_value(json).unwrap();
+        tree.validate().issues
+    };
+
+    let issues = tamper(&|nodes| nodes[1]["value"]["depth"] = 7.into());
+    assert!(
+        matches!(
+            issues[..],
+            [TreeIssue::Depth {
+                expected: 3,
+                found: 7,
+                ..
+            }]
+        ),
+        "{:?}",
+        issues
+    );
+
+    let issues = tamper(&|nodes| nodes[1]["value"]["normal"]["x"] = (-2.0).into());
+    assert!(issues.contains(&TreeIssue::UnnormalizedNormal {
+        node: tree.locate(Vec2::new(-300.0, -57.5)).index()
+    }));
+
+    let issues =
+        tamper(&|nodes| nodes[2]["value"]["faces"][0]["vertices"][0]["x"] = (-190.0).into());
+    assert!(
+        matches!(issues[..], [TreeIssue::NotCoplanar { face: 0, .. }]),
+        "{:?}",
+        issues
+    );
+
+    // Detach the subtree of node 2
+    let issues = tamper(&|nodes| nodes[3]["value"]["back"] = serde_json::Value::Null);
+    assert_eq!(issues.len(), 2);
+    assert!(issues
+        .iter()
+        .all(|v| matches!(v, TreeIssue::Unreachable { .. })));
+
+    let report = serde_json::from_value::<BSPTree>({
+        let mut json = json.clone();
+        json["nodes"][1]["value"]["depth"] = 7.into();
+        json
+    })
+    .unwrap()
+    .validate();
+    assert!(!report.is_valid());
+    assert!(report
+        .to_string()
+        .contains("has depth 7, but lies at depth 3"));
+}
@@ -1,4 +1,4 @@
-use std::f32::consts::PI;
+use std::{collections::HashSet, f32::consts::PI, time::Duration};
 
 use bsp_pathfinding::*;
 use glam::{Mat3, Vec2};
@@ -14,6 +14,69 @@ fn face() {
     assert!(f.normal().distance(Vec2::Y) < 0.01);
 }
 
+#[test]
+fn shape_rect() {
+    let rect = Shape::rect(Vec2::new(2.0, 1.0), Vec2::new(1.0, 0.0));
+
+    let faces = rect.faces();
+
+    let normals = [-Vec2::Y, Vec2::X, Vec2::Y, -Vec2::X];
+
+    assert!(faces.map(|val| val.normal()).eq(normals));
+}
+
+#[test]
+fn side_lattice() {
+    use Side::*;
+
+    let sides = [Front, Back, Coplanar, Intersecting];
+
+    for a in sides {
+        assert_eq!(a.min_side(Front), a.min_side(Front).min_side(Front));
+        assert_eq!(a.max_side(Back), a.max_side(Back).max_side(Back));
+        assert_eq!(a.merge(Coplanar), a);
+        assert_eq!(a.merge(Intersecting), Intersecting);
+
+        for b in sides {
+            assert_eq!(a.min_side(b), b.min_side(a));
+            assert_eq!(a.max_side(b), b.max_side(a));
+            assert_eq!(a.merge(b), b.merge(a));
+            assert_ne!(a.min_side(b), Intersecting);
+            assert_ne!(a.max_side(b), Intersecting);
+        }
+    }
+
+    // Every pair, as (a, b, min_side, max_side, merge)
+    let table = [
+        (Front, Front, Front, Front, Front),
+        (Front, Back, Back, Front, Intersecting),
+        (Front, Coplanar, Coplanar, Front, Front),
+        (Front, Intersecting, Back, Front, Intersecting),
+        (Back, Front, Back, Front, Intersecting),
+        (Back, Back, Back, Back, Back),
+        (Back, Coplanar, Back, Coplanar, Back),
+        (Back, Intersecting, Back, Front, Intersecting),
+        (Coplanar, Front, Coplanar, Front, Front),
+        (Coplanar, Back, Back, Coplanar, Back),
+        (Coplanar, Coplanar, Coplanar, Coplanar, Coplanar),
+        (Coplanar, Intersecting, Back, Front, Intersecting),
+        (Intersecting, Front, Back, Front, Intersecting),
+        (Intersecting, Back, Back, Front, Intersecting),
+        (Intersecting, Coplanar, Back, Front, Intersecting),
+        (Intersecting, Intersecting, Back, Front, Intersecting),
+    ];
+
+    for (a, b, min, max, merged) in table {
+        assert_eq!(a.min_side(b), min, "{:?}.min_side({:?})", a, b);
+        assert_eq!(a.max_side(b), max, "{:?}.max_side({:?})", a, b);
+        assert_eq!(a.merge(b), merged, "{:?}.merge({:?})", a, b);
+    }
+
+    // A face touching the plane lies in front of it
+    let face = Face::new([Vec2::Y, Vec2::new(1.0, 0.0)]);
+    assert_eq!(face.side_of(Vec2::ZERO, Vec2::Y), Front);
+}
+
 #[test]
 fn simple() {
     // Define a simple scene
@@ -60,11 +123,11 @@ fn incremental() {
     let start = Vec2::new(-100.0, 0.0);
     let end = Vec2::new(100.0, 30.0);
 
-    let path = nav
+    let mut path = nav
         .find_path(start, end, heuristics::euclidiean, SearchInfo::default())
-        .expect("Failed to find a path");
+        .ok();
 
-    let start = path[1].point();
+    let start = path.as_ref().expect("Failed to find a path")[1].point();
     // let end = Vec2::new(100.0, 35.0);
 
     let path = nav
@@ -73,7 +136,7 @@ fn incremental() {
             end,
             heuristics::euclidiean,
             SearchInfo::default(),
-            path,
+            &mut path,
         )
         .unwrap();
 
@@ -85,3 +148,4888 @@ fn incremental() {
         end,
     ]));
 }
+
+#[test]
+fn raycast() {
+    let square = Shape::rect(Vec2::new(50.0, 50.0), Vec2::new(0.0, 0.0));
+    let left = Shape::rect(Vec2::new(10.0, 200.0), Vec2::new(-200.0, 10.0));
+
+    let nav = NavigationContext::new([square, left].iter().flatten());
+    let tree = nav.tree().unwrap();
+
+    let hit = tree
+        .raycast(Vec2::new(-100.0, 10.0), Vec2::X, 500.0)
+        .expect("Ray should hit the square");
+
+    assert!(hit.point().distance(Vec2::new(-25.0, 10.0)) < 0.01);
+    assert!(hit.normal().distance(-Vec2::X) < 0.01);
+    assert!((hit.distance() - 75.0).abs() < 0.01);
+
+    let hit = tree
+        .raycast(Vec2::new(-100.0, 10.0), -Vec2::X, 500.0)
+        .expect("Ray should hit the left wall");
+
+    assert!(hit.point().distance(Vec2::new(-195.0, 10.0)) < 0.01);
+
+    assert_eq!(tree.raycast(Vec2::new(-100.0, 10.0), Vec2::X, 50.0), None);
+    assert_eq!(tree.raycast(Vec2::new(-100.0, 40.0), Vec2::X, 500.0), None);
+}
+
+#[test]
+fn malformed_portals() {
+    let square = Shape::rect(Vec2::new(50.0, 50.0), Vec2::new(0.0, 0.0));
+    let nav = NavigationContext::new(square.faces());
+    let tree = nav.tree().unwrap();
+
+    let root = tree.root();
+    let (front, _) = tree
+        .descendants()
+        .find(|(index, _)| *index != root)
+        .unwrap();
+
+    let mut portals = Portals::new();
+    let vertices = [Vec2::new(0.0, 0.0), Vec2::new(1.0, 0.0)];
+    let sides = [Side::Front; 2];
+
+    portals.extend([
+        ClippedFace::new(vertices, sides, [false; 2], root, root),
+        ClippedFace::new(vertices, sides, [false; 2], root, NodeIndex::default()),
+        ClippedFace::new(vertices, sides, [false; 2], root, front),
+    ]);
+
+    assert!(matches!(
+        portals.diagnostics(),
+        [
+            PortalDiagnostic::SelfPortal { .. },
+            PortalDiagnostic::StaleNode { .. }
+        ]
+    ));
+
+    assert_eq!(portals.get(root).count(), 1);
+    assert_eq!(portals.get(front).count(), 1);
+
+    // A node of another tree, which never existed in this one
+    let other = Shape::rect(Vec2::new(10.0, 10.0), Vec2::new(100.0, 0.0));
+    let big = NavigationContext::new([square, other].iter().flatten());
+    let foreign = big
+        .tree()
+        .unwrap()
+        .nodes()
+        .keys()
+        .find(|&v| !tree.nodes().contains_key(v))
+        .unwrap();
+
+    let portal = ClippedFace::new(vertices, sides, [false; 2], front, foreign);
+    assert_eq!(
+        portals.push_checked(tree, portal),
+        Err(PortalDiagnostic::StaleNode {
+            node: foreign,
+            face: Face::new(vertices)
+        })
+    );
+    assert!(portals.get(foreign).next().is_none());
+    assert_eq!(portals.get(front).count(), 1);
+}
+
+#[test]
+fn project_to_navigable() {
+    let square = Shape::rect(Vec2::new(50.0, 50.0), Vec2::new(0.0, 0.0));
+    let other = Shape::rect(Vec2::new(50.0, 50.0), Vec2::new(40.0, 40.0));
+
+    let nav = NavigationContext::new([square, other].iter().flatten());
+    let tree = nav.tree().unwrap();
+
+    for p in [
+        Vec2::new(24.0, 24.0),
+        Vec2::new(-20.0, 0.0),
+        Vec2::new(30.0, 0.0),
+        Vec2::new(-60.0, -60.0),
+    ] {
+        let projected = nav.project_to_navigable(p, 5.0).unwrap();
+
+        assert!(!tree.locate(projected).covered());
+        let (_, closest) = tree.closest_face(projected).unwrap();
+        assert!(closest.distance(projected) >= 5.0 - TOLERANCE);
+    }
+
+    let p = Vec2::new(-60.0, -60.0);
+    assert_eq!(nav.project_to_navigable(p, 5.0), Some(p));
+}
+
+#[test]
+fn layer_of_rounds_like_construction() {
+    let square = Shape::rect(Vec2::new(50.0, 50.0), Vec2::new(0.0, 0.0));
+    let other = Shape::rect(Vec2::new(50.0, 50.0), Vec2::new(100.0, 0.0));
+
+    // Heights just above a bucket midpoint belong to the upper bucket
+    let nav = LayeredNavigationContext::new(
+        square
+            .faces()
+            .map(|v| (0.0, v))
+            .chain(other.faces().map(|v| (0.06, v))),
+    );
+
+    assert_eq!(nav.layer_ids().count(), 2);
+    let upper = nav.layer_of(0.06).unwrap();
+    assert_eq!(upper.index(), 1);
+    assert!(nav.locate(upper, Vec2::new(100.0, 0.0)).unwrap().covered());
+
+    assert_eq!(nav.layer_of(0.04).unwrap().index(), 0);
+    assert_eq!(nav.layer_of(0.1).unwrap().index(), 1);
+}
+
+#[test]
+fn layers() {
+    let square = Shape::rect(Vec2::new(50.0, 50.0), Vec2::new(0.0, 0.0));
+    let other = Shape::rect(Vec2::new(50.0, 50.0), Vec2::new(100.0, 0.0));
+
+    let nav = LayeredNavigationContext::new(
+        square
+            .faces()
+            .map(|v| (0.0, v))
+            .chain(other.faces().map(|v| (1.0, v))),
+    );
+
+    let ground = nav.layer_of(0.0).unwrap();
+    let upper = nav.layer_of(1.04).unwrap();
+
+    assert_ne!(ground, upper);
+    assert_eq!(nav.layer_of(-5.0), Some(ground));
+    assert_eq!(nav.layer_of(0.5), Some(ground));
+    assert_eq!(nav.layer_of(20.0), Some(upper));
+    assert_eq!(nav.layer_of(f32::NAN), None);
+    assert!(nav.layer_ids().eq([ground, upper]));
+
+    assert!(nav.locate(ground, Vec2::ZERO).unwrap().covered());
+    assert!(!nav.locate(upper, Vec2::ZERO).unwrap().covered());
+}
+
+#[test]
+fn partial() {
+    let square = Shape::rect(Vec2::new(50.0, 50.0), Vec2::new(0.0, 0.0));
+    let left = Shape::rect(Vec2::new(10.0, 200.0), Vec2::new(-200.0, 10.0));
+
+    let nav = NavigationContext::new([square, left].iter().flatten());
+
+    let start = Vec2::new(-100.0, 0.0);
+    // Inside the square
+    let end = Vec2::new(10.0, 0.0);
+
+    assert_eq!(
+        nav.find_path(start, end, heuristics::euclidiean, SearchInfo::default())
+            .unwrap_err(),
+        PathError::EndCovered
+    );
+
+    let path = nav
+        .find_path(
+            start,
+            end,
+            heuristics::euclidiean,
+            SearchInfo {
+                allow_partial: true,
+                ..Default::default()
+            },
+        )
+        .expect("Partial path");
+
+    dbg!(&path);
+
+    assert!(path.is_partial());
+    assert_eq!(path[0].point(), start);
+
+    let last = path.last().unwrap().point();
+    assert!(!nav.locate(last).unwrap().covered());
+    assert!(last.distance(end) < start.distance(end));
+}
+
+#[test]
+fn follower_resmooth() {
+    let square = Shape::rect(Vec2::new(50.0, 50.0), Vec2::new(0.0, 0.0));
+    let left = Shape::rect(Vec2::new(10.0, 200.0), Vec2::new(-200.0, 10.0));
+    let right = Shape::rect(Vec2::new(10.0, 200.0), Vec2::new(200.0, 10.0));
+    let bottom = Shape::rect(Vec2::new(200.0, 10.0), Vec2::new(10.0, -200.0));
+    let top = Shape::rect(Vec2::new(200.0, 10.0), Vec2::new(10.0, 200.0));
+
+    let nav = NavigationContext::new([square, left, right, top, bottom].iter().flatten());
+
+    let start = Vec2::new(-100.0, 0.0);
+    let end = Vec2::new(100.0, 30.0);
+
+    let path = nav
+        .find_path(start, end, heuristics::euclidiean, SearchInfo::default())
+        .expect("Failed to find a path");
+
+    let mut follower = PathFollower::new(path);
+
+    assert_eq!(follower.advance(start, 1.0), Some(Vec2::new(-25.0, 25.0)));
+
+    // Agent was pushed upwards and now sees past the corner
+    let position = Vec2::new(-60.0, 60.0);
+    follower.resmooth_ahead(nav.portals(), position, 0.0, 3);
+
+    for waypoint in &follower.path()[1..] {
+        if let Some(portal) = waypoint.portal() {
+            let face = nav.portals().from_ref(portal).face().to_owned();
+            assert!(
+                face.closest_point(waypoint.point())
+                    .distance(waypoint.point())
+                    < 0.1
+            );
+        }
+    }
+
+    let target = follower.advance(position, 1.0).unwrap();
+    assert!(target.y >= 25.0);
+    assert_eq!(follower.path().last().unwrap().point(), end);
+
+    for waypoint in follower.path().clone().iter() {
+        follower.advance(waypoint.point(), 1.0);
+    }
+
+    assert!(follower.is_finished());
+}
+
+#[test]
+fn path_errors() {
+    let square = Shape::rect(Vec2::new(50.0, 50.0), Vec2::new(0.0, 0.0));
+    let left = Shape::rect(Vec2::new(50.0, 200.0), Vec2::new(-100.0, 0.0));
+    let right = Shape::rect(Vec2::new(50.0, 200.0), Vec2::new(100.0, 0.0));
+    // Seal off the top with a gap of 10
+    let top = Shape::rect(Vec2::new(245.0, 10.0), Vec2::new(-2.5 - 125.0, 105.0));
+    let top2 = Shape::rect(Vec2::new(245.0, 10.0), Vec2::new(7.5 + 125.0, 105.0));
+    let bottom = Shape::rect(Vec2::new(250.0, 10.0), Vec2::new(0.0, -105.0));
+
+    let nav = NavigationContext::new([square, left, right, top, top2, bottom].iter().flatten());
+
+    let info = SearchInfo {
+        agent_radius: 10.0,
+        ..Default::default()
+    };
+
+    let inside = Vec2::new(-50.0, 0.0);
+    let outside = Vec2::new(0.0, 200.0);
+
+    assert_eq!(
+        nav.find_path(Vec2::ZERO, inside, heuristics::euclidiean, info)
+            .unwrap_err(),
+        PathError::StartCovered
+    );
+    assert_eq!(
+        nav.find_path(inside, Vec2::ZERO, heuristics::euclidiean, info)
+            .unwrap_err(),
+        PathError::EndCovered
+    );
+
+    match nav.find_path(inside, outside, heuristics::euclidiean, info) {
+        Err(PathError::PortalTooNarrow { needed, found }) => {
+            assert_eq!(needed, 20.0);
+            assert!(found < needed);
+        }
+        v => panic!("Expected PortalTooNarrow, got {:?}", v),
+    }
+
+    let info = SearchInfo {
+        agent_radius: 0.0,
+        ..Default::default()
+    };
+
+    assert!(nav
+        .find_path(inside, outside, heuristics::euclidiean, info)
+        .is_ok());
+}
+
+#[test]
+fn reachability() {
+    let square = Shape::rect(Vec2::new(50.0, 50.0), Vec2::new(0.0, 0.0));
+    let left = Shape::rect(Vec2::new(50.0, 200.0), Vec2::new(-100.0, 0.0));
+    let right = Shape::rect(Vec2::new(50.0, 200.0), Vec2::new(100.0, 0.0));
+    // Seal off the top with a gap of 10
+    let top = Shape::rect(Vec2::new(245.0, 10.0), Vec2::new(-2.5 - 125.0, 105.0));
+    let top2 = Shape::rect(Vec2::new(245.0, 10.0), Vec2::new(7.5 + 125.0, 105.0));
+    let bottom = Shape::rect(Vec2::new(250.0, 10.0), Vec2::new(0.0, -105.0));
+
+    let nav = NavigationContext::new([square, left, right, top, top2, bottom].iter().flatten());
+
+    let tree = nav.tree().unwrap();
+
+    let inside = Vec2::new(-50.0, 0.0);
+    let inside2 = Vec2::new(50.0, 50.0);
+    let outside = Vec2::new(0.0, 200.0);
+
+    assert!(nav.is_reachable(inside, outside));
+    assert!(nav.is_reachable(inside, inside2));
+    assert!(!nav.is_reachable(inside, Vec2::ZERO));
+
+    let wide = Connectivity::new(tree, nav.portals(), 10.0);
+    let inside = tree.locate(inside).index();
+    let inside2 = tree.locate(inside2).index();
+    let outside = tree.locate(outside).index();
+
+    assert!(wide.is_connected(inside, inside2));
+    assert!(!wide.is_connected(inside, outside));
+    assert!(wide.count() > nav.connectivity().count());
+}
+
+#[test]
+fn optimize_time() {
+    let square = Shape::rect(Vec2::new(50.0, 50.0), Vec2::new(0.0, 0.0));
+    let left = Shape::rect(Vec2::new(10.0, 200.0), Vec2::new(-200.0, 10.0));
+    let right = Shape::rect(Vec2::new(10.0, 200.0), Vec2::new(200.0, 10.0));
+    let bottom = Shape::rect(Vec2::new(200.0, 10.0), Vec2::new(10.0, -200.0));
+    let top = Shape::rect(Vec2::new(200.0, 10.0), Vec2::new(10.0, 200.0));
+
+    let nav = NavigationContext::new([square, left, right, top, bottom].iter().flatten());
+    let tree = nav.tree().unwrap();
+
+    let start = Vec2::new(-100.0, 0.0);
+    let end = Vec2::new(100.0, 10.0);
+
+    let path = nav
+        .find_path(start, end, heuristics::euclidiean, SearchInfo::default())
+        .unwrap();
+
+    // Shortest path goes above the square
+    assert!(path.iter().all(|v| v.y >= 0.0));
+
+    // Make everything above the square a swamp
+    let mut speeds = slotmap::SecondaryMap::new();
+    for x in -20..=20 {
+        for y in 3..19 {
+            let node = tree.locate(Vec2::new(x as f32, y as f32 * 10.0));
+            speeds.insert(node.index(), 0.1);
+        }
+    }
+
+    let info = SearchInfo {
+        optimize: Optimize::Time,
+        node_speeds: Some(&speeds),
+        ..Default::default()
+    };
+
+    let path = nav
+        .find_path(start, end, heuristics::euclidiean, info)
+        .unwrap();
+
+    assert!(path.iter().any(|v| v.y < 0.0));
+}
+
+#[test]
+fn components() {
+    // Closed room
+    let room = Shape::new(&[
+        Vec2::new(-50.0, -50.0),
+        Vec2::new(-50.0, 50.0),
+        Vec2::new(50.0, 50.0),
+        Vec2::new(50.0, -50.0),
+        Vec2::new(-50.0, -50.0),
+    ]);
+    let outer = Shape::rect(Vec2::new(10.0, 10.0), Vec2::new(100.0, 0.0));
+
+    let nav = NavigationContext::new([room, outer].iter().flatten());
+
+    let inside = nav.component_of(Vec2::new(0.0, 0.0)).unwrap();
+    let outside = nav.component_of(Vec2::new(80.0, 0.0)).unwrap();
+
+    assert_ne!(inside, outside);
+    assert_eq!(nav.component_of(Vec2::new(100.0, 0.0)), None);
+    assert_eq!(nav.component_of(Vec2::new(60.0, 40.0)), Some(outside));
+
+    let tree = nav.tree().unwrap();
+    let node = tree.locate(Vec2::new(10.0, 10.0)).index();
+    assert_eq!(nav.components()[node], inside);
+    assert!(nav.connectivity().nodes_in(inside).any(|v| v == node));
+}
+
+#[test]
+fn winding() {
+    let clockwise = [
+        Vec2::new(-25.0, -25.0),
+        Vec2::new(-25.0, 25.0),
+        Vec2::new(25.0, 25.0),
+        Vec2::new(25.0, -25.0),
+    ];
+
+    assert_eq!(Shape::new(&clockwise).winding(), Some(Winding::Clockwise));
+    assert_eq!(Shape::new(&clockwise).signed_area(), -2500.0);
+
+    let mut counterclockwise = clockwise;
+    counterclockwise.reverse();
+
+    // Either winding results in a solid square
+    for vertices in [clockwise, counterclockwise] {
+        let shape = Shape::polygon(&vertices);
+        assert_eq!(shape.winding(), Some(Winding::CounterClockwise));
+        assert_eq!(shape.vertices().len(), 5);
+
+        let nav = NavigationContext::new(&shape);
+        let tree = nav.tree().unwrap();
+        assert!(tree.locate(Vec2::ZERO).covered());
+        assert!(!tree.locate(Vec2::new(50.0, 0.0)).covered());
+    }
+
+    // Overriding the winding makes the inside walkable
+    let room = Shape::polygon(&counterclockwise).with_winding(Winding::Clockwise);
+    assert_eq!(room.winding(), Some(Winding::Clockwise));
+
+    let nav = NavigationContext::new(&room);
+    let tree = nav.tree().unwrap();
+    assert!(!tree.locate(Vec2::ZERO).covered());
+    assert!(tree.locate(Vec2::new(50.0, 0.0)).covered());
+
+    let line = Shape::new(&[Vec2::ZERO, Vec2::X]);
+    assert_eq!(line.winding(), None);
+}
+
+#[test]
+fn round_shapes() {
+    let circle = Shape::circle(10.0, Vec2::new(5.0, 0.0), 12);
+    assert_eq!(circle.vertices().len(), 13);
+    assert_eq!(circle.winding(), Some(Winding::CounterClockwise));
+
+    // Edges touch the circle from outside
+    for face in &circle {
+        assert!((face.midpoint().distance(Vec2::new(5.0, 0.0)) - 10.0).abs() < 1e-3);
+    }
+
+    let capsule = Shape::capsule(Vec2::new(-20.0, 0.0), Vec2::new(20.0, 0.0), 5.0, 4);
+    assert_eq!(capsule.winding(), Some(Winding::CounterClockwise));
+    assert_eq!(capsule.vertices().len(), 11);
+
+    let nav = NavigationContext::new(&capsule);
+    let tree = nav.tree().unwrap();
+    for covered in [Vec2::ZERO, Vec2::new(24.0, 0.0), Vec2::new(-10.0, 4.9)] {
+        assert!(tree.locate(covered).covered(), "{covered}");
+    }
+
+    for free in [
+        Vec2::new(26.0, 0.0),
+        Vec2::new(0.0, 6.0),
+        Vec2::new(24.0, 4.0),
+    ] {
+        assert!(!tree.locate(free).covered(), "{free}");
+    }
+
+    // Degenerate capsules are circles
+    let dot = Shape::capsule(Vec2::ONE, Vec2::ONE, 5.0, 4);
+    assert!((dot.signed_area() - Shape::circle(5.0, Vec2::ONE, 8).signed_area()).abs() < 1e-3);
+}
+
+#[test]
+fn boolean_ops() {
+    let area = |shapes: &[Shape]| shapes.iter().map(Shape::signed_area).sum::<f32>();
+
+    let a = Shape::rect(Vec2::new(20.0, 20.0), Vec2::ZERO);
+    let b = Shape::rect(Vec2::new(20.0, 20.0), Vec2::new(10.0, 10.0));
+
+    let union = a.union(&b);
+    assert_eq!(union.len(), 1);
+    assert_eq!(union[0].vertices().len(), 9);
+    assert!((area(&union) - 700.0).abs() < 1e-3);
+
+    let intersection = a.intersection(&b);
+    assert_eq!(intersection.len(), 1);
+    assert!((area(&intersection) - 100.0).abs() < 1e-3);
+
+    let difference = a.difference(&b);
+    assert_eq!(difference.len(), 1);
+    assert!((area(&difference) - 300.0).abs() < 1e-3);
+
+    // Adjacent shapes merge into a single rectangle
+    let c = Shape::rect(Vec2::new(20.0, 20.0), Vec2::new(20.0, 0.0));
+    let union = a.union(&c);
+    assert_eq!(union.len(), 1);
+    assert_eq!(union[0].vertices().len(), 5);
+    assert!((area(&union) - 800.0).abs() < 1e-3);
+
+    // Cutting out the middle leaves a hole
+    let inner = Shape::rect(Vec2::new(10.0, 10.0), Vec2::ZERO);
+    let ring = a.difference(&inner);
+    assert_eq!(ring.len(), 2);
+    assert_eq!(
+        ring.iter()
+            .filter(|v| v.winding() == Some(Winding::Clockwise))
+            .count(),
+        1
+    );
+    assert!((area(&ring) - 300.0).abs() < 1e-3);
+
+    let nav = NavigationContext::new(ring.iter().flatten());
+    let tree = nav.tree().unwrap();
+    assert!(!tree.locate(Vec2::ZERO).covered());
+    assert!(tree.locate(Vec2::new(7.5, 0.0)).covered());
+    assert!(!tree.locate(Vec2::new(15.0, 0.0)).covered());
+
+    assert!(a
+        .intersection(&Shape::rect(Vec2::ONE, Vec2::splat(50.0)))
+        .is_empty());
+
+    let merged = Shape::union_all([&a, &b, &c]);
+    assert_eq!(merged.len(), 1);
+    assert!((area(&merged) - 1000.0).abs() < 1e-3);
+
+    // Interior faces are removed
+    let faces = merged.iter().flatten().count();
+    assert!(faces < [&a, &b, &c].into_iter().flatten().count());
+}
+
+#[test]
+fn offset_shapes() {
+    let area = |shapes: &[Shape]| shapes.iter().map(Shape::signed_area).sum::<f32>();
+    let square = Shape::rect(Vec2::new(20.0, 20.0), Vec2::ZERO);
+
+    // Straight edges move out by the distance, and corners are rounded
+    let grown = square.offset(5.0);
+    assert_eq!(grown.len(), 1);
+    let expected = 400.0 + 4.0 * 20.0 * 5.0 + core::f32::consts::PI * 25.0;
+    assert!((area(&grown) - expected).abs() < 5.0, "{}", area(&grown));
+
+    let nav = NavigationContext::new(grown.iter().flatten());
+    let tree = nav.tree().unwrap();
+    assert!(tree.locate(Vec2::new(14.0, 0.0)).covered());
+    assert!(!tree.locate(Vec2::new(16.0, 0.0)).covered());
+    assert!(!tree.locate(Vec2::new(14.0, 14.0)).covered());
+
+    let shrunk = square.offset(-5.0);
+    assert_eq!(shrunk.len(), 1);
+    assert!((area(&shrunk) - 100.0).abs() < 1e-3);
+    assert_eq!(shrunk[0].vertices().len(), 5);
+
+    assert!(square.offset(-11.0).is_empty());
+    assert!((area(&square.offset(0.0)) - 400.0).abs() < 1e-3);
+
+    // Shrinking a dumbbell through its narrow handle splits it
+    let dumbbell = Shape::polygon(&[
+        Vec2::new(-30.0, -10.0),
+        Vec2::new(-10.0, -10.0),
+        Vec2::new(-10.0, -2.0),
+        Vec2::new(10.0, -2.0),
+        Vec2::new(10.0, -10.0),
+        Vec2::new(30.0, -10.0),
+        Vec2::new(30.0, 10.0),
+        Vec2::new(10.0, 10.0),
+        Vec2::new(10.0, 2.0),
+        Vec2::new(-10.0, 2.0),
+        Vec2::new(-10.0, 10.0),
+        Vec2::new(-30.0, 10.0),
+    ]);
+
+    let split = dumbbell.offset(-3.0);
+    assert_eq!(split.len(), 2);
+    // The rounded inner corners bulge out slightly towards the handle
+    assert!(
+        (area(&split) - 2.0 * 14.0 * 14.0).abs() < 5.0,
+        "{}",
+        area(&split)
+    );
+
+    // Growing the concave dumbbell fills in the handle
+    let grown = dumbbell.offset(3.0);
+    assert_eq!(grown.len(), 1);
+    assert!(grown[0].winding() == Some(Winding::CounterClockwise));
+}
+
+#[test]
+fn transform_shapes() {
+    let prefab = Shape::rect(Vec2::new(20.0, 10.0), Vec2::ZERO);
+
+    let placed = prefab
+        .rotated(core::f32::consts::FRAC_PI_2)
+        .translated(Vec2::new(100.0, 0.0));
+    assert_eq!(
+        placed.transform(Mat3::IDENTITY).vertices(),
+        placed.vertices()
+    );
+
+    let nav = NavigationContext::new(&placed);
+    let tree = nav.tree().unwrap();
+    assert!(tree.locate(Vec2::new(100.0, 8.0)).covered());
+    assert!(!tree.locate(Vec2::new(108.0, 0.0)).covered());
+
+    // Mirroring keeps normals outward
+    let mirrored = prefab.scaled(Vec2::new(-2.0, 1.0));
+    assert_eq!(mirrored.winding(), Some(Winding::CounterClockwise));
+    assert_eq!(mirrored.signed_area(), 2.0 * prefab.signed_area());
+
+    let mirrored = mirrored.into_iter().collect::<Vec<_>>();
+    for (a, b) in prefab.into_iter().zip(mirrored.iter().rev()) {
+        assert_eq!(a.normal() * Vec2::new(-1.0, 1.0), b.normal());
+    }
+
+    let face = Face::new([Vec2::ZERO, Vec2::X]);
+    let moved = face.rotated(core::f32::consts::PI).translated(Vec2::Y);
+    assert!(moved.vertices()[1].distance(Vec2::new(-1.0, 1.0)) < 1e-5);
+    assert!(moved.normal().distance(Vec2::Y) < 1e-5);
+    assert_eq!(face.scaled(Vec2::new(1.0, -1.0)).normal(), -face.normal());
+}
+
+#[test]
+fn polyline() {
+    let wall = Shape::polyline(&[Vec2::new(-10.0, 0.0), Vec2::new(10.0, 0.0)], 2.0);
+    assert_eq!(wall.vertices().len(), 5);
+    assert_eq!(wall.winding(), Some(Winding::CounterClockwise));
+    assert_eq!(wall.signed_area(), 40.0);
+
+    let fence = Shape::polyline(
+        &[
+            Vec2::new(-20.0, 0.0),
+            Vec2::new(0.0, 0.0),
+            Vec2::new(0.0, 20.0),
+            Vec2::new(20.0, 20.0),
+        ],
+        2.0,
+    );
+    assert_eq!(fence.winding(), Some(Winding::CounterClockwise));
+    assert!((fence.signed_area() - 120.0).abs() < 1e-3);
+
+    let left = Shape::rect(Vec2::new(10.0, 200.0), Vec2::new(-200.0, 10.0));
+    let right = Shape::rect(Vec2::new(10.0, 200.0), Vec2::new(200.0, 10.0));
+    let bottom = Shape::rect(Vec2::new(200.0, 10.0), Vec2::new(10.0, -200.0));
+    let top = Shape::rect(Vec2::new(200.0, 10.0), Vec2::new(10.0, 200.0));
+
+    let nav = NavigationContext::new([fence, left, right, top, bottom].iter().flatten());
+    let tree = nav.tree().unwrap();
+    for covered in [
+        Vec2::new(-10.0, 0.5),
+        Vec2::new(0.0, 10.0),
+        Vec2::new(0.9, -0.9),
+    ] {
+        assert!(tree.locate(covered).covered(), "{covered}");
+    }
+
+    // Both sides of the fence are free
+    for free in [
+        Vec2::new(-10.0, 2.0),
+        Vec2::new(-10.0, -2.0),
+        Vec2::new(10.0, 10.0),
+    ] {
+        assert!(!tree.locate(free).covered(), "{free}");
+    }
+
+    let path = nav.find_path(
+        Vec2::new(-10.0, 5.0),
+        Vec2::new(-10.0, -5.0),
+        heuristics::euclidiean,
+        SearchInfo::default(),
+    );
+    assert!(path.is_ok());
+
+    // A sharp turn is beveled on the outside
+    let hairpin = Shape::polyline(
+        &[
+            Vec2::new(0.0, 0.0),
+            Vec2::new(20.0, 0.0),
+            Vec2::new(0.0, 10.0),
+        ],
+        2.0,
+    );
+    assert_eq!(hairpin.vertices().len(), 8);
+    let point = Shape::polyline(&[Vec2::ONE], 2.0);
+    assert!(point.vertices().is_empty());
+    assert_eq!(point.into_iter().count(), 0);
+}
+
+#[test]
+fn from_slice() {
+    let square = Shape::rect(Vec2::new(50.0, 50.0), Vec2::new(0.0, 0.0));
+    let left = Shape::rect(Vec2::new(10.0, 200.0), Vec2::new(-200.0, 10.0));
+    let faces: Vec<_> = [square, left].iter().flatten().collect();
+
+    let a = NavigationContext::new(faces.iter().copied());
+    let b = NavigationContext::from_slice(&faces);
+
+    let a = a.tree().unwrap();
+    let b = b.tree().unwrap();
+
+    assert_eq!(a.nodes().len(), b.nodes().len());
+    assert_eq!(
+        a.locate(Vec2::ZERO).covered(),
+        b.locate(Vec2::ZERO).covered()
+    );
+}
+
+#[test]
+fn flow_field() {
+    let square = Shape::rect(Vec2::new(50.0, 50.0), Vec2::new(0.0, 0.0));
+    let left = Shape::rect(Vec2::new(10.0, 200.0), Vec2::new(-200.0, 10.0));
+    let right = Shape::rect(Vec2::new(10.0, 200.0), Vec2::new(200.0, 10.0));
+    let bottom = Shape::rect(Vec2::new(200.0, 10.0), Vec2::new(10.0, -200.0));
+    let top = Shape::rect(Vec2::new(200.0, 10.0), Vec2::new(10.0, 200.0));
+
+    let nav = NavigationContext::new([square, left, right, top, bottom].iter().flatten());
+    let tree = nav.tree().unwrap();
+
+    let goal = Vec2::new(100.0, 30.0);
+    let field = nav.flow_field(goal, 0.0).unwrap();
+
+    assert_eq!(field.cost(tree, goal), Some(0.0));
+
+    for start in [
+        Vec2::new(-100.0, 0.0),
+        Vec2::new(0.0, -100.0),
+        Vec2::new(-150.0, 150.0),
+    ] {
+        let mut position = start;
+        let mut prev_cost = field.cost(tree, position).unwrap();
+
+        for _ in 0..1000 {
+            if position.distance(goal) < 1.0 {
+                break;
+            }
+
+            let dir = field.direction(tree, position).unwrap();
+            position += dir * 0.5;
+
+            assert!(!tree.locate(position).covered());
+            let cost = field.cost(tree, position).unwrap();
+            assert!(cost <= prev_cost + 0.01);
+            prev_cost = cost;
+        }
+
+        assert!(position.distance(goal) < 1.0);
+    }
+}
+
+#[test]
+fn rasterize_visibility() {
+    let square = Shape::rect(Vec2::new(50.0, 50.0), Vec2::new(0.0, 0.0));
+    let other = Shape::regular_polygon(5, 20.0, Vec2::new(60.0, -60.0));
+
+    let nav = NavigationContext::new([square, other].iter().flatten());
+    let tree = nav.tree().unwrap();
+
+    let origin = Vec2::new(-80.0, 10.0);
+    let min = Vec2::new(-100.0, -100.0);
+    let max = Vec2::new(100.0, 100.0);
+    let cell_size = 5.0;
+
+    let grid = tree.rasterize_visibility(origin, (min, max), cell_size);
+    assert_eq!(grid.len(), 40 * 40);
+
+    // Compare against per cell raycasts
+    let mismatches = grid
+        .iter()
+        .enumerate()
+        .filter(|(i, &visible)| {
+            let center =
+                min + (Vec2::new((i % 40) as f32, (i / 40) as f32) + Vec2::splat(0.5)) * cell_size;
+            let dist = center.distance(origin);
+            let expected = tree.raycast(origin, center - origin, dist).is_none();
+            visible != expected
+        })
+        .count();
+
+    assert!(mismatches < grid.len() / 100, "{} mismatches", mismatches);
+
+    // Directly behind the square
+    let cell = |p: Vec2| {
+        let p = ((p - min) / cell_size).floor();
+        grid[p.y as usize * 40 + p.x as usize]
+    };
+
+    assert!(!cell(Vec2::new(60.0, 10.0)));
+    assert!(cell(Vec2::new(-60.0, 10.0)));
+    assert!(cell(Vec2::new(60.0, 80.0)));
+
+    // Degenerate cell sizes yield an empty grid
+    for cell_size in [0.0, -5.0, f32::NAN, f32::INFINITY] {
+        assert!(tree
+            .rasterize_visibility(origin, (min, max), cell_size)
+            .is_empty());
+    }
+}
+
+#[test]
+fn multi_goal() {
+    let square = Shape::rect(Vec2::new(50.0, 50.0), Vec2::new(0.0, 0.0));
+    let left = Shape::rect(Vec2::new(10.0, 200.0), Vec2::new(-200.0, 10.0));
+    let right = Shape::rect(Vec2::new(10.0, 200.0), Vec2::new(200.0, 10.0));
+    let bottom = Shape::rect(Vec2::new(200.0, 10.0), Vec2::new(10.0, -200.0));
+    let top = Shape::rect(Vec2::new(200.0, 10.0), Vec2::new(10.0, 200.0));
+
+    let nav = NavigationContext::new([square, left, right, top, bottom].iter().flatten());
+
+    let start = Vec2::new(-100.0, 0.0);
+    let goals = [
+        Vec2::new(150.0, 150.0),
+        // Covered
+        Vec2::new(0.0, 0.0),
+        Vec2::new(-100.0, 100.0),
+    ];
+
+    let (goal, path) = nav
+        .find_path_to_any(start, &goals, heuristics::euclidiean, SearchInfo::default())
+        .unwrap();
+
+    assert_eq!(goal, 2);
+    assert_eq!(path.points().last().map(|val| val.point()), Some(goals[2]));
+
+    assert_eq!(
+        nav.find_path_to_any(
+            start,
+            &goals[1..2],
+            heuristics::euclidiean,
+            SearchInfo::default()
+        )
+        .err(),
+        Some(PathError::EndCovered)
+    );
+}
+
+#[test]
+fn navigator() {
+    let square = Shape::rect(Vec2::new(50.0, 50.0), Vec2::new(0.0, 0.0));
+    let left = Shape::rect(Vec2::new(10.0, 200.0), Vec2::new(-200.0, 10.0));
+    let right = Shape::rect(Vec2::new(10.0, 200.0), Vec2::new(200.0, 10.0));
+    let bottom = Shape::rect(Vec2::new(200.0, 10.0), Vec2::new(10.0, -200.0));
+    let top = Shape::rect(Vec2::new(200.0, 10.0), Vec2::new(10.0, 200.0));
+
+    let nav = NavigationContext::new([square, left, right, top, bottom].iter().flatten());
+    let mut navigator = Navigator::new(nav, NavigatorSettings::default());
+
+    let mut position = Vec2::new(-100.0, 0.0);
+    let target = Vec2::new(100.0, 30.0);
+    let agent = navigator.add_agent(position, 5.0);
+
+    assert_eq!(navigator.status(agent), Some(AgentStatus::Idle));
+    assert_eq!(navigator.tick(0.1).count(), 0);
+
+    navigator.move_to(agent, target);
+
+    let speed = 50.0;
+    for _ in 0..200 {
+        let steering = navigator.tick(0.1).find(|(id, _)| *id == agent);
+        let steering = match steering {
+            Some((_, steering)) => steering,
+            None => break,
+        };
+
+        let dir = steering - position;
+        position += dir.normalize_or_zero() * dir.length().min(speed * 0.1);
+        assert!(!navigator.nav().tree().unwrap().locate(position).covered());
+
+        navigator.set_position(agent, position);
+    }
+
+    assert_eq!(navigator.status(agent), Some(AgentStatus::Arrived));
+    assert!(position.distance(target) <= navigator.settings().waypoint_radius);
+
+    // Unreachable targets fail
+    navigator.move_to(agent, Vec2::ZERO);
+    navigator.tick(0.1).for_each(drop);
+    assert_eq!(
+        navigator.status(agent),
+        Some(AgentStatus::Failed(PathError::EndCovered))
+    );
+
+    navigator.stop(agent);
+    assert_eq!(navigator.status(agent), Some(AgentStatus::Idle));
+}
+
+#[test]
+fn heuristic_weight() {
+    let square = Shape::rect(Vec2::new(50.0, 50.0), Vec2::new(0.0, 0.0));
+    let left = Shape::rect(Vec2::new(10.0, 200.0), Vec2::new(-200.0, 10.0));
+    let right = Shape::rect(Vec2::new(10.0, 200.0), Vec2::new(200.0, 10.0));
+    let bottom = Shape::rect(Vec2::new(200.0, 10.0), Vec2::new(10.0, -200.0));
+    let top = Shape::rect(Vec2::new(200.0, 10.0), Vec2::new(10.0, 200.0));
+
+    let nav = NavigationContext::new([square, left, right, top, bottom].iter().flatten());
+
+    let start = Vec2::new(-100.0, 0.0);
+    let end = Vec2::new(100.0, 30.0);
+
+    let length = |path: &Path| {
+        path.windows(2)
+            .map(|w| w[0].point().distance(w[1].point()))
+            .sum::<f32>()
+    };
+
+    let optimal = nav
+        .find_path(start, end, heuristics::euclidiean, SearchInfo::default())
+        .unwrap();
+
+    let weight = 3.0;
+    let info = SearchInfo {
+        heuristic_weight: weight,
+        ..Default::default()
+    };
+
+    let weighted = nav
+        .find_path(start, end, heuristics::euclidiean, info)
+        .unwrap();
+
+    assert_eq!(weighted.last().map(|v| v.point()), Some(end));
+    assert!(length(&weighted) <= length(&optimal) * weight + TOLERANCE);
+}
+
+#[test]
+fn query_stats() {
+    let square = Shape::rect(Vec2::new(50.0, 50.0), Vec2::new(0.0, 0.0));
+    let left = Shape::rect(Vec2::new(10.0, 200.0), Vec2::new(-200.0, 10.0));
+    let right = Shape::rect(Vec2::new(10.0, 200.0), Vec2::new(200.0, 10.0));
+    let bottom = Shape::rect(Vec2::new(200.0, 10.0), Vec2::new(10.0, -200.0));
+    let top = Shape::rect(Vec2::new(200.0, 10.0), Vec2::new(10.0, 200.0));
+
+    let nav = NavigationContext::new([square, left, right, top, bottom].iter().flatten());
+    let tree = nav.tree().unwrap();
+
+    let start = Vec2::new(-100.0, 0.0);
+    let end = Vec2::new(100.0, 30.0);
+
+    let mut stats = QueryStats::new();
+    let path = nav
+        .find_path_observed(
+            start,
+            end,
+            heuristics::euclidiean,
+            SearchInfo::default(),
+            &mut stats,
+        )
+        .unwrap();
+
+    assert!(stats.expansions() > 0);
+    assert!(stats.pushes() >= stats.expansions());
+
+    let heatmap = stats.expansion_heatmap();
+    assert_eq!(
+        heatmap.values().sum::<usize>(),
+        stats.pushes() + stats.expansions()
+    );
+
+    // The endpoints of the path were both expanded
+    assert!(heatmap.contains_key(tree.locate(start).index()));
+    assert!(heatmap.contains_key(tree.locate(end).index()));
+    assert_eq!(path.last().map(|v| v.point()), Some(end));
+
+    stats.clear();
+    assert_eq!(stats, QueryStats::default());
+}
+
+#[test]
+fn theta_star() {
+    let square = Shape::rect(Vec2::new(50.0, 50.0), Vec2::new(0.0, 0.0));
+    let left = Shape::rect(Vec2::new(10.0, 200.0), Vec2::new(-200.0, 10.0));
+    let right = Shape::rect(Vec2::new(10.0, 200.0), Vec2::new(200.0, 10.0));
+    let bottom = Shape::rect(Vec2::new(200.0, 10.0), Vec2::new(10.0, -200.0));
+    let top = Shape::rect(Vec2::new(200.0, 10.0), Vec2::new(10.0, 200.0));
+
+    let nav = NavigationContext::new([square, left, right, top, bottom].iter().flatten());
+    let tree = nav.tree().unwrap();
+
+    let length = |path: &Path| {
+        path.windows(2)
+            .map(|w| w[0].point().distance(w[1].point()))
+            .sum::<f32>()
+    };
+
+    for (start, end) in [
+        (Vec2::new(-100.0, 0.0), Vec2::new(100.0, 30.0)),
+        (Vec2::new(-150.0, -150.0), Vec2::new(150.0, 150.0)),
+        (Vec2::new(0.0, -100.0), Vec2::new(0.0, 100.0)),
+    ] {
+        let info = SearchInfo {
+            agent_radius: 2.0,
+            ..Default::default()
+        };
+
+        let astar = nav
+            .find_path(start, end, heuristics::euclidiean, info)
+            .unwrap();
+
+        let theta = nav
+            .find_path(
+                start,
+                end,
+                heuristics::euclidiean,
+                SearchInfo {
+                    kind: SearchKind::ThetaStar,
+                    ..info
+                },
+            )
+            .unwrap();
+
+        assert_eq!(theta.last().map(|v| v.point()), Some(end));
+        assert!(theta.iter().all(|v| !tree.locate(v.point()).covered()));
+        assert!(length(&theta) <= length(&astar) + TOLERANCE);
+    }
+}
+
+#[test]
+fn dstar_lite() {
+    let square = Shape::rect(Vec2::new(50.0, 50.0), Vec2::new(0.0, 0.0));
+    let left = Shape::rect(Vec2::new(10.0, 200.0), Vec2::new(-200.0, 10.0));
+    let right = Shape::rect(Vec2::new(10.0, 200.0), Vec2::new(200.0, 10.0));
+    let bottom = Shape::rect(Vec2::new(200.0, 10.0), Vec2::new(10.0, -200.0));
+    let top = Shape::rect(Vec2::new(200.0, 10.0), Vec2::new(10.0, 200.0));
+
+    let nav = NavigationContext::new([square, left, right, top, bottom].iter().flatten());
+    let tree = nav.tree().unwrap();
+    let portals = nav.portals();
+
+    let start = Vec2::new(-100.0, 0.0);
+    let end = Vec2::new(100.0, 10.0);
+
+    let mut planner = DStarLite::new(tree, start, end, 2.0).unwrap();
+
+    let path = planner.path(portals).unwrap();
+    assert_eq!(path.first().map(|v| v.point()), Some(start));
+    assert_eq!(path.last().map(|v| v.point()), Some(end));
+    assert!(path.iter().all(|v| v.y >= 0.0));
+
+    // Block everything above the square
+    let above = (-20..=20)
+        .flat_map(|x| (3..19).map(move |y| Vec2::new(x as f32, y as f32 * 10.0)))
+        .map(|p| tree.locate(p).index())
+        .collect::<std::collections::BTreeSet<_>>();
+
+    for &node in &above {
+        planner.set_blocked(portals, node, true);
+    }
+
+    let path = planner.path(portals).unwrap();
+    assert_eq!(path.last().map(|v| v.point()), Some(end));
+    assert!(path.iter().any(|v| v.y < 0.0));
+
+    // Move the agent along the path and reopen
+    planner.set_start(tree, path[1].point()).unwrap();
+    for &node in &above {
+        planner.set_blocked(portals, node, false);
+    }
+
+    let path = planner.path(portals).unwrap();
+    assert_eq!(path.last().map(|v| v.point()), Some(end));
+    assert!(path.iter().all(|v| !tree.locate(v.point()).covered()));
+
+    assert_eq!(
+        DStarLite::new(tree, start, Vec2::ZERO, 2.0).err(),
+        Some(PathError::EndCovered)
+    );
+}
+
+#[test]
+fn dstar_lite_portals() {
+    let square = Shape::rect(Vec2::new(50.0, 50.0), Vec2::new(0.0, 0.0));
+    let left = Shape::rect(Vec2::new(10.0, 200.0), Vec2::new(-200.0, 10.0));
+    let right = Shape::rect(Vec2::new(10.0, 200.0), Vec2::new(200.0, 10.0));
+    let bottom = Shape::rect(Vec2::new(200.0, 10.0), Vec2::new(10.0, -200.0));
+    let top = Shape::rect(Vec2::new(200.0, 10.0), Vec2::new(10.0, 200.0));
+
+    let nav = NavigationContext::new([square, left, right, top, bottom].iter().flatten());
+    let tree = nav.tree().unwrap();
+    let mut portals = nav.portals().clone();
+
+    let start = Vec2::new(-100.0, 0.0);
+    let end = Vec2::new(100.0, 10.0);
+
+    let length = |path: &Path| {
+        path.windows(2)
+            .map(|w| w[0].point().distance(w[1].point()))
+            .sum::<f32>()
+    };
+
+    let mut planner = DStarLite::new(tree, start, end, 2.0).unwrap();
+    let path = planner.path(&portals).unwrap();
+    let original = length(&path);
+
+    // Close a door on every portal along the path
+    let doors = path.iter().filter_map(|v| v.portal()).collect::<Vec<_>>();
+    assert!(!doors.is_empty());
+    for &portal in &doors {
+        portals.set_enabled(portal, false);
+        planner.portal_changed(&portals, portal);
+    }
+
+    let path = planner.path(&portals).unwrap();
+    assert_eq!(path.last().map(|v| v.point()), Some(end));
+    assert!(path
+        .iter()
+        .filter_map(|v| v.portal())
+        .all(|portal| portals.is_enabled(portal)));
+
+    let fresh = DStarLite::new(tree, start, end, 2.0)
+        .unwrap()
+        .path(&portals)
+        .unwrap();
+    assert!((length(&path) - length(&fresh)).abs() < 0.01);
+
+    // And open them again
+    for &portal in &doors {
+        portals.set_enabled(portal, true);
+        planner.portal_changed(&portals, portal);
+    }
+
+    let path = planner.path(&portals).unwrap();
+    assert!((length(&path) - original).abs() < 0.01);
+}
+
+#[test]
+fn snap_waypoints() {
+    let square = Shape::rect(Vec2::new(50.0, 50.0), Vec2::new(0.0, 0.0));
+    let left = Shape::rect(Vec2::new(10.0, 200.0), Vec2::new(-200.0, 10.0));
+    let right = Shape::rect(Vec2::new(10.0, 200.0), Vec2::new(200.0, 10.0));
+    let bottom = Shape::rect(Vec2::new(200.0, 10.0), Vec2::new(10.0, -200.0));
+    let top = Shape::rect(Vec2::new(200.0, 10.0), Vec2::new(10.0, 200.0));
+
+    let nav = NavigationContext::new([square, left, right, top, bottom].iter().flatten());
+    let portals = nav.portals();
+
+    let info = SearchInfo {
+        agent_radius: 5.0,
+        snap_waypoints: true,
+        ..Default::default()
+    };
+
+    let path = nav
+        .find_path(
+            Vec2::new(-100.0, 0.0),
+            Vec2::new(100.0, 30.0),
+            heuristics::euclidiean,
+            info,
+        )
+        .unwrap();
+
+    for waypoint in &path {
+        let portal = match waypoint.portal() {
+            Some(portal) => portals.from_ref(portal),
+            None => continue,
+        };
+
+        let face = portal.apply_margin(info.agent_radius);
+        let dist = (waypoint.point() - face.vertices()[0])
+            .dot(portal.normal())
+            .abs();
+
+        assert!(!(1e-3..=TOLERANCE).contains(&dist));
+        if dist < 1e-3 {
+            assert!(
+                face.closest_point(waypoint.point())
+                    .distance(waypoint.point())
+                    < 1e-3
+            );
+        }
+    }
+}
+
+#[test]
+fn ara_star() {
+    let square = Shape::rect(Vec2::new(50.0, 50.0), Vec2::new(0.0, 0.0));
+    let left = Shape::rect(Vec2::new(10.0, 200.0), Vec2::new(-200.0, 10.0));
+    let right = Shape::rect(Vec2::new(10.0, 200.0), Vec2::new(200.0, 10.0));
+    let bottom = Shape::rect(Vec2::new(200.0, 10.0), Vec2::new(10.0, -200.0));
+    let top = Shape::rect(Vec2::new(200.0, 10.0), Vec2::new(10.0, 200.0));
+
+    let nav = NavigationContext::new([square, left, right, top, bottom].iter().flatten());
+    let tree = nav.tree().unwrap();
+    let portals = nav.portals();
+
+    let start = Vec2::new(-100.0, 0.0);
+    let end = Vec2::new(100.0, 30.0);
+
+    let info = SearchInfo {
+        agent_radius: 2.0,
+        heuristic_weight: 3.0,
+        ..Default::default()
+    };
+
+    let mut planner = AraStar::new(tree, start, end, heuristics::euclidiean, info, 1.0).unwrap();
+
+    // No time to find anything
+    assert!(matches!(planner.improve(portals, Duration::ZERO), Ok(None)));
+    assert!(!planner.is_optimal());
+
+    let path = planner
+        .improve(portals, Duration::from_secs(10))
+        .unwrap()
+        .unwrap();
+
+    assert_eq!(path.first().map(|v| v.point()), Some(start));
+    assert_eq!(path.last().map(|v| v.point()), Some(end));
+    assert!(path.iter().all(|v| !tree.locate(v.point()).covered()));
+
+    assert!(planner.is_optimal());
+    assert_eq!(planner.weight(), 1.0);
+
+    assert_eq!(
+        AraStar::new(tree, start, Vec2::ZERO, heuristics::euclidiean, info, 1.0).err(),
+        Some(PathError::EndCovered)
+    );
+}
+
+#[test]
+fn transformed() {
+    let square = Shape::rect(Vec2::new(50.0, 50.0), Vec2::new(0.0, 0.0));
+    let left = Shape::rect(Vec2::new(10.0, 200.0), Vec2::new(-200.0, 10.0));
+    let right = Shape::rect(Vec2::new(10.0, 200.0), Vec2::new(200.0, 10.0));
+    let bottom = Shape::rect(Vec2::new(200.0, 10.0), Vec2::new(10.0, -200.0));
+    let top = Shape::rect(Vec2::new(200.0, 10.0), Vec2::new(10.0, 200.0));
+
+    let faces = [square, left, right, top, bottom]
+        .iter()
+        .flatten()
+        .collect::<Vec<_>>();
+
+    let nav = NavigationContext::new(faces.iter().copied());
+
+    let length = |path: &Path| {
+        path.windows(2)
+            .map(|w| w[0].point().distance(w[1].point()))
+            .sum::<f32>()
+    };
+
+    let start = Vec2::new(-100.0, 0.0);
+    let end = Vec2::new(100.0, 30.0);
+    let info = SearchInfo {
+        agent_radius: 2.0,
+        ..Default::default()
+    };
+
+    let expected = length(
+        &nav.find_path(start, end, heuristics::euclidiean, info)
+            .unwrap(),
+    );
+
+    let rotate = Mat3::from_scale_angle_translation(Vec2::ONE, 0.7, Vec2::new(300.0, -20.0));
+    let mirror = Mat3::from_scale(Vec2::new(-1.0, 1.0));
+
+    for transform in [rotate, mirror, rotate * mirror] {
+        let moved = nav.transformed(transform);
+        let tree = moved.tree().unwrap();
+
+        let start = transform.transform_point2(start);
+        let end = transform.transform_point2(end);
+
+        assert!(moved
+            .locate(transform.transform_point2(Vec2::ZERO))
+            .unwrap()
+            .covered());
+        assert!(!moved.locate(start).unwrap().covered());
+
+        let path = moved
+            .find_path(start, end, heuristics::euclidiean, info)
+            .unwrap();
+
+        assert_eq!(path.last().map(|v| v.point()), Some(end));
+        assert!(path.iter().all(|v| !tree.locate(v.point()).covered()));
+        assert!((length(&path) - expected).abs() < 1.0);
+    }
+}
+
+#[test]
+fn bidirectional() {
+    let square = Shape::rect(Vec2::new(50.0, 50.0), Vec2::new(0.0, 0.0));
+    let left = Shape::rect(Vec2::new(10.0, 200.0), Vec2::new(-200.0, 10.0));
+    let right = Shape::rect(Vec2::new(10.0, 200.0), Vec2::new(200.0, 10.0));
+    let bottom = Shape::rect(Vec2::new(200.0, 10.0), Vec2::new(10.0, -200.0));
+    let top = Shape::rect(Vec2::new(200.0, 10.0), Vec2::new(10.0, 200.0));
+
+    let nav = NavigationContext::new([square, left, right, top, bottom].iter().flatten());
+    let tree = nav.tree().unwrap();
+
+    let length = |path: &Path| {
+        path.windows(2)
+            .map(|w| w[0].point().distance(w[1].point()))
+            .sum::<f32>()
+    };
+
+    let info = SearchInfo {
+        agent_radius: 2.0,
+        ..Default::default()
+    };
+
+    let bidirectional = SearchInfo {
+        kind: SearchKind::Bidirectional,
+        ..info
+    };
+
+    for (start, end) in [
+        (Vec2::new(-100.0, 0.0), Vec2::new(100.0, 30.0)),
+        (Vec2::new(-150.0, -150.0), Vec2::new(150.0, 150.0)),
+        (Vec2::new(0.0, -100.0), Vec2::new(0.0, 100.0)),
+        (Vec2::new(-100.0, 0.0), Vec2::new(-90.0, 0.0)),
+    ] {
+        let expected = nav
+            .find_path(start, end, heuristics::euclidiean, info)
+            .unwrap();
+
+        let path = nav
+            .find_path(start, end, heuristics::euclidiean, bidirectional)
+            .unwrap();
+
+        assert_eq!(path.first().map(|v| v.point()), Some(start));
+        assert_eq!(path.last().map(|v| v.point()), Some(end));
+        assert!(path.iter().all(|v| !tree.locate(v.point()).covered()));
+        assert!(length(&path) <= length(&expected) * 1.1);
+    }
+
+    // Falls back to a unidirectional search for detailed errors
+    let square = Shape::rect(Vec2::new(50.0, 50.0), Vec2::new(0.0, 0.0));
+    let left = Shape::rect(Vec2::new(50.0, 200.0), Vec2::new(-100.0, 0.0));
+    let right = Shape::rect(Vec2::new(50.0, 200.0), Vec2::new(100.0, 0.0));
+    // Seal off the top with a gap of 10
+    let top = Shape::rect(Vec2::new(245.0, 10.0), Vec2::new(-2.5 - 125.0, 105.0));
+    let top2 = Shape::rect(Vec2::new(245.0, 10.0), Vec2::new(7.5 + 125.0, 105.0));
+    let bottom = Shape::rect(Vec2::new(250.0, 10.0), Vec2::new(0.0, -105.0));
+
+    let nav = NavigationContext::new([square, left, right, top, bottom, top2].iter().flatten());
+
+    let start = Vec2::new(-50.0, 0.0);
+    let end = Vec2::new(0.0, 200.0);
+    let info = SearchInfo {
+        agent_radius: 10.0,
+        ..Default::default()
+    };
+
+    let expected = nav.find_path(start, end, heuristics::euclidiean, info);
+    assert!(matches!(expected, Err(PathError::PortalTooNarrow { .. })));
+
+    assert_eq!(
+        nav.find_path(
+            start,
+            end,
+            heuristics::euclidiean,
+            SearchInfo {
+                kind: SearchKind::Bidirectional,
+                ..info
+            }
+        )
+        .err(),
+        expected.err()
+    );
+}
+
+#[test]
+fn faces_facing() {
+    let square = Shape::rect(Vec2::new(50.0, 50.0), Vec2::new(0.0, 0.0));
+    let far = Shape::rect(Vec2::new(20.0, 20.0), Vec2::new(100.0, 0.0));
+
+    let tree = BSPTree::new([square, far].iter().flatten().collect()).unwrap();
+
+    // Segment to the left of the square
+    let faces = tree.faces_facing(Vec2::new(-100.0, -10.0), Vec2::new(-100.0, 10.0));
+
+    // Only the left side of the square is seen
+    assert!(!faces.is_empty());
+    assert!(faces
+        .iter()
+        .all(|face| face.normal().distance(-Vec2::X) < 0.01
+            && face.vertices.iter().all(|v| (v.x + 25.0).abs() < 0.01)));
+
+    // A long segment above sees the tops of both shapes
+    let faces = tree.faces_facing(Vec2::new(-200.0, 100.0), Vec2::new(200.0, 100.0));
+    let tops = faces
+        .iter()
+        .filter(|face| face.normal().distance(Vec2::Y) < 0.01)
+        .count();
+
+    assert!(tops >= 2);
+    assert!(faces.iter().all(|face| face.normal().y >= -0.01));
+}
+
+#[test]
+fn distance_field() {
+    let square = Shape::rect(Vec2::new(50.0, 50.0), Vec2::new(0.0, 0.0));
+    let left = Shape::rect(Vec2::new(10.0, 200.0), Vec2::new(-200.0, 10.0));
+    let right = Shape::rect(Vec2::new(10.0, 200.0), Vec2::new(200.0, 10.0));
+    let bottom = Shape::rect(Vec2::new(200.0, 10.0), Vec2::new(10.0, -200.0));
+    let top = Shape::rect(Vec2::new(200.0, 10.0), Vec2::new(10.0, 200.0));
+
+    let nav = NavigationContext::new([square, left, right, top, bottom].iter().flatten());
+    let tree = nav.tree().unwrap();
+
+    let length = |path: &Path| {
+        path.windows(2)
+            .map(|w| w[0].point().distance(w[1].point()))
+            .sum::<f32>()
+    };
+
+    let goal = Vec2::new(100.0, 30.0);
+    let field = nav.flow_field(goal, 2.0).unwrap();
+    let distances = nav.distance_field(goal, 2.0).unwrap();
+    assert_eq!(distances.get(tree.locate(goal).index()), Some(&0.0));
+    assert_eq!(distances.len(), field.cells().len());
+
+    let info = SearchInfo {
+        agent_radius: 2.0,
+        ..Default::default()
+    };
+
+    for start in [
+        Vec2::new(-100.0, 0.0),
+        Vec2::new(-150.0, -150.0),
+        Vec2::new(0.0, 100.0),
+        Vec2::new(90.0, 30.0),
+    ] {
+        let path = field.path(tree, nav.portals(), start).unwrap();
+
+        assert_eq!(path.first().map(|v| v.point()), Some(start));
+        assert_eq!(path.last().map(|v| v.point()), Some(goal));
+        assert!(path.iter().all(|v| !tree.locate(v.point()).covered()));
+
+        let expected = nav
+            .find_path(start, goal, heuristics::euclidiean, info)
+            .unwrap();
+        assert!(length(&path) <= length(&expected) * 1.2);
+
+        let node = tree.locate(start).index();
+        assert_eq!(distances.get(node).copied(), field.distance(node));
+        assert!(distances[node] <= length(&expected) + 0.01);
+    }
+
+    assert_eq!(
+        field.path(tree, nav.portals(), Vec2::ZERO).err(),
+        Some(PathError::StartCovered)
+    );
+}
+
+#[test]
+fn doors() {
+    let square = Shape::rect(Vec2::new(50.0, 50.0), Vec2::new(0.0, 0.0));
+    let left = Shape::rect(Vec2::new(10.0, 200.0), Vec2::new(-200.0, 10.0));
+    let right = Shape::rect(Vec2::new(10.0, 200.0), Vec2::new(200.0, 10.0));
+    let bottom = Shape::rect(Vec2::new(200.0, 10.0), Vec2::new(10.0, -200.0));
+    let top = Shape::rect(Vec2::new(200.0, 10.0), Vec2::new(10.0, 200.0));
+
+    let nav = NavigationContext::new([square, left, right, top, bottom].iter().flatten());
+
+    let mut queue = PathQueue::new();
+    let mut doors = Doors::new();
+
+    let paths = [
+        (Vec2::new(-100.0, 0.0), Vec2::new(100.0, 30.0)),
+        (Vec2::new(-100.0, 0.0), Vec2::new(-100.0, -100.0)),
+        (Vec2::new(100.0, 0.0), Vec2::new(-100.0, 30.0)),
+    ]
+    .iter()
+    .map(|&(start, end)| {
+        let path = nav
+            .find_path(start, end, heuristics::euclidiean, SearchInfo::default())
+            .unwrap();
+        queue.insert(path)
+    })
+    .collect::<Vec<_>>();
+
+    // Place a door on every portal crossed by the first path
+    let door = doors.insert(
+        queue
+            .get(paths[0])
+            .unwrap()
+            .iter()
+            .filter_map(|v| v.portal()),
+    );
+
+    assert_eq!(doors.is_open(door), Some(true));
+    assert!(queue.tick(&mut doors, 10).is_empty());
+
+    assert_eq!(doors.apply_batch([(door, false), (door, false)]), 1);
+    assert_eq!(doors.is_open(door), Some(false));
+
+    // Revalidate one path per frame
+    let mut blocked = Vec::new();
+    for _ in 0..paths.len() {
+        let found = queue.tick(&mut doors, 1);
+        assert!(found.len() <= 1);
+        blocked.extend(found);
+    }
+
+    assert_eq!(queue.pending(), 0);
+    assert!(blocked.contains(&paths[0]));
+    assert!(!blocked.contains(&paths[1]));
+
+    assert!(doors.remove(door));
+    assert!(!doors.blocks_path(queue.get(paths[0]).unwrap()));
+}
+
+#[test]
+fn closed_door_connectivity() {
+    let left = Shape::rect(Vec2::new(10.0, 210.0), Vec2::new(-100.0, 0.0));
+    let right = Shape::rect(Vec2::new(10.0, 210.0), Vec2::new(100.0, 0.0));
+    let top = Shape::rect(Vec2::new(210.0, 10.0), Vec2::new(0.0, 100.0));
+    let bottom = Shape::rect(Vec2::new(210.0, 10.0), Vec2::new(0.0, -100.0));
+    // Dividing wall with a single gap between -20 and 20
+    let upper = Shape::rect(Vec2::new(10.0, 80.0), Vec2::new(0.0, 60.0));
+    let lower = Shape::rect(Vec2::new(10.0, 80.0), Vec2::new(0.0, -60.0));
+
+    let mut nav = NavigationContext::new([left, right, top, bottom, upper, lower].iter().flatten());
+
+    let (a, b) = (Vec2::new(-50.0, 0.0), Vec2::new(50.0, 0.0));
+    assert!(nav.is_reachable(a, b));
+
+    // Close every portal in the gap
+    let door = nav
+        .portals()
+        .iter()
+        .flatten()
+        .filter(|portal| {
+            let mid = portal.face().midpoint();
+            mid.x.abs() < 5.5 && mid.y.abs() < 20.0
+        })
+        .map(|portal| portal.portal_ref())
+        .collect::<Vec<_>>();
+    assert!(!door.is_empty());
+
+    for &portal in &door {
+        nav.set_portal_enabled(portal, false);
+    }
+
+    assert!(!nav.is_reachable(a, b));
+    assert_ne!(nav.component_of(a), nav.component_of(b));
+    assert_eq!(
+        nav.find_path(a, b, heuristics::euclidiean, SearchInfo::default())
+            .err(),
+        Some(PathError::NoRoute)
+    );
+
+    for &portal in &door {
+        nav.set_portal_enabled(portal, true);
+    }
+
+    assert!(nav.is_reachable(a, b));
+    assert_eq!(nav.component_of(a), nav.component_of(b));
+}
+
+#[test]
+fn path_reuse() {
+    // Zigzag corridor to produce a path longer than the inline capacity
+    let walls = (0..6)
+        .map(|i| {
+            let y = if i % 2 == 0 { 25.0 } else { -25.0 };
+            Shape::rect(Vec2::new(10.0, 150.0), Vec2::new(i as f32 * 50.0, y))
+        })
+        .chain([
+            Shape::rect(Vec2::new(500.0, 10.0), Vec2::new(150.0, 105.0)),
+            Shape::rect(Vec2::new(500.0, 10.0), Vec2::new(150.0, -105.0)),
+            Shape::rect(Vec2::new(10.0, 200.0), Vec2::new(-105.0, 0.0)),
+            Shape::rect(Vec2::new(10.0, 200.0), Vec2::new(405.0, 0.0)),
+        ])
+        .collect::<Vec<_>>();
+
+    let nav = NavigationContext::new(walls.iter().flatten());
+
+    let start = Vec2::new(-50.0, 0.0);
+    let end = Vec2::new(300.0, 0.0);
+
+    let mut path = None;
+    let first = nav
+        .find_path_inc(
+            start,
+            end,
+            heuristics::euclidiean,
+            SearchInfo::default(),
+            &mut path,
+        )
+        .unwrap();
+
+    assert!(first.len() > 8);
+    let ptr = first.as_ptr();
+
+    let second = nav
+        .find_path_inc(
+            start + Vec2::Y,
+            end,
+            heuristics::euclidiean,
+            SearchInfo::default(),
+            &mut path,
+        )
+        .unwrap();
+
+    assert_eq!(second.as_ptr(), ptr);
+    assert_eq!(second[0].point(), start + Vec2::Y);
+
+    // Failure leaves the path untouched
+    assert!(nav
+        .find_path_inc(
+            start,
+            Vec2::new(0.0, 25.0),
+            heuristics::euclidiean,
+            SearchInfo::default(),
+            &mut path
+        )
+        .is_err());
+
+    assert_eq!(path.as_ref().unwrap()[0].point(), start + Vec2::Y);
+
+    // Empty scenes reuse the path as well
+    let empty = NavigationContext::new([]);
+    let path = empty
+        .find_path_inc(
+            start,
+            end,
+            heuristics::euclidiean,
+            SearchInfo::default(),
+            &mut path,
+        )
+        .unwrap();
+
+    assert_eq!(path.as_ptr(), ptr);
+    assert_eq!(path.len(), 2);
+}
+
+#[test]
+fn search_budget() {
+    let square = Shape::rect(Vec2::new(50.0, 50.0), Vec2::new(0.0, 0.0));
+    let left = Shape::rect(Vec2::new(10.0, 200.0), Vec2::new(-200.0, 10.0));
+    let right = Shape::rect(Vec2::new(10.0, 200.0), Vec2::new(200.0, 10.0));
+    let bottom = Shape::rect(Vec2::new(200.0, 10.0), Vec2::new(10.0, -200.0));
+    let top = Shape::rect(Vec2::new(200.0, 10.0), Vec2::new(10.0, 200.0));
+
+    let nav = NavigationContext::new([square, left, right, top, bottom].iter().flatten());
+
+    let start = Vec2::new(-150.0, -150.0);
+    let end = Vec2::new(150.0, 150.0);
+
+    let path = nav
+        .find_path(start, end, heuristics::euclidiean, SearchInfo::default())
+        .unwrap();
+    assert!(!path.is_truncated());
+    assert!(!path.is_partial());
+
+    let mut stats = QueryStats::new();
+    let path = nav
+        .find_path_observed(
+            start,
+            end,
+            heuristics::euclidiean,
+            SearchInfo {
+                max_expansions: Some(2),
+                ..Default::default()
+            },
+            &mut stats,
+        )
+        .unwrap();
+
+    assert_eq!(stats.expansions(), 2);
+    assert!(path.is_truncated());
+    assert!(path.is_partial());
+    assert_eq!(path[0].point(), start);
+
+    let last = path.last().unwrap().point();
+    assert!(last.distance(end) < start.distance(end));
+
+    // A goal popped once the budget is used up is still reached
+    let mut stats = QueryStats::new();
+    nav.find_path_observed(
+        start,
+        end,
+        heuristics::euclidiean,
+        SearchInfo::default(),
+        &mut stats,
+    )
+    .unwrap();
+    let needed = stats.expansions();
+
+    for (max, truncated) in [(needed - 1, false), (needed - 2, true)] {
+        let info = SearchInfo {
+            max_expansions: Some(max),
+            ..Default::default()
+        };
+        let path = nav
+            .find_path(start, end, heuristics::euclidiean, info)
+            .unwrap();
+        assert_eq!(path.is_truncated(), truncated);
+        assert_eq!(path.last().unwrap().point() == end, !truncated);
+    }
+
+    let path = nav
+        .find_path(
+            start,
+            end,
+            heuristics::euclidiean,
+            SearchInfo {
+                max_duration: Some(Duration::ZERO),
+                ..Default::default()
+            },
+        )
+        .unwrap();
+
+    assert!(path.is_truncated());
+    assert_eq!(path[0].point(), start);
+}
+
+#[test]
+fn goal_radius() {
+    let square = Shape::rect(Vec2::new(50.0, 50.0), Vec2::new(0.0, 0.0));
+    let left = Shape::rect(Vec2::new(10.0, 200.0), Vec2::new(-200.0, 10.0));
+    let right = Shape::rect(Vec2::new(10.0, 200.0), Vec2::new(200.0, 10.0));
+    let bottom = Shape::rect(Vec2::new(200.0, 10.0), Vec2::new(10.0, -200.0));
+    let top = Shape::rect(Vec2::new(200.0, 10.0), Vec2::new(10.0, 200.0));
+
+    let nav = NavigationContext::new([square, left, right, top, bottom].iter().flatten());
+
+    let start = Vec2::new(-100.0, 0.0);
+    // Inside the square
+    let end = Vec2::new(0.0, 0.0);
+
+    assert_eq!(
+        nav.find_path(start, end, heuristics::euclidiean, SearchInfo::default())
+            .err(),
+        Some(PathError::EndCovered)
+    );
+
+    let info = SearchInfo {
+        goal_radius: 40.0,
+        ..Default::default()
+    };
+
+    let path = nav
+        .find_path(start, end, heuristics::euclidiean, info)
+        .unwrap();
+
+    assert!(!path.is_partial());
+    assert_eq!(path[0].point(), start);
+    assert!(path.last().unwrap().point().distance(end) <= 40.0 + TOLERANCE);
+
+    // Reachable goals in neighbouring nodes terminate early
+    let end = Vec2::new(100.0, 30.0);
+    let path = nav
+        .find_path(
+            start,
+            end,
+            heuristics::euclidiean,
+            SearchInfo {
+                goal_radius: 500.0,
+                ..Default::default()
+            },
+        )
+        .unwrap();
+
+    assert_eq!(path.len(), 1);
+    assert_eq!(path[0].point(), start);
+}
+
+#[test]
+fn boundary_leaves() {
+    let square = Shape::rect(Vec2::new(50.0, 50.0), Vec2::new(0.0, 0.0));
+    let left = Shape::rect(Vec2::new(10.0, 200.0), Vec2::new(-200.0, 10.0));
+    let right = Shape::rect(Vec2::new(10.0, 200.0), Vec2::new(200.0, 10.0));
+    let bottom = Shape::rect(Vec2::new(200.0, 10.0), Vec2::new(10.0, -200.0));
+    let top = Shape::rect(Vec2::new(200.0, 10.0), Vec2::new(10.0, 200.0));
+
+    let nav = NavigationContext::new([square.clone(), left, right, top, bottom].iter().flatten());
+    let tree = nav.tree().unwrap();
+
+    let leaves = tree.boundary_leaves().collect::<Vec<_>>();
+    assert!(!leaves.is_empty());
+
+    for (leaf, faces) in &leaves {
+        assert!(tree[*leaf].front().is_none());
+        assert!(!faces.is_empty());
+    }
+
+    // Every face of the square borders some leaf
+    for face in square.faces() {
+        assert!(
+            leaves
+                .iter()
+                .any(|(_, faces)| faces.iter().any(|f| f.overlaps(&face))),
+            "{:?} does not border any leaf",
+            face
+        );
+    }
+
+    // The leaf left of the square touches its left face
+    let node = tree.locate(Vec2::new(-30.0, 0.0)).index();
+    let (_, faces) = leaves.iter().find(|(leaf, _)| *leaf == node).unwrap();
+    assert!(faces
+        .iter()
+        .any(|face| face.normal().dot(Vec2::new(-1.0, 0.0)) > 0.9
+            && (face.midpoint().x + 25.0).abs() < TOLERANCE));
+}
+
+#[test]
+fn region_goals() {
+    let square = Shape::rect(Vec2::new(50.0, 50.0), Vec2::new(0.0, 0.0));
+    let left = Shape::rect(Vec2::new(10.0, 200.0), Vec2::new(-200.0, 10.0));
+    let right = Shape::rect(Vec2::new(10.0, 200.0), Vec2::new(200.0, 10.0));
+    let bottom = Shape::rect(Vec2::new(200.0, 10.0), Vec2::new(10.0, -200.0));
+    let top = Shape::rect(Vec2::new(200.0, 10.0), Vec2::new(10.0, 200.0));
+
+    let nav = NavigationContext::new([square, left, right, top, bottom].iter().flatten());
+    let tree = nav.tree().unwrap();
+
+    let start = Vec2::new(-100.0, 0.0);
+    let end = Vec2::new(100.0, 30.0);
+
+    let length = |path: &Path| {
+        path.windows(2)
+            .map(|w| w[0].point().distance(w[1].point()))
+            .sum::<f32>()
+    };
+
+    // Point goals behave like regular searches
+    let expected = nav
+        .find_path(start, end, heuristics::euclidiean, SearchInfo::default())
+        .unwrap();
+    let path = nav
+        .find_path_region(
+            start,
+            &end.into(),
+            heuristics::euclidiean,
+            SearchInfo::default(),
+        )
+        .unwrap();
+    assert_eq!(path.points(), expected.points());
+
+    // Node goals end as soon as the node is entered
+    let node = tree.locate(end).index();
+    let path = nav
+        .find_path_region(
+            start,
+            &node.into(),
+            heuristics::euclidiean,
+            SearchInfo::default(),
+        )
+        .unwrap();
+    // The path ends where it enters the node
+    let last = path.last().unwrap().point();
+    assert!(nav
+        .portals()
+        .get(node)
+        .any(|portal| portal.face().closest_point(last).distance(last) < TOLERANCE));
+    assert!(length(&path) <= length(&expected) + TOLERANCE);
+
+    // Capture zone on the other side of the square
+    let zone = vec![
+        Vec2::new(60.0, -20.0),
+        Vec2::new(90.0, -20.0),
+        Vec2::new(90.0, 20.0),
+        Vec2::new(60.0, 20.0),
+    ];
+    let goal = Goal::Polygon(zone.clone());
+    let path = nav
+        .find_path_region(start, &goal, heuristics::euclidiean, SearchInfo::default())
+        .unwrap();
+
+    let last = path.last().unwrap().point();
+    assert!(last.x >= 60.0 - TOLERANCE && last.x <= 90.0 + TOLERANCE);
+    assert!(last.y.abs() <= 20.0 + TOLERANCE);
+    // Entering the zone is shorter than walking to its center
+    assert!(length(&path) < start.distance(Vec2::new(75.0, 0.0)) + 50.0);
+
+    // The start is already inside the zone
+    let path = nav
+        .find_path_region(
+            Vec2::new(75.0, 0.0),
+            &goal,
+            heuristics::euclidiean,
+            SearchInfo::default(),
+        )
+        .unwrap();
+    assert_eq!(path.last().unwrap().point(), Vec2::new(75.0, 0.0));
+
+    assert_eq!(
+        nav.find_path_region(
+            start,
+            &Goal::Nodes(Vec::new()),
+            heuristics::euclidiean,
+            SearchInfo::default()
+        )
+        .err(),
+        Some(PathError::NoRoute)
+    );
+}
+
+#[test]
+fn ramp() {
+    let ramp = Ramp::new(
+        Vec2::new(0.0, 0.0),
+        Vec2::new(0.0, 100.0),
+        20.0,
+        (0.0, 4.0),
+        4,
+    );
+
+    let faces = ramp.layered_faces();
+    assert_eq!(faces.len(), 8);
+    assert!(faces
+        .iter()
+        .all(|(height, _)| *height > 0.0 && *height < 4.0));
+
+    // Normals face into the ramp
+    for (_, face) in &faces {
+        let rel = Vec2::new(0.0, face.midpoint().y) - face.midpoint();
+        assert!(face.normal().dot(rel) > 0.0);
+    }
+
+    let nav = LayeredNavigationContext::new(faces);
+    assert_eq!(nav.layer_ids().count(), 4);
+
+    // The third section is walkable at its layer
+    let layer = nav.layer_of(2.5).unwrap();
+    assert!(!nav.locate(layer, Vec2::new(0.0, 60.0)).unwrap().covered());
+    assert!(nav.locate(layer, Vec2::new(15.0, 60.0)).unwrap().covered());
+}
+
+#[test]
+fn node_costs() {
+    let square = Shape::rect(Vec2::new(50.0, 50.0), Vec2::new(0.0, 0.0));
+    let left = Shape::rect(Vec2::new(10.0, 200.0), Vec2::new(-200.0, 10.0));
+    let right = Shape::rect(Vec2::new(10.0, 200.0), Vec2::new(200.0, 10.0));
+    let bottom = Shape::rect(Vec2::new(200.0, 10.0), Vec2::new(10.0, -200.0));
+    let top = Shape::rect(Vec2::new(200.0, 10.0), Vec2::new(10.0, 200.0));
+
+    let nav = NavigationContext::new([square, left, right, top, bottom].iter().flatten());
+    let tree = nav.tree().unwrap();
+
+    let start = Vec2::new(-100.0, 0.0);
+    let end = Vec2::new(100.0, 10.0);
+
+    // Make everything above the square mud
+    let mut costs = slotmap::SecondaryMap::new();
+    for x in -20..=20 {
+        for y in 3..19 {
+            let node = tree.locate(Vec2::new(x as f32, y as f32 * 10.0));
+            costs.insert(node.index(), 10.0);
+        }
+    }
+
+    let info = SearchInfo {
+        node_costs: Some(&costs),
+        ..Default::default()
+    };
+
+    let path = nav
+        .find_path(start, end, heuristics::euclidiean, info)
+        .unwrap();
+
+    assert!(path.iter().any(|v| v.y < 0.0));
+
+    // Cheap nodes keep the heuristic admissible
+    for cost in costs.values_mut() {
+        *cost = 0.1;
+    }
+
+    let info = SearchInfo {
+        node_costs: Some(&costs),
+        ..Default::default()
+    };
+
+    let path = nav
+        .find_path(start, end, heuristics::euclidiean, info)
+        .unwrap();
+
+    assert!(path.iter().all(|v| v.y >= 0.0));
+}
+
+#[test]
+fn corner_radius() {
+    let square = Shape::rect(Vec2::new(50.0, 50.0), Vec2::new(0.0, 0.0));
+    let left = Shape::rect(Vec2::new(10.0, 200.0), Vec2::new(-200.0, 10.0));
+    let right = Shape::rect(Vec2::new(10.0, 200.0), Vec2::new(200.0, 10.0));
+    let bottom = Shape::rect(Vec2::new(200.0, 10.0), Vec2::new(10.0, -200.0));
+    let top = Shape::rect(Vec2::new(200.0, 10.0), Vec2::new(10.0, 200.0));
+
+    let nav = NavigationContext::new([square.clone(), left, right, top, bottom].iter().flatten());
+
+    let start = Vec2::new(-100.0, 0.0);
+    let end = Vec2::new(100.0, 30.0);
+
+    // Distance from the inner waypoints to the nearest corner of the square
+    let clearance = |path: &Path| {
+        path[1..path.len() - 1]
+            .iter()
+            .flat_map(|w| {
+                square
+                    .faces()
+                    .map(move |f| f.vertices[0].distance(w.point()))
+            })
+            .fold(f32::MAX, f32::min)
+    };
+
+    let info = SearchInfo {
+        agent_radius: 2.0,
+        ..Default::default()
+    };
+
+    let path = nav
+        .find_path(start, end, heuristics::euclidiean, info)
+        .unwrap();
+    assert!(path.len() > 2);
+    assert!(clearance(&path) < 5.0);
+
+    let info = SearchInfo {
+        agent_radius: 2.0,
+        corner_radius: Some(10.0),
+        ..Default::default()
+    };
+
+    let path = nav
+        .find_path(start, end, heuristics::euclidiean, info)
+        .unwrap();
+    assert!(clearance(&path) >= 10.0 - TOLERANCE);
+}
+
+#[test]
+fn portal_costs() {
+    let square = Shape::rect(Vec2::new(50.0, 50.0), Vec2::new(0.0, 0.0));
+    let left = Shape::rect(Vec2::new(10.0, 200.0), Vec2::new(-200.0, 10.0));
+    let right = Shape::rect(Vec2::new(10.0, 200.0), Vec2::new(200.0, 10.0));
+    let bottom = Shape::rect(Vec2::new(200.0, 10.0), Vec2::new(10.0, -200.0));
+    let top = Shape::rect(Vec2::new(200.0, 10.0), Vec2::new(10.0, 200.0));
+
+    let mut nav = NavigationContext::new([square, left, right, top, bottom].iter().flatten());
+
+    let start = Vec2::new(-100.0, 0.0);
+    let end = Vec2::new(100.0, 10.0);
+
+    let path = nav
+        .find_path(start, end, heuristics::euclidiean, SearchInfo::default())
+        .unwrap();
+
+    // Shortest path goes above the square
+    assert!(path.iter().all(|v| v.y >= 0.0));
+
+    // Every portal crossed above the square is a closed door
+    let doors = path.iter().filter_map(|v| v.portal()).collect::<Vec<_>>();
+    assert!(!doors.is_empty());
+    for &door in &doors {
+        nav.set_portal_cost(door, 1000.0);
+        assert_eq!(nav.portals().cost(door), 1000.0);
+    }
+
+    let path = nav
+        .find_path(start, end, heuristics::euclidiean, SearchInfo::default())
+        .unwrap();
+    assert!(path.iter().any(|v| v.y < 0.0));
+    assert!(path
+        .iter()
+        .filter_map(|v| v.portal())
+        .all(|portal| nav.portals().cost(portal) == 0.0));
+}
+
+#[test]
+fn shared_nav() {
+    let square = Shape::rect(Vec2::new(50.0, 50.0), Vec2::new(0.0, 0.0));
+    let left = Shape::rect(Vec2::new(10.0, 200.0), Vec2::new(-200.0, 10.0));
+    let right = Shape::rect(Vec2::new(10.0, 200.0), Vec2::new(200.0, 10.0));
+    let bottom = Shape::rect(Vec2::new(200.0, 10.0), Vec2::new(10.0, -200.0));
+    let top = Shape::rect(Vec2::new(200.0, 10.0), Vec2::new(10.0, 200.0));
+
+    let nav = SharedNav::new(NavigationContext::new(
+        [square, left, right, top, bottom].iter().flatten(),
+    ));
+
+    let start = Vec2::new(-100.0, 0.0);
+    let expected = nav
+        .find_path(
+            start,
+            Vec2::new(100.0, 30.0),
+            heuristics::euclidiean,
+            SearchInfo::default(),
+        )
+        .unwrap();
+
+    let handles = (0..4)
+        .map(|i| {
+            let nav = nav.clone();
+            std::thread::spawn(move || {
+                let end = Vec2::new(100.0, 30.0 - i as f32 * 10.0);
+                nav.find_path(start, end, heuristics::euclidiean, SearchInfo::default())
+                    .unwrap()
+            })
+        })
+        .collect::<Vec<_>>();
+
+    let paths = handles
+        .into_iter()
+        .map(|handle| handle.join().unwrap())
+        .collect::<Vec<_>>();
+
+    assert_eq!(paths[0].points(), expected.points());
+
+    // Mutation does not affect other clones
+    let mut other = nav.clone();
+    assert!(other.ptr_eq(&nav));
+    other
+        .make_mut()
+        .set_portal_cost(paths[0][1].portal().unwrap(), 10.0);
+    assert!(!other.ptr_eq(&nav));
+    assert_eq!(nav.portals().cost(paths[0][1].portal().unwrap()), 0.0);
+}
+
+#[test]
+fn rrt() {
+    use rand::{rngs::StdRng, SeedableRng};
+
+    let square = Shape::rect(Vec2::new(50.0, 50.0), Vec2::new(0.0, 0.0));
+    let left = Shape::rect(Vec2::new(10.0, 200.0), Vec2::new(-200.0, 10.0));
+    let right = Shape::rect(Vec2::new(10.0, 200.0), Vec2::new(200.0, 10.0));
+    let bottom = Shape::rect(Vec2::new(200.0, 10.0), Vec2::new(10.0, -200.0));
+    let top = Shape::rect(Vec2::new(200.0, 10.0), Vec2::new(10.0, 200.0));
+
+    let nav = NavigationContext::new([square, left, right, top, bottom].iter().flatten());
+    let tree = nav.tree().unwrap();
+
+    let start = Vec2::new(-100.0, 0.0);
+    let end = Vec2::new(100.0, 30.0);
+
+    let settings = RrtSettings {
+        agent_radius: 2.0,
+        turn_radius: Some(20.0),
+        ..Default::default()
+    };
+
+    // Crosses the straight line between start and end
+    let obstacles = [MovingObstacle::new(
+        Vec2::new(-60.0, -100.0),
+        Vec2::new(0.0, 1.0),
+        10.0,
+    )];
+
+    let mut rng = StdRng::seed_from_u64(42);
+    let path = nav
+        .find_path_rrt(start, end, &settings, &obstacles, &mut rng)
+        .unwrap();
+
+    assert_eq!(path[0].point(), start);
+    assert_eq!(path.last().unwrap().point(), end);
+
+    let mut time = 0.0;
+    let mut heading = Vec2::ZERO;
+    for w in path.windows(2) {
+        let (a, b) = (w[0].point(), w[1].point());
+        let dir = b - a;
+
+        assert!(dir.length() <= settings.step.max(settings.goal_radius) + TOLERANCE);
+        assert!(tree.raycast(a, dir, dir.length()).is_none());
+        assert!(!tree.locate(b).covered());
+
+        // Turns are limited by the turning radius
+        let dir = dir.normalize();
+        if heading != Vec2::ZERO {
+            let angle = heading.perp_dot(dir).atan2(heading.dot(dir));
+            assert!(angle.abs() <= settings.step / 20.0 + 1e-3);
+        }
+        heading = dir;
+
+        time += a.distance(b) / settings.speed;
+        let obstacle = obstacles[0].position_at(time);
+        assert!(obstacle.distance(b) >= obstacles[0].radius + settings.agent_radius);
+    }
+
+    assert_eq!(
+        nav.find_path_rrt(start, Vec2::ZERO, &settings, &[], &mut rng)
+            .err(),
+        Some(PathError::EndCovered)
+    );
+}
+
+#[test]
+fn one_way_portals() {
+    let square = Shape::rect(Vec2::new(50.0, 50.0), Vec2::new(0.0, 0.0));
+    let left = Shape::rect(Vec2::new(10.0, 200.0), Vec2::new(-200.0, 10.0));
+    let right = Shape::rect(Vec2::new(10.0, 200.0), Vec2::new(200.0, 10.0));
+    let bottom = Shape::rect(Vec2::new(200.0, 10.0), Vec2::new(10.0, -200.0));
+    let top = Shape::rect(Vec2::new(200.0, 10.0), Vec2::new(10.0, 200.0));
+
+    let mut nav = NavigationContext::new([square, left, right, top, bottom].iter().flatten());
+
+    let a = Vec2::new(-100.0, 0.0);
+    let b = Vec2::new(100.0, 10.0);
+
+    let path = nav
+        .find_path(a, b, heuristics::euclidiean, SearchInfo::default())
+        .unwrap();
+
+    // Shortest path goes above the square
+    assert!(path.iter().all(|v| v.y >= 0.0));
+
+    // Only allow passing above the square from right to left
+    let crossed = path.iter().filter_map(|v| v.portal()).collect::<Vec<_>>();
+    for &portal in &crossed {
+        let face = *nav.portals().from_ref(portal).face();
+        let reverse = nav
+            .get(portal.dst())
+            .find(|v| v.dst() == portal.src() && *v.face() == face)
+            .unwrap()
+            .portal_ref();
+
+        nav.set_one_way(reverse, true);
+        assert!(nav.get(portal.src()).all(|v| v.face() != &face));
+    }
+
+    let path = nav
+        .find_path(a, b, heuristics::euclidiean, SearchInfo::default())
+        .unwrap();
+    assert!(path.iter().any(|v| v.y < 0.0));
+
+    let path = nav
+        .find_path(b, a, heuristics::euclidiean, SearchInfo::default())
+        .unwrap();
+    assert!(path.iter().all(|v| v.y >= 0.0));
+
+    // Fields are built backwards from the goal and respect the direction
+    let field = nav.flow_field(b, 0.0).unwrap();
+    let path = field.path(nav.tree().unwrap(), nav.portals(), a).unwrap();
+    assert!(path.iter().any(|v| v.y < 0.0));
+
+    let info = SearchInfo {
+        kind: SearchKind::Bidirectional,
+        ..Default::default()
+    };
+    let path = nav.find_path(a, b, heuristics::euclidiean, info).unwrap();
+    assert!(path.iter().any(|v| v.y < 0.0));
+
+    // Two way again
+    for &portal in &crossed {
+        nav.set_one_way(portal, false);
+    }
+
+    let path = nav
+        .find_path(a, b, heuristics::euclidiean, SearchInfo::default())
+        .unwrap();
+    assert!(path.iter().all(|v| v.y >= 0.0));
+}
+
+#[test]
+fn disabled_portals() {
+    let square = Shape::rect(Vec2::new(50.0, 50.0), Vec2::new(0.0, 0.0));
+    let left = Shape::rect(Vec2::new(10.0, 200.0), Vec2::new(-200.0, 10.0));
+    let right = Shape::rect(Vec2::new(10.0, 200.0), Vec2::new(200.0, 10.0));
+    let bottom = Shape::rect(Vec2::new(200.0, 10.0), Vec2::new(10.0, -200.0));
+    let top = Shape::rect(Vec2::new(200.0, 10.0), Vec2::new(10.0, 200.0));
+
+    let mut nav = NavigationContext::new([square, left, right, top, bottom].iter().flatten());
+
+    let a = Vec2::new(-100.0, 0.0);
+    let b = Vec2::new(100.0, 10.0);
+
+    let path = nav
+        .find_path(a, b, heuristics::euclidiean, SearchInfo::default())
+        .unwrap();
+
+    // Shortest path goes above the square
+    assert!(path.iter().all(|v| v.y >= 0.0));
+
+    // Close the doors above the square
+    let doors = path.iter().filter_map(|v| v.portal()).collect::<Vec<_>>();
+    for &door in &doors {
+        nav.set_portal_enabled(door, false);
+        assert!(!nav.portals().is_enabled(door));
+        assert!(nav.get(door.src()).all(|v| v.dst() != door.dst()));
+        assert!(nav.get(door.dst()).all(|v| v.dst() != door.src()));
+    }
+
+    for (start, end) in [(a, b), (b, a)] {
+        let path = nav
+            .find_path(start, end, heuristics::euclidiean, SearchInfo::default())
+            .unwrap();
+        assert!(path.iter().any(|v| v.y < 0.0));
+    }
+
+    for &door in &doors {
+        nav.set_portal_enabled(door, true);
+    }
+
+    let path = nav
+        .find_path(a, b, heuristics::euclidiean, SearchInfo::default())
+        .unwrap();
+    assert!(path.iter().all(|v| v.y >= 0.0));
+}
+
+#[test]
+fn prebuilt() {
+    let square = Shape::rect(Vec2::new(50.0, 50.0), Vec2::new(0.0, 0.0));
+    let faces = square.faces().collect::<Vec<_>>();
+
+    // The inside of the square lies behind all faces
+    let chain = |faces: &[Face]| {
+        faces
+            .iter()
+            .enumerate()
+            .map(|(i, &face)| {
+                PrebuiltNode::new(vec![face], None, Some(i + 1).filter(|&v| v < faces.len()))
+            })
+            .collect::<Vec<_>>()
+    };
+
+    let tree = BSPTree::from_prebuilt(chain(&faces)).unwrap();
+    assert!(tree.locate(Vec2::ZERO).covered());
+    assert!(!tree.locate(Vec2::new(30.0, 0.0)).covered());
+    assert!(!tree.locate(Vec2::new(0.0, -30.0)).covered());
+
+    let mut nodes = chain(&faces);
+    nodes[1].back = Some(7);
+    assert_eq!(
+        BSPTree::from_prebuilt(nodes).err(),
+        Some(PrebuiltError::InvalidChild { node: 1, child: 7 })
+    );
+
+    let mut nodes = chain(&faces);
+    nodes[3].back = Some(0);
+    assert_eq!(
+        BSPTree::from_prebuilt(nodes).err(),
+        Some(PrebuiltError::SharedChild { node: 0 })
+    );
+
+    let mut nodes = chain(&faces);
+    nodes[0].front = nodes[0].back.take();
+    assert!(matches!(
+        BSPTree::from_prebuilt(nodes).err(),
+        Some(PrebuiltError::WrongSide { ancestor: 0, .. })
+    ));
+
+    assert_eq!(
+        BSPTree::from_prebuilt(Vec::new()).err(),
+        Some(PrebuiltError::Empty)
+    );
+}
+
+#[test]
+fn off_mesh_links() {
+    // Two sealed rooms
+    let wall = Shape::rect(Vec2::new(10.0, 390.0), Vec2::new(0.0, 0.0));
+    let left = Shape::rect(Vec2::new(10.0, 390.0), Vec2::new(-200.0, 0.0));
+    let right = Shape::rect(Vec2::new(10.0, 390.0), Vec2::new(200.0, 0.0));
+    let bottom = Shape::rect(Vec2::new(410.0, 10.0), Vec2::new(0.0, -200.0));
+    let top = Shape::rect(Vec2::new(410.0, 10.0), Vec2::new(0.0, 200.0));
+
+    let mut nav = NavigationContext::new([wall, left, right, top, bottom].iter().flatten());
+
+    let a = Vec2::new(-100.0, 0.0);
+    let b = Vec2::new(100.0, 0.0);
+
+    assert_eq!(
+        nav.find_path(a, b, heuristics::euclidiean, SearchInfo::default())
+            .err(),
+        Some(PathError::NoRoute)
+    );
+    assert!(!nav.is_reachable(a, b));
+
+    // Covered points can not be linked
+    assert_eq!(nav.add_link(Vec2::ZERO, b, 1.0), None);
+
+    let entry = Vec2::new(-10.0, 20.0);
+    let exit = Vec2::new(10.0, 20.0);
+    let link = nav.add_link(entry, exit, 5.0).unwrap();
+    assert_eq!(nav.portals().link(link).unwrap().entry, entry);
+    assert!(nav.is_reachable(a, b));
+
+    for kind in [
+        SearchKind::AStar,
+        SearchKind::ThetaStar,
+        SearchKind::Bidirectional,
+    ] {
+        let info = SearchInfo {
+            kind,
+            ..Default::default()
+        };
+
+        let path = nav.find_path(a, b, heuristics::euclidiean, info).unwrap();
+        assert_eq!(path.first().unwrap().point(), a);
+        assert_eq!(path.last().unwrap().point(), b);
+
+        let jump = path.iter().position(|v| v.link().is_some()).unwrap();
+        assert_eq!(path[jump].link(), Some(link));
+        assert_eq!(path[jump].point(), entry);
+        assert_eq!(path[jump + 1].point(), exit);
+        assert_eq!(path.iter().filter(|v| v.link().is_some()).count(), 1);
+    }
+
+    // Links are one way
+    assert_eq!(
+        nav.find_path(b, a, heuristics::euclidiean, SearchInfo::default())
+            .err(),
+        Some(PathError::NoRoute)
+    );
+
+    // While connectivity is undirected, and only rules out paths
+    assert!(nav.is_reachable(b, a));
+    assert_eq!(nav.component_of(a), nav.component_of(b));
+}
+
+#[test]
+fn area_tags() {
+    const WATER: u32 = 2;
+
+    let square = Shape::rect(Vec2::new(50.0, 50.0), Vec2::new(0.0, 0.0));
+    let left = Shape::rect(Vec2::new(10.0, 200.0), Vec2::new(-200.0, 10.0));
+    let right = Shape::rect(Vec2::new(10.0, 200.0), Vec2::new(200.0, 10.0));
+    let bottom = Shape::rect(Vec2::new(200.0, 10.0), Vec2::new(10.0, -200.0));
+    let top = Shape::rect(Vec2::new(200.0, 10.0), Vec2::new(10.0, 200.0));
+
+    let mut nav = NavigationContext::new([square, left, right, top, bottom].iter().flatten());
+
+    let a = Vec2::new(-100.0, 0.0);
+    let b = Vec2::new(100.0, 10.0);
+
+    // Flood the space above the square
+    for x in (-24..=24).step_by(4) {
+        for y in (26..=194).step_by(4) {
+            nav.set_area_at(Vec2::new(x as f32, y as f32), WATER);
+        }
+    }
+
+    let start = nav.locate(a).unwrap().index();
+    let end = nav.locate(b).unwrap().index();
+    assert_eq!(nav.area(start), DEFAULT_AREA);
+    assert_eq!(nav.area(end), DEFAULT_AREA);
+
+    let infantry = SearchInfo {
+        allowed_areas: DEFAULT_AREA,
+        ..Default::default()
+    };
+
+    let hovercraft = SearchInfo {
+        allowed_areas: DEFAULT_AREA | WATER,
+        ..Default::default()
+    };
+
+    for kind in [SearchKind::AStar, SearchKind::Bidirectional] {
+        let path = nav
+            .find_path(
+                a,
+                b,
+                heuristics::euclidiean,
+                SearchInfo { kind, ..infantry },
+            )
+            .unwrap();
+        assert!(path.iter().any(|v| v.y < 0.0));
+
+        let path = nav
+            .find_path(
+                a,
+                b,
+                heuristics::euclidiean,
+                SearchInfo { kind, ..hovercraft },
+            )
+            .unwrap();
+        assert!(path.iter().all(|v| v.y >= 0.0));
+    }
+
+    // Only water is allowed
+    let fish = SearchInfo {
+        allowed_areas: WATER,
+        ..Default::default()
+    };
+    assert_eq!(
+        nav.find_path(a, b, heuristics::euclidiean, fish).err(),
+        Some(PathError::NoRoute)
+    );
+}
+
+#[test]
+fn portal_data() {
+    #[derive(Debug, PartialEq)]
+    struct Lock {
+        faction: u32,
+    }
+
+    let square = Shape::rect(Vec2::new(50.0, 50.0), Vec2::new(0.0, 0.0));
+    let left = Shape::rect(Vec2::new(10.0, 200.0), Vec2::new(-200.0, 10.0));
+    let right = Shape::rect(Vec2::new(10.0, 200.0), Vec2::new(200.0, 10.0));
+    let bottom = Shape::rect(Vec2::new(200.0, 10.0), Vec2::new(10.0, -200.0));
+    let top = Shape::rect(Vec2::new(200.0, 10.0), Vec2::new(10.0, 200.0));
+
+    let mut nav = NavigationContext::new([square, left, right, top, bottom].iter().flatten());
+
+    let a = Vec2::new(-100.0, 0.0);
+    let b = Vec2::new(100.0, 10.0);
+
+    let path = nav
+        .find_path(a, b, heuristics::euclidiean, SearchInfo::default())
+        .unwrap();
+    assert!(path.iter().all(|v| v.y >= 0.0));
+
+    // Lock the route above the square to faction 1
+    let doors = path.iter().filter_map(|v| v.portal()).collect::<Vec<_>>();
+    for &door in &doors {
+        nav.set_portal_data(door, Lock { faction: 1 });
+        assert_eq!(nav.portals().data::<Lock>(door), Some(&Lock { faction: 1 }));
+        assert_eq!(nav.portals().data::<u32>(door), None);
+    }
+
+    let find = |edge_cost: &dyn Fn(&Portals, Portal) -> Option<f32>| {
+        let info = SearchInfo {
+            edge_cost: Some(EdgeCost(edge_cost)),
+            ..Default::default()
+        };
+
+        nav.find_path(a, b, heuristics::euclidiean, info).unwrap()
+    };
+
+    let locked = |faction: u32| {
+        move |portals: &Portals, portal: Portal| match portals.data::<Lock>(portal.portal_ref()) {
+            Some(lock) if lock.faction != faction => None,
+            _ => Some(0.0),
+        }
+    };
+
+    let path = find(&locked(1));
+    assert!(path.iter().all(|v| v.y >= 0.0));
+    assert!(path
+        .iter()
+        .filter(|v| v.portal().is_some())
+        .all(|v| v.data::<Lock>(nav.portals()) == Some(&Lock { faction: 1 })));
+
+    let path = find(&locked(2));
+    assert!(path.iter().any(|v| v.y < 0.0));
+    assert!(path.iter().all(|v| v.data::<Lock>(nav.portals()).is_none()));
+
+    // A toll larger than the detour
+    let path = find(&|portals, portal| {
+        Some(
+            portals
+                .data::<Lock>(portal.portal_ref())
+                .map_or(0.0, |_| 1000.0),
+        )
+    });
+    assert!(path.iter().any(|v| v.y < 0.0));
+
+    let mut portals = nav.portals().clone();
+    for &door in &doors {
+        portals.clear_data(door);
+        assert_eq!(portals.data::<Lock>(door), None);
+    }
+}
+
+#[test]
+fn corridor_clamp() {
+    let square = Shape::rect(Vec2::new(50.0, 50.0), Vec2::new(0.0, 0.0));
+    let left = Shape::rect(Vec2::new(10.0, 200.0), Vec2::new(-200.0, 10.0));
+    let right = Shape::rect(Vec2::new(10.0, 200.0), Vec2::new(200.0, 10.0));
+    let bottom = Shape::rect(Vec2::new(200.0, 10.0), Vec2::new(10.0, -200.0));
+    let top = Shape::rect(Vec2::new(200.0, 10.0), Vec2::new(10.0, 200.0));
+
+    let nav = NavigationContext::new([square, left, right, top, bottom].iter().flatten());
+    let tree = nav.tree().unwrap();
+
+    let a = Vec2::new(-100.0, 0.0);
+    let b = Vec2::new(100.0, 10.0);
+
+    let path = nav
+        .find_path(a, b, heuristics::euclidiean, SearchInfo::default())
+        .unwrap();
+
+    let corridor = Corridor::new(tree, nav.portals(), &path);
+    assert_eq!(corridor.nodes().first(), Some(&tree.locate(a).index()));
+    assert_eq!(corridor.nodes().last(), Some(&tree.locate(b).index()));
+
+    // Points on the path are left as is
+    for w in path.windows(2) {
+        let mid = w[0].point().lerp(w[1].point(), 0.5);
+        assert!(corridor.contains(mid));
+        assert_eq!(corridor.clamp(mid), mid);
+    }
+
+    // Pushed into the square, or below it while the path goes above
+    for p in [Vec2::new(0.0, 10.0), Vec2::new(0.0, -100.0)] {
+        assert!(!corridor.contains(p));
+
+        let clamped = corridor.clamp(p);
+        assert!(corridor.contains(clamped));
+        assert!(!tree.locate(clamped).covered());
+        assert!(clamped.x.abs().max(clamped.y.abs()) >= 25.0 - TOLERANCE);
+        assert!(clamped.distance(p) < 100.0);
+    }
+}
+
+#[test]
+fn corridor_portals() {
+    let square = Shape::rect(Vec2::new(50.0, 50.0), Vec2::new(0.0, 0.0));
+    let left = Shape::rect(Vec2::new(10.0, 200.0), Vec2::new(-200.0, 10.0));
+    let right = Shape::rect(Vec2::new(10.0, 200.0), Vec2::new(200.0, 10.0));
+    let bottom = Shape::rect(Vec2::new(200.0, 10.0), Vec2::new(10.0, -200.0));
+    let top = Shape::rect(Vec2::new(200.0, 10.0), Vec2::new(10.0, 200.0));
+
+    let nav = NavigationContext::new([square, left, right, top, bottom].iter().flatten());
+    let tree = nav.tree().unwrap();
+
+    let a = Vec2::new(-100.0, 0.0);
+    let b = Vec2::new(100.0, 10.0);
+
+    let path = nav
+        .find_path(a, b, heuristics::euclidiean, SearchInfo::default())
+        .unwrap();
+
+    let corridor = path.corridor(tree, nav.portals());
+    assert!(corridor.len() > 1);
+    assert_eq!(corridor.cells().len(), corridor.len());
+    assert_eq!(corridor.portals().len(), corridor.len() - 1);
+    assert_eq!(corridor.segments().len(), corridor.len() - 1);
+
+    // Every waypoint lies in the corridor
+    for w in path.iter() {
+        assert!(corridor.contains(w.point()));
+    }
+
+    // Consecutive cells share the portal segment between them
+    let on_cell = |cell: &[Vec2], p: Vec2| {
+        cell.iter()
+            .zip(cell.iter().cycle().skip(1))
+            .any(|(&u, &v)| Face::new([u, v]).closest_point(p).distance(p) < 0.1)
+    };
+
+    for (i, (portal, segment)) in corridor
+        .portals()
+        .iter()
+        .zip(corridor.segments())
+        .enumerate()
+    {
+        let portal = portal.unwrap();
+        let segment = segment.unwrap();
+        assert_eq!(portal.src(), corridor.nodes()[i]);
+        assert_eq!(portal.dst(), corridor.nodes()[i + 1]);
+
+        let (p1, p2) = segment.into_tuple();
+        for p in [p1, p2, p1.lerp(p2, 0.5)] {
+            assert!(on_cell(&corridor.cells()[i], p));
+            assert!(on_cell(&corridor.cells()[i + 1], p));
+        }
+    }
+}
+
+#[test]
+fn blocked_nodes() {
+    let square = Shape::rect(Vec2::new(50.0, 50.0), Vec2::new(0.0, 0.0));
+    let left = Shape::rect(Vec2::new(10.0, 200.0), Vec2::new(-200.0, 10.0));
+    let right = Shape::rect(Vec2::new(10.0, 200.0), Vec2::new(200.0, 10.0));
+    let bottom = Shape::rect(Vec2::new(200.0, 10.0), Vec2::new(10.0, -200.0));
+    let top = Shape::rect(Vec2::new(200.0, 10.0), Vec2::new(10.0, 200.0));
+
+    let nav = NavigationContext::new([square, left, right, top, bottom].iter().flatten());
+
+    let a = Vec2::new(-100.0, 0.0);
+    let b = Vec2::new(100.0, 10.0);
+
+    // Set the space above the square on fire
+    let fire = (-24..=24)
+        .step_by(4)
+        .flat_map(|x| (26..=194).step_by(4).map(move |y| (x, y)))
+        .map(|(x, y)| nav.locate(Vec2::new(x as f32, y as f32)).unwrap())
+        .filter(|v| !v.covered())
+        .map(|v| v.index())
+        .collect::<HashSet<_>>();
+
+    assert!(!fire.contains(&nav.locate(a).unwrap().index()));
+    assert!(!fire.contains(&nav.locate(b).unwrap().index()));
+
+    for kind in [SearchKind::AStar, SearchKind::Bidirectional] {
+        let info = SearchInfo {
+            kind,
+            blocked_nodes: Some(&fire),
+            ..Default::default()
+        };
+
+        let path = nav.find_path(a, b, heuristics::euclidiean, info).unwrap();
+        assert!(path.iter().any(|v| v.y < 0.0));
+        assert!(path.iter().all(|v| !fire.contains(&v.node())));
+    }
+
+    // The shared navigation data is untouched
+    let path = nav
+        .find_path(a, b, heuristics::euclidiean, SearchInfo::default())
+        .unwrap();
+    assert!(path.iter().all(|v| v.y >= 0.0));
+
+    // The goal itself is blocked
+    let end = HashSet::from([nav.locate(b).unwrap().index()]);
+    let info = SearchInfo {
+        blocked_nodes: Some(&end),
+        ..Default::default()
+    };
+    assert_eq!(
+        nav.find_path(a, b, heuristics::euclidiean, info).err(),
+        Some(PathError::NoRoute)
+    );
+}
+
+#[test]
+fn turn_penalty() {
+    let wall = |x: f32, y0: f32, y1: f32| {
+        Shape::rect(Vec2::new(10.0, y1 - y0), Vec2::new(x, (y0 + y1) / 2.0))
+    };
+
+    // Two walls with a low and a high gap each. The shortest route zigzags
+    // through the low gaps.
+    let shapes = [
+        Shape::rect(Vec2::new(410.0, 10.0), Vec2::new(0.0, -200.0)),
+        Shape::rect(Vec2::new(410.0, 10.0), Vec2::new(0.0, 200.0)),
+        Shape::rect(Vec2::new(10.0, 390.0), Vec2::new(-200.0, 0.0)),
+        Shape::rect(Vec2::new(10.0, 390.0), Vec2::new(200.0, 0.0)),
+        wall(-50.0, -195.0, 25.0),
+        wall(-50.0, 35.0, 55.0),
+        wall(-50.0, 65.0, 195.0),
+        wall(50.0, -195.0, -35.0),
+        wall(50.0, -25.0, 55.0),
+        wall(50.0, 65.0, 195.0),
+    ];
+
+    let nav = NavigationContext::new(shapes.iter().flatten());
+
+    let a = Vec2::new(-150.0, 0.0);
+    let b = Vec2::new(150.0, 0.0);
+
+    let turning = |path: &Path| -> f32 {
+        path.windows(3)
+            .map(|w| {
+                (w[1].point() - w[0].point())
+                    .angle_between(w[2].point() - w[1].point())
+                    .abs()
+            })
+            .sum()
+    };
+
+    let length = |path: &Path| -> f32 {
+        path.windows(2)
+            .map(|w| w[0].point().distance(w[1].point()))
+            .sum()
+    };
+
+    let shortest = nav
+        .find_path(a, b, heuristics::euclidiean, SearchInfo::default())
+        .unwrap();
+    assert!(shortest.iter().any(|v| v.y < 0.0));
+
+    for kind in [SearchKind::AStar, SearchKind::Bidirectional] {
+        let info = SearchInfo {
+            kind,
+            turn_penalty: 20.0,
+            ..Default::default()
+        };
+
+        let straight = nav.find_path(a, b, heuristics::euclidiean, info).unwrap();
+        assert!(straight.iter().all(|v| v.y >= 0.0));
+        assert!(turning(&straight) < turning(&shortest));
+        assert!(length(&straight) > length(&shortest));
+    }
+}
+
+#[test]
+fn clearance_weight() {
+    let square = Shape::rect(Vec2::new(50.0, 50.0), Vec2::new(0.0, 0.0));
+    let left = Shape::rect(Vec2::new(10.0, 200.0), Vec2::new(-200.0, 10.0));
+    let right = Shape::rect(Vec2::new(10.0, 200.0), Vec2::new(200.0, 10.0));
+    let bottom = Shape::rect(Vec2::new(200.0, 10.0), Vec2::new(10.0, -200.0));
+    let top = Shape::rect(Vec2::new(200.0, 10.0), Vec2::new(10.0, 200.0));
+
+    let nav = NavigationContext::new([square, left, right, top, bottom].iter().flatten());
+    let tree = nav.tree().unwrap();
+
+    let a = Vec2::new(-100.0, 0.0);
+    let b = Vec2::new(100.0, 10.0);
+
+    // The closest any crossing comes to a wall
+    let min_clearance = |path: &Path| {
+        path.iter()
+            .filter(|v| v.portal().is_some())
+            .map(|v| tree.closest_face(v.point()).unwrap().1.distance(v.point()))
+            .fold(f32::MAX, f32::min)
+    };
+
+    let grazing = nav
+        .find_path(a, b, heuristics::euclidiean, SearchInfo::default())
+        .unwrap();
+    assert!(min_clearance(&grazing) < 1.0);
+
+    let mut prev = min_clearance(&grazing);
+    for clearance_weight in [0.5, 1.0] {
+        let info = SearchInfo {
+            clearance_weight,
+            ..Default::default()
+        };
+
+        let path = nav.find_path(a, b, heuristics::euclidiean, info).unwrap();
+        assert_eq!(path.first().unwrap().point(), a);
+        assert_eq!(path.last().unwrap().point(), b);
+        assert!(path.iter().all(|v| !tree.locate(v.point()).covered()));
+
+        let clearance = min_clearance(&path);
+        assert!(clearance > prev + 10.0);
+        prev = clearance;
+    }
+}
+
+#[test]
+fn baked_clearance() {
+    let wall = |x: f32, y0: f32, y1: f32| {
+        Shape::rect(Vec2::new(10.0, y1 - y0), Vec2::new(x, (y0 + y1) / 2.0))
+    };
+
+    // Two rooms connected by a gap of 20
+    let shapes = [
+        Shape::rect(Vec2::new(410.0, 10.0), Vec2::new(0.0, -200.0)),
+        Shape::rect(Vec2::new(410.0, 10.0), Vec2::new(0.0, 200.0)),
+        Shape::rect(Vec2::new(10.0, 390.0), Vec2::new(-200.0, 0.0)),
+        Shape::rect(Vec2::new(10.0, 390.0), Vec2::new(200.0, 0.0)),
+        wall(0.0, -195.0, -10.0),
+        wall(0.0, 10.0, 195.0),
+    ];
+
+    let nav = NavigationContext::new(shapes.iter().flatten());
+
+    // The portals through the gap
+    let gap = nav
+        .portals()
+        .iter()
+        .flatten()
+        .filter(|portal| {
+            let mid = portal.face().midpoint();
+            mid.x.abs() <= 5.0 + TOLERANCE && mid.y.abs() < 10.0
+        })
+        .collect::<Vec<_>>();
+
+    assert!(!gap.is_empty());
+    for portal in &gap {
+        assert!((portal.clearance() - 10.0).abs() < 1.0);
+    }
+
+    let a = Vec2::new(-100.0, 0.0);
+    let b = Vec2::new(100.0, 0.0);
+
+    for (agent_radius, passes) in [(9.0, true), (11.0, false)] {
+        let info = SearchInfo {
+            agent_radius,
+            ..Default::default()
+        };
+
+        let path = nav.find_path(a, b, heuristics::euclidiean, info);
+        assert_eq!(path.is_ok(), passes);
+
+        let connectivity = Connectivity::new(nav.tree().unwrap(), nav.portals(), agent_radius);
+        let (a, b) = (nav.locate(a).unwrap(), nav.locate(b).unwrap());
+        assert_eq!(connectivity.is_connected(a.index(), b.index()), passes);
+    }
+
+    // Plenty of standing room in the open
+    let room = nav.locate(a).unwrap().index();
+    assert!(nav.portals().leaf_clearance(room).unwrap() > 50.0);
+}
+
+#[test]
+fn clearance_matches_closest_face() {
+    let shapes = (0..6)
+        .flat_map(|x| (0..4).map(move |y| (x, y)))
+        .map(|(x, y)| {
+            let origin = Vec2::new(x as f32 * 60.0, y as f32 * 45.0 + x as f32 * 7.0);
+            Shape::rect(Vec2::new(20.0 + y as f32 * 5.0, 15.0), origin)
+        })
+        .collect::<Vec<_>>();
+
+    let nav = NavigationContext::new(shapes.iter().flatten());
+    let tree = nav.tree().unwrap();
+
+    // Points inside, between and far outside the obstacles
+    let points = (-3..12)
+        .flat_map(|x| (-3..9).map(move |y| Vec2::new(x as f32 * 37.0, y as f32 * 29.0)))
+        .chain([Vec2::new(5000.0, -3000.0)])
+        .collect::<Vec<_>>();
+
+    let probes = points
+        .iter()
+        .map(|&p| Face::new([p, p + Vec2::new(1e-3, 0.0)]))
+        .collect::<Vec<_>>();
+
+    for (p, clearance) in points.iter().zip(tree.clearances(&probes)) {
+        let (_, closest) = tree.closest_face(*p).unwrap();
+        assert!(
+            (clearance - closest.distance(*p)).abs() < 1e-2,
+            "{p}: {clearance}"
+        );
+    }
+}
+
+#[test]
+fn nav_patch() {
+    let square = Shape::rect(Vec2::new(50.0, 50.0), Vec2::new(0.0, 0.0));
+    let left = Shape::rect(Vec2::new(10.0, 200.0), Vec2::new(-200.0, 10.0));
+    let right = Shape::rect(Vec2::new(10.0, 200.0), Vec2::new(200.0, 10.0));
+    let bottom = Shape::rect(Vec2::new(200.0, 10.0), Vec2::new(10.0, -200.0));
+    let top = Shape::rect(Vec2::new(200.0, 10.0), Vec2::new(10.0, 200.0));
+
+    // The level patch adds a crate above the square
+    let obstacle = Shape::rect(Vec2::new(20.0, 20.0), Vec2::new(0.0, 60.0));
+
+    let shapes = [square, left, right, top, bottom];
+    let before = NavigationContext::new(shapes.iter().flatten());
+    let mut after = NavigationContext::new(shapes.iter().chain([&obstacle]).flatten());
+    after.set_area_at(Vec2::new(100.0, 100.0), 2).unwrap();
+
+    assert!(before.diff(&before).is_empty());
+
+    let patch = before.diff(&after);
+    assert!(!patch.is_empty());
+
+    let total = after.tree().unwrap().nodes().len() + after.portals().iter().flatten().count();
+    eprintln!("Patch: {} of {}", patch.len(), total);
+    assert!(patch.len() < total);
+
+    let mut patched = before.clone();
+    patched.apply_patch(&patch).unwrap();
+    assert!(patched.diff(&after).is_empty());
+
+    let clearance = |nav: &NavigationContext, p: Vec2| {
+        let node = nav.tree().unwrap().locate(p).index();
+        nav.portals().leaf_clearance(node)
+    };
+    for p in [Vec2::new(-100.0, 0.0), Vec2::new(100.0, 100.0)] {
+        assert!(clearance(&patched, p).is_some());
+        assert_eq!(clearance(&patched, p), clearance(&after, p));
+    }
+
+    let start = Vec2::new(-100.0, 0.0);
+    let end = Vec2::new(100.0, 10.0);
+
+    let expected = after
+        .find_path(start, end, heuristics::euclidiean, SearchInfo::default())
+        .unwrap();
+    let path = patched
+        .find_path(start, end, heuristics::euclidiean, SearchInfo::default())
+        .unwrap();
+
+    assert!(path
+        .iter()
+        .map(|v| v.point())
+        .eq(expected.iter().map(|v| v.point())));
+    assert_eq!(
+        patched.area(patched.locate(Vec2::new(100.0, 100.0)).unwrap().index()),
+        2
+    );
+
+    // The patch no longer applies
+    let mut again = patched.clone();
+    assert!(matches!(
+        again.apply_patch(&patch),
+        Err(PatchError::BaseMismatch { .. })
+    ));
+}
+
+#[test]
+fn too_narrow() {
+    let wall = |x: f32, y0: f32, y1: f32| {
+        Shape::rect(Vec2::new(10.0, y1 - y0), Vec2::new(x, (y0 + y1) / 2.0))
+    };
+
+    // Two rooms connected by a gap of 20, with a sealed closet in the right
+    // room
+    let shapes = [
+        Shape::rect(Vec2::new(410.0, 10.0), Vec2::new(0.0, -200.0)),
+        Shape::rect(Vec2::new(410.0, 10.0), Vec2::new(0.0, 200.0)),
+        Shape::rect(Vec2::new(10.0, 390.0), Vec2::new(-200.0, 0.0)),
+        Shape::rect(Vec2::new(10.0, 390.0), Vec2::new(200.0, 0.0)),
+        wall(0.0, -195.0, -10.0),
+        wall(0.0, 10.0, 195.0),
+        Shape::rect(Vec2::new(70.0, 10.0), Vec2::new(100.0, 70.0)),
+        Shape::rect(Vec2::new(70.0, 10.0), Vec2::new(100.0, 130.0)),
+        wall(70.0, 75.0, 125.0),
+        wall(130.0, 75.0, 125.0),
+    ];
+
+    let nav = NavigationContext::new(shapes.iter().flatten());
+
+    let start = Vec2::new(-100.0, 0.0);
+    let info = SearchInfo {
+        agent_radius: 11.0,
+        ..Default::default()
+    };
+
+    match nav.find_path(
+        start,
+        Vec2::new(100.0, -100.0),
+        heuristics::euclidiean,
+        info,
+    ) {
+        Err(PathError::PortalTooNarrow { needed, found }) => {
+            assert_eq!(needed, 22.0);
+            assert!((found - 20.0).abs() < 2.0, "{}", found);
+        }
+        v => panic!("Expected PortalTooNarrow, got {:?}", v),
+    }
+
+    // The closet can't be reached by any agent
+    let closet = Vec2::new(100.0, 100.0);
+    assert!(!nav.locate(closet).unwrap().covered());
+    assert_eq!(
+        nav.find_path(start, closet, heuristics::euclidiean, info)
+            .unwrap_err(),
+        PathError::NoRoute
+    );
+    assert_eq!(
+        nav.find_path_region(start, &Goal::Point(closet), heuristics::euclidiean, info)
+            .unwrap_err(),
+        PathError::NoRoute
+    );
+
+    let goal = Goal::Polygon(vec![
+        Vec2::new(80.0, -120.0),
+        Vec2::new(120.0, -120.0),
+        Vec2::new(120.0, -80.0),
+    ]);
+    assert!(matches!(
+        nav.find_path_region(start, &goal, heuristics::euclidiean, info),
+        Err(PathError::PortalTooNarrow { .. })
+    ));
+}
+
+#[test]
+fn picking() {
+    let square = Shape::rect(Vec2::new(50.0, 50.0), Vec2::new(0.0, 0.0));
+    let left = Shape::rect(Vec2::new(10.0, 200.0), Vec2::new(-200.0, 10.0));
+    let right = Shape::rect(Vec2::new(10.0, 200.0), Vec2::new(200.0, 10.0));
+    let bottom = Shape::rect(Vec2::new(200.0, 10.0), Vec2::new(10.0, -200.0));
+    let top = Shape::rect(Vec2::new(200.0, 10.0), Vec2::new(10.0, 200.0));
+
+    let nav = NavigationContext::new([square, left, right, top, bottom].iter().flatten());
+    let tree = nav.tree().unwrap();
+
+    // Open points pick their own leaf
+    let above = Vec2::new(0.0, 30.0);
+    assert_eq!(
+        tree.pick_node(above),
+        Some((nav.locate(above).unwrap().index(), 0.0))
+    );
+
+    // Covered points pick the closest open leaf
+    let (node, dist) = tree.pick_node(Vec2::new(0.0, 20.0)).unwrap();
+    assert!((dist - 5.0).abs() < TOLERANCE);
+    assert!(tree.cell(node).is_some());
+
+    let (face, dist) = tree.pick_face(above, 10.0).unwrap();
+    assert!((dist - 5.0).abs() < TOLERANCE);
+    assert!(face.vertices.iter().all(|v| (v.y - 25.0).abs() < TOLERANCE));
+    assert!(tree.pick_face(Vec2::new(0.0, 100.0), 10.0).is_none());
+
+    let portal = nav.portals().iter().flatten().next().unwrap();
+    let point = portal.face().midpoint() - portal.normal();
+
+    let (picked, dist) = nav.portals().pick_portal(point, 5.0).unwrap();
+    assert!(dist <= 1.0 + TOLERANCE);
+    assert_eq!(picked.src(), nav.locate(point).unwrap().index());
+    assert!(nav.portals().pick_portal(Vec2::ZERO, 1.0).is_none());
+}
+
+#[test]
+fn inflated() {
+    let square = Shape::rect(Vec2::new(50.0, 50.0), Vec2::new(0.0, 0.0));
+    let left = Shape::rect(Vec2::new(10.0, 200.0), Vec2::new(-200.0, 10.0));
+    let right = Shape::rect(Vec2::new(10.0, 200.0), Vec2::new(200.0, 10.0));
+    let bottom = Shape::rect(Vec2::new(200.0, 10.0), Vec2::new(10.0, -200.0));
+    let top = Shape::rect(Vec2::new(200.0, 10.0), Vec2::new(10.0, 200.0));
+
+    // Sides and rounded corners
+    let faces = inflate(&square.faces().collect::<Vec<_>>(), 10.0);
+    assert_eq!(faces.len(), 4 + 4 * ARC_SEGMENTS / 4);
+
+    // Concave corners are trimmed
+    let l = Shape::new(&[
+        Vec2::new(0.0, 0.0),
+        Vec2::new(20.0, 0.0),
+        Vec2::new(20.0, 10.0),
+        Vec2::new(10.0, 10.0),
+        Vec2::new(10.0, 20.0),
+        Vec2::new(0.0, 20.0),
+        Vec2::new(0.0, 0.0),
+    ]);
+    let faces = inflate(&l.faces().collect::<Vec<_>>(), 2.0);
+    assert!(faces
+        .iter()
+        .flatten()
+        .any(|v| v.distance(Vec2::new(12.0, 12.0)) < TOLERANCE));
+
+    let radius = 10.0;
+    let nav = NavigationContext::new_inflated(
+        [square, left, right, top, bottom].iter().flatten(),
+        radius,
+    );
+
+    // Within the radius of the square
+    assert!(nav.locate(Vec2::new(0.0, 30.0)).unwrap().covered());
+
+    let start = Vec2::new(-100.0, 0.0);
+    let end = Vec2::new(100.0, 10.0);
+
+    let path = nav
+        .find_path(start, end, heuristics::euclidiean, SearchInfo::default())
+        .unwrap();
+
+    // The whole path, not only the waypoints, keeps clear of the square
+    let dist = |p: Vec2| (p.abs() - Vec2::splat(25.0)).max(Vec2::ZERO).length();
+    for (a, b) in path.iter().zip(path.iter().skip(1)) {
+        for i in 0..=32 {
+            let p = a.point().lerp(b.point(), i as f32 / 32.0);
+            assert!(dist(p) > radius - 0.5, "{} is too close", p);
+        }
+    }
+}
+
+#[test]
+fn hash_state_pinned() {
+    let square = Shape::rect(Vec2::new(50.0, 50.0), Vec2::new(0.0, 0.0));
+    let left = Shape::rect(Vec2::new(10.0, 200.0), Vec2::new(-200.0, 10.0));
+
+    let mut nav = NavigationContext::new([square, left].iter().flatten());
+    nav.add_link(Vec2::new(-100.0, 0.0), Vec2::new(100.0, 0.0), 5.0)
+        .unwrap();
+    assert!(nav.portals().iter().flatten().count() > 0);
+
+    // Hashes are compared between peers on different platforms, and must not
+    // change between targets or releases
+    assert_eq!(nav.tree().unwrap().hash_state(), 0xb87fedaa48a4652e);
+    assert_eq!(nav.portals().hash_state(), 0xda23736c052ccb10);
+    assert_eq!(nav.hash_state(), 0x13a9fd65bdd32bdb);
+}
+
+#[test]
+fn hash_state() {
+    let square = Shape::rect(Vec2::new(50.0, 50.0), Vec2::new(0.0, 0.0));
+    let left = Shape::rect(Vec2::new(10.0, 200.0), Vec2::new(-200.0, 10.0));
+    let right = Shape::rect(Vec2::new(10.0, 200.0), Vec2::new(200.0, 10.0));
+    let obstacle = Shape::rect(Vec2::new(20.0, 20.0), Vec2::new(0.0, 60.0));
+
+    let shapes = [square, left, right];
+    let a = NavigationContext::new(shapes.iter().flatten());
+    let mut b = NavigationContext::new(shapes.iter().flatten());
+
+    // Peers building the same level agree
+    assert_eq!(a.hash_state(), b.hash_state());
+    assert_eq!(
+        a.tree().unwrap().hash_state(),
+        b.tree().unwrap().hash_state()
+    );
+    assert_eq!(a.portals().hash_state(), b.portals().hash_state());
+
+    // Runtime state is part of the hash
+    let portal = b.portals().iter().flatten().next().unwrap().portal_ref();
+    b.set_portal_enabled(portal, false);
+    assert_ne!(a.portals().hash_state(), b.portals().hash_state());
+    assert_ne!(a.hash_state(), b.hash_state());
+    assert_eq!(
+        a.tree().unwrap().hash_state(),
+        b.tree().unwrap().hash_state()
+    );
+
+    b.set_portal_enabled(portal, true);
+    b.set_area_at(Vec2::new(100.0, 100.0), 2).unwrap();
+    assert_ne!(a.hash_state(), b.hash_state());
+
+    let c = NavigationContext::new(shapes.iter().chain([&obstacle]).flatten());
+    assert_ne!(
+        a.tree().unwrap().hash_state(),
+        c.tree().unwrap().hash_state()
+    );
+
+    // Patched contexts match their target, despite being built differently
+    let mut patched = a.clone();
+    patched.apply_patch(&a.diff(&c)).unwrap();
+    assert_eq!(patched.hash_state(), c.hash_state());
+}
+
+#[test]
+fn repath_scheduling() {
+    let square = Shape::rect(Vec2::new(50.0, 50.0), Vec2::new(0.0, 0.0));
+    let left = Shape::rect(Vec2::new(10.0, 200.0), Vec2::new(-200.0, 10.0));
+    let right = Shape::rect(Vec2::new(10.0, 200.0), Vec2::new(200.0, 10.0));
+    let bottom = Shape::rect(Vec2::new(200.0, 10.0), Vec2::new(10.0, -200.0));
+    let top = Shape::rect(Vec2::new(200.0, 10.0), Vec2::new(10.0, 200.0));
+
+    let nav = NavigationContext::new([square, left, right, top, bottom].iter().flatten());
+
+    // Only a single replan fits in each tick
+    let settings = NavigatorSettings {
+        replan_budget: Some(Duration::ZERO),
+        ..Default::default()
+    };
+
+    let target = Vec2::new(100.0, 30.0);
+    let positions = [
+        Vec2::new(-100.0, 0.0),
+        Vec2::new(-100.0, 100.0),
+        Vec2::new(-150.0, -50.0),
+    ];
+
+    let spawn = |policy| {
+        let mut navigator = Navigator::new(nav.clone(), NavigatorSettings { policy, ..settings });
+        let agents = positions.map(|p| navigator.add_agent(p, 5.0));
+        agents.iter().for_each(|&id| navigator.move_to(id, target));
+        (navigator, agents)
+    };
+
+    // Returns the agent replanned by the next tick
+    let tick = |navigator: &mut Navigator, agents: &[AgentId]| {
+        let stale = agents
+            .iter()
+            .filter(|&&id| navigator.is_stale(id))
+            .copied()
+            .collect::<HashSet<_>>();
+        navigator.tick(0.1).for_each(drop);
+
+        let replanned = stale
+            .iter()
+            .filter(|&&id| !navigator.is_stale(id))
+            .copied()
+            .collect::<Vec<_>>();
+        assert_eq!(replanned.len(), 1);
+        replanned[0]
+    };
+
+    let (mut navigator, agents) = spawn(RepathPolicy::RoundRobin);
+    for &id in &agents {
+        assert_eq!(tick(&mut navigator, &agents), id);
+    }
+
+    let (mut navigator, agents) = spawn(RepathPolicy::Nearest(Vec2::new(-150.0, -100.0)));
+    assert_eq!(tick(&mut navigator, &agents), agents[2]);
+    assert_eq!(tick(&mut navigator, &agents), agents[0]);
+
+    // Waiting agents keep track of how long they have been stale
+    assert_eq!(navigator.staleness(agents[0]), Some(0.0));
+    assert!((navigator.staleness(agents[1]).unwrap() - 0.2).abs() < 1e-4);
+    assert!(navigator.steering(agents[1]).is_none());
+
+    let (mut navigator, agents) = spawn(RepathPolicy::Stalest);
+    assert_eq!(tick(&mut navigator, &agents), agents[0]);
+
+    // Retargeting does not skip the line
+    navigator.move_to(agents[0], Vec2::new(100.0, 100.0));
+    assert_ne!(tick(&mut navigator, &agents), agents[0]);
+    assert_ne!(tick(&mut navigator, &agents), agents[0]);
+    assert_eq!(tick(&mut navigator, &agents), agents[0]);
+}
+
+#[test]
+#[cfg(not(target_arch = "wasm32"))]
+fn chrome_trace() {
+    let square = Shape::rect(Vec2::new(50.0, 50.0), Vec2::new(0.0, 0.0));
+    let left = Shape::rect(Vec2::new(10.0, 200.0), Vec2::new(-200.0, 10.0));
+    let right = Shape::rect(Vec2::new(10.0, 200.0), Vec2::new(200.0, 10.0));
+    let bottom = Shape::rect(Vec2::new(200.0, 10.0), Vec2::new(10.0, -200.0));
+    let top = Shape::rect(Vec2::new(200.0, 10.0), Vec2::new(10.0, 200.0));
+
+    let mut tracer = Tracer::new();
+    let nav = NavigationContext::new_traced(
+        [square, left, right, top, bottom].iter().flatten(),
+        &mut tracer,
+    );
+
+    tracer.mark("frame \"0\"");
+    let a = Vec2::new(-100.0, 0.0);
+    let b = Vec2::new(100.0, 10.0);
+    let path = nav
+        .find_path_traced(
+            a,
+            b,
+            heuristics::euclidiean,
+            SearchInfo::default(),
+            &mut tracer,
+        )
+        .unwrap();
+
+    let events = tracer.events();
+    let names = events.iter().map(|e| e.name.as_ref()).collect::<Vec<_>>();
+    assert_eq!(
+        names,
+        [
+            "build tree",
+            "generate portals",
+            "connectivity",
+            "frame \"0\"",
+            "find_path"
+        ]
+    );
+
+    let json: serde_json::Value = serde_json::from_str(&tracer.to_json()).unwrap();
+    let events = json["traceEvents"].as_array().unwrap();
+    assert_eq!(events.len(), 5);
+    assert!(events.iter().all(|e| e["ts"].as_f64().is_some()));
+    assert_eq!(events[3]["name"], "frame \"0\"");
+    assert_eq!(events[3]["ph"], "i");
+
+    let query = &events[4];
+    assert_eq!(query["ph"], "X");
+    assert_eq!(query["cat"], "query");
+    assert!(query["args"]["expansions"].as_f64().unwrap() > 0.0);
+    assert_eq!(query["args"]["waypoints"], path.len() as f64);
+
+    tracer.clear();
+    assert!(tracer.events().is_empty());
+}
+
+#[test]
+fn out_of_bounds() {
+    let square = Shape::rect(Vec2::new(50.0, 50.0), Vec2::new(0.0, 0.0));
+    let bottom = Shape::rect(Vec2::new(200.0, 10.0), Vec2::new(10.0, -200.0));
+    let top = Shape::rect(Vec2::new(200.0, 10.0), Vec2::new(10.0, 200.0));
+
+    let nav = NavigationContext::new([square, top, bottom].iter().flatten());
+    let tree = nav.tree().unwrap();
+
+    let a = Vec2::new(-1000.0, 0.0);
+    let b = Vec2::new(100.0, 10.0);
+    assert!(!tree.in_bounds(a, 100.0));
+    assert!(tree.in_bounds(b, 0.0));
+    assert!(tree.locate_checked(a, 100.0).is_none());
+    assert_eq!(
+        tree.locate_checked(b, 0.0).map(|node| node.index()),
+        Some(tree.locate(b).index())
+    );
+
+    let find = |out_of_bounds, bounds_margin| {
+        let info = SearchInfo {
+            out_of_bounds,
+            bounds_margin,
+            ..Default::default()
+        };
+        nav.find_path(a, b, heuristics::euclidiean, info)
+    };
+
+    // Points are located in the outermost leaves
+    let path = find(BoundsPolicy::Extend, 0.0).unwrap();
+    assert_eq!(path.first().unwrap().point(), a);
+
+    let path = find(BoundsPolicy::Clamp, 0.0).unwrap();
+    let start = path.first().unwrap().point();
+    assert_eq!(start, tree.clamp_to_bounds(a, 0.0));
+    assert!(tree.in_bounds(start, 0.0));
+    assert_eq!(path.last().unwrap().point(), b);
+
+    assert_eq!(
+        find(BoundsPolicy::Error, 100.0).err(),
+        Some(PathError::OutOfBounds { point: a })
+    );
+
+    let region = nav.find_path_region(
+        b,
+        &Goal::Point(a),
+        heuristics::euclidiean,
+        SearchInfo {
+            out_of_bounds: BoundsPolicy::Error,
+            ..Default::default()
+        },
+    );
+    assert_eq!(region.err(), Some(PathError::OutOfBounds { point: a }));
+}
+
+#[test]
+fn navmesh() {
+    let square = Shape::rect(Vec2::new(50.0, 50.0), Vec2::new(0.0, 0.0));
+    let left = Shape::rect(Vec2::new(10.0, 200.0), Vec2::new(-200.0, 10.0));
+    let right = Shape::rect(Vec2::new(10.0, 200.0), Vec2::new(200.0, 10.0));
+    let bottom = Shape::rect(Vec2::new(200.0, 10.0), Vec2::new(10.0, -200.0));
+    let top = Shape::rect(Vec2::new(200.0, 10.0), Vec2::new(10.0, 200.0));
+
+    let mut nav = NavigationContext::new([square, left, right, top, bottom].iter().flatten());
+    let a = Vec2::new(-100.0, 0.0);
+    let b = Vec2::new(100.0, 10.0);
+    nav.add_link(a, b, 5.0).unwrap();
+
+    let mesh = nav.to_navmesh();
+    let tree = nav.tree().unwrap();
+    assert!(!mesh.polygons.is_empty());
+    assert_eq!(mesh.links.len(), 1);
+
+    let contains = |polygon: &NavPolygon, p: Vec2| {
+        let vertices = mesh.polygon_vertices(polygon).collect::<Vec<_>>();
+        vertices
+            .iter()
+            .zip(vertices.iter().cycle().skip(1))
+            .all(|(&u, &v)| (v - u).perp_dot(p - u) >= -TOLERANCE)
+    };
+
+    let link = mesh.links[0];
+    assert!(contains(&mesh.polygons[link.src], a));
+    assert!(contains(&mesh.polygons[link.dst], b));
+
+    for (i, polygon) in mesh.polygons.iter().enumerate() {
+        assert!(polygon.vertices.len() >= 3);
+        assert!(polygon.vertices.iter().all(|&v| v < mesh.vertices.len()));
+        assert!(tree.cell(polygon.node).is_some());
+
+        // Adjacency is symmetric, and portals lie on both polygons
+        for edge in &polygon.neighbours {
+            let other = &mesh.polygons[edge.polygon];
+            assert!(other.neighbours.iter().any(|e| e.polygon == i));
+
+            let mid = edge.portal.midpoint();
+            assert!(contains(polygon, mid));
+            assert!(contains(other, mid));
+        }
+    }
+
+    // Every open point lies in the polygon of its leaf
+    let (min, max) = tree.bounds();
+    for x in 0..20 {
+        for y in 0..20 {
+            let p =
+                min.lerp(max, 0.025) + (max - min) * 0.95 * Vec2::new(x as f32, y as f32) / 19.0;
+            let node = tree.locate(p);
+            if node.covered() {
+                continue;
+            }
+
+            let polygon = mesh.polygons.iter().find(|v| v.node == node.index());
+            assert!(polygon.is_some_and(|polygon| contains(polygon, p)));
+        }
+    }
+
+    assert_eq!(
+        NavigationContext::default().to_navmesh(),
+        NavMesh::default()
+    );
+}
+
+#[test]
+fn escort_path() {
+    let square = Shape::rect(Vec2::new(50.0, 50.0), Vec2::new(0.0, 0.0));
+    let left = Shape::rect(Vec2::new(10.0, 200.0), Vec2::new(-200.0, 10.0));
+    let right = Shape::rect(Vec2::new(10.0, 200.0), Vec2::new(200.0, 10.0));
+    let bottom = Shape::rect(Vec2::new(200.0, 10.0), Vec2::new(10.0, -200.0));
+    let top = Shape::rect(Vec2::new(200.0, 10.0), Vec2::new(10.0, 200.0));
+
+    let nav = NavigationContext::new([square, left, right, top, bottom].iter().flatten());
+    let tree = nav.tree().unwrap();
+
+    let a = Vec2::new(-100.0, 0.0);
+    let b = Vec2::new(100.0, 10.0);
+    let info = SearchInfo {
+        agent_radius: 2.0,
+        ..Default::default()
+    };
+
+    let escort = nav
+        .find_path_escort(a, b, heuristics::euclidiean, info, 10.0)
+        .unwrap();
+
+    let (leader, follower) = (escort.leader(), escort.follower());
+    assert_eq!(leader.len(), follower.len());
+    assert_eq!(escort.offsets().len(), leader.len());
+    assert!(escort.offsets().contains(&10.0));
+
+    for (i, (l, f)) in leader.iter().zip(follower.iter()).enumerate() {
+        assert!(!tree.locate(f.point()).covered());
+        assert_eq!(f.portal(), l.portal());
+        assert_eq!(escort.is_single_file(i), f.point() == l.point());
+
+        assert!(leader.corridor(tree, nav.portals()).contains(f.point()));
+    }
+
+    for w in follower.windows(2) {
+        let rel = w[1].point() - w[0].point();
+        assert!(tree.raycast(w[0].point(), rel, rel.length()).is_none());
+    }
+
+    // Too wide to walk side by side anywhere
+    let escort = nav
+        .find_path_escort(a, b, heuristics::euclidiean, info, 1000.0)
+        .unwrap();
+    assert!((0..escort.leader().len()).all(|i| escort.is_single_file(i)));
+    assert!(escort
+        .leader()
+        .iter()
+        .zip(escort.follower().iter())
+        .all(|(l, f)| l.point() == f.point()));
+
+    let escort = NavigationContext::default()
+        .find_path_escort(a, b, heuristics::euclidiean, info, 10.0)
+        .unwrap();
+    assert_eq!(escort.offsets(), [10.0, 10.0]);
+    let side = (b - a).normalize().perp();
+    assert_eq!(escort.follower()[0].point(), a + side * 10.0);
+}
+
+#[test]
+fn escort_respects_areas() {
+    let square = Shape::rect(Vec2::new(50.0, 50.0), Vec2::new(0.0, 0.0));
+    let left = Shape::rect(Vec2::new(10.0, 200.0), Vec2::new(-200.0, 10.0));
+    let right = Shape::rect(Vec2::new(10.0, 200.0), Vec2::new(200.0, 10.0));
+    let bottom = Shape::rect(Vec2::new(200.0, 10.0), Vec2::new(10.0, -200.0));
+    let top = Shape::rect(Vec2::new(200.0, 10.0), Vec2::new(10.0, 200.0));
+
+    let mut nav = NavigationContext::new([square, left, right, top, bottom].iter().flatten());
+
+    let a = Vec2::new(-100.0, 0.0);
+    let b = Vec2::new(100.0, 10.0);
+    let info = SearchInfo {
+        agent_radius: 2.0,
+        allowed_areas: DEFAULT_AREA,
+        ..Default::default()
+    };
+
+    let escort = nav
+        .find_path_escort(a, b, heuristics::euclidiean, info, 10.0)
+        .unwrap();
+
+    // Restrict a node the pair passes through, which is only known to the
+    // context
+    let (start, end) = (
+        nav.locate(a).unwrap().index(),
+        nav.locate(b).unwrap().index(),
+    );
+    let water = escort
+        .follower()
+        .iter()
+        .map(|v| nav.locate(v.point()).unwrap().index())
+        .find(|&v| v != start && v != end)
+        .unwrap();
+    nav.set_area(water, 2);
+
+    let escort = nav
+        .find_path_escort(a, b, heuristics::euclidiean, info, 10.0)
+        .unwrap();
+
+    assert!(escort.offsets().contains(&10.0));
+    for waypoint in escort.leader().iter().chain(escort.follower().iter()) {
+        assert_ne!(nav.locate(waypoint.point()).unwrap().index(), water);
+    }
+
+    // The leader may leave its restricted start node, but the follower does
+    // not step aside into it
+    nav.set_area(start, 2);
+    let escort = nav
+        .find_path_escort(a, b, heuristics::euclidiean, info, 10.0)
+        .unwrap();
+    assert!(escort.is_single_file(0));
+    assert_eq!(escort.follower()[0].point(), a);
+}
+
+#[test]
+fn diagnose_path() {
+    let square = Shape::rect(Vec2::new(50.0, 50.0), Vec2::new(0.0, 0.0));
+    let left = Shape::rect(Vec2::new(10.0, 200.0), Vec2::new(-200.0, 10.0));
+    let right = Shape::rect(Vec2::new(10.0, 200.0), Vec2::new(200.0, 10.0));
+    let bottom = Shape::rect(Vec2::new(200.0, 10.0), Vec2::new(10.0, -200.0));
+    let top = Shape::rect(Vec2::new(200.0, 10.0), Vec2::new(10.0, 200.0));
+
+    let nav = NavigationContext::new([square, left, right, top, bottom].iter().flatten());
+    let tree = nav.tree().unwrap();
+
+    let a = Vec2::new(-100.0, 0.0);
+    let b = Vec2::new(100.0, 10.0);
+    let path = nav
+        .find_path(a, b, heuristics::euclidiean, SearchInfo::default())
+        .unwrap();
+    assert_eq!(path.diagnose(tree, nav.portals()), []);
+
+    // Cut straight through the square
+    let inside = Vec2::new(0.0, 5.0);
+    let path = Path::from_points(vec![
+        WayPoint::new(a, tree.locate(a).index(), None),
+        WayPoint::new(inside, tree.locate(a).index(), None),
+        WayPoint::new(b, tree.locate(b).index(), None),
+    ]);
+
+    let diagnostics = path.diagnose(tree, nav.portals());
+    assert_eq!(diagnostics.len(), 1);
+    match &diagnostics[0] {
+        Diagnostic::CoveredWaypoint {
+            index,
+            point,
+            faces,
+            depth,
+            ..
+        } => {
+            assert_eq!(*index, 1);
+            assert_eq!(*point, inside);
+            assert!(!faces.is_empty());
+            assert!(*depth > 0.0);
+        }
+    }
+}
+
+#[test]
+#[cfg(feature = "petgraph")]
+fn portal_graph() {
+    use petgraph::algo::has_path_connecting;
+
+    let square = Shape::rect(Vec2::new(50.0, 50.0), Vec2::new(0.0, 0.0));
+    let left = Shape::rect(Vec2::new(10.0, 200.0), Vec2::new(-200.0, 10.0));
+    let right = Shape::rect(Vec2::new(10.0, 200.0), Vec2::new(200.0, 10.0));
+    let bottom = Shape::rect(Vec2::new(200.0, 10.0), Vec2::new(10.0, -200.0));
+    let top = Shape::rect(Vec2::new(200.0, 10.0), Vec2::new(10.0, 200.0));
+
+    let nav = NavigationContext::new([square, left, right, top, bottom].iter().flatten());
+    let tree = nav.tree().unwrap();
+    let graph = nav.portals().to_graph(tree);
+
+    assert_eq!(graph.node_count(), tree.cells().count());
+    for edge in graph.edge_indices() {
+        let (src, dst) = graph.edge_endpoints(edge).unwrap();
+        let portal = graph[edge];
+        assert_eq!(portal.src(), graph[src]);
+        assert_eq!(portal.dst(), graph[dst]);
+    }
+
+    let find = |p: Vec2| {
+        let node = tree.locate(p).index();
+        graph.node_indices().find(|&i| graph[i] == node).unwrap()
+    };
+
+    let (a, b) = (find(Vec2::new(-100.0, 0.0)), find(Vec2::new(100.0, 10.0)));
+    assert!(has_path_connecting(&graph, a, b, None));
+}
+
+#[test]
+fn simulation() {
+    use bsp_pathfinding::sim::*;
+
+    let square = Shape::rect(Vec2::new(50.0, 50.0), Vec2::new(0.0, 0.0));
+    let left = Shape::rect(Vec2::new(10.0, 200.0), Vec2::new(-200.0, 10.0));
+    let right = Shape::rect(Vec2::new(10.0, 200.0), Vec2::new(200.0, 10.0));
+    let bottom = Shape::rect(Vec2::new(200.0, 10.0), Vec2::new(10.0, -200.0));
+    let top = Shape::rect(Vec2::new(200.0, 10.0), Vec2::new(10.0, 200.0));
+
+    let nav = NavigationContext::new([square, left, right, top, bottom].iter().flatten());
+
+    let a = Vec2::new(-100.0, 0.0);
+    let b = Vec2::new(100.0, 10.0);
+
+    let setup = || {
+        let mut sim = Simulation::new(nav.clone(), SimSettings::default());
+        let agents = [sim.add_agent(a, 5.0, 100.0), sim.add_agent(b, 5.0, 100.0)];
+        sim.move_to(agents[0], b);
+        sim.move_to(agents[1], a);
+        (sim, agents)
+    };
+
+    let (mut sim, agents) = setup();
+    for _ in 0..600 {
+        // Uneven frame times
+        sim.update(1.0 / 45.0);
+        let overlap = 10.0
+            - sim
+                .position(agents[0])
+                .unwrap()
+                .distance(sim.position(agents[1]).unwrap());
+        assert!(overlap < 1.0, "Agents overlap by {}", overlap);
+    }
+
+    for (agent, target) in agents.iter().zip([b, a]) {
+        assert_eq!(sim.status(*agent), Some(AgentStatus::Arrived));
+        assert!(sim.position(*agent).unwrap().distance(target) < 10.0);
+    }
+
+    // The same steps produce the same movement, regardless of frame times
+    let (mut other, _) = setup();
+    (0..sim.steps()).for_each(|_| other.step());
+    assert_eq!(other.steps(), sim.steps());
+    for &agent in &agents {
+        assert_eq!(other.position(agent), sim.position(agent));
+    }
+}
+
+#[test]
+fn tree_to_dot() {
+    let square = Shape::rect(Vec2::new(50.0, 50.0), Vec2::new(0.0, 0.0));
+    let left = Shape::rect(Vec2::new(10.0, 200.0), Vec2::new(-200.0, 10.0));
+
+    let tree = BSPTree::new([square, left].iter().flatten().collect()).unwrap();
+    let dot = tree.to_dot();
+
+    assert!(dot.starts_with("digraph BSPTree {"));
+    assert!(dot.trim_end().ends_with('}'));
+
+    let nodes = tree.nodes().len();
+    let leaves = tree
+        .nodes()
+        .values()
+        .map(|node| node.front().is_none() as usize + node.back().is_none() as usize)
+        .sum::<usize>();
+
+    // An edge to each child, including the open and covered leaves
+    assert_eq!(dot.matches(" -> ").count(), nodes - 1 + leaves);
+    assert_eq!(dot.matches("label=open").count(), tree.cells().count());
+    assert!(dot.contains(&format!("{:?}", tree.root())));
+    assert!(dot.contains("depth 0"));
+    assert!(!dot.contains("fillcolor=\"#"));
+}
+
+#[test]
+fn heatmap_export() {
+    use bsp_pathfinding::export::svg::*;
+
+    let square = Shape::rect(Vec2::new(50.0, 50.0), Vec2::new(0.0, 0.0));
+    let left = Shape::rect(Vec2::new(10.0, 200.0), Vec2::new(-200.0, 10.0));
+    let right = Shape::rect(Vec2::new(10.0, 200.0), Vec2::new(200.0, 10.0));
+    let bottom = Shape::rect(Vec2::new(200.0, 10.0), Vec2::new(10.0, -200.0));
+    let top = Shape::rect(Vec2::new(200.0, 10.0), Vec2::new(10.0, 200.0));
+
+    let nav = NavigationContext::new([square, left, right, top, bottom].iter().flatten());
+    let tree = nav.tree().unwrap();
+
+    let mut stats = QueryStats::new();
+    nav.find_path_observed(
+        Vec2::new(-100.0, 0.0),
+        Vec2::new(100.0, 10.0),
+        heuristics::euclidiean,
+        SearchInfo::default(),
+        &mut stats,
+    )
+    .unwrap();
+
+    let heatmap = stats.expansion_heatmap();
+    assert!(!heatmap.is_empty());
+
+    let options = SvgOptions {
+        heatmap: Some(heatmap),
+        ..Default::default()
+    };
+    let svg = to_svg(&nav, None, &options);
+    assert!(svg.contains("<g id=\"heatmap\""));
+    assert_eq!(svg.matches("fill-opacity=").count(), heatmap.len());
+    assert!(svg.contains("fill-opacity=\"1.000\""));
+
+    let dot = tree.to_dot_heatmap(heatmap);
+    assert_eq!(dot.matches("touches").count(), heatmap.len());
+    assert!(dot.contains("fillcolor=\"#ff0000\""));
+}
+
+#[test]
+fn svg_export() {
+    use bsp_pathfinding::export::svg::*;
+
+    let square = Shape::rect(Vec2::new(50.0, 50.0), Vec2::new(0.0, 0.0));
+    let left = Shape::rect(Vec2::new(10.0, 200.0), Vec2::new(-200.0, 10.0));
+    let right = Shape::rect(Vec2::new(10.0, 200.0), Vec2::new(200.0, 10.0));
+    let bottom = Shape::rect(Vec2::new(200.0, 10.0), Vec2::new(10.0, -200.0));
+    let top = Shape::rect(Vec2::new(200.0, 10.0), Vec2::new(10.0, 200.0));
+
+    let nav = NavigationContext::new([square, left, right, top, bottom].iter().flatten());
+    let tree = nav.tree().unwrap();
+    let path = nav
+        .find_path(
+            Vec2::new(-100.0, 0.0),
+            Vec2::new(100.0, 10.0),
+            heuristics::euclidiean,
+            SearchInfo::default(),
+        )
+        .unwrap();
+
+    let svg = to_svg(&nav, Some(&path), &SvgOptions::default());
+    assert!(svg.starts_with("<svg xmlns=\"http://www.w3.org/2000/svg\""));
+    assert!(svg.trim_end().ends_with("</svg>"));
+
+    let faces = tree
+        .descendants()
+        .map(|(_, node)| node.faces().len())
+        .sum::<usize>();
+    assert!(svg.matches("<line").count() > faces);
+    assert_eq!(svg.matches("<circle").count(), path.len());
+    assert_eq!(svg.matches("<polyline").count(), 1);
+    for group in ["cells", "planes", "faces", "portals", "path"] {
+        assert!(svg.contains(&format!("<g id=\"{}\"", group)));
+    }
+
+    // Only the faces are drawn
+    let options = SvgOptions {
+        cells: false,
+        planes: false,
+        portals: false,
+        ..Default::default()
+    };
+    let svg = to_svg(&nav, None, &options);
+    assert_eq!(svg.matches("<line").count(), faces);
+    assert!(!svg.contains("<polygon"));
+
+    // Empty scenes render the path alone
+    let svg = to_svg(&NavigationContext::default(), Some(&path), &options);
+    assert_eq!(svg.matches("<circle").count(), path.len());
+}
+
+#[test]
+#[cfg(feature = "geojson")]
+fn geojson() {
+    use bsp_pathfinding::geojson::*;
+
+    // A clockwise building with a courtyard, and a tower
+    let json = r#"{
+        "type": "FeatureCollection",
+        "features": [
+            {
+                "type": "Feature",
+                "properties": { "name": "building" },
+                "geometry": {
+                    "type": "Polygon",
+                    "coordinates": [
+                        [[-50, -50], [-50, 50], [50, 50], [50, -50], [-50, -50]],
+                        [[-20, -20], [20, -20], [20, 20], [-20, 20], [-20, -20]]
+                    ]
+                }
+            },
+            {
+                "type": "Feature",
+                "properties": {},
+                "geometry": {
+                    "type": "MultiPolygon",
+                    "coordinates": [[[[100, 0], [110, 0], [110, 10], [100, 10]]]]
+                }
+            },
+            { "type": "Feature", "properties": {}, "geometry": null }
+        ]
+    }"#;
+
+    let shapes = shapes_from_geojson(json).unwrap();
+    assert_eq!(shapes.len(), 3);
+
+    let nav = NavigationContext::new(shapes.iter().flatten());
+    let tree = nav.tree().unwrap();
+    assert!(tree.locate(Vec2::new(35.0, 0.0)).covered());
+    assert!(tree.locate(Vec2::new(105.0, 5.0)).covered());
+    assert!(!tree.locate(Vec2::new(0.0, 0.0)).covered());
+    assert!(!tree.locate(Vec2::new(75.0, 0.0)).covered());
+
+    let exported: serde_json::Value = serde_json::from_str(&free_space_to_geojson(&nav)).unwrap();
+    let features = exported["features"].as_array().unwrap();
+    assert_eq!(features.len(), nav.to_navmesh().polygons.len());
+    for feature in features {
+        let ring = feature["geometry"]["coordinates"][0].as_array().unwrap();
+        assert!(ring.len() >= 4);
+        assert_eq!(ring.first(), ring.last());
+    }
+
+    assert!(matches!(
+        shapes_from_geojson("{ \"type\": "),
+        Err(GeoJsonError::Syntax { line: 1, .. })
+    ));
+    assert_eq!(
+        shapes_from_geojson(r#"{ "type": "Polygon", "coordinates": [[[0, 0]]] }"#).err(),
+        Some(GeoJsonError::Malformed)
+    );
+}
+
+#[test]
+fn svg_import() {
+    use bsp_pathfinding::import::svg::*;
+
+    // A clockwise square in SVG coordinates, a circle drawn with arcs, a
+    // curved wall and a triangle
+    let svg = r#"<svg xmlns="http://www.w3.org/2000/svg" viewBox="0 0 400 400">
+        <g id="layer1">
+            <path id="square" style="fill:none" d="M 0,0 H 100 V 100 H 0 Z" />
+            <path d="m200 50a25 25 0 1 0 50 0a25 25 0 1 0-50 0z"/>
+            <path d='M 0 200 C 0 300, 100 300, 100 200' />
+            <polygon points="300,0 350,0 325,50" />
+        </g>
+    </svg>"#;
+
+    let shapes = shapes_from_svg(svg, 0.5).unwrap();
+    assert_eq!(shapes.len(), 4);
+
+    // The circle is flattened within the tolerance
+    let center = Vec2::new(225.0, -50.0);
+    assert!(shapes[1].vertices().len() > 8);
+    assert!(shapes[1]
+        .vertices()
+        .iter()
+        .all(|v| (v.distance(center) - 25.0).abs() < 0.5 + 1e-3));
+
+    // The curve is open and ends where it was drawn
+    let curve = shapes[2].vertices();
+    assert!(curve.len() > 4);
+    assert!(curve[0].distance(Vec2::new(0.0, -200.0)) < 1e-3);
+    assert!(curve[curve.len() - 1].distance(Vec2::new(100.0, -200.0)) < 1e-3);
+
+    let nav = NavigationContext::new(shapes.iter().flatten());
+    let tree = nav.tree().unwrap();
+    assert!(tree.locate(Vec2::new(50.0, -50.0)).covered());
+    assert!(tree.locate(center).covered());
+    assert!(tree.locate(Vec2::new(325.0, -20.0)).covered());
+    assert!(!tree.locate(Vec2::new(150.0, -50.0)).covered());
+
+    assert_eq!(
+        shapes_from_svg(r#"<svg><path d="M 0 0 L 10" /></svg>"#, 0.5).err(),
+        Some(SvgError::InvalidElement { offset: 5 })
+    );
+}
+
+#[test]
+#[cfg(feature = "tiled")]
+fn tiled_map() {
+    // Four blocked tiles in a square, a rectangle and a rotated rectangle
+    let tmx = r#"<?xml version="1.0" encoding="UTF-8"?>
+<map version="1.9" orientation="orthogonal" renderorder="right-down" width="4" height="3" tilewidth="10" tileheight="10" infinite="0" nextlayerid="4" nextobjectid="4">
+ <tileset firstgid="1" name="tiles" tilewidth="10" tileheight="10" tilecount="2" columns="2">
+  <tile id="1">
+   <properties>
+    <property name="collides" type="bool" value="true"/>
+   </properties>
+  </tile>
+ </tileset>
+ <layer id="1" name="ground" width="4" height="3">
+  <data encoding="csv">
+2,2,1,1,
+2,2,1,1,
+1,1,1,1
+</data>
+ </layer>
+ <objectgroup id="2" name="collision">
+  <object id="1" x="30" y="0" width="10" height="10"/>
+  <object id="2" x="100" y="100" width="20" height="10" rotation="90"/>
+  <object id="3" x="50" y="50">
+   <point/>
+  </object>
+ </objectgroup>
+ <objectgroup id="3" name="hidden" visible="0">
+  <object id="4" x="200" y="200" width="10" height="10"/>
+ </objectgroup>
+</map>"#;
+
+    let path = std::env::temp_dir().join(format!("bsp-pathfinding-{}.tmx", std::process::id()));
+    std::fs::write(&path, tmx).unwrap();
+    let map = tiled::Loader::new().load_tmx_map(&path).unwrap();
+    std::fs::remove_file(&path).unwrap();
+
+    let shapes = Shape::from_tiled(&map);
+    assert_eq!(shapes.len(), 3);
+
+    let nav = NavigationContext::new(shapes.iter().flatten());
+    let tree = nav.tree().unwrap();
+
+    assert!(tree.locate(Vec2::new(5.0, -15.0)).covered());
+    assert!(tree.locate(Vec2::new(15.0, -5.0)).covered());
+    assert!(!tree.locate(Vec2::new(25.0, -5.0)).covered());
+    assert!(tree.locate(Vec2::new(35.0, -5.0)).covered());
+    assert!(tree.locate(Vec2::new(95.0, -110.0)).covered());
+    assert!(!tree.locate(Vec2::new(110.0, -105.0)).covered());
+    assert!(!tree.locate(Vec2::new(205.0, -205.0)).covered());
+}
+
+#[test]
+fn shapes_from_grid() {
+    // A ring, and two cells touching at a corner
+    let grid = [
+        "........", //
+        ".###....", ".#.#....", ".###.#..", "......#.",
+    ];
+
+    let shapes = Shape::from_grid(8, 5, |x, y| grid[y].as_bytes()[x] == b'#');
+    assert_eq!(shapes.len(), 4);
+    assert!(shapes.iter().all(|shape| shape.vertices().len() == 5));
+
+    let nav = NavigationContext::new(shapes.iter().flatten());
+    let tree = nav.tree().unwrap();
+
+    assert!(tree.locate(Vec2::new(1.5, 1.5)).covered());
+    assert!(tree.locate(Vec2::new(3.5, 2.5)).covered());
+    assert!(!tree.locate(Vec2::new(2.5, 2.5)).covered());
+    assert!(tree.locate(Vec2::new(5.5, 3.5)).covered());
+    assert!(tree.locate(Vec2::new(6.5, 4.5)).covered());
+    assert!(!tree.locate(Vec2::new(6.5, 3.5)).covered());
+    assert!(!tree.locate(Vec2::new(5.5, 4.5)).covered());
+}
+
+#[test]
+#[cfg(feature = "image")]
+fn shapes_from_png() {
+    use bsp_pathfinding::import::bitmap::*;
+
+    // A dark disk on white
+    let image = image::GrayImage::from_fn(40, 30, |x, y| {
+        let dist = Vec2::new(x as f32 - 20.0, y as f32 - 15.0).length();
+        image::Luma([if dist < 10.0 { 0 } else { 255 }])
+    });
+
+    let mut png = Vec::new();
+    image::DynamicImage::ImageLuma8(image)
+        .write_to(
+            &mut std::io::Cursor::new(&mut png),
+            image::ImageOutputFormat::Png,
+        )
+        .unwrap();
+
+    let exact = shapes_from_png(&png, 0.0).unwrap();
+    let shapes = shapes_from_png(&png, 1.0).unwrap();
+    assert_eq!(exact.len(), 1);
+    assert_eq!(shapes.len(), 1);
+    assert!(shapes[0].vertices().len() < exact[0].vertices().len() / 2);
+
+    let nav = NavigationContext::new(shapes.iter().flatten());
+    let tree = nav.tree().unwrap();
+    assert!(tree.locate(Vec2::new(20.5, -15.5)).covered());
+    assert!(tree.locate(Vec2::new(27.5, -15.5)).covered());
+    assert!(!tree.locate(Vec2::new(35.5, -15.5)).covered());
+    assert!(!tree.locate(Vec2::new(5.5, -5.5)).covered());
+}
+
+#[test]
+fn rasterize() {
+    let square = Shape::rect(Vec2::new(50.0, 50.0), Vec2::new(0.0, 0.0));
+    let left = Shape::rect(Vec2::new(10.0, 200.0), Vec2::new(-200.0, 10.0));
+    let right = Shape::rect(Vec2::new(10.0, 200.0), Vec2::new(200.0, 10.0));
+    let bottom = Shape::rect(Vec2::new(200.0, 10.0), Vec2::new(10.0, -200.0));
+    let top = Shape::rect(Vec2::new(200.0, 10.0), Vec2::new(10.0, 200.0));
+
+    let nav = NavigationContext::new([square, left, right, top, bottom].iter().flatten());
+    let grid = nav.rasterize(10.0);
+    assert_eq!(grid.origin(), Vec2::new(-205.0, -205.0));
+    assert_eq!((grid.width(), grid.height()), (41, 41));
+    assert_eq!(grid.cells().len(), 41 * 41);
+    assert_eq!(grid.distances(), None);
+
+    let (x, y) = grid.cell_at(Vec2::ZERO).unwrap();
+    assert_eq!(grid.cell_center(x, y), Vec2::ZERO);
+    assert!(grid.is_blocked(x, y));
+
+    let (x, y) = grid.cell_at(Vec2::new(-100.0, 0.0)).unwrap();
+    assert!(!grid.is_blocked(x, y));
+    assert!(grid.is_blocked(41, 0));
+    assert_eq!(grid.cell_at(Vec2::new(-300.0, 0.0)), None);
+
+    let grid = nav.rasterize_with_distances(10.0);
+    let (x, y) = grid.cell_at(Vec2::new(-100.0, 0.0)).unwrap();
+    assert!((grid.distance(x, y).unwrap() - 75.0).abs() < 1e-3);
+    let (x, y) = grid.cell_at(Vec2::ZERO).unwrap();
+    assert!((grid.distance(x, y).unwrap() + 25.0).abs() < 1e-3);
+
+    assert_eq!(NavigationContext::new([]).rasterize(1.0).cells().len(), 0);
+}
+
+#[test]
+#[cfg(feature = "bevy")]
+fn bevy_plugin() {
+    use bevy::{
+        app::App,
+        math,
+        transform::{bundles::TransformBundle, components::Transform, TransformPlugin},
+    };
+    use bsp_pathfinding::bevy_plugin::*;
+
+    let mut app = App::new();
+    app.add_plugins((TransformPlugin, NavigationPlugin::default()));
+
+    let square = app
+        .world_mut()
+        .spawn((
+            Obstacle(Shape::rect(Vec2::new(50.0, 50.0), Vec2::ZERO)),
+            TransformBundle::default(),
+        ))
+        .id();
+
+    let walls = [
+        ((10.0, 200.0), (-200.0, 10.0)),
+        ((10.0, 200.0), (200.0, 10.0)),
+        ((200.0, 10.0), (10.0, -200.0)),
+        ((200.0, 10.0), (10.0, 200.0)),
+    ];
+
+    for ((w, h), (x, y)) in walls {
+        app.world_mut().spawn((
+            Obstacle(Shape::rect(Vec2::new(w, h), Vec2::ZERO)),
+            TransformBundle::from_transform(Transform::from_xyz(x, y, 0.0)),
+        ));
+    }
+
+    let agent = app
+        .world_mut()
+        .spawn(PathRequest::new(
+            math::Vec2::new(-100.0, 0.0),
+            math::Vec2::new(100.0, 10.0),
+        ))
+        .id();
+
+    app.update();
+
+    let nav = app.world().resource::<NavigationContext>();
+    let tree = nav.tree().unwrap();
+    assert!(tree.locate(Vec2::ZERO).covered());
+    assert!(tree.locate(Vec2::new(-200.0, 10.0)).covered());
+    assert!(!tree.locate(Vec2::new(-100.0, 0.0)).covered());
+
+    let agent = app.world().entity(agent);
+    assert!(agent.get::<PathRequest>().is_none());
+    let points = agent.get::<PathResult>().unwrap().points();
+    assert_eq!(points.last(), Some(&math::Vec2::new(100.0, 10.0)));
+    assert!(points.len() > 2);
+
+    // Move the square
+    app.world_mut()
+        .get_mut::<Transform>(square)
+        .unwrap()
+        .translation = bevy::math::Vec3::new(0.0, 100.0, 0.0);
+    app.update();
+
+    let tree = app.world().resource::<NavigationContext>().tree().unwrap();
+    assert!(!tree.locate(Vec2::ZERO).covered());
+    assert!(tree.locate(Vec2::new(0.0, 100.0)).covered());
+
+    app.world_mut().despawn(square);
+    app.update();
+
+    let tree = app.world().resource::<NavigationContext>().tree().unwrap();
+    assert!(!tree.locate(Vec2::new(0.0, 100.0)).covered());
+}
+
+#[test]
+fn nav_world() {
+    let mut world = NavWorld::new();
+    assert!(!world.commit());
+
+    let square = Shape::rect(Vec2::new(50.0, 50.0), Vec2::ZERO);
+    let wall = Shape::rect(Vec2::new(10.0, 200.0), Vec2::ZERO);
+
+    world.upsert(1, &square, Mat3::IDENTITY);
+    world.upsert(2, &wall, Mat3::from_translation(Vec2::new(-200.0, 10.0)));
+    world.upsert(3, &wall, Mat3::from_translation(Vec2::new(200.0, 10.0)));
+    assert_eq!(world.len(), 3);
+    assert!(world.is_dirty());
+    assert!(world.commit());
+    assert!(!world.commit());
+
+    let tree = world.nav().tree().unwrap();
+    assert!(tree.locate(Vec2::ZERO).covered());
+    assert!(tree.locate(Vec2::new(200.0, 0.0)).covered());
+
+    // Unchanged obstacles do not rebuild
+    world.upsert(1, &square, Mat3::IDENTITY);
+    assert!(!world.is_dirty());
+
+    world.upsert(1, &square, Mat3::from_translation(Vec2::new(0.0, 100.0)));
+    assert!(world.commit());
+    let tree = world.nav().tree().unwrap();
+    assert!(!tree.locate(Vec2::ZERO).covered());
+    assert!(tree.locate(Vec2::new(0.0, 100.0)).covered());
+
+    assert!(world.remove(&2));
+    assert!(!world.remove(&2));
+    assert!(!world.contains(&2));
+    assert!(world.commit());
+    let tree = world.nav().tree().unwrap();
+    assert!(!tree.locate(Vec2::new(-200.0, 0.0)).covered());
+    assert!(tree.locate(Vec2::new(200.0, 0.0)).covered());
+
+    // Updating after a removal keeps the order
+    world.upsert(3, &wall, Mat3::from_translation(Vec2::new(100.0, 10.0)));
+    assert!(world.commit());
+    assert!(world
+        .nav()
+        .tree()
+        .unwrap()
+        .locate(Vec2::new(100.0, 0.0))
+        .covered());
+
+    world.clear();
+    assert!(world.is_empty());
+    assert!(world.commit());
+    assert!(world.nav().tree().is_none());
+}
+
+#[test]
+fn nav_world_handles() {
+    let mut world = NavWorld::default();
+
+    let square = Shape::rect(Vec2::new(50.0, 50.0), Vec2::ZERO);
+    let a = world.insert(&square, Mat3::IDENTITY);
+    let b = world.insert(&square, Mat3::from_translation(Vec2::new(100.0, 0.0)));
+    assert_ne!(a, b);
+    assert!(world.commit());
+
+    assert_eq!(world.shape(&a).unwrap().vertices(), square.vertices());
+    assert_eq!(
+        world.transform(&b),
+        Some(Mat3::from_translation(Vec2::new(100.0, 0.0)))
+    );
+    assert_eq!(world.iter().map(|v| *v.0).collect::<Vec<_>>(), [a, b]);
+
+    // Moving an obstacle rebuilds with the same shape
+    assert!(world.set_transform(&a, Mat3::from_translation(Vec2::new(0.0, 100.0))));
+    assert!(world.is_dirty());
+
+    // Building does not commit
+    let nav = world.build_navigation();
+    assert!(world.is_dirty());
+    assert!(nav.tree().unwrap().locate(Vec2::new(0.0, 100.0)).covered());
+    assert!(!nav.tree().unwrap().locate(Vec2::ZERO).covered());
+
+    assert!(world.commit());
+    assert!(world.set_transform(&a, Mat3::from_translation(Vec2::new(0.0, 100.0))));
+    assert!(!world.is_dirty());
+
+    assert!(world.remove(&a));
+    assert!(!world.set_transform(&a, Mat3::IDENTITY));
+    assert!(world.shape(&a).is_none());
+
+    // Handles are not reused
+    let c = world.insert(&square, Mat3::IDENTITY);
+    assert_ne!(c, a);
+}
+
+#[test]
+#[cfg(feature = "parry2d")]
+fn parry_shapes() {
+    use parry2d::{
+        math::{Isometry, Point, Vector},
+        shape::{Cuboid, Segment, SharedShape, TriMesh},
+    };
+
+    // A square mesh with a clockwise and a counterclockwise triangle
+    let mesh = TriMesh::new(
+        vec![
+            Point::new(-25.0, -25.0),
+            Point::new(25.0, -25.0),
+            Point::new(25.0, 25.0),
+            Point::new(-25.0, 25.0),
+        ],
+        vec![[0, 1, 2], [0, 3, 2]],
+    );
+
+    let compound = SharedShape::compound(vec![
+        (
+            Isometry::translation(-100.0, 0.0),
+            SharedShape::cuboid(10.0, 10.0),
+        ),
+        (Isometry::translation(100.0, 0.0), SharedShape::ball(10.0)),
+    ]);
+
+    let cuboid = Cuboid::new(Vector::new(10.0, 10.0));
+    let rotated = Isometry::new(Vector::new(100.0, 0.0), PI / 4.0);
+
+    let mut shapes = Shape::from_parry(&mesh, &Isometry::identity());
+    assert_eq!(shapes.len(), 1);
+    assert_eq!(shapes[0].vertices().len(), 5);
+
+    shapes.extend(Shape::from_parry(&cuboid, &rotated));
+    shapes.extend(Shape::from_parry(
+        compound.as_ref(),
+        &Isometry::translation(0.0, -100.0),
+    ));
+    assert_eq!(shapes.len(), 4);
+
+    let nav = NavigationContext::new(shapes.iter().flatten());
+    let tree = nav.tree().unwrap();
+    assert!(tree.locate(Vec2::ZERO).covered());
+    assert!(tree.locate(Vec2::new(100.0, 12.0)).covered());
+    assert!(!tree.locate(Vec2::new(100.0, 15.0)).covered());
+    assert!(tree.locate(Vec2::new(-100.0, -100.0)).covered());
+    assert!(tree.locate(Vec2::new(105.0, -100.0)).covered());
+    assert!(!tree.locate(Vec2::new(50.0, -50.0)).covered());
+
+    let shape = Shape::from(&cuboid);
+    assert_eq!(shape.vertices()[0], shape.vertices()[4]);
+    assert!(shape
+        .faces()
+        .all(|face| face.normal().dot(face.midpoint()) > 0.0));
+
+    let face = Face::from(Segment::new(Point::new(0.0, 0.0), Point::new(1.0, 0.0)));
+    assert_eq!(face.vertices(), [Vec2::ZERO, Vec2::X]);
+}
+
+#[test]
+#[cfg(feature = "nalgebra")]
+fn nalgebra_interop() {
+    use bsp_pathfinding::nalgebra_interop::*;
+    use nalgebra::{Point2, Vector2};
+
+    let square: Vec<_> = [
+        (-25.0, -25.0),
+        (25.0, -25.0),
+        (25.0, 25.0),
+        (-25.0, 25.0),
+        (-25.0, -25.0),
+    ]
+    .iter()
+    .map(|&(x, y)| Point2::new(x, y))
+    .collect();
+
+    let shape = Shape::from(&square[..]);
+    assert_eq!(Vec::<Point2<f32>>::from(&shape), square);
+
+    let face = Face::from([square[0], square[1]]);
+    assert_eq!(
+        face.vertices(),
+        [Vec2::new(-25.0, -25.0), Vec2::new(25.0, -25.0)]
+    );
+    assert_eq!(<[Point2<f32>; 2]>::from(face), [square[0], square[1]]);
+
+    let left = Shape::rect(Vec2::new(10.0, 200.0), Vec2::new(-200.0, 10.0));
+    let right = Shape::rect(Vec2::new(10.0, 200.0), Vec2::new(200.0, 10.0));
+    let nav = NavigationContext::new([shape, left, right].iter().flatten());
+    let start = Point2::new(-100.0, 0.0);
+    let end = Point2::new(100.0, 10.0);
+    let path = nav
+        .find_path(
+            start.into_glam(),
+            end.into_glam(),
+            heuristics::euclidiean,
+            SearchInfo::default(),
+        )
+        .unwrap();
+
+    let points = Vec::<Point2<f32>>::from(&path);
+    assert_eq!(points.first(), Some(&start));
+    assert_eq!(points.last(), Some(&end));
+
+    assert_eq!(Vector2::new(1.0, 2.0).into_glam(), Vec2::new(1.0, 2.0));
+    assert_eq!(Vec2::new(1.0, 2.0).into_vector(), Vector2::new(1.0, 2.0));
+}
+
+#[test]
+#[cfg(feature = "f64")]
+fn double_precision() {
+    // A face 0.13 in front of a plane, far from where the plane is defined.
+    // Single precision cancels the distance into the tolerance.
+    let p = Vec2::new(1.0e6, -2.0e5);
+    let normal = Vec2::new(0.6, 0.8);
+    let face = Face::new([
+        Vec2::new(199134.7, 400649.16),
+        Vec2::new(199126.7, 400655.16),
+    ]);
+
+    assert_eq!(face.side_of(p, normal), Side::Front);
+}
+
+#[test]
+fn generic_face() {
+    use glam::DVec2;
+
+    let face = Face::new([DVec2::new(0.0, 0.0), DVec2::new(4.0, 0.0)]);
+    assert_eq!(face.normal(), DVec2::new(0.0, -1.0));
+    assert_eq!(face.length(), 4.0);
+    assert_eq!(face.midpoint(), DVec2::new(2.0, 0.0));
+    assert_eq!(
+        face.closest_point(DVec2::new(6.0, 3.0)),
+        DVec2::new(4.0, 0.0)
+    );
+
+    let p = DVec2::new(1.0, 0.0);
+    let normal = DVec2::new(1.0, 0.0);
+    assert_eq!(face.side_of(p, normal), Side::Intersecting);
+    assert_eq!(
+        face.split(p, normal),
+        [
+            Face::new([p, DVec2::new(4.0, 0.0)]),
+            Face::new([DVec2::new(0.0, 0.0), p]),
+        ]
+    );
+
+    // Far from the origin, where f32 can not represent the offset
+    let far = DVec2::new(1.0e9, 0.0);
+    let face = Face::new([far + DVec2::new(0.0, 0.25), far + DVec2::new(0.0, 1.0)]);
+    assert_eq!(face.side_of(far, DVec2::new(0.0, 1.0)), Side::Front);
+
+    // The generic face agrees with the default one
+    let a = Face::new([Vec2::new(-3.0, 1.0), Vec2::new(2.0, 5.0)]);
+    let b = Face::new([DVec2::new(-3.0, 1.0), DVec2::new(2.0, 5.0)]);
+    assert!((a.normal().x as f64 - b.normal().x).abs() < 1e-6);
+    assert!((a.normal().y as f64 - b.normal().y).abs() < 1e-6);
+}
+
+#[test]
+#[cfg(feature = "ffi")]
+fn ffi() {
+    use bsp_pathfinding::ffi::*;
+
+    let rect = |size: Vec2, origin: Vec2| {
+        [(-1.0, -1.0), (1.0, -1.0), (1.0, 1.0), (-1.0, 1.0)]
+            .iter()
+            .flat_map(|&(x, y)| [origin.x + x * size.x, origin.y + y * size.y])
+            .collect::<Vec<f32>>()
+    };
+
+    let shapes = [
+        rect(Vec2::new(50.0, 50.0), Vec2::new(0.0, 0.0)),
+        rect(Vec2::new(10.0, 200.0), Vec2::new(-200.0, 10.0)),
+        rect(Vec2::new(10.0, 200.0), Vec2::new(200.0, 10.0)),
+        rect(Vec2::new(200.0, 10.0), Vec2::new(10.0, -200.0)),
+        rect(Vec2::new(200.0, 10.0), Vec2::new(10.0, 200.0)),
+    ];
+
+    unsafe {
+        let nav = nav_create();
+        let mut ids = Vec::new();
+        for shape in &shapes {
+            let mut id = 0;
+            assert_eq!(
+                nav_add_shape(nav, shape.as_ptr(), shape.len() / 2, &mut id),
+                NavStatus::Ok
+            );
+            ids.push(id);
+        }
+
+        let mut len = 0;
+        assert_eq!(
+            nav_find_path(
+                nav,
+                -100.0,
+                0.0,
+                100.0,
+                10.0,
+                0.0,
+                std::ptr::null_mut(),
+                0,
+                &mut len
+            ),
+            NavStatus::BufferTooSmall
+        );
+        assert!(len > 2);
+
+        let mut points = vec![0.0; len * 2];
+        assert_eq!(
+            nav_find_path(
+                nav,
+                -100.0,
+                0.0,
+                100.0,
+                10.0,
+                0.0,
+                points.as_mut_ptr(),
+                len,
+                &mut len
+            ),
+            NavStatus::Ok
+        );
+        assert_eq!(points[..2], [-100.0, 0.0]);
+        assert_eq!(points[len * 2 - 2..], [100.0, 10.0]);
+
+        assert_eq!(
+            nav_find_path(
+                nav,
+                0.0,
+                0.0,
+                100.0,
+                10.0,
+                0.0,
+                points.as_mut_ptr(),
+                len,
+                &mut len
+            ),
+            NavStatus::StartCovered
+        );
+
+        // Without the square in the way, the path is straight
+        assert_eq!(nav_remove_shape(nav, ids[0]), NavStatus::Ok);
+        assert_eq!(nav_remove_shape(nav, ids[0]), NavStatus::InvalidArgument);
+        assert_eq!(
+            nav_find_path(
+                nav,
+                -100.0,
+                0.0,
+                100.0,
+                10.0,
+                0.0,
+                points.as_mut_ptr(),
+                len,
+                &mut len
+            ),
+            NavStatus::Ok
+        );
+        assert_eq!(len, 2);
+
+        assert_eq!(
+            nav_add_shape(std::ptr::null_mut(), shapes[0].as_ptr(), 4, &mut 0),
+            NavStatus::InvalidArgument
+        );
+
+        nav_destroy(nav);
+    }
+}
+
+#[test]
+#[cfg(feature = "python")]
+fn python() {
+    use pyo3::{ffi::c_str, prelude::*, wrap_pymodule};
+
+    pyo3::prepare_freethreaded_python();
+    Python::with_gil(|py| {
+        let module = wrap_pymodule!(bsp_pathfinding::python::bsp_pathfinding)(py);
+        let globals = pyo3::types::PyDict::new(py);
+        globals.set_item("bsp", module).unwrap();
+
+        py.run(
+            c_str!(
+                r#"
+nav = bsp.NavigationContext([
+    bsp.Shape.rect((50, 50), (0, 0)),
+    bsp.Shape.rect((10, 200), (-200, 10)),
+    bsp.Shape.rect((10, 200), (200, 10)),
+    bsp.Shape.rect((200, 10), (10, -200)),
+    bsp.Shape.rect((200, 10), (10, 200)),
+])
+
+path = nav.find_path((-100, 0), (100, 10), agent_radius=5)
+assert path[0] == (-100, 0) and path[-1] == (100, 10), path
+assert len(path) > 2
+assert len(nav.portals()) > 0
+
+try:
+    nav.find_path((0, 0), (100, 10))
+    assert False
+except bsp.PathError as e:
+    assert "covered" in str(e)
+
+triangle = bsp.Shape([(0, 0), (1, 0), (0, 1)])
+assert triangle.vertices[0] == triangle.vertices[-1]
+"#
+            ),
+            Some(&globals),
+            None,
+        )
+        .unwrap();
+    });
+}
+
+#[test]
+#[cfg(feature = "rkyv")]
+fn rkyv_archive() {
+    let square = Shape::rect(Vec2::new(50.0, 50.0), Vec2::new(0.0, 0.0));
+    let left = Shape::rect(Vec2::new(10.0, 200.0), Vec2::new(-200.0, 10.0));
+    let right = Shape::rect(Vec2::new(10.0, 200.0), Vec2::new(200.0, 10.0));
+    let bottom = Shape::rect(Vec2::new(200.0, 10.0), Vec2::new(10.0, -200.0));
+    let top = Shape::rect(Vec2::new(200.0, 10.0), Vec2::new(10.0, 200.0));
+
+    let nav = NavigationContext::new([square, left, right, top, bottom].iter().flatten());
+
+    let bytes = nav.to_archive().to_bytes();
+    let archived = NavArchive::access(&bytes).unwrap();
+    assert_eq!(archived.len(), nav.tree().unwrap().nodes().len());
+
+    // Queries on the archive agree with the tree without deserializing
+    let tree = nav.tree().unwrap();
+    for x in (-250..=250).step_by(25) {
+        for y in (-250..=250).step_by(25) {
+            let p = Vec2::new(x as f32, y as f32);
+            let (_, covered) = archived.locate(p).unwrap();
+            assert_eq!(covered, tree.locate(p).covered(), "{}", p);
+        }
+    }
+
+    let restored = NavigationContext::from_archive(archived).unwrap();
+    assert_eq!(
+        restored.portals().iter().flatten().count(),
+        nav.portals().iter().flatten().count()
+    );
+
+    let clearance = |nav: &NavigationContext, p: Vec2| {
+        let node = nav.tree().unwrap().locate(p).index();
+        nav.portals().leaf_clearance(node)
+    };
+    for p in [Vec2::new(-100.0, 0.0), Vec2::new(100.0, 100.0)] {
+        assert!(clearance(&restored, p).is_some());
+        assert_eq!(clearance(&restored, p), clearance(&nav, p));
+    }
+
+    let start = Vec2::new(-100.0, 0.0);
+    let end = Vec2::new(100.0, 10.0);
+    let find = |nav: &NavigationContext| {
+        nav.find_path(start, end, heuristics::euclidiean, SearchInfo::default())
+            .unwrap()
+            .iter()
+            .map(|v| v.point())
+            .collect::<Vec<_>>()
+    };
+
+    assert_eq!(find(&restored), find(&nav));
+
+    assert!(NavArchive::access(&bytes[..bytes.len() / 2]).is_err());
+
+    let empty = NavigationContext::new([]).to_archive();
+    assert!(empty.is_empty());
+    let bytes = empty.to_bytes();
+    let restored = NavigationContext::from_archive(NavArchive::access(&bytes).unwrap()).unwrap();
+    assert!(restored.tree().is_none());
+}
+
+#[test]
+fn region_ids() {
+    let square = Shape::rect(Vec2::new(50.0, 50.0), Vec2::new(0.0, 0.0));
+    let left = Shape::rect(Vec2::new(10.0, 200.0), Vec2::new(-200.0, 10.0));
+    let right = Shape::rect(Vec2::new(10.0, 200.0), Vec2::new(200.0, 10.0));
+    let bottom = Shape::rect(Vec2::new(200.0, 10.0), Vec2::new(10.0, -200.0));
+    let top = Shape::rect(Vec2::new(200.0, 10.0), Vec2::new(10.0, 200.0));
+
+    let shapes = [square, left, right, top, bottom];
+    let mut nav = NavigationContext::new(shapes.iter().flatten());
+    let tree = nav.tree().unwrap();
+
+    let ids = tree.region_ids().collect::<Vec<_>>();
+    assert!(!ids.is_empty());
+    assert_eq!(
+        ids.iter().map(|v| v.1).collect::<HashSet<_>>().len(),
+        ids.len()
+    );
+
+    for &(node, id) in &ids {
+        assert_eq!(tree.region_id(node), Some(id));
+        assert_eq!(tree.region(id), Some(node));
+    }
+
+    // Rebuilding yields new node indices, but the same regions
+    let rebuilt = NavigationContext::new(shapes.iter().flatten());
+    let rebuilt_ids = rebuilt.tree().unwrap().region_ids().collect::<Vec<_>>();
+    assert_eq!(
+        ids.iter().map(|v| v.1).collect::<HashSet<_>>(),
+        rebuilt_ids.iter().map(|v| v.1).collect::<HashSet<_>>()
+    );
+
+    let water = Vec2::new(-100.0, 0.0);
+    nav.set_area_at(water, 2).unwrap();
+
+    // Data survives a rebuild with an unrelated obstacle added
+    let pillar = Shape::rect(Vec2::new(10.0, 10.0), Vec2::new(150.0, 150.0));
+    let rebuilt = NavigationContext::new(shapes.iter().chain([&pillar]).flatten());
+    let areas = rebuilt.remap_regions(&nav, nav.areas());
+
+    let node = rebuilt.tree().unwrap().locate(water).index();
+    assert_eq!(areas.get(node), Some(&2));
+
+    let empty = NavigationContext::new([]);
+    assert!(empty.remap_regions(&nav, nav.areas()).is_empty());
+}
+
+#[test]
+fn side_tables() {
+    let square = Shape::rect(Vec2::new(50.0, 50.0), Vec2::new(0.0, 0.0));
+    let left = Shape::rect(Vec2::new(10.0, 200.0), Vec2::new(-200.0, 10.0));
+    let right = Shape::rect(Vec2::new(10.0, 200.0), Vec2::new(200.0, 10.0));
+
+    #[derive(Debug, Clone, PartialEq)]
+    struct RoomName(&'static str);
+    #[derive(Debug, Clone, Copy, PartialEq)]
+    struct Threat(f32);
+
+    let shapes = [square, left, right];
+    let mut nav = NavigationContext::new(shapes.iter().flatten());
+    assert!(nav.node_data::<RoomName>().is_none());
+
+    let hall = nav.locate(Vec2::new(-100.0, 0.0)).unwrap().index();
+    nav.node_data_mut().insert(hall, RoomName("hall"));
+    nav.node_data_mut().insert(hall, Threat(0.5));
+
+    assert_eq!(
+        nav.node_data::<RoomName>().unwrap().get(hall),
+        Some(&RoomName("hall"))
+    );
+    assert_eq!(
+        nav.node_data::<Threat>().unwrap().get(hall),
+        Some(&Threat(0.5))
+    );
+
+    let portal = nav.portals().get(hall).next().unwrap().portal_ref();
+    assert_eq!(nav.portal_table_mut().insert(portal, Threat(2.0)), None);
+    assert_eq!(
+        nav.portal_table_mut().insert(portal, Threat(3.0)),
+        Some(Threat(2.0))
+    );
+
+    let table = nav.portal_table::<Threat>().unwrap();
+    assert_eq!(table.len(), 1);
+    assert_eq!(table.get(portal), Some(&Threat(3.0)));
+    assert!(nav.portal_table::<RoomName>().is_none());
+
+    // Tables are cloned with the context
+    let cloned = nav.clone();
+    nav.node_data_mut::<Threat>().clear();
+    assert_eq!(cloned.node_data::<Threat>().unwrap().len(), 1);
+
+    // And carried over rebuilds by region
+    let rebuilt = NavigationContext::new(shapes.iter().flatten());
+    let names = nav.take_node_data::<RoomName>().unwrap();
+    let names = rebuilt.remap_regions(&nav, &names);
+    let node = rebuilt.locate(Vec2::new(-100.0, 0.0)).unwrap().index();
+    assert_eq!(names.get(node), Some(&RoomName("hall")));
+    assert!(nav.node_data::<RoomName>().is_none());
+}
+
+#[test]
+fn validate_tree() {
+    use rand::{rngs::StdRng, SeedableRng};
+
+    let square = Shape::rect(Vec2::new(50.0, 50.0), Vec2::new(0.0, 0.0));
+    let left = Shape::rect(Vec2::new(10.0, 200.0), Vec2::new(-200.0, 10.0));
+    let right = Shape::rect(Vec2::new(10.0, 200.0), Vec2::new(200.0, 10.0));
+    let bottom = Shape::rect(Vec2::new(200.0, 10.0), Vec2::new(10.0, -200.0));
+    let top = Shape::rect(Vec2::new(200.0, 10.0), Vec2::new(10.0, 200.0));
+    let hexagon = Shape::regular_polygon(6, 30.0, Vec2::new(100.0, -100.0));
+
+    let shapes = [square, left, right, top, bottom, hexagon];
+    let faces = shapes.iter().flatten().collect::<Vec<_>>();
+
+    let mut rng = StdRng::seed_from_u64(7);
+    let trees = [
+        BSPTree::from_slice(&faces).unwrap(),
+        BSPTree::new_shuffle(faces.iter().copied(), &mut rng).unwrap(),
+        BSPTree::new_inflated(&faces, 5.0).unwrap(),
+        BSPTree::from_slice(&faces)
+            .unwrap()
+            .transformed(Mat3::from_scale_angle_translation(
+                Vec2::new(-1.0, 2.0),
+                0.3,
+                Vec2::new(5.0, 0.0),
+            )),
+    ];
+
+    for tree in &trees {
+        let report = tree.validate();
+        assert!(report.is_valid(), "{}", report);
+        assert_eq!(report.reachable, tree.nodes().len());
+        assert_eq!(
+            report.max_depth,
+            tree.descendants().map(|v| v.1.depth()).max().unwrap()
+        );
+    }
+}
+
+#[test]
+fn validate_portals() {
+    let square = Shape::rect(Vec2::new(50.0, 50.0), Vec2::new(0.0, 0.0));
+    let left = Shape::rect(Vec2::new(10.0, 200.0), Vec2::new(-200.0, 10.0));
+    let right = Shape::rect(Vec2::new(10.0, 200.0), Vec2::new(200.0, 10.0));
+    let bottom = Shape::rect(Vec2::new(200.0, 10.0), Vec2::new(10.0, -200.0));
+    let top = Shape::rect(Vec2::new(200.0, 10.0), Vec2::new(10.0, 200.0));
+    let hexagon = Shape::regular_polygon(6, 30.0, Vec2::new(100.0, -100.0));
+
+    let mut nav =
+        NavigationContext::new([square, left, right, top, bottom, hexagon].iter().flatten());
+    let first = nav.portals().iter().flatten().next().unwrap();
+    let (portal, face) = (first.portal_ref(), *first.face());
+    nav.set_one_way(portal, true);
+
+    let issues = nav.portals().validate(nav.tree().unwrap());
+    assert_eq!(issues, []);
+
+    let mirrored = nav.transformed(Mat3::from_scale(Vec2::new(-1.0, 1.0)));
+    assert_eq!(mirrored.portals().validate(mirrored.tree().unwrap()), []);
+
+    // Hand made portals, of which one faces the wrong way
+    let tree = nav.tree().unwrap();
+    let push = |vertices| {
+        let mut portals = nav.portals().clone();
+        portals
+            .push(ClippedFace::new(
+                vertices,
+                [Side::Coplanar; 2],
+                [false; 2],
+                portal.src(),
+                portal.dst(),
+            ))
+            .unwrap();
+        portals.validate(tree)
+    };
+
+    let [a, b] = face.vertices;
+    let (mut valid, mut invalid) = (push([a, b]), push([b, a]));
+    if !valid.is_empty() {
+        core::mem::swap(&mut valid, &mut invalid);
+    }
+
+    assert_eq!(valid, []);
+    assert_eq!(invalid.len(), 2);
+    assert!(invalid
+        .iter()
+        .all(|v| matches!(v, PortalIssue::WrongNormal(_))));
+    assert!(invalid[0].to_string().contains("not pointing into dst"));
+}
+
+#[test]
+fn degenerate_input() {
+    let p = Vec2::new(1.0, 2.0);
+    assert_eq!(Face::try_new([p, p]).err(), Some(FaceDefect::ZeroLength));
+    assert_eq!(
+        Face::try_new([p, p + Vec2::new(0.01, 0.0)]).err(),
+        Some(FaceDefect::TooShort)
+    );
+    assert_eq!(
+        Face::try_new([p, Vec2::new(f32::NAN, 0.0)]).err(),
+        Some(FaceDefect::NonFinite)
+    );
+    assert_eq!(
+        Face::try_new([p, Vec2::new(f32::INFINITY, 0.0)]).err(),
+        Some(FaceDefect::NonFinite)
+    );
+    assert!(Face::try_new([p, Vec2::ZERO]).is_ok());
+
+    let square = Shape::rect(Vec2::new(50.0, 50.0), Vec2::new(0.0, 0.0));
+    let mut faces = square.faces().collect::<Vec<_>>();
+    assert!(BSPTree::try_from_slice(&faces).unwrap().is_some());
+    assert!(matches!(BSPTree::try_from_slice(&[]), Ok(None)));
+
+    faces.insert(1, Face::new([p, p]));
+    faces.push(Face::new([p, Vec2::new(f32::NAN, 1.0)]));
+
+    let errors = NavigationContext::try_new(faces).err().unwrap();
+    assert_eq!(
+        errors,
+        [
+            FaceError {
+                index: 1,
+                defect: FaceDefect::ZeroLength
+            },
+            FaceError {
+                index: 5,
+                defect: FaceDefect::NonFinite
+            },
+        ]
+    );
+    assert_eq!(errors[0].to_string(), "Face 1 has zero length");
+}
+
+#[test]
+fn dedup_faces_of_adjacent_shapes() {
+    let a = Shape::rect(Vec2::new(50.0, 50.0), Vec2::new(0.0, 0.0));
+    let b = Shape::rect(Vec2::new(50.0, 50.0), Vec2::new(50.0, 0.0));
+    // Shares part of the right edge of b
+    let c = Shape::rect(Vec2::new(20.0, 20.0), Vec2::new(85.0, 0.0));
+
+    let faces = [&a, &b].into_iter().flatten().collect::<Vec<_>>();
+    let deduped = dedup_faces(&faces);
+
+    // The shared edge is removed from both sides
+    assert_eq!(deduped.len(), 6);
+    assert!(deduped
+        .iter()
+        .all(|face| face.vertices[0].x != 25.0 || face.vertices[1].x != 25.0));
+
+    // Duplicates collapse into one
+    let twice = [&a, &a].into_iter().flatten().collect::<Vec<_>>();
+    let deduped = dedup_faces(&twice);
+    assert_eq!(deduped.len(), 4);
+    for face in a.faces() {
+        assert!(deduped.contains(&face));
+    }
+
+    // Partially overlapping opposite faces are cut to the uncovered parts
+    let faces = [&b, &c].into_iter().flatten().collect::<Vec<_>>();
+    let deduped = dedup_faces(&faces);
+    let seam = deduped
+        .iter()
+        .filter(|face| face.vertices.iter().all(|v| v.x == 75.0))
+        .collect::<Vec<_>>();
+    assert_eq!(seam.len(), 2);
+    assert!(seam.iter().all(|face| face.normal() == Vec2::X));
+    assert_eq!(seam.iter().map(|face| face.length()).sum::<f32>(), 30.0);
+
+    // Unrelated faces are untouched
+    let square = Shape::rect(Vec2::new(50.0, 50.0), Vec2::new(0.0, 0.0));
+    let left = Shape::rect(Vec2::new(10.0, 200.0), Vec2::new(-200.0, 10.0));
+    let faces = [&square, &left].into_iter().flatten().collect::<Vec<_>>();
+    assert_eq!(dedup_faces(&faces), faces);
+
+    let nav = NavigationContext::new([&a, &b, &a].into_iter().flatten());
+    let deduped = NavigationContext::new_deduplicated([&a, &b, &a].into_iter().flatten());
+    assert!(deduped.tree().unwrap().nodes().len() < nav.tree().unwrap().nodes().len());
+    assert!(deduped.locate(Vec2::new(25.0, 0.0)).unwrap().covered());
+    assert!(!deduped.locate(Vec2::new(-30.0, 0.0)).unwrap().covered());
+    assert!(deduped.tree().unwrap().validate().is_valid());
+}
+
+#[test]
+fn weld_faces() {
+    let chain = [
+        Face::new([Vec2::new(0.0, 0.0), Vec2::new(1.0, 0.0)]),
+        Face::new([Vec2::new(2.0, 0.0), Vec2::new(3.0, 0.0)]),
+        Face::new([Vec2::new(1.0, 0.0), Vec2::new(2.0, 0.0)]),
+        // Facing the other way
+        Face::new([Vec2::new(5.0, 0.0), Vec2::new(4.0, 0.0)]),
+        // Separated by a gap
+        Face::new([Vec2::new(10.0, 0.0), Vec2::new(11.0, 0.0)]),
+    ];
+
+    let welded = Face::weld(&chain, 0.01);
+    assert_eq!(welded.len(), 3);
+    assert!(welded.contains(&Face::new([Vec2::new(0.0, 0.0), Vec2::new(3.0, 0.0)])));
+    assert!(welded.contains(&chain[3]));
+    assert!(welded.contains(&chain[4]));
+
+    // A gap within the tolerance is bridged
+    assert_eq!(Face::weld(&chain[..2], 1.5).len(), 1);
+
+    // A row of tiles becomes a single box
+    let tiles = (0..8)
+        .map(|i| Shape::rect(Vec2::splat(10.0), Vec2::new(i as f32 * 10.0, 0.0)))
+        .collect::<Vec<_>>();
+    let faces = tiles.iter().flatten().collect::<Vec<_>>();
+    let welded = Face::weld(&dedup_faces(&faces), TOLERANCE);
+    assert_eq!(welded.len(), 4);
+
+    let tree = BSPTree::from_slice(&welded).unwrap();
+    assert!(tree.nodes().len() < BSPTree::from_slice(&faces).unwrap().nodes().len());
+    assert!(tree.locate(Vec2::new(40.0, 0.0)).covered());
+    assert!(!tree.locate(Vec2::new(40.0, 6.0)).covered());
+}